@@ -1,26 +1,32 @@
 use phase2_coordinator::{
+    audit_log::AdminAction,
     authentication::{KeyPair, Production, Signature},
-    commands::{Computation, RandomSource, SEED_LENGTH},
+    commands::{self, Beacon, Computation, RandomSource, SEED_LENGTH},
+    environment::CircuitSet,
     io::{self, verify_signature, KeyPairUser},
     objects::{ContributionFileSignature, ContributionInfo, ContributionState, TrimmedContributionInfo},
-    rest_utils::{ContributorStatus, PostChunkRequest, TOKENS_ZIP_FILE, UPDATE_TIME},
-    storage::Object,
+    rest_utils::{ContributionStatus, ContributorStatus, PostChunkRequest, TOKENS_ZIP_FILE, UPDATE_TIME},
+    tokens::SignedToken,
 };
 
 use reqwest::{Client, Url};
 
 use anyhow::Result;
 use async_stream::try_stream;
+use bytes::Bytes;
 use crossterm::{
     execute,
     terminal::{Clear, ClearType, ScrollDown},
 };
 use ed25519_compact::{KeyPair as EdKeyPair, Seed};
-use futures_util::StreamExt;
+use futures_util::{Stream, StreamExt};
 use phase2_cli::{
     ascii_logo::{ASCII_CONTRIBUTION_DONE, ASCII_LOGO},
     keys::{self, EncryptedKeypair, TomlConfig},
-    requests, CeremonyOpt, CoordinatorUrl, Token, VerifySignatureContribution,
+    requests,
+    tokens::{self, Schedule},
+    AdminOpt, BeaconOpt, CeremonyOpt, CohortFiles, CoordinatorUrl, Export, GenerateTokens, Token, TokensOpt, Transcript,
+    VerifySignatureContribution,
 };
 use serde_json;
 use setup_utils::calculate_hash;
@@ -30,6 +36,7 @@ use std::{
     collections::HashMap,
     fs::{self, File, OpenOptions},
     io::Read,
+    path::Path,
     process,
     sync::Arc,
     time::{Duration, Instant, UNIX_EPOCH},
@@ -51,6 +58,9 @@ use bs58;
 const OFFLINE_CONTRIBUTION_FILE_NAME: &str = "contribution.params";
 const OFFLINE_CHALLENGE_FILE_NAME: &str = "challenge.params";
 
+const UPLOAD_PART_SIZE: usize = 8 * 1024 * 1024;
+const UPLOAD_MAX_RETRIES: u32 = 5;
+
 const CUSTOM_SEED_MSG_NO: &str = "Enter a variable-length random string to be used as entropy in combination with your OS randomness.\nYou can type frenetically, smash your keyboard, or enter a string representation of your alternative source of entropy.\nThe only limitation is your terminal’s max command length.\nThis string will be hashed together with your OS randomness to produce the seed for ChaCha RNG";
 const CUSTOM_SEED_MSG_YES: &str = "Provide your custom random seed to initialize the ChaCha random number generator.\nYour seed might come from an external source of randomness like atmospheric noise, radioactive elements, lava lite etc. or an airgapped machine.";
 
@@ -97,16 +107,12 @@ fn initialize_contribution() -> Result<ContributionInfo> {
 }
 
 #[inline(always)]
-fn get_file_as_byte_vec(filename: &str, round_height: u64, contribution_id: u64) -> Result<Vec<u8>> {
+fn get_file_as_byte_vec(filename: &str, contribution_size: u64) -> Result<Vec<u8>> {
     let mut f = File::open(filename)?;
     let metadata = fs::metadata(filename)?;
 
-    let anoma_file_size: u64 = Object::anoma_contribution_file_size(round_height, contribution_id);
-    let mut buffer = vec![0; anoma_file_size as usize];
-    debug!(
-        "namada_contribution_file_size: round_height {}, contribution_id {}",
-        round_height, contribution_id
-    );
+    let mut buffer = vec![0; contribution_size as usize];
+    debug!("namada_contribution_file_size: {}", contribution_size);
     debug!("metadata file length {}", metadata.len());
     f.read(&mut buffer)?;
 
@@ -124,9 +130,81 @@ fn get_progress_bar(len: u64) -> ProgressBar {
     progress_bar
 }
 
+/// Collects the challenge from the given stream, showing the download progress
+async fn download_challenge<S>(mut challenge_stream: S, challenge_len: u64) -> Result<Vec<u8>>
+where
+    S: Stream<Item = reqwest::Result<Bytes>> + Unpin,
+{
+    let progress_bar = get_progress_bar(challenge_len);
+    let mut challenge: Vec<u8> = Vec::new();
+    while let Some(b) = challenge_stream.next().await {
+        let b = b?;
+        challenge.extend_from_slice(&b);
+        progress_bar.inc(b.len() as u64);
+    }
+    progress_bar.finish();
+
+    Ok(challenge)
+}
+
+/// Uploads the contribution directly to the Coordinator in parts of [`UPLOAD_PART_SIZE`] bytes. If a request fails, the
+/// upload is resumed from the last byte received by the Coordinator, giving up after [`UPLOAD_MAX_RETRIES`] failures
+/// without progress.
+async fn upload_contribution_direct(
+    client: &Client,
+    coordinator: &Url,
+    keypair: &KeyPair,
+    round_height: u64,
+    contribution: &[u8],
+) -> Result<()> {
+    let progress_bar = get_progress_bar(contribution.len() as u64);
+    let mut offset = 0;
+    let mut retries = 0;
+    // After a failure, the offset is read back from the Coordinator before uploading the next part
+    let mut resume = false;
+
+    while (offset as usize) < contribution.len() {
+        let result = if resume {
+            requests::get_upload_offset(client, coordinator, keypair, round_height).await
+        } else {
+            let end = std::cmp::min(offset as usize + UPLOAD_PART_SIZE, contribution.len());
+
+            requests::post_contribution_part(
+                client,
+                coordinator,
+                keypair,
+                round_height,
+                offset,
+                &contribution[offset as usize..end],
+            )
+            .await
+        };
+
+        match result {
+            Ok(received) => {
+                if received > offset {
+                    retries = 0;
+                }
+                offset = received;
+                resume = false;
+            }
+            Err(e) if retries < UPLOAD_MAX_RETRIES => {
+                debug!("Upload of contribution part failed: {}, resuming", e);
+                retries += 1;
+                resume = true;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        progress_bar.set_position(offset);
+    }
+    progress_bar.finish();
+
+    Ok(())
+}
+
 /// Contest and offline execution branches
 #[inline(always)]
-fn compute_contribution_offline() -> Result<()> {
+fn compute_contribution_offline(circuits: &CircuitSet) -> Result<()> {
     // Print instructions to the user
     let mut msg = format!("{}\n\n", "Instructions".bold().underline().bright_cyan(),);
     msg.push_str(format!("{}",format!(
@@ -152,8 +230,8 @@ fn compute_contribution_offline() -> Result<()> {
         format!(
             "{}",
             format!(
-                "{:4}2) Execute the command \"cargo run --release --bin phase2 --features=cli contribute offline\"\n",
-                ""
+                "{:4}2) Execute the command \"cargo run --release --bin phase2 --features=cli contribute offline --circuits {}\"\n",
+                "", circuits
             )
             .as_str()
             .bright_cyan()
@@ -185,7 +263,7 @@ fn compute_contribution_offline() -> Result<()> {
 }
 
 /// Computes randomness
-fn compute_contribution(custom_seed: bool, challenge: &[u8], filename: &str) -> Result<()> {
+fn compute_contribution(custom_seed: bool, circuits: &CircuitSet, challenge: &[u8], filename: &str) -> Result<()> {
     let rand_source = if custom_seed {
         let seed_str = io::get_user_input(
             "Enter your custom random seed (64 characters / 32 bytes in hexadecimal format without a '0x' prefix):"
@@ -210,10 +288,7 @@ fn compute_contribution(custom_seed: bool, challenge: &[u8], filename: &str) ->
 
     let writer = OpenOptions::new().append(true).open(filename)?;
 
-    #[cfg(debug_assertions)]
-    Computation::contribute_test_masp(challenge, writer, &rand_source);
-    #[cfg(not(debug_assertions))]
-    Computation::contribute_masp(challenge, writer, &rand_source);
+    Computation::contribute_circuits(circuits, challenge, writer, &rand_source);
 
     println!(
         "{}",
@@ -232,8 +307,10 @@ async fn contribute(
     keypair: &KeyPair,
     mut contrib_info: ContributionInfo,
     heartbeat_handle: &JoinHandle<()>,
+    direct_transfer: bool,
 ) -> Result<u64> {
     // Get the necessary info to compute the contribution
+    let circuits = requests::get_circuits(client, coordinator).await?;
     println!("{} Locking chunk", "[4/11]".bold().dimmed());
     let locked_locators = requests::get_lock_chunk(client, coordinator, keypair).await?;
     contrib_info.timestamps.challenge_locked = Utc::now();
@@ -248,17 +325,17 @@ async fn contribute(
     let round_height = response_locator.round_height();
     contrib_info.ceremony_round = round_height;
 
-    let challenge_url = requests::get_challenge_url(client, coordinator, keypair, &round_height).await?;
-    println!("{} Getting challenge", "[5/11]".bold().dimmed());
-    let mut challenge_stream = requests::get_challenge(client, challenge_url.as_str()).await?;
-    let progress_bar = get_progress_bar(challenge_stream.1);
-    let mut challenge: Vec<u8> = Vec::new();
-    while let Some(b) = challenge_stream.0.next().await {
-        let b = b?;
-        challenge.extend_from_slice(&b);
-        progress_bar.inc(b.len() as u64);
-    }
-    progress_bar.finish();
+    let challenge = if direct_transfer {
+        println!("{} Getting challenge", "[5/11]".bold().dimmed());
+        let (challenge_stream, challenge_len) =
+            requests::get_challenge_direct(client, coordinator, keypair, round_height).await?;
+        download_challenge(challenge_stream, challenge_len).await?
+    } else {
+        let challenge_url = requests::get_challenge_url(client, coordinator, keypair, &round_height).await?;
+        println!("{} Getting challenge", "[5/11]".bold().dimmed());
+        let (challenge_stream, challenge_len) = requests::get_challenge(client, challenge_url.as_str()).await?;
+        download_challenge(challenge_stream, challenge_len).await?
+    };
     contrib_info.timestamps.challenge_downloaded = Utc::now();
 
     // Saves the challenge locally, in case the contributor is paranoid and wants to double check himself. It is also used in the offline contrib path
@@ -274,6 +351,9 @@ async fn contribute(
     debug!("Challenge hash is {}", pretty_hash!(&challenge_hash));
     debug!("Challenge length {}", challenge.len());
 
+    // The contribution appends a public key to the parameters of each circuit of the challenge
+    let contribution_size = challenge.len() as u64 + circuits.per_round_file_size_increase();
+
     // Prepare contribution file with the challege hash
     println!("{} Setting up contribution file", "[6/11]".bold().dimmed());
     let contrib_filename = if contrib_info.is_another_machine {
@@ -294,7 +374,7 @@ async fn contribute(
     let contrib_filename_copy = contrib_filename.clone();
     contrib_info.timestamps.start_computation = Utc::now();
     if contrib_info.is_another_machine {
-        tokio::task::spawn_blocking(move || compute_contribution_offline()).await??;
+        tokio::task::spawn_blocking(move || compute_contribution_offline(&circuits)).await??;
     } else {
         let custom_seed = contrib_info.is_own_seed_of_randomness;
         if custom_seed {
@@ -303,19 +383,19 @@ async fn contribute(
             println!("{}", CUSTOM_SEED_MSG_NO.bright_cyan());
         }
         tokio::task::spawn_blocking(move || {
-            compute_contribution(custom_seed, challenge.as_ref(), contrib_filename_copy.as_str())
+            compute_contribution(
+                custom_seed,
+                &circuits,
+                challenge.as_ref(),
+                contrib_filename_copy.as_str(),
+            )
         })
         .await??;
     }
     let contrib_filename_copy = contrib_filename.clone();
-    let contribution = tokio::task::spawn_blocking(move || {
-        get_file_as_byte_vec(
-            contrib_filename_copy.as_str(),
-            round_height,
-            response_locator.contribution_id(),
-        )
-    })
-    .await??;
+    let contribution =
+        tokio::task::spawn_blocking(move || get_file_as_byte_vec(contrib_filename_copy.as_str(), contribution_size))
+            .await??;
 
     contrib_info.timestamps.end_computation = Utc::now();
     trace!("Response writer {:?}", response_writer);
@@ -349,33 +429,40 @@ async fn contribute(
     let signature = Production.sign(keypair.sigkey(), &contribution_state.signature_message()?)?;
     let contribution_file_signature = ContributionFileSignature::new(signature, contribution_state)?;
 
-    let (contribution_url, contribution_signature_url) =
-        requests::get_contribution_url(client, coordinator, keypair, &round_height).await?;
-    println!("{} Uploading contribution", "[9/11]".bold().dimmed());
-    let contrib_file = async_fs::File::open(contrib_filename.as_str()).await?;
-    let contrib_size = async_fs::metadata(contrib_filename.as_str()).await?.len();
-    let mut stream = ReaderStream::new(contrib_file);
-    let pb = get_progress_bar(contrib_size);
-    let pb_clone = pb.clone();
-
-    let contrib_stream = try_stream! {
-        while let Some(b) = stream.next().await {
-            let b = b?;
-            pb.inc(b.len() as u64);
-            yield b;
-        }
-    };
+    if direct_transfer {
+        println!("{} Uploading contribution", "[9/11]".bold().dimmed());
+        upload_contribution_direct(client, coordinator, keypair, round_height, &contribution).await?;
+        requests::post_contribution_signature(client, coordinator, keypair, round_height, &contribution_file_signature)
+            .await?;
+    } else {
+        let (contribution_url, contribution_signature_url) =
+            requests::get_contribution_url(client, coordinator, keypair, &round_height).await?;
+        println!("{} Uploading contribution", "[9/11]".bold().dimmed());
+        let contrib_file = async_fs::File::open(contrib_filename.as_str()).await?;
+        let contrib_size = async_fs::metadata(contrib_filename.as_str()).await?.len();
+        let mut stream = ReaderStream::new(contrib_file);
+        let pb = get_progress_bar(contrib_size);
+        let pb_clone = pb.clone();
+
+        let contrib_stream = try_stream! {
+            while let Some(b) = stream.next().await {
+                let b = b?;
+                pb.inc(b.len() as u64);
+                yield b;
+            }
+        };
 
-    requests::upload_chunk(
-        client,
-        contribution_url.as_str(),
-        contribution_signature_url.as_str(),
-        contrib_stream,
-        contrib_size,
-        &contribution_file_signature,
-    )
-    .await?;
-    pb_clone.finish();
+        requests::upload_chunk(
+            client,
+            contribution_url.as_str(),
+            contribution_signature_url.as_str(),
+            contrib_stream,
+            contrib_size,
+            &contribution_file_signature,
+        )
+        .await?;
+        pb_clone.finish();
+    }
     contrib_info.timestamps.end_contribution = Utc::now();
 
     // Compute signature of contributor info
@@ -413,6 +500,28 @@ async fn contribute(
     //  We don't need to await the hearbeat future
     heartbeat_handle.abort();
 
+    // The coordinator verifies the contribution before replying, keep polling in case the verification got delayed
+    loop {
+        match requests::get_contribution_status(client, coordinator, keypair).await? {
+            ContributionStatus::Valid => {
+                println!("{}", "Your contribution has been verified and accepted!".green().bold());
+                break;
+            }
+            ContributionStatus::Invalid(reason) => {
+                println!(
+                    "{}",
+                    format!("Your contribution has been rejected: {}", reason).red().bold()
+                );
+                break;
+            }
+            ContributionStatus::Pending | ContributionStatus::Verifying => time::sleep(UPDATE_TIME).await,
+            ContributionStatus::Other => {
+                println!("{}", "Couldn't retrieve the status of your contribution.".red().bold());
+                break;
+            }
+        }
+    }
+
     Ok(round_height)
 }
 
@@ -424,6 +533,7 @@ async fn contribution_loop(
     keypair: Arc<KeyPair>,
     token: String,
     mut contrib_info: ContributionInfo,
+    direct_transfer: bool,
 ) {
     println!("{} Joining queue", "[3/11]".bold().dimmed());
 
@@ -498,9 +608,16 @@ async fn contribution_loop(
                 status_count += 1;
             }
             ContributorStatus::Round => {
-                round_height = contribute(&client, &coordinator, &keypair, contrib_info.clone(), &heartbeat_handle)
-                    .await
-                    .expect(&format!("{}", "Contribution failed".red().bold()));
+                round_height = contribute(
+                    &client,
+                    &coordinator,
+                    &keypair,
+                    contrib_info.clone(),
+                    &heartbeat_handle,
+                    direct_transfer,
+                )
+                .await
+                .expect(&format!("{}", "Contribution failed".red().bold()));
             }
             ContributorStatus::Finished => {
                 let content = fs::read(&format!("namada_contributor_info_round_{}.json", round_height))
@@ -508,7 +625,7 @@ async fn contribution_loop(
                 let contrib_info: ContributionInfo = serde_json::from_slice(&content).unwrap();
 
                 println!("{}\n{}\n\nI've contributed to @namadanetwork #NamadaTrustedSetup at round #{} with the contribution hash {}. Let's enable #interchain privacy.\n\n{}",
-                                                "Done! Thank you for your contribution! If your contribution is valid, it will appear on ceremony.namada.net. Check it out!".green().bold(),
+                                                "Done! Thank you for your contribution! It will appear on ceremony.namada.net. Check it out!".green().bold(),
                                                 "If you’d like to share that you contributed with your frens and the world, you can use:".bright_cyan(),
                                                 round_height,
                                 contrib_info.contribution_hash,
@@ -617,6 +734,26 @@ async fn update_coordinator(client: &Client, coordinator: &Url, keypair: &KeyPai
     }
 }
 
+#[inline(always)]
+async fn finalize_ceremony(client: &Client, coordinator: &Url, keypair: &KeyPair, beacon: &Beacon) {
+    match requests::post_finalize_ceremony(client, coordinator, keypair, beacon).await {
+        Ok(record) => {
+            println!(
+                "Applied the beacon to round {} contribution {}",
+                record.round_height, record.contribution_id
+            );
+            println!("Beacon seed: 0x{}", record.seed);
+            println!(
+                "{}",
+                format!("Final contribution hash: 0x{}", record.contribution_hash)
+                    .green()
+                    .bold()
+            );
+        }
+        Err(e) => eprintln!("{}", e.to_string().red().bold()),
+    }
+}
+
 #[inline(always)]
 async fn update_cohorts(client: &Client, coordinator: &Url, keypair: &KeyPair) {
     // Get content of zip file
@@ -629,6 +766,169 @@ async fn update_cohorts(client: &Client, coordinator: &Url, keypair: &KeyPair) {
     }
 }
 
+#[inline(always)]
+async fn admin_action(client: &Client, coordinator: &Url, keypair: &KeyPair, action: &AdminAction) {
+    match requests::post_admin_action(client, coordinator, keypair, action).await {
+        Ok(()) => println!("{}", format!("Done: {}", action).green().bold()),
+        Err(e) => {
+            eprintln!("{}", e.to_string().red().bold());
+            process::exit(1);
+        }
+    }
+}
+
+#[inline(always)]
+async fn get_audit_log(client: &Client, coordinator: &Url, keypair: &KeyPair) {
+    match requests::get_audit_log(client, coordinator, keypair).await {
+        Ok(entries) => {
+            for entry in entries {
+                let time = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(entry.timestamp as u64));
+                let line = format!("{} {} ({}): {}", time.to_rfc3339(), entry.operator, entry.pubkey, entry.action);
+                match entry.error {
+                    Some(error) => println!("{} {}", line, format!("failed: {}", error).red()),
+                    None => println!("{}", line),
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e.to_string().red().bold());
+            process::exit(1);
+        }
+    }
+}
+
+/// Generates the cohort files of the tokens and, for the invited participants, the ones of their e-mails
+fn generate_tokens(opt: GenerateTokens) -> Result<()> {
+    let issuer = match &opt.issuer {
+        Some(path) => Some(io::keypair_from_mnemonic(path)?),
+        None => None,
+    };
+    let emails = match &opt.emails {
+        Some(path) => tokens::read_emails(path)?,
+        None => Vec::new(),
+    };
+    let schedule = Schedule {
+        start: opt.start,
+        cohort_duration: opt.cohort_duration,
+        per_cohort: opt.per_cohort,
+        invited: emails.len(),
+        ffa_cohorts: opt.ffa_cohorts,
+    };
+    let cohorts = tokens::generate(&schedule, issuer.as_ref())?;
+
+    // Check both directories before writing to any of them
+    tokens::create_output_dir(&opt.files.dir)?;
+    if !emails.is_empty() {
+        tokens::create_output_dir(&opt.mailchimp_dir)?;
+        let invited = tokens::assign_emails(&emails, &cohorts);
+        tokens::write_cohort_files(&opt.mailchimp_dir, tokens::MAILCHIMP_FILE_PREFIX, &invited)?;
+    }
+    tokens::write_cohort_files(&opt.files.dir, &opt.files.prefix, &cohorts)?;
+
+    let ceremony_end = opt.start + opt.cohort_duration * (cohorts.len() as u64 - 1);
+    println!(
+        "Ceremony start: {}",
+        DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(opt.start))
+    );
+    println!(
+        "Ceremony end: {}",
+        DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(ceremony_end))
+    );
+    println!(
+        "Cohorts: {} invited, {} free for all",
+        schedule.invited_cohorts(),
+        schedule.ffa_cohorts
+    );
+    println!(
+        "{}",
+        format!(
+            "Generated {} tokens in {}",
+            cohorts.iter().map(Vec::len).sum::<usize>(),
+            opt.files.dir.display()
+        )
+        .green()
+        .bold()
+    );
+
+    Ok(())
+}
+
+/// Prints the claims of a token, and checks its signature if the issuer is given
+fn inspect_token(token: &str, issuer: Option<&str>) -> Result<()> {
+    let decoded = SignedToken::decode(token)?;
+    let claims = &decoded.claims;
+
+    println!("Cohort: {}", claims.index);
+    println!(
+        "From: {}",
+        DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(claims.from))
+    );
+    println!(
+        "To: {}",
+        DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(claims.to))
+    );
+    println!("Id: {}", claims.id);
+    match (&decoded.signature, issuer) {
+        (None, _) => println!("Signature: none, the token must be listed in the cohort files"),
+        (Some(_), None) => println!("Signature: present, not verified"),
+        (Some(_), Some(issuer)) if decoded.verify(issuer) => println!("{}", "Signature: valid".green().bold()),
+        (Some(_), Some(_)) => {
+            eprintln!("{}", "Signature: not from the given issuer".red().bold());
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the given tokens from the cohort files
+fn revoke_tokens(files: CohortFiles, revoked: Vec<String>) -> Result<()> {
+    let mut cohorts = tokens::read_cohort_files(&files.dir, &files.prefix)?;
+    let not_found = tokens::revoke(&mut cohorts, &revoked)?;
+    tokens::write_cohort_files(&files.dir, &files.prefix, &cohorts)?;
+
+    for token in &not_found {
+        eprintln!("{}", format!("Token {} not found", token).yellow());
+    }
+    println!(
+        "{}",
+        format!("Revoked {} tokens", revoked.len() - not_found.len())
+            .green()
+            .bold()
+    );
+
+    Ok(())
+}
+
+/// Checks the cohort files and writes the zip archive to upload with update-cohorts
+fn pack_tokens(files: CohortFiles, output: &Path) -> Result<()> {
+    let cohorts = tokens::read_cohort_files(&files.dir, &files.prefix)?;
+
+    let issues = tokens::check(&cohorts);
+    if !issues.is_empty() {
+        for issue in &issues {
+            eprintln!("{}", issue.red());
+        }
+        eprintln!(
+            "{}",
+            format!("Found {} issues in the cohort files, nothing was packed", issues.len())
+                .red()
+                .bold()
+        );
+        process::exit(1);
+    }
+
+    fs::write(output, tokens::pack(&files.prefix, &cohorts)?)?;
+    println!(
+        "{}",
+        format!("Packed {} cohorts in {}", cohorts.len(), output.display())
+            .green()
+            .bold()
+    );
+
+    Ok(())
+}
+
 enum Branch {
     AnotherMachine,
     Default(bool),
@@ -636,7 +936,7 @@ enum Branch {
 
 /// Performs the entire contribution cycle
 #[inline(always)]
-async fn contribution_prelude(url: CoordinatorUrl, token: String, branch: Branch) {
+async fn contribution_prelude(url: CoordinatorUrl, token: String, branch: Branch, direct_transfer: bool) {
     // Check the token info
     let decoded_bytes = bs58::decode(token.clone()).into_vec();
     if let Ok(token_bytes) = decoded_bytes {
@@ -717,6 +1017,7 @@ async fn contribution_prelude(url: CoordinatorUrl, token: String, branch: Branch
         Arc::new(keypair),
         token,
         contrib_info,
+        direct_transfer,
     )
     .await;
 }
@@ -729,13 +1030,24 @@ async fn main() {
     match opt {
         CeremonyOpt::Contribute(branch) => {
             match branch {
-                phase2_cli::Branches::AnotherMachine { request } => {
-                    contribution_prelude(request.url, request.token, Branch::AnotherMachine).await
-                }
-                phase2_cli::Branches::Default { request, custom_seed } => {
-                    contribution_prelude(request.url, request.token, Branch::Default(custom_seed)).await
+                phase2_cli::Branches::AnotherMachine {
+                    request,
+                    direct_transfer,
+                } => contribution_prelude(request.url, request.token, Branch::AnotherMachine, direct_transfer).await,
+                phase2_cli::Branches::Default {
+                    request,
+                    custom_seed,
+                    direct_transfer,
+                } => {
+                    contribution_prelude(
+                        request.url,
+                        request.token,
+                        Branch::Default(custom_seed),
+                        direct_transfer,
+                    )
+                    .await
                 }
-                phase2_cli::Branches::Offline { custom_seed } => {
+                phase2_cli::Branches::Offline { custom_seed, circuits } => {
                     if custom_seed {
                         println!(
                     "{}\n{}",
@@ -757,7 +1069,7 @@ async fn main() {
                         println!("{}", CUSTOM_SEED_MSG_NO.bright_cyan());
                     }
                     tokio::task::spawn_blocking(move || {
-                        compute_contribution(custom_seed, &challenge, OFFLINE_CONTRIBUTION_FILE_NAME)
+                        compute_contribution(custom_seed, &circuits, &challenge, OFFLINE_CONTRIBUTION_FILE_NAME)
                     })
                     .await
                     .unwrap()
@@ -766,7 +1078,8 @@ async fn main() {
             }
         }
         CeremonyOpt::CloseCeremony(url) => {
-            let keypair = tokio::task::spawn_blocking(|| io::keypair_from_mnemonic())
+            let mnemonic = url.mnemonic.clone();
+            let keypair = tokio::task::spawn_blocking(move || io::keypair_from_mnemonic(mnemonic))
                 .await
                 .unwrap()
                 .expect(&format!("{}", "Error while generating the keypair".red().bold()));
@@ -774,6 +1087,55 @@ async fn main() {
             let client = Client::new();
             close_ceremony(&client, &url.coordinator, &keypair).await;
         }
+        CeremonyOpt::ExportParams(Export {
+            contribution,
+            output,
+            circuits,
+        }) => {
+            let contribution = fs::read(&contribution).expect(&format!("{}", "Failed to read file".red().bold()));
+            let output_dir = output.clone();
+            let result =
+                tokio::task::spawn_blocking(move || commands::export_parameters(&contribution, &circuits, &output_dir))
+                    .await
+                    .expect(&format!("{}", "Error while exporting the parameters".red().bold()));
+
+            match result {
+                Ok(exported) => {
+                    for circuit in exported {
+                        println!("{}: {}", circuit.params_file, circuit.params_hash);
+                        println!("{}: {}", circuit.vk_file, circuit.vk_hash);
+                    }
+                    println!(
+                        "{}",
+                        format!("Exported the parameters to {}", output.display())
+                            .green()
+                            .bold()
+                    );
+                }
+                Err(e) => {
+                    eprintln!("{}", format!("Couldn't export the parameters: {}", e).red().bold());
+                    process::exit(1);
+                }
+            }
+        }
+        CeremonyOpt::Finalize(BeaconOpt {
+            url,
+            beacon,
+            iterations_exp,
+        }) => {
+            let mnemonic = url.mnemonic.clone();
+            let keypair = tokio::task::spawn_blocking(move || io::keypair_from_mnemonic(mnemonic))
+                .await
+                .unwrap()
+                .expect(&format!("{}", "Error while generating the keypair".red().bold()));
+
+            let client = Client::new();
+            let beacon = Beacon {
+                value: beacon,
+                iterations_exp,
+            };
+            finalize_ceremony(&client, &url.coordinator, &keypair, &beacon).await;
+        }
         CeremonyOpt::ExportKeypair(mnemonic_path) => {
             tokio::task::spawn_blocking(|| {
                 let content = fs::read_to_string(mnemonic_path.path).unwrap();
@@ -806,6 +1168,14 @@ async fn main() {
                 println!("{}", "Keypair was correctly generated in the \"keypair.toml\" file. You can copy its content to the \"wallet.toml\" file. Refer to the Namada documentation on how to generate a wallet.".bold().green());
             }).await.expect(&format!("{}", "Error while generating the keypair".red().bold()));
         }
+        CeremonyOpt::OperatorPubkey(mnemonic_path) => {
+            let keypair = tokio::task::spawn_blocking(|| io::keypair_from_mnemonic(mnemonic_path.path))
+                .await
+                .unwrap()
+                .expect(&format!("{}", "Error while generating the keypair".red().bold()));
+
+            println!("{}", keypair.pubkey());
+        }
         CeremonyOpt::GenerateAddresses(contributors) => {
             tokio::task::spawn_blocking(move || {
                 let content = fs::read(&contributors.path).unwrap();
@@ -841,7 +1211,8 @@ async fn main() {
             get_coordinator_state(&state.url.coordinator, secret).await;
         }
         CeremonyOpt::UpdateCohorts(url) => {
-            let keypair = tokio::task::spawn_blocking(|| io::keypair_from_mnemonic())
+            let mnemonic = url.mnemonic.clone();
+            let keypair = tokio::task::spawn_blocking(move || io::keypair_from_mnemonic(mnemonic))
                 .await
                 .unwrap()
                 .expect(&format!("{}", "Error while generating the keypair".red().bold()));
@@ -849,9 +1220,39 @@ async fn main() {
             let client = Client::new();
             update_cohorts(&client, &url.coordinator, &keypair).await;
         }
+        CeremonyOpt::Tokens(opt) => {
+            let result = tokio::task::spawn_blocking(move || match opt {
+                TokensOpt::Generate(generate) => generate_tokens(generate),
+                TokensOpt::Inspect { token, issuer } => inspect_token(&token, issuer.as_deref()),
+                TokensOpt::Revoke { files, tokens } => revoke_tokens(files, tokens),
+                TokensOpt::Pack { files, output } => pack_tokens(files, &output),
+            })
+            .await
+            .expect(&format!("{}", "Error while managing the tokens".red().bold()));
+
+            if let Err(e) = result {
+                eprintln!("{}", e.to_string().red().bold());
+                process::exit(1);
+            }
+        }
+        CeremonyOpt::Admin(opt) => {
+            let (url, action) = opt.into_action();
+            let mnemonic = url.mnemonic.clone();
+            let keypair = tokio::task::spawn_blocking(move || io::keypair_from_mnemonic(mnemonic))
+                .await
+                .unwrap()
+                .expect(&format!("{}", "Error while generating the keypair".red().bold()));
+
+            let client = Client::new();
+            match action {
+                Some(action) => admin_action(&client, &url.coordinator, &keypair, &action).await,
+                None => get_audit_log(&client, &url.coordinator, &keypair).await,
+            }
+        }
         #[cfg(debug_assertions)]
         CeremonyOpt::VerifyContributions(url) => {
-            let keypair = tokio::task::spawn_blocking(|| io::keypair_from_mnemonic())
+            let mnemonic = url.mnemonic.clone();
+            let keypair = tokio::task::spawn_blocking(move || io::keypair_from_mnemonic(mnemonic))
                 .await
                 .unwrap()
                 .expect(&format!("{}", "Error while generating the keypair".red().bold()));
@@ -861,7 +1262,8 @@ async fn main() {
         }
         #[cfg(debug_assertions)]
         CeremonyOpt::UpdateCoordinator(url) => {
-            let keypair = tokio::task::spawn_blocking(|| io::keypair_from_mnemonic())
+            let mnemonic = url.mnemonic.clone();
+            let keypair = tokio::task::spawn_blocking(move || io::keypair_from_mnemonic(mnemonic))
                 .await
                 .unwrap()
                 .expect(&format!("{}", "Error while generating the keypair".red().bold()));
@@ -892,5 +1294,69 @@ async fn main() {
                 println!("The contribution signature is not correct.")
             }
         }
+        CeremonyOpt::VerifyTranscript(Transcript { path, circuits }) => {
+            println!(
+                "Verifying the transcript in {}, this could take a while...",
+                path.display()
+            );
+            let result = tokio::task::spawn_blocking(move || {
+                let contributions = commands::verify_transcript(&path, &circuits)?;
+                let beacon = commands::verify_beacon(&path, &circuits)?;
+
+                Ok::<_, commands::TranscriptError>((contributions, beacon))
+            })
+            .await
+            .expect(&format!("{}", "Error while verifying the transcript".red().bold()));
+
+            match result {
+                Ok((contributions, beacon)) => {
+                    for contribution in contributions {
+                        println!(
+                            "Round {} contribution {}: 0x{}",
+                            contribution.round_height, contribution.contribution_id, contribution.contribution_hash
+                        );
+                    }
+                    match beacon {
+                        Some(record) => println!(
+                            "Beacon 0x{} with 2^{} iterations: 0x{}",
+                            record.beacon.value.trim_start_matches("0x"),
+                            record.beacon.iterations_exp,
+                            record.contribution_hash
+                        ),
+                        None => println!("{}", "The beacon hasn't been applied yet.".yellow()),
+                    }
+                    println!("{}", "The transcript is valid.".green().bold());
+                }
+                Err(e) => {
+                    eprintln!("{}", format!("The transcript is not valid: {}", e).red().bold());
+                    process::exit(1);
+                }
+            }
+        }
+        #[cfg(feature = "sqlite")]
+        CeremonyOpt::MigrateStorage => {
+            // The storage to migrate is the one of the coordinator configuration
+            let config_path = phase2_coordinator::config::path_from_env();
+            let environment = phase2_coordinator::config::Config::load(config_path.as_deref())
+                .and_then(|config| config.environment(&KeyPair::new()));
+            let environment = match environment {
+                Ok(environment) => environment,
+                Err(e) => {
+                    eprintln!("{}", format!("Invalid configuration: {}", e).red().bold());
+                    process::exit(1);
+                }
+            };
+
+            match phase2_coordinator::storage::Sqlite::migrate(&environment) {
+                Ok(migrated) => println!(
+                    "{}",
+                    format!("Migrated {} records to the database", migrated).green().bold()
+                ),
+                Err(e) => {
+                    eprintln!("{}", format!("Migration failed: {}", e).red().bold());
+                    process::exit(1);
+                }
+            }
+        }
     }
 }
@@ -6,10 +6,13 @@ use std::path::PathBuf;
 pub mod ascii_logo;
 pub mod keys;
 pub mod requests;
+pub mod tokens;
 
 use phase2_coordinator::{
-    objects::round::LockedLocators,
-    rest_utils::{ContributorStatus, PostChunkRequest},
+    audit_log::AdminAction,
+    environment::CircuitSet,
+    objects::{round::LockedLocators, Participant},
+    rest_utils::{ContributionStatus, ContributorStatus, PostChunkRequest},
 };
 
 use reqwest::Url;
@@ -26,6 +29,14 @@ pub struct CoordinatorUrl {
         parse(try_from_str)
     )]
     pub coordinator: Url,
+    #[structopt(
+        long,
+        help = "The mnemonic file of the key signing the requests, the one of the coordinator or of an operator",
+        default_value = "coordinator.mnemonic",
+        env = "NAMADA_OPERATOR_MNEMONIC",
+        parse(from_os_str)
+    )]
+    pub mnemonic: PathBuf,
 }
 
 /// Accepts both the ceremony token and the secret token for reserved endpoints
@@ -64,11 +75,23 @@ pub enum Branches {
     AnotherMachine {
         #[structopt(flatten)]
         request: RequestWithToken,
+        #[structopt(
+            long,
+            env = "NAMADA_DIRECT_TRANSFER",
+            help = "Download the challenge and upload the contribution directly from/to the Coordinator, without going through the object store"
+        )]
+        direct_transfer: bool,
     },
     #[structopt(about = "The default contribution path, executes both communication and computation on this machine")]
     Default {
         #[structopt(flatten)]
         request: RequestWithToken,
+        #[structopt(
+            long,
+            env = "NAMADA_DIRECT_TRANSFER",
+            help = "Download the challenge and upload the contribution directly from/to the Coordinator, without going through the object store"
+        )]
+        direct_transfer: bool,
         #[structopt(
             long,
             help = "Give a custom random seed (32 bytes / 64 characters in hexadecimal) for the ChaCha RNG"
@@ -84,6 +107,13 @@ pub enum Branches {
             help = "Give a custom random seed (32 bytes / 64 characters in hexadecimal) for the ChaCha RNG"
         )]
         custom_seed: bool,
+        #[structopt(
+            long,
+            env = "NAMADA_CIRCUITS",
+            default_value = "masp-full",
+            help = "The set of circuits of the ceremony, as shown by \"namada-ts contribute another-machine\" (masp-full, masp-test or a comma separated list of circuit names)"
+        )]
+        circuits: CircuitSet,
     },
 }
 
@@ -126,6 +156,252 @@ pub struct VerifySignatureContribution {
     pub parameter_path: Option<PathBuf>
 }
 
+#[derive(Debug, StructOpt)]
+pub struct Transcript {
+    #[structopt(
+        help = "The path to the transcript directory, containing the round_N folders",
+        required = true,
+        parse(try_from_str)
+    )]
+    pub path: PathBuf,
+    #[structopt(
+        long,
+        env = "NAMADA_CIRCUITS",
+        default_value = "masp-full",
+        help = "The set of circuits of the ceremony (masp-full, masp-test or a comma separated list of circuit names)"
+    )]
+    pub circuits: CircuitSet,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Export {
+    #[structopt(
+        help = "The path to the contribution file to export, e.g. the beacon.params file of the transcript",
+        required = true,
+        parse(try_from_str)
+    )]
+    pub contribution: PathBuf,
+    #[structopt(
+        long,
+        default_value = "params",
+        help = "The directory to write the parameters, verifying keys and checksums to",
+        parse(try_from_str)
+    )]
+    pub output: PathBuf,
+    #[structopt(
+        long,
+        env = "NAMADA_CIRCUITS",
+        default_value = "masp-full",
+        help = "The set of circuits of the ceremony (masp-full, masp-test or a comma separated list of circuit names)"
+    )]
+    pub circuits: CircuitSet,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct BeaconOpt {
+    #[structopt(flatten)]
+    pub url: CoordinatorUrl,
+    #[structopt(
+        long,
+        required = true,
+        help = "The public randomness of the beacon (32 bytes / 64 characters in hexadecimal), e.g. a block hash"
+    )]
+    pub beacon: String,
+    #[structopt(
+        long,
+        default_value = "10",
+        help = "The beacon is hashed 2^iterations-exp times with SHA256 to derive the seed of the final contribution"
+    )]
+    pub iterations_exp: u32,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CohortFiles {
+    #[structopt(
+        long,
+        default_value = "tokens",
+        help = "The directory of the cohort files",
+        parse(from_os_str)
+    )]
+    pub dir: PathBuf,
+    #[structopt(
+        long,
+        default_value = "namada_tokens_cohort",
+        env = "TOKENS_FILE_PREFIX",
+        help = "The prefix of the cohort files, as configured in the coordinator"
+    )]
+    pub prefix: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct GenerateTokens {
+    #[structopt(flatten)]
+    pub files: CohortFiles,
+    #[structopt(
+        long,
+        env = "CEREMONY_START_TIMESTAMP",
+        help = "The unix timestamp of the start of the ceremony"
+    )]
+    pub start: u64,
+    #[structopt(
+        long,
+        env = "NAMADA_COHORT_TIME",
+        default_value = "86400",
+        help = "The duration of each cohort, in seconds"
+    )]
+    pub cohort_duration: u64,
+    #[structopt(long, help = "The number of participants of each cohort")]
+    pub per_cohort: usize,
+    #[structopt(
+        long,
+        help = "The CSV file of the e-mails of the invited participants, in its first column and without header",
+        parse(from_os_str)
+    )]
+    pub emails: Option<PathBuf>,
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "The number of free for all cohorts following the ones of the invited participants"
+    )]
+    pub ffa_cohorts: usize,
+    #[structopt(
+        long,
+        default_value = "mailchimp",
+        help = "The directory to write the e-mails and tokens of the invited participants to",
+        parse(from_os_str)
+    )]
+    pub mailchimp_dir: PathBuf,
+    #[structopt(
+        long,
+        help = "The mnemonic file of the issuer signing the tokens, whose public key is tokens.issuer_pubkey in the coordinator configuration",
+        parse(from_os_str)
+    )]
+    pub issuer: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum TokensOpt {
+    #[structopt(about = "Generate the cohort files of the tokens of the ceremony")]
+    Generate(GenerateTokens),
+    #[structopt(about = "Print the cohort and the validity window of a token")]
+    Inspect {
+        #[structopt(help = "The token to inspect")]
+        token: String,
+        #[structopt(
+            long,
+            help = "The hex encoded public key of the issuer, to verify the signature of the token"
+        )]
+        issuer: Option<String>,
+    },
+    #[structopt(about = "Remove tokens from the cohort files")]
+    Revoke {
+        #[structopt(flatten)]
+        files: CohortFiles,
+        #[structopt(help = "The tokens to revoke", required = true)]
+        tokens: Vec<String>,
+    },
+    #[structopt(
+        about = "Check the cohort files and pack them in the zip archive uploaded by \"namada-ts update-cohorts\""
+    )]
+    Pack {
+        #[structopt(flatten)]
+        files: CohortFiles,
+        #[structopt(
+            long,
+            default_value = "tokens.zip",
+            help = "The path of the zip archive",
+            parse(from_os_str)
+        )]
+        output: PathBuf,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ParticipantAction {
+    #[structopt(help = "The public key of the participant", required = true)]
+    pub pubkey: String,
+    #[structopt(long, help = "The participant is a verifier rather than a contributor")]
+    pub verifier: bool,
+    #[structopt(flatten)]
+    pub url: CoordinatorUrl,
+}
+
+impl ParticipantAction {
+    pub fn participant(&self) -> Participant {
+        if self.verifier {
+            Participant::new_verifier(&self.pubkey)
+        } else {
+            Participant::new_contributor(&self.pubkey)
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AdminOpt {
+    #[structopt(about = "Ban a participant from the ceremony")]
+    Ban {
+        #[structopt(long, required = true, help = "The reason of the ban")]
+        reason: String,
+        #[structopt(flatten)]
+        target: ParticipantAction,
+    },
+    #[structopt(about = "Lift the ban of a participant")]
+    Unban(ParticipantAction),
+    #[structopt(about = "Drop a participant from the current round")]
+    Drop(ParticipantAction),
+    #[structopt(about = "Remove a contributor from the queue")]
+    RemoveFromQueue(ParticipantAction),
+    #[structopt(about = "Move a contributor to a position of the queue, starting from 0")]
+    MoveInQueue {
+        #[structopt(help = "The public key of the contributor", required = true)]
+        pubkey: String,
+        #[structopt(help = "The position in the queue", required = true)]
+        position: usize,
+        #[structopt(flatten)]
+        url: CoordinatorUrl,
+    },
+    #[structopt(about = "Lock the ceremony, no new round starts until the lock is disabled")]
+    EnableManualLock(CoordinatorUrl),
+    #[structopt(about = "Unlock the ceremony")]
+    DisableManualLock(CoordinatorUrl),
+    #[structopt(about = "Reset the current round, discarding its contributions")]
+    ResetRound(CoordinatorUrl),
+    #[structopt(about = "Print the audit log of the admin actions")]
+    AuditLog(CoordinatorUrl),
+}
+
+impl AdminOpt {
+    /// Returns the url of the coordinator and the action to request, none for the audit log.
+    pub fn into_action(self) -> (CoordinatorUrl, Option<AdminAction>) {
+        match self {
+            Self::Ban { reason, target } => {
+                let participant = target.participant();
+                (target.url, Some(AdminAction::Ban { participant, reason }))
+            }
+            Self::Unban(target) => {
+                let participant = target.participant();
+                (target.url, Some(AdminAction::Unban { participant }))
+            }
+            Self::Drop(target) => {
+                let participant = target.participant();
+                (target.url, Some(AdminAction::Drop { participant }))
+            }
+            Self::RemoveFromQueue(target) => {
+                let participant = target.participant();
+                (target.url, Some(AdminAction::RemoveFromQueue { participant }))
+            }
+            Self::MoveInQueue { pubkey, position, url } => {
+                let participant = Participant::new_contributor(&pubkey);
+                (url, Some(AdminAction::MoveInQueue { participant, position }))
+            }
+            Self::EnableManualLock(url) => (url, Some(AdminAction::EnableManualLock)),
+            Self::DisableManualLock(url) => (url, Some(AdminAction::DisableManualLock)),
+            Self::ResetRound(url) => (url, Some(AdminAction::ResetRound)),
+            Self::AuditLog(url) => (url, None),
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "namada-ts", about = "Namada CLI for trusted setup.")]
 pub enum CeremonyOpt {
@@ -133,8 +409,14 @@ pub enum CeremonyOpt {
     Contribute(Branches),
     #[structopt(about = "Stop the coordinator and close the ceremony")]
     CloseCeremony(CoordinatorUrl),
+    #[structopt(about = "Export the parameters of each circuit in the format loaded by the Namada node")]
+    ExportParams(Export),
+    #[structopt(about = "Apply a randomness beacon to the last contribution to finalize the ceremony")]
+    Finalize(BeaconOpt),
     #[structopt(about = "Generate a Namada keypair from a mnemonic")]
     ExportKeypair(MnemonicPath),
+    #[structopt(about = "Print the public key of a mnemonic, to be declared in the operator keys of the coordinator")]
+    OperatorPubkey(MnemonicPath),
     #[structopt(about = "Generate the list of addresses of the contributors")]
     GenerateAddresses(Contributors),
     #[cfg(debug_assertions)]
@@ -147,9 +429,22 @@ pub enum CeremonyOpt {
     VerifyContributions(CoordinatorUrl),
     #[structopt(about = "Update the cohorts' tokens")]
     UpdateCohorts(CoordinatorUrl),
+    #[structopt(about = "Generate, inspect, revoke and pack the cohorts' tokens")]
+    Tokens(TokensOpt),
+    #[structopt(about = "Moderate the participants and control the rounds, as an admin of the coordinator")]
+    Admin(AdminOpt),
     #[cfg(debug_assertions)]
     #[structopt(about = "Update manually the coordinator")]
     UpdateCoordinator(CoordinatorUrl),
     #[structopt(about = "Verify a contribution")]
     VerifyContribution(VerifySignatureContribution),
+    #[structopt(
+        about = "Verify the whole transcript of the ceremony, from the initial parameters to the last contribution"
+    )]
+    VerifyTranscript(Transcript),
+    #[cfg(feature = "sqlite")]
+    #[structopt(
+        about = "Migrate the JSON records of the coordinator in the ./transcript directory to the SQLite storage"
+    )]
+    MigrateStorage,
 }
@@ -3,13 +3,16 @@
 use bytes::Bytes;
 use futures_util::Stream;
 use phase2_coordinator::{
+    audit_log::{AdminAction, AuditEntry},
     authentication::{KeyPair, Production, Signature},
+    commands::{Beacon, BeaconRecord},
+    environment::CircuitSet,
     objects::ContributionInfo,
     rest_utils::{
-        RequestContent, SignatureHeaders, ACCESS_SECRET_HEADER, BODY_DIGEST_HEADER, CONTENT_LENGTH_HEADER,
-        PUBKEY_HEADER, SIGNATURE_HEADER,
+        BanRequest, MoveInQueueRequest, ParticipantRequest, RequestContent, SignatureHeaders, ACCESS_SECRET_HEADER,
+        BODY_DIGEST_HEADER, CONTENT_LENGTH_HEADER, NONCE_HEADER, PUBKEY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
     },
-    ContributionFileSignature,
+    ContributionFileSignature, Participant,
 };
 use reqwest::{
     header::{HeaderMap, HeaderValue, CONTENT_TYPE},
@@ -21,7 +24,7 @@ use std::convert::{TryFrom, TryInto};
 use thiserror::Error;
 use tracing::debug;
 
-use crate::{ContributorStatus, LockedLocators, PostChunkRequest};
+use crate::{ContributionStatus, ContributorStatus, LockedLocators, PostChunkRequest};
 
 /// Error returned from a request.
 #[derive(Debug, Error)]
@@ -60,6 +63,8 @@ impl TryFrom<SignatureHeaders<'_>> for HeaderWrap {
     fn try_from(value: SignatureHeaders) -> std::result::Result<Self, Self::Error> {
         let mut result = HeaderMap::new();
         result.insert(PUBKEY_HEADER, HeaderValue::from_str(value.pubkey)?);
+        result.insert(TIMESTAMP_HEADER, value.timestamp.into());
+        result.insert(NONCE_HEADER, HeaderValue::from_str(&value.nonce)?);
 
         if let Some(sig) = value.signature {
             result.insert(SIGNATURE_HEADER, HeaderValue::from_str(&sig)?);
@@ -96,9 +101,11 @@ impl Sign for SignatureHeaders<'_> {
 enum Request<'a, T: Serialize> {
     Get,
     Post(Option<&'a T>),
+    Upload(&'a [u8]),
 }
 
-/// Submit a signed json encoded request to the provided enpoint
+/// Submit a signed json encoded request to the provided enpoint. The request is signed again at every attempt, since
+/// the coordinator rejects the replayed signatures.
 async fn submit_request<T: Serialize>(
     client: &Client,
     coordinator_address: &Url,
@@ -115,6 +122,16 @@ where
         .map_err(|_| RequestError::AddressParseError)?;
     let mut content: Option<RequestContent> = None;
 
+    // The path signed with the request, as received by the coordinator
+    let path = match address.query() {
+        Some(query) => format!("{}?{}", address.path(), query),
+        None => address.path().to_owned(),
+    };
+    let method = match request {
+        Request::Get => "GET",
+        Request::Post(_) | Request::Upload(_) => "POST",
+    };
+
     let mut req = match request {
         Request::Get => client.get(address),
         Request::Post(body) => match body {
@@ -133,23 +150,36 @@ where
             }
             None => client.post(address),
         },
+        Request::Upload(bytes) => {
+            let mut hasher = Sha256::new();
+            hasher.update(bytes);
+            let digest = hasher.finalize();
+
+            content = Some(RequestContent::new(bytes.len(), digest));
+            client
+                .post(address)
+                .body(bytes.to_vec())
+                .header(CONTENT_TYPE, HeaderValue::from_static("application/octet-stream"))
+        }
     };
 
-    // Generate signatures headers if required
-    if let Some(kp) = keypair {
-        let mut headers = SignatureHeaders::new(kp.pubkey(), content, None);
-        headers.try_sign(kp.sigkey())?;
-        let header_map: HeaderWrap = headers.try_into()?;
-        req = req.headers(header_map.into());
-    }
-
     // Add custom headers if required
     if let Some(header_map) = custom_headers {
         req = req.headers(header_map);
     }
 
     loop {
-        let response = req.try_clone().expect("Expected request not stream").send().await?;
+        let mut attempt = req.try_clone().expect("Expected request not stream");
+
+        // Generate signatures headers if required
+        if let Some(kp) = keypair {
+            let mut headers = SignatureHeaders::new(kp.pubkey(), method, path.as_str().into(), content.clone(), None);
+            headers.try_sign(kp.sigkey())?;
+            let header_map: HeaderWrap = headers.try_into()?;
+            attempt = attempt.headers(header_map.into());
+        }
+
+        let response = attempt.send().await?;
 
         match decapsulate_response(response).await {
             Ok(response) => return Ok(response),
@@ -245,6 +275,27 @@ pub async fn get_challenge(
     Ok((decapsulate_response(response).await?.bytes_stream(), stream_len))
 }
 
+/// Send a request to the [Coordinator](`phase2-coordinator::Coordinator`) to download the next challenge directly, without going through the object store.
+pub async fn get_challenge_direct(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    round_height: u64,
+) -> Result<(impl Stream<Item = reqwest::Result<Bytes>>, u64)> {
+    let response = submit_request::<()>(
+        client,
+        coordinator_address,
+        &format!("contributor/challenge/{}", round_height),
+        Some(keypair),
+        None,
+        Request::Get,
+    )
+    .await?;
+    let stream_len = response.content_length().unwrap_or_default();
+
+    Ok((response.bytes_stream(), stream_len))
+}
+
 /// Send a request to the [Coordinator](`phase2-coordinator::Coordinator`) to get the target Strings where to upload the contribution and its signature.
 pub async fn get_contribution_url(
     client: &Client,
@@ -301,6 +352,69 @@ where
     Ok(())
 }
 
+/// Get the number of bytes of the contribution already uploaded directly to the [Coordinator](`phase2-coordinator::Coordinator`).
+pub async fn get_upload_offset(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    round_height: u64,
+) -> Result<u64> {
+    let response = submit_request::<()>(
+        client,
+        coordinator_address,
+        &format!("contributor/upload/{}", round_height),
+        Some(keypair),
+        None,
+        Request::Get,
+    )
+    .await?;
+
+    Ok(response.json::<u64>().await?)
+}
+
+/// Upload a part of the contribution directly to the [Coordinator](`phase2-coordinator::Coordinator`), starting at the given offset. Returns the number of bytes received so far by the Coordinator.
+pub async fn post_contribution_part(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    round_height: u64,
+    offset: u64,
+    part: &[u8],
+) -> Result<u64> {
+    let response = submit_request::<()>(
+        client,
+        coordinator_address,
+        &format!("contributor/upload/{}/{}", round_height, offset),
+        Some(keypair),
+        None,
+        Request::Upload(part),
+    )
+    .await?;
+
+    Ok(response.json::<u64>().await?)
+}
+
+/// Upload the signature of the contribution directly to the [Coordinator](`phase2-coordinator::Coordinator`).
+pub async fn post_contribution_signature(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    round_height: u64,
+    contribution_signature: &ContributionFileSignature,
+) -> Result<()> {
+    submit_request::<ContributionFileSignature>(
+        client,
+        coordinator_address,
+        &format!("contributor/upload_signature/{}", round_height),
+        Some(keypair),
+        None,
+        Request::Post(Some(contribution_signature)),
+    )
+    .await?;
+
+    Ok(())
+}
+
 /// Send a request to notify the [Coordinator](`phase2-coordinator::Coordinator`) of an uploaded contribution.
 pub async fn post_contribute_chunk(
     client: &Client,
@@ -394,6 +508,25 @@ pub async fn get_contributor_queue_status(
     Ok(response.json::<ContributorStatus>().await?)
 }
 
+/// Get the status of the contribution of the contributor.
+pub async fn get_contribution_status(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+) -> Result<ContributionStatus> {
+    let response = submit_request::<()>(
+        client,
+        coordinator_address,
+        "contributor/contribution_status",
+        Some(keypair),
+        None,
+        Request::Get,
+    )
+    .await?;
+
+    Ok(response.json::<ContributionStatus>().await?)
+}
+
 /// Send [`ContributionInfo`] to the Coordinator.
 pub async fn post_contribution_info(
     client: &Client,
@@ -441,6 +574,13 @@ pub async fn ping_coordinator(client: &Client, coordinator_address: &Url) -> Res
     Ok(())
 }
 
+/// Retrieve the set of circuits the ceremony is run on
+pub async fn get_circuits(client: &Client, coordinator_address: &Url) -> Result<CircuitSet> {
+    let response = submit_request::<()>(client, coordinator_address, "/circuits", None, None, Request::Get).await?;
+
+    Ok(response.json::<CircuitSet>().await?)
+}
+
 /// Retrieve the list of contributions, json encoded
 #[cfg(debug_assertions)]
 pub async fn get_contributions_info(coordinator_address: &Url) -> Result<Vec<u8>> {
@@ -497,3 +637,156 @@ pub async fn post_update_cohorts(
 
     Ok(())
 }
+
+/// Move the contributor of the given public key to the given position of the queue, starting from 0.
+pub async fn post_move_in_queue(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    pubkey: &str,
+    position: usize,
+) -> Result<()> {
+    let request = MoveInQueueRequest {
+        pubkey: pubkey.to_string(),
+        position,
+    };
+    submit_request::<MoveInQueueRequest>(
+        client,
+        coordinator_address,
+        "/queue/move",
+        Some(keypair),
+        None,
+        Request::Post(Some(&request)),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Post a request without body to the given admin endpoint.
+async fn post_admin_path(client: &Client, coordinator_address: &Url, keypair: &KeyPair, path: &str) -> Result<()> {
+    submit_request::<()>(
+        client,
+        coordinator_address,
+        path,
+        Some(keypair),
+        None,
+        Request::Post(None),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Post a request on a participant to the given admin endpoint.
+async fn post_participant_action(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    path: &str,
+    participant: &Participant,
+) -> Result<()> {
+    let request = ParticipantRequest {
+        participant: participant.clone(),
+    };
+    submit_request::<ParticipantRequest>(
+        client,
+        coordinator_address,
+        path,
+        Some(keypair),
+        None,
+        Request::Post(Some(&request)),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Request the given admin action to the [Coordinator](`phase2-coordinator::Coordinator`).
+pub async fn post_admin_action(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    action: &AdminAction,
+) -> Result<()> {
+    match action {
+        AdminAction::Ban { participant, reason } => {
+            let request = BanRequest {
+                participant: participant.clone(),
+                reason: reason.clone(),
+            };
+            submit_request::<BanRequest>(
+                client,
+                coordinator_address,
+                "/admin/ban",
+                Some(keypair),
+                None,
+                Request::Post(Some(&request)),
+            )
+            .await?;
+
+            Ok(())
+        }
+        AdminAction::Unban { participant } => {
+            post_participant_action(client, coordinator_address, keypair, "/admin/unban", participant).await
+        }
+        AdminAction::Drop { participant } => {
+            post_participant_action(client, coordinator_address, keypair, "/admin/drop", participant).await
+        }
+        AdminAction::RemoveFromQueue { participant } => {
+            post_participant_action(
+                client,
+                coordinator_address,
+                keypair,
+                "/admin/remove_from_queue",
+                participant,
+            )
+            .await
+        }
+        AdminAction::MoveInQueue { participant, position } => {
+            post_move_in_queue(client, coordinator_address, keypair, &participant.address(), *position).await
+        }
+        AdminAction::EnableManualLock => {
+            post_admin_path(client, coordinator_address, keypair, "/admin/enable_manual_lock").await
+        }
+        AdminAction::DisableManualLock => {
+            post_admin_path(client, coordinator_address, keypair, "/admin/disable_manual_lock").await
+        }
+        AdminAction::ResetRound => post_admin_path(client, coordinator_address, keypair, "/admin/reset_round").await,
+    }
+}
+
+/// Get the entries of the audit log of the admin actions.
+pub async fn get_audit_log(client: &Client, coordinator_address: &Url, keypair: &KeyPair) -> Result<Vec<AuditEntry>> {
+    let response = submit_request::<()>(
+        client,
+        coordinator_address,
+        "/admin/audit_log",
+        Some(keypair),
+        None,
+        Request::Get,
+    )
+    .await?;
+
+    Ok(response.json::<Vec<AuditEntry>>().await?)
+}
+
+/// Finalize the ceremony by applying the given randomness beacon to the last contribution.
+pub async fn post_finalize_ceremony(
+    client: &Client,
+    coordinator_address: &Url,
+    keypair: &KeyPair,
+    beacon: &Beacon,
+) -> Result<BeaconRecord> {
+    let response = submit_request::<Beacon>(
+        client,
+        coordinator_address,
+        "/finalize",
+        Some(keypair),
+        None,
+        Request::Post(Some(beacon)),
+    )
+    .await?;
+
+    Ok(response.json::<BeaconRecord>().await?)
+}
@@ -0,0 +1,301 @@
+//! Generation and management of the cohort files of the contribution tokens, in the format loaded by the coordinator
+//! (see [`phase2_coordinator::tokens`]), replacing the `generate_tokens.py` script.
+
+use phase2_coordinator::{
+    authentication::KeyPair,
+    rest_utils::{SIGNED_TOKEN_REGEX, TOKEN_REGEX},
+    tokens::{self, SignedToken, TokenClaims, TokenError},
+};
+use rand::{rngs::OsRng, RngCore};
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use zip::{write::FileOptions, ZipWriter};
+
+/// Length in bytes of the random identifiers of the tokens, hex encoded.
+const ID_LENGTH: usize = 12;
+/// Prefix of the files of the e-mails and tokens of the invited cohorts, read by `cohorts_schedule_emails.py`.
+pub const MAILCHIMP_FILE_PREFIX: &str = "namada_cohort";
+
+#[derive(Debug, Error)]
+pub enum TokensError {
+    #[error("Error with {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("The output directory {0} is not empty")]
+    NonEmptyDirectory(PathBuf),
+    #[error("The number of participants per cohort must be positive")]
+    NoParticipants,
+    #[error("Token {0} is signed, it is accepted by the coordinator without being listed in the cohort files")]
+    SignedToken(String),
+    #[error("{0}")]
+    Token(#[from] TokenError),
+    #[error("Error while writing the zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+}
+
+type Result<T> = std::result::Result<T, TokensError>;
+
+/// The cohorts to generate the tokens of.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    /// The unix timestamp of the start of the first cohort.
+    pub start: u64,
+    /// The duration of each cohort, in seconds.
+    pub cohort_duration: u64,
+    /// The number of participants of each cohort.
+    pub per_cohort: usize,
+    /// The number of invited participants, who fill the first cohorts.
+    pub invited: usize,
+    /// The number of free for all cohorts following the invited ones.
+    pub ffa_cohorts: usize,
+}
+
+impl Schedule {
+    /// Returns the number of cohorts of the invited participants.
+    pub fn invited_cohorts(&self) -> usize {
+        (self.invited + self.per_cohort - 1) / self.per_cohort
+    }
+
+    /// Returns the claims of a new token of the given cohort, starting from 1.
+    fn claims(&self, cohort: usize) -> TokenClaims {
+        let from = self.start + self.cohort_duration * (cohort as u64 - 1);
+        let mut id = [0u8; ID_LENGTH];
+        OsRng.fill_bytes(&mut id);
+
+        TokenClaims {
+            from,
+            to: from + self.cohort_duration,
+            index: cohort as u64,
+            id: hex::encode(id),
+        }
+    }
+}
+
+///
+/// Generates the tokens of the given schedule: one per invited participant, followed by the free for all cohorts
+/// and a last empty cohort, which closes the ceremony. The tokens are signed if an issuer is given.
+///
+/// On an empty cohort size or a failure to sign the tokens, this function returns a `TokensError`.
+///
+pub fn generate(schedule: &Schedule, issuer: Option<&KeyPair>) -> Result<Vec<Vec<String>>> {
+    if schedule.per_cohort == 0 {
+        return Err(TokensError::NoParticipants);
+    }
+
+    let invited_cohorts = schedule.invited_cohorts();
+    let mut cohorts = Vec::with_capacity(invited_cohorts + schedule.ffa_cohorts + 1);
+    for cohort in 1..=invited_cohorts + schedule.ffa_cohorts {
+        let participants = match cohort <= invited_cohorts {
+            true => std::cmp::min(
+                schedule.per_cohort,
+                schedule.invited - (cohort - 1) * schedule.per_cohort,
+            ),
+            false => schedule.per_cohort,
+        };
+
+        let tokens = (0..participants)
+            .map(|_| {
+                let claims = schedule.claims(cohort);
+                let token = match issuer {
+                    Some(issuer) => SignedToken::sign(claims, issuer)?,
+                    None => SignedToken {
+                        claims,
+                        signature: None,
+                    },
+                };
+
+                Ok(token.encode())
+            })
+            .collect::<Result<Vec<String>>>()?;
+        cohorts.push(tokens);
+    }
+    cohorts.push(Vec::new());
+
+    Ok(cohorts)
+}
+
+///
+/// Returns the e-mails of the given CSV file, from its first column. The file has no header.
+///
+/// On failure to read the file, this function returns a `TokensError`.
+///
+pub fn read_emails(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).map_err(|e| TokensError::Io(path.to_path_buf(), e))?;
+
+    Ok(content
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .map(|email| email.trim().trim_matches('"').to_string())
+        .filter(|email| !email.is_empty())
+        .collect())
+}
+
+/// Returns the pairs of e-mail and token of each invited cohort, in the order they were generated. The tokens are
+/// zipped first, so that no e-mail is consumed past the end of a cohort.
+pub fn assign_emails(emails: &[String], cohorts: &[Vec<String>]) -> Vec<Vec<(String, String)>> {
+    let mut emails = emails.iter();
+
+    cohorts
+        .iter()
+        .map(|tokens| {
+            tokens
+                .iter()
+                .zip(emails.by_ref())
+                .map(|(t, e)| (e.clone(), t.clone()))
+                .collect()
+        })
+        .take_while(|cohort: &Vec<(String, String)>| !cohort.is_empty())
+        .collect()
+}
+
+///
+/// Creates the given directory, which must be empty if it exists.
+///
+/// On a non empty directory or a failure to create it, this function returns a `TokensError`.
+///
+pub fn create_output_dir(dir: &Path) -> Result<()> {
+    if let Ok(mut entries) = fs::read_dir(dir) {
+        if entries.next().is_some() {
+            return Err(TokensError::NonEmptyDirectory(dir.to_path_buf()));
+        }
+    }
+
+    fs::create_dir_all(dir).map_err(|e| TokensError::Io(dir.to_path_buf(), e))
+}
+
+///
+/// Writes each cohort to the file `{prefix}_{n}.json` of the given directory, starting from 1.
+///
+/// On failure to write the files, this function returns a `TokensError`.
+///
+pub fn write_cohort_files<T: serde::Serialize>(dir: &Path, prefix: &str, cohorts: &[T]) -> Result<()> {
+    for (i, cohort) in cohorts.iter().enumerate() {
+        let path = dir.join(tokens::cohort_file_name(prefix, i + 1));
+        let content = serde_json::to_vec(cohort).map_err(TokenError::from)?;
+        fs::write(&path, content).map_err(|e| TokensError::Io(path, e))?;
+    }
+
+    Ok(())
+}
+
+///
+/// Reads the cohort files of the given directory, which must only contain them.
+///
+/// On failure to read the files or on an invalid file, this function returns a `TokensError`.
+///
+pub fn read_cohort_files(dir: &Path, prefix: &str) -> Result<Vec<Vec<String>>> {
+    let entries = fs::read_dir(dir).map_err(|e| TokensError::Io(dir.to_path_buf(), e))?;
+    let mut files = HashMap::new();
+
+    for entry in entries {
+        let path = entry.map_err(|e| TokensError::Io(dir.to_path_buf(), e))?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let content = fs::read(&path).map_err(|e| TokensError::Io(path, e))?;
+        files.insert(file_name, content);
+    }
+
+    Ok(tokens::parse_cohort_files(prefix, &files)?)
+}
+
+///
+/// Returns the issues of the given cohorts which would make the coordinator reject their tokens: tokens not matching
+/// [`TOKEN_REGEX`], or [`SIGNED_TOKEN_REGEX`] for the signed ones, not decoding to their claims or claiming another
+/// cohort, and duplicates.
+///
+pub fn check(cohorts: &[Vec<String>]) -> Vec<String> {
+    let regex = Regex::new(TOKEN_REGEX).expect("The token regex should be valid");
+    let signed_regex = Regex::new(SIGNED_TOKEN_REGEX).expect("The signed token regex should be valid");
+    let mut issues = Vec::new();
+
+    for (i, tokens) in cohorts.iter().enumerate() {
+        let cohort = i + 1;
+        for token in tokens {
+            let regex = match is_signed(token) {
+                true => &signed_regex,
+                false => &regex,
+            };
+            if !regex.is_match(token) {
+                issues.push(format!(
+                    "Token {} of cohort {} is not a base58 token of the expected length",
+                    token, cohort
+                ));
+                continue;
+            }
+
+            match SignedToken::decode(token) {
+                Ok(decoded) if decoded.claims.index != cohort as u64 => issues.push(format!(
+                    "Token {} of cohort {} claims cohort {}",
+                    token, cohort, decoded.claims.index
+                )),
+                Ok(_) => (),
+                Err(e) => issues.push(format!("Token {} of cohort {} is not valid: {}", token, cohort, e)),
+            }
+        }
+    }
+
+    for (token, cohorts) in tokens::find_duplicates(cohorts) {
+        issues.push(format!(
+            "Token {} is listed more than once, in cohorts {:?}",
+            token, cohorts
+        ));
+    }
+
+    issues
+}
+
+/// Returns true if the given token carries a signature.
+fn is_signed(token: &str) -> bool {
+    matches!(SignedToken::decode(token), Ok(SignedToken { signature: Some(_), .. }))
+}
+
+///
+/// Removes the given tokens from the cohorts. Returns the tokens which were not found.
+///
+/// The signed tokens are accepted by the coordinator without being listed in the cohort files, so that removing
+/// them wouldn't revoke them: on a signed token, nothing is removed and this function returns a `TokensError`.
+///
+pub fn revoke<'t>(cohorts: &mut [Vec<String>], revoked: &'t [String]) -> Result<Vec<&'t str>> {
+    if let Some(token) = revoked.iter().find(|token| is_signed(token)) {
+        return Err(TokensError::SignedToken(token.clone()));
+    }
+
+    let mut not_found = Vec::new();
+
+    for token in revoked {
+        let mut found = false;
+        for tokens in cohorts.iter_mut() {
+            let len = tokens.len();
+            tokens.retain(|t| t != token);
+            found |= tokens.len() != len;
+        }
+
+        if !found {
+            not_found.push(token.as_str());
+        }
+    }
+
+    Ok(not_found)
+}
+
+///
+/// Returns the zip archive of the given cohorts, with the cohort files at its root, to be uploaded with
+/// `update-cohorts`.
+///
+/// On failure to write the archive, this function returns a `TokensError`.
+///
+pub fn pack(prefix: &str, cohorts: &[Vec<String>]) -> Result<Vec<u8>> {
+    let mut zip_writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for (i, tokens) in cohorts.iter().enumerate() {
+        zip_writer.start_file(tokens::cohort_file_name(prefix, i + 1), FileOptions::default())?;
+        let content = serde_json::to_vec(tokens).map_err(TokenError::from)?;
+        zip_writer.write_all(&content).map_err(zip::result::ZipError::from)?;
+    }
+
+    Ok(zip_writer.finish()?.into_inner())
+}
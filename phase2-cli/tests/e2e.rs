@@ -7,13 +7,18 @@
 use std::{io::Write, net::IpAddr, sync::Arc};
 
 use phase2_coordinator::{
+    audit_log::{AdminAction, AuditLog, SharedAuditLog},
     authentication::{KeyPair, Production, Signature},
-    commands::{Computation, RandomSource},
+    commands::{Beacon, Computation, RandomSource},
     coordinator_state::CoordinatorState,
-    environment::Testing,
+    environment::{CircuitSet, Testing},
+    object_store::SharedObjectStore,
     objects::{ContributionInfo, LockedLocators, TrimmedContributionInfo},
+    operators::{Operators, SharedOperators},
     rest,
-    rest_utils::{self, PostChunkRequest, TOKENS_ZIP_FILE},
+    rest_utils::{self, ContributionStatus, PostChunkRequest, TOKENS_ZIP_FILE},
+    s3::S3Ctx,
+    secret::{self, AccessSecret, SharedAccessSecret, StdoutSink},
     storage::{ContributionLocator, ContributionSignatureLocator, Object},
     testing::coordinator,
     ContributionFileSignature, ContributionState, Coordinator, Participant,
@@ -33,11 +38,16 @@ use async_stream::try_stream;
 use futures_util::StreamExt;
 use toml::Value;
 
-use phase2_cli::requests;
+use phase2_cli::{
+    requests::{self, RequestError},
+    tokens::{self, Schedule},
+};
 use reqwest::{Client, Url};
 use zip::write::FileOptions;
 
 const ROUND_HEIGHT: u64 = 1;
+/// The duration of each cohort, in seconds.
+const COHORT_TIME: u64 = 15;
 
 struct TestParticipant {
     _inner: Participant,
@@ -51,13 +61,27 @@ struct TestCtx {
     unknown_participant: TestParticipant,
     coordinator: TestParticipant,
     coordinator_url: String,
+    access_secret: String,
+    observer_keypair: KeyPair,
     // Keep TempDir in scope for some tests
     _tokens_tmp_dir: tempfile::TempDir,
+    _auth_tmp_dir: tempfile::TempDir,
+}
+
+/// Checks that the request was rejected by the guard of the endpoint, with a 401 rather than a server error.
+fn assert_unauthorized<T>(response: Result<T, RequestError>) {
+    match response {
+        Err(RequestError::Client(message)) => assert!(message.contains("is not allowed to access"), "{}", message),
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("The request should have been rejected"),
+    }
 }
 
 /// Launch the rocket server for testing with the proper configuration as a separate async Task.
 async fn test_prelude() -> (TestCtx, JoinHandle<Result<Rocket<Ignite>, Error>>) {
+    // The configuration is read from the env once for all the tests
     std::env::set_var("TOKEN_BLACKLIST", "true");
+    std::env::set_var("NAMADA_COHORT_TIME", COHORT_TIME.to_string());
     // NOTE: never set NAMADA_MPC_IP_BAN here because we cannot test the IPs here (cannot mock them)
 
     // Reset storage to prevent state conflicts between tests and initialize test environment
@@ -133,6 +157,23 @@ async fn test_prelude() -> (TestCtx, JoinHandle<Result<Rocket<Ignite>, Error>>)
     let (_, locked_locators) = coordinator.try_lock(&contributor1).unwrap();
 
     let coordinator: Arc<RwLock<Coordinator>> = Arc::new(RwLock::new(coordinator));
+    let object_store: SharedObjectStore = Arc::new(S3Ctx::new().unwrap());
+    let access_secret = secret::generate_secret();
+    let shared_secret: SharedAccessSecret = Arc::new(AccessSecret::new(access_secret.clone(), Box::new(StdoutSink)));
+    // A separate audit log and operator keys for each test, with an observer
+    let auth_tmp_dir = tempfile::tempdir().unwrap();
+    let audit_log: SharedAuditLog = Arc::new(AuditLog::new(auth_tmp_dir.path().join("audit_log.jsonl")));
+    let observer_keypair = KeyPair::new();
+    let operators_path = auth_tmp_dir.path().join("operators.toml");
+    std::fs::write(
+        &operators_path,
+        format!(
+            "[[operators]]\nname = \"bob\"\npubkey = \"{}\"\nrole = \"observer\"\n",
+            observer_keypair.pubkey()
+        ),
+    )
+    .unwrap();
+    let operators: SharedOperators = Arc::new(Operators::load(&operators_path).unwrap());
 
     let build = rocket::build()
         .mount(
@@ -141,22 +182,42 @@ async fn test_prelude() -> (TestCtx, JoinHandle<Result<Rocket<Ignite>, Error>>)
                 rest::join_queue,
                 rest::lock_chunk,
                 rest::contribute_chunk,
+                rest::get_challenge,
+                rest::get_upload_offset,
+                rest::post_contribution_part,
+                rest::post_contribution_signature,
                 rest::update_coordinator,
                 rest::heartbeat,
                 rest::stop_coordinator,
                 rest::verify_chunks,
                 rest::get_contributor_queue_status,
+                rest::get_contribution_status,
                 rest::post_contribution_info,
                 rest::get_contributions_info,
                 rest::get_healthcheck,
+                rest::get_circuits,
                 rest::get_contribution_url,
                 rest::get_challenge_url,
                 rest::get_coordinator_state,
                 rest::update_cohorts,
+                rest::move_in_queue,
+                rest::ban_participant,
+                rest::unban_participant,
+                rest::drop_participant,
+                rest::remove_from_queue,
+                rest::enable_manual_lock,
+                rest::disable_manual_lock,
+                rest::reset_round,
+                rest::get_audit_log,
+                rest::finalize_ceremony,
                 rest::post_attestation
             ],
         )
         .manage(coordinator)
+        .manage(object_store)
+        .manage(shared_secret)
+        .manage(operators)
+        .manage(audit_log)
         .register(
             "/",
             catchers![
@@ -197,7 +258,10 @@ async fn test_prelude() -> (TestCtx, JoinHandle<Result<Rocket<Ignite>, Error>>)
         unknown_participant,
         coordinator: coord_verifier,
         coordinator_url,
+        access_secret,
+        observer_keypair,
         _tokens_tmp_dir: tmp_dir,
+        _auth_tmp_dir: auth_tmp_dir,
     };
 
     (ctx, handle)
@@ -214,7 +278,7 @@ async fn stop_coordinator() {
     // Wrong, request from non-coordinator participant
     let url = Url::parse(&ctx.coordinator_url).unwrap();
     let response = requests::get_stop_coordinator(&client, &url, &ctx.contributors[0].keypair).await;
-    assert!(response.is_err());
+    assert_unauthorized(response);
 
     // Shut the server down
     let response = requests::get_stop_coordinator(&client, &url, &ctx.coordinator.keypair).await;
@@ -273,7 +337,7 @@ async fn update_cohorts() {
     let url = Url::parse(&ctx.coordinator_url).unwrap();
     let response =
         requests::post_update_cohorts(&client, &url, &ctx.contributors[0].keypair, &new_invalid_tokens).await;
-    assert!(response.is_err());
+    assert_unauthorized(response);
     assert!(std::fs::metadata(TOKENS_ZIP_FILE).is_err());
 
     // Wrong new tokens
@@ -295,10 +359,62 @@ async fn update_cohorts() {
     handle.abort()
 }
 
+#[tokio::test]
+async fn update_cohorts_packed_tokens() {
+    let client = Client::new();
+    // Spawn the server and get the test context
+    let (ctx, handle) = test_prelude().await;
+    // Wait for server startup
+    time::sleep(Duration::from_secs(1)).await;
+    std::fs::remove_file(TOKENS_ZIP_FILE).ok();
+
+    // Generate the next cohorts, keeping the tokens of the current one
+    let schedule = Schedule {
+        start: 1660000000,
+        cohort_duration: 86400,
+        per_cohort: 2,
+        invited: 3,
+        ffa_cohorts: 1,
+    };
+    let mut cohorts = tokens::generate(&schedule, None).unwrap();
+    assert_eq!(cohorts.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 1, 2, 0]);
+    assert!(tokens::check(&cohorts).is_empty());
+    cohorts[0] = tokens::read_cohort_files(ctx._tokens_tmp_dir.path(), "namada_tokens_cohort")
+        .unwrap()
+        .remove(0);
+
+    // A token listed twice is reported
+    let mut duplicated = cohorts.clone();
+    duplicated[2].push(cohorts[1][0].clone());
+    assert_eq!(tokens::check(&duplicated).len(), 1);
+
+    // Cohort files with another prefix are rejected
+    let url = Url::parse(&ctx.coordinator_url).unwrap();
+    let wrong_prefix = tokens::pack("namada_cohort", &cohorts).unwrap();
+    let response = requests::post_update_cohorts(&client, &url, &ctx.coordinator.keypair, &wrong_prefix).await;
+    assert!(response.is_err());
+    assert!(std::fs::metadata(TOKENS_ZIP_FILE).is_err());
+
+    let revoked = vec![cohorts[2][0].clone()];
+    assert!(tokens::revoke(&mut cohorts, &revoked).unwrap().is_empty());
+
+    // The signed tokens can't be revoked from the cohort files
+    let mut signed = tokens::generate(&schedule, Some(&KeyPair::new())).unwrap();
+    assert!(tokens::check(&signed).is_empty());
+    let revoked = vec![signed[0][0].clone()];
+    assert!(tokens::revoke(&mut signed, &revoked).is_err());
+    assert_eq!(signed[0].len(), 2);
+    let packed = tokens::pack("namada_tokens_cohort", &cohorts).unwrap();
+    let response = requests::post_update_cohorts(&client, &url, &ctx.coordinator.keypair, &packed).await;
+    assert!(response.is_ok());
+    assert!(std::fs::metadata(TOKENS_ZIP_FILE).is_ok());
+
+    // Drop the server
+    handle.abort()
+}
+
 #[tokio::test]
 async fn get_status() {
-    let access_token = "test-access_token";
-    std::env::set_var("ACCESS_SECRET", access_token);
     // Spawn the server and get the test context
     let (ctx, handle) = test_prelude().await;
     // Wait for server startup
@@ -306,7 +422,7 @@ async fn get_status() {
 
     // Retrieve coordinator.json file with valid token
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    let response = requests::get_coordinator_state(&url, access_token).await;
+    let response = requests::get_coordinator_state(&url, &ctx.access_secret).await;
     assert!(response.is_ok());
 
     // Check deserialization
@@ -387,9 +503,7 @@ async fn update_coordinator() {
 
     // Wrong, request from non-coordinator
     let url = Url::parse(&ctx.coordinator_url).unwrap();
-    assert!(requests::get_update(&client, &url, &ctx.contributors[0].keypair)
-        .await
-        .is_err());
+    assert_unauthorized(requests::get_update(&client, &url, &ctx.contributors[0].keypair).await);
 
     // Ok
     requests::get_update(&client, &url, &ctx.coordinator.keypair)
@@ -473,6 +587,155 @@ async fn join_queue() {
     handle.abort()
 }
 
+#[tokio::test]
+async fn move_in_queue() {
+    let client = Client::new();
+    // Spawn the server and get the test context
+    let (ctx, handle) = test_prelude().await;
+    // Wait for server startup
+    time::sleep(Duration::from_secs(1)).await;
+
+    let url = Url::parse(&ctx.coordinator_url).unwrap();
+    requests::post_join_queue(
+        &client,
+        &url,
+        &ctx.unknown_participant.keypair,
+        &String::from("9nFeNpukSn1eVwNc2vkfP7sQsLG3oS7623phb2Zzc23GAdXjuby4XAbwbWbx1uNaYrZorVLio4ZSt3u95sgi4fsS8hiZ3XkEttBF6q4461dGpoWv7ek"),
+    )
+    .await
+    .unwrap();
+    requests::post_join_queue(
+        &client,
+        &url,
+        &ctx.contributors[1].keypair,
+        &String::from("9nFeNpukSn1eVwNc2vkfP8SP4HrxTh9F86CY5pNWw8RF3jZa91q2i3yvE7ugpn9w2RzoZBZrdskgckmvJuVKq6ZWxfV8TepZYFd9SeARGHexi7tGGV2"),
+    )
+    .await
+    .unwrap();
+    let pubkey = ctx.contributors[1].keypair.pubkey();
+
+    // Wrong request, from a contributor
+    assert_unauthorized(requests::post_move_in_queue(&client, &url, &ctx.contributors[1].keypair, pubkey, 0).await);
+
+    // Wrong request, participant not in the queue
+    assert!(requests::post_move_in_queue(
+        &client,
+        &url,
+        &ctx.coordinator.keypair,
+        ctx.contributors[0].keypair.pubkey(),
+        0
+    )
+    .await
+    .is_err());
+
+    // Ok request
+    requests::post_move_in_queue(&client, &url, &ctx.coordinator.keypair, pubkey, 0)
+        .await
+        .unwrap();
+
+    // Drop the server
+    handle.abort()
+}
+
+/// Test the admin actions and their audit log.
+#[tokio::test]
+async fn admin_actions() {
+    let client = Client::new();
+    // Spawn the server and get the test context
+    let (ctx, handle) = test_prelude().await;
+    // Wait for server startup
+    time::sleep(Duration::from_secs(1)).await;
+
+    let url = Url::parse(&ctx.coordinator_url).unwrap();
+    requests::post_join_queue(
+        &client,
+        &url,
+        &ctx.contributors[1].keypair,
+        &String::from("9nFeNpukSn1eVwNc2vkfP8SP4HrxTh9F86CY5pNWw8RF3jZa91q2i3yvE7ugpn9w2RzoZBZrdskgckmvJuVKq6ZWxfV8TepZYFd9SeARGHexi7tGGV2"),
+    )
+    .await
+    .unwrap();
+    let ban = AdminAction::Ban {
+        participant: Participant::new_contributor(ctx.contributors[1].keypair.pubkey()),
+        reason: String::from("Spam"),
+    };
+
+    // Wrong requests, from a contributor and from an observer
+    assert_unauthorized(requests::post_admin_action(&client, &url, &ctx.contributors[0].keypair, &ban).await);
+    assert_unauthorized(requests::get_audit_log(&client, &url, &ctx.contributors[0].keypair).await);
+    assert_unauthorized(requests::post_admin_action(&client, &url, &ctx.observer_keypair, &ban).await);
+
+    // Wrong request, participant not in the queue
+    let remove = AdminAction::RemoveFromQueue {
+        participant: Participant::new_contributor(ctx.unknown_participant.keypair.pubkey()),
+    };
+    assert!(
+        requests::post_admin_action(&client, &url, &ctx.coordinator.keypair, &remove)
+            .await
+            .is_err()
+    );
+
+    // Ok requests
+    for action in [&ban, &AdminAction::EnableManualLock, &AdminAction::DisableManualLock] {
+        requests::post_admin_action(&client, &url, &ctx.coordinator.keypair, action)
+            .await
+            .unwrap();
+    }
+
+    // Every request of an admin is recorded, the failed ones with their error, and read by the observers
+    let entries = requests::get_audit_log(&client, &url, &ctx.observer_keypair)
+        .await
+        .unwrap();
+    let actions: Vec<_> = entries.iter().map(|entry| entry.action.clone()).collect();
+    assert_eq!(
+        actions,
+        vec![
+            remove,
+            ban,
+            AdminAction::EnableManualLock,
+            AdminAction::DisableManualLock
+        ]
+    );
+    assert!(entries[0].error.is_some());
+    assert!(entries[1..].iter().all(|entry| entry.error.is_none()));
+
+    // Drop the server
+    handle.abort()
+}
+
+#[tokio::test]
+async fn finalize_ceremony() {
+    let client = Client::new();
+    // Spawn the server and get the test context
+    let (ctx, handle) = test_prelude().await;
+    // Wait for server startup
+    time::sleep(Duration::from_secs(1)).await;
+
+    let url = Url::parse(&ctx.coordinator_url).unwrap();
+    let beacon = Beacon {
+        value: hex::encode([7u8; 32]),
+        iterations_exp: 4,
+    };
+
+    // Wrong request, the ceremony is not locked
+    match requests::post_finalize_ceremony(&client, &url, &ctx.coordinator.keypair, &beacon).await {
+        Err(RequestError::Client(message)) => assert!(message.contains("CeremonyNotLocked"), "{}", message),
+        _ => panic!("The ceremony shouldn't be finalized before being locked"),
+    }
+
+    // Once locked, the beacon still waits for the contribution in progress
+    requests::post_admin_action(&client, &url, &ctx.coordinator.keypair, &AdminAction::EnableManualLock)
+        .await
+        .unwrap();
+    match requests::post_finalize_ceremony(&client, &url, &ctx.coordinator.keypair, &beacon).await {
+        Err(RequestError::Server(message)) => assert!(message.contains("CurrentRoundNotFinished"), "{}", message),
+        _ => panic!("The ceremony shouldn't be finalized during a contribution"),
+    }
+
+    // Drop the server
+    handle.abort()
+}
+
 /// Test wrong usage of lock_chunk.
 #[tokio::test]
 async fn wrong_lock_chunk() {
@@ -525,7 +788,7 @@ async fn wrong_verify() {
     // Wrong, request from non-coordinator participant
     let url = Url::parse(&ctx.coordinator_url).unwrap();
     let response = requests::get_verify_chunks(&client, &url, &ctx.contributors[0].keypair).await;
-    assert!(response.is_err())
+    assert_unauthorized(response)
 }
 
 #[tokio::test]
@@ -574,9 +837,6 @@ async fn contribution() {
     use rand::Rng;
     use setup_utils::calculate_hash;
 
-    const COHORT_TIME: u64 = 15;
-    std::env::set_var("NAMADA_COHORT_TIME", COHORT_TIME.to_string()); // 15 seconds for each cohort
-
     let client = Client::new();
     // Spawn the server and get the test context
     let (ctx, handle) = test_prelude().await;
@@ -616,10 +876,10 @@ async fn contribution() {
     let mut contribution: Vec<u8> = Vec::new();
     contribution.write_all(challenge_hash.as_slice()).unwrap();
     let seed = RandomSource::Seed(rand::thread_rng().gen::<[u8; 32]>());
-    Computation::contribute_test_masp(&challenge, &mut contribution, &seed);
+    Computation::contribute_circuits(&CircuitSet::MaspTest, &challenge, &mut contribution, &seed);
 
-    // Initial contribution size is 2332 but the Coordinator expect ANOMA_BASE_FILE_SIZE. Extend to this size with trailing 0s
-    let contrib_size = Object::anoma_contribution_file_size(ROUND_HEIGHT, 1);
+    // Initial contribution size is 2332 but the Coordinator expect the base file size of the circuits. Extend to this size with trailing 0s
+    let contrib_size = Object::anoma_contribution_file_size(&CircuitSet::MaspTest, ROUND_HEIGHT, 1).unwrap();
     contribution.resize(contrib_size as usize, 0);
 
     let contribution_file_signature_locator = ContributionSignatureLocator::new(ROUND_HEIGHT, 0, 1, false);
@@ -678,6 +938,12 @@ async fn contribution() {
         .await
         .unwrap();
 
+    // The contribution has been verified right away
+    let status = requests::get_contribution_status(&client, &url, &ctx.contributors[0].keypair)
+        .await
+        .unwrap();
+    assert_eq!(status, ContributionStatus::Valid);
+
     // Verify chunk
     requests::get_verify_chunks(&client, &url, &ctx.coordinator.keypair)
         .await
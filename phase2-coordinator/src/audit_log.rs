@@ -0,0 +1,183 @@
+//! The audit log of the admin actions: every request to an admin endpoint is appended to a JSON lines file, with the
+//! operator who sent it and its outcome, so that the moderation of the ceremony can be reviewed afterwards.
+
+use crate::{objects::Participant, operators::OperatorKey};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+use time::OffsetDateTime;
+
+#[derive(Debug, Error)]
+pub enum AuditLogError {
+    #[error("Error with {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Invalid entry in {0}: {1}")]
+    Json(PathBuf, serde_json::Error),
+}
+
+type Result<T> = std::result::Result<T, AuditLogError>;
+
+/// An action of the admin endpoints.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AdminAction {
+    Ban { participant: Participant, reason: String },
+    Unban { participant: Participant },
+    Drop { participant: Participant },
+    RemoveFromQueue { participant: Participant },
+    MoveInQueue { participant: Participant, position: usize },
+    EnableManualLock,
+    DisableManualLock,
+    ResetRound,
+}
+
+impl fmt::Display for AdminAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ban { participant, reason } => write!(f, "ban {} ({})", participant, reason),
+            Self::Unban { participant } => write!(f, "unban {}", participant),
+            Self::Drop { participant } => write!(f, "drop {}", participant),
+            Self::RemoveFromQueue { participant } => write!(f, "remove {} from the queue", participant),
+            Self::MoveInQueue { participant, position } => {
+                write!(f, "move {} to position {} of the queue", participant, position)
+            }
+            Self::EnableManualLock => write!(f, "enable the manual lock"),
+            Self::DisableManualLock => write!(f, "disable the manual lock"),
+            Self::ResetRound => write!(f, "reset the current round"),
+        }
+    }
+}
+
+/// An entry of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// The unix timestamp of the action.
+    pub timestamp: i64,
+    /// The name of the operator who requested the action.
+    pub operator: String,
+    /// The public key the request was signed with.
+    pub pubkey: String,
+    #[serde(flatten)]
+    pub action: AdminAction,
+    /// The error the action failed with, if it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// Returns the entry of the given action of the operator, now.
+    pub fn new(operator: &OperatorKey, action: AdminAction, error: Option<String>) -> Self {
+        Self {
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            operator: operator.name.clone(),
+            pubkey: operator.pubkey.clone(),
+            action,
+            error,
+        }
+    }
+}
+
+/// The file of the audit log, only appended to.
+pub struct AuditLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+/// The audit log shared among the endpoints of the rest server.
+pub type SharedAuditLog = Arc<AuditLog>;
+
+impl AuditLog {
+    /// Returns the audit log of the given file, created on the first action.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    ///
+    /// Appends the given entry to the file.
+    ///
+    /// On failure to write the file, this function returns an `AuditLogError`.
+    ///
+    pub fn record(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry).map_err(|e| AuditLogError::Json(self.path.clone(), e))?;
+        line.push(b'\n');
+
+        // A single write of the whole line, so that the entries are never interleaved
+        let _guard = self.lock.lock().expect("Lock should not be poisoned");
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(&line))
+            .map_err(|e| AuditLogError::Io(self.path.clone(), e))
+    }
+
+    ///
+    /// Returns the entries of the file, in the order of the actions. A missing file has no entry.
+    ///
+    /// On failure to read the file or on an invalid entry, this function returns an `AuditLogError`.
+    ///
+    pub fn entries(&self) -> Result<Vec<AuditEntry>> {
+        let _guard = self.lock.lock().expect("Lock should not be poisoned");
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AuditLogError::Io(self.path.clone(), e)),
+        };
+
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| AuditLogError::Json(self.path.clone(), e)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators::OperatorRole;
+
+    #[test]
+    fn test_audit_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit_log = AuditLog::new(dir.path().join("audit_log.jsonl"));
+        assert_eq!(audit_log.entries().unwrap(), vec![]);
+
+        let operator = OperatorKey {
+            name: "alice".to_string(),
+            pubkey: "alice_key".to_string(),
+            role: OperatorRole::Admin,
+        };
+        let ban = AuditEntry::new(
+            &operator,
+            AdminAction::Ban {
+                participant: Participant::new_contributor("contributor_key"),
+                reason: "Spam".to_string(),
+            },
+            None,
+        );
+        let reset = AuditEntry::new(
+            &operator,
+            AdminAction::ResetRound,
+            Some("Round height is zero".to_string()),
+        );
+        audit_log.record(&ban).unwrap();
+        audit_log.record(&reset).unwrap();
+
+        // The entries are appended, one per line
+        let content = std::fs::read_to_string(dir.path().join("audit_log.jsonl")).unwrap();
+        assert_eq!(content.lines().count(), 2);
+        assert!(content.contains("\"action\":\"reset_round\""));
+        assert_eq!(audit_log.entries().unwrap(), vec![ban, reset]);
+    }
+}
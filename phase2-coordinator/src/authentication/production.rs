@@ -79,15 +79,19 @@ impl SigTrait for Production {
 
     /// Verifies the given signature for the given message and public key,
     /// and returns `true` if the signature is valid.
-    /// Public key and signature are expected to be [`hex`] encoded.
+    /// Public key and signature are expected to be [`hex`] encoded, they are invalid otherwise.
     fn verify(&self, public_key: &str, message: &str, signature: &str) -> bool {
-        let public_key_bytes = hex::decode(public_key).expect("Invalid public key encoding");
-        let public_key = PublicKey::from_slice(public_key_bytes.as_ref()).expect("Invalid public key");
-
-        let signature_bytes = hex::decode(signature).expect("Invalid signature encoding");
-        let signature = Signature::from_slice(signature_bytes.as_ref()).expect("Invalid signature");
-
-        public_key.verify(message, &signature).is_ok()
+        let public_key = hex::decode(public_key)
+            .ok()
+            .and_then(|bytes| PublicKey::from_slice(bytes.as_ref()).ok());
+        let signature = hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(bytes.as_ref()).ok());
+
+        match (public_key, signature) {
+            (Some(public_key), Some(signature)) => public_key.verify(message, &signature).is_ok(),
+            _ => false,
+        }
     }
 }
 
@@ -103,5 +107,9 @@ mod tests {
         let signature = sig_scheme.sign(keypair.sigkey(), msg).unwrap();
 
         assert!(sig_scheme.verify(keypair.pubkey(), msg, signature.as_ref()));
+
+        // Malformed keys and signatures don't verify
+        assert!(!sig_scheme.verify("not hex", msg, signature.as_ref()));
+        assert!(!sig_scheme.verify(keypair.pubkey(), msg, &signature[2..]));
     }
 }
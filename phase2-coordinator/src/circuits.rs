@@ -0,0 +1,279 @@
+//! Registry of the circuits a ceremony can be run on.
+//!
+//! The transcript of a ceremony is the 64 bytes hash of the previous contribution followed by the
+//! [`MPCParameters`] of each circuit of the [`CircuitSet`](crate::environment::CircuitSet), in order.
+//! The MASP circuits are registered by default, other circuits can be added with [`register`] before
+//! instantiating the [`Coordinator`](crate::Coordinator).
+
+use crate::CoordinatorError;
+
+use bellman::{Circuit, ConstraintSystem, SynthesisError};
+use bls12_381::Scalar;
+use lazy_static::lazy_static;
+use masp_phase2::MPCParameters;
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{Arc, RwLock},
+};
+use tracing::debug;
+
+/// The number of bytes the parameters of a circuit grow by on each new contribution.
+pub const CONTRIBUTION_PUBLIC_KEY_SIZE: u64 = 544;
+
+pub const MASP_SPEND: &str = "masp-spend";
+pub const MASP_OUTPUT: &str = "masp-output";
+pub const MASP_CONVERT: &str = "masp-convert";
+pub const MASP_TEST: &str = "masp-test";
+
+/// The size of the serialized initial parameters of the MASP Spend, Output and Convert circuits together.
+/// Only their total is known, the [`CircuitSet::MaspFull`](crate::environment::CircuitSet::MaspFull) set
+/// uses it instead of generating the parameters of each circuit to measure them.
+pub const MASP_FULL_PARAMS_SIZE: u64 = 84_720_180;
+/// The size of the serialized initial parameters of the MASP test circuit.
+pub const MASP_TEST_PARAMS_SIZE: u64 = 2_268;
+
+/// A circuit whose phase 2 parameters can be generated by the ceremony.
+pub trait CeremonyCircuit: Send + Sync {
+    /// The name the circuit is registered with.
+    fn name(&self) -> &str;
+
+    /// Generates the initial phase 2 parameters of the circuit.
+    fn initial_parameters(&self) -> Result<MPCParameters, SynthesisError>;
+
+    /// The size of the serialized initial parameters. If `None`, the size is measured
+    /// by generating them the first time it's needed.
+    fn params_size(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A [`CeremonyCircuit`] built from a constructor of a bellman [`Circuit`].
+pub struct BellmanCircuit<C, F> {
+    name: String,
+    constructor: F,
+    params_size: Option<u64>,
+    _circuit: PhantomData<fn() -> C>,
+}
+
+impl<C, F> BellmanCircuit<C, F>
+where
+    C: Circuit<Scalar>,
+    F: Fn() -> C + Send + Sync,
+{
+    pub fn new(name: &str, constructor: F) -> Self {
+        Self {
+            name: name.to_string(),
+            constructor,
+            params_size: None,
+            _circuit: PhantomData,
+        }
+    }
+
+    /// Sets the expected size of the serialized initial parameters.
+    pub fn with_params_size(mut self, params_size: u64) -> Self {
+        self.params_size = Some(params_size);
+        self
+    }
+}
+
+impl<C, F> CeremonyCircuit for BellmanCircuit<C, F>
+where
+    C: Circuit<Scalar>,
+    F: Fn() -> C + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn initial_parameters(&self) -> Result<MPCParameters, SynthesisError> {
+        MPCParameters::new((self.constructor)())
+    }
+
+    fn params_size(&self) -> Option<u64> {
+        self.params_size
+    }
+}
+
+/// Toy circuit used to run fast test ceremonies.
+pub struct TestCircuit {
+    pub x: Option<Scalar>,
+}
+
+impl Circuit<Scalar> for TestCircuit {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let x_value = self.x;
+        let x = cs.alloc(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?;
+
+        cs.enforce(|| "x = x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x);
+
+        Ok(())
+    }
+}
+
+/// The circuits available to a ceremony, indexed by name.
+pub struct CircuitRegistry {
+    circuits: HashMap<String, Arc<dyn CeremonyCircuit>>,
+    params_sizes: HashMap<String, u64>,
+}
+
+impl CircuitRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            circuits: HashMap::new(),
+            params_sizes: HashMap::new(),
+        }
+    }
+
+    /// Creates a registry with the MASP circuits.
+    pub fn with_masp() -> Self {
+        let mut registry = Self::new();
+
+        let circuits: Vec<Arc<dyn CeremonyCircuit>> = vec![
+            Arc::new(BellmanCircuit::new(MASP_SPEND, || {
+                masp_proofs::circuit::sapling::Spend {
+                    value_commitment: None,
+                    proof_generation_key: None,
+                    payment_address: None,
+                    commitment_randomness: None,
+                    ar: None,
+                    auth_path: vec![None; 32], // Tree depth is 32 for sapling
+                    anchor: None,
+                }
+            })),
+            Arc::new(BellmanCircuit::new(MASP_OUTPUT, || {
+                masp_proofs::circuit::sapling::Output {
+                    value_commitment: None,
+                    payment_address: None,
+                    commitment_randomness: None,
+                    esk: None,
+                    asset_identifier: vec![None; 256],
+                }
+            })),
+            Arc::new(BellmanCircuit::new(MASP_CONVERT, || {
+                masp_proofs::circuit::convert::Convert {
+                    value_commitment: None,
+                    auth_path: vec![None; 32], // Tree depth is 32 for sapling
+                    anchor: None,
+                }
+            })),
+            Arc::new(
+                BellmanCircuit::new(MASP_TEST, || TestCircuit { x: Some(Scalar::one()) })
+                    .with_params_size(MASP_TEST_PARAMS_SIZE),
+            ),
+        ];
+        for circuit in circuits {
+            registry.register(circuit).expect("MASP circuits have distinct names");
+        }
+
+        registry
+    }
+
+    /// Adds a circuit to the registry. Fails if a circuit with the same name is already registered.
+    pub fn register(&mut self, circuit: Arc<dyn CeremonyCircuit>) -> Result<(), CoordinatorError> {
+        let name = circuit.name().to_string();
+        if self.circuits.contains_key(&name) {
+            return Err(CoordinatorError::CircuitAlreadyRegistered(name));
+        }
+
+        if let Some(params_size) = circuit.params_size() {
+            self.params_sizes.insert(name.clone(), params_size);
+        }
+        self.circuits.insert(name, circuit);
+
+        Ok(())
+    }
+
+    /// Returns the circuit registered with the given name.
+    pub fn get(&self, name: &str) -> Result<Arc<dyn CeremonyCircuit>, CoordinatorError> {
+        self.circuits
+            .get(name)
+            .cloned()
+            .ok_or_else(|| CoordinatorError::CircuitMissing(name.to_string()))
+    }
+}
+
+impl Default for CircuitRegistry {
+    fn default() -> Self {
+        Self::with_masp()
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<CircuitRegistry> = RwLock::new(CircuitRegistry::default());
+}
+
+/// Adds a circuit to the global registry.
+pub fn register(circuit: impl CeremonyCircuit + 'static) -> Result<(), CoordinatorError> {
+    REGISTRY
+        .write()
+        .expect("Unable to lock the circuit registry")
+        .register(Arc::new(circuit))
+}
+
+/// Returns the circuit registered with the given name in the global registry.
+pub fn get(name: &str) -> Result<Arc<dyn CeremonyCircuit>, CoordinatorError> {
+    REGISTRY.read().expect("Unable to lock the circuit registry").get(name)
+}
+
+/// Returns the size of the serialized initial parameters of the given circuit. The size is measured
+/// and cached the first time, unless provided by the circuit itself.
+pub fn params_size(name: &str) -> Result<u64, CoordinatorError> {
+    if let Some(params_size) = REGISTRY
+        .read()
+        .expect("Unable to lock the circuit registry")
+        .params_sizes
+        .get(name)
+    {
+        return Ok(*params_size);
+    }
+
+    debug!("Measuring the size of the {} parameters", name);
+    let mut params = Vec::new();
+    get(name)?
+        .initial_parameters()
+        .map_err(|_| CoordinatorError::CircuitSynthesisFailed(name.to_string()))?
+        .write(&mut params)?;
+    let params_size = params.len() as u64;
+
+    REGISTRY
+        .write()
+        .expect("Unable to lock the circuit registry")
+        .params_sizes
+        .insert(name.to_string(), params_size);
+
+    Ok(params_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let mut registry = CircuitRegistry::default();
+        assert!(registry.get(MASP_SPEND).is_ok());
+        assert!(matches!(
+            registry.get("unknown"),
+            Err(CoordinatorError::CircuitMissing(_))
+        ));
+
+        let circuit = BellmanCircuit::new("custom", || TestCircuit { x: None }).with_params_size(42);
+        registry.register(Arc::new(circuit)).unwrap();
+        assert_eq!(registry.get("custom").unwrap().name(), "custom");
+        assert_eq!(registry.params_sizes.get("custom"), Some(&42));
+
+        let duplicate = BellmanCircuit::new(MASP_TEST, || TestCircuit { x: None });
+        assert!(matches!(
+            registry.register(Arc::new(duplicate)),
+            Err(CoordinatorError::CircuitAlreadyRegistered(_))
+        ));
+    }
+
+    #[test]
+    fn test_params_size() {
+        // The test parameters are 2_268 bytes long, preceded by the 64 bytes hash in the transcript
+        assert_eq!(params_size(MASP_TEST).unwrap(), 2_268);
+    }
+}
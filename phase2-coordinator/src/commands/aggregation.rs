@@ -1,7 +1,7 @@
 use crate::{
     environment::Environment,
     objects::Round,
-    storage::{ContributionLocator, Disk, DiskObjectReader, Locator, Object, StorageLocator, StorageObject},
+    storage::{ContributionLocator, Locator, Object, Storage},
     CoordinatorError,
 };
 
@@ -14,7 +14,7 @@ impl Aggregation {
     /// Runs aggregation for a given environment, storage, and round.
     /// NOTE: The Namada Trusted Setup runs only phase 2 and doesn't chunk the contributions. The aggregation has been left as a placeholder to avoid breaking the whole library logic. In practice, there is no aggregation.
     #[inline]
-    pub(crate) fn run(environment: &Environment, storage: &mut Disk, round: &Round) -> anyhow::Result<()> {
+    pub(crate) fn run(environment: &Environment, storage: &mut impl Storage, round: &Round) -> anyhow::Result<()> {
         let start = Instant::now();
 
         // Fetch the round height.
@@ -35,7 +35,7 @@ impl Aggregation {
         }
 
         // Initialize the round locator.
-        storage.initialize(round_locator.clone(), Object::round_file_size(environment))?;
+        storage.initialize(round_locator.clone(), Object::round_file_size(environment)?)?;
 
         // Load the contribution files.
         let readers = Self::readers(environment, storage, round)?;
@@ -49,11 +49,7 @@ impl Aggregation {
     /// Attempts to open every contribution for the given round and
     /// returns readers to each chunk contribution file.
     #[inline]
-    fn readers<'a>(
-        environment: &Environment,
-        storage: &'a Disk,
-        round: &Round,
-    ) -> anyhow::Result<Vec<DiskObjectReader>> {
+    fn readers<S: Storage>(environment: &Environment, storage: &S, round: &Round) -> anyhow::Result<Vec<S::Reader>> {
         let mut readers = vec![];
 
         // Fetch the round height.
@@ -105,7 +101,7 @@ mod tests {
         authentication::Dummy,
         commands::{Aggregation, Seed, SigningKey, SEED_LENGTH},
         objects::Task,
-        storage::Locator,
+        storage::{Locator, Storage},
         testing::prelude::*,
         Coordinator,
     };
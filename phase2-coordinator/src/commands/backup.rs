@@ -0,0 +1,513 @@
+//! Backup of a ceremony to a single archive, and restoration of the ceremony from it on another machine.
+//!
+//! The backup is a zip archive of the transcript directory of the coordinator, under `transcript/`, and of its
+//! token files, under `tokens/`, together with the [`Environment`] of the coordinator in [`ENVIRONMENT_FILE`] and
+//! a [`BackupManifest`] listing the size and the BLAKE2b-512 hash of every file in [`MANIFEST_FILE`].
+//!
+//! The archive is restored to staging directories next to the destination ones, checked against its manifest and
+//! the records of the ceremony checked for consistency, before being moved in place. Both commands must be run
+//! while the coordinator is stopped.
+
+use crate::{
+    environment::{CircuitSet, Environment},
+    objects::{ContributionFileSignature, Round},
+    CoordinatorState,
+};
+
+use blake2::{Blake2b512, Digest};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+use thiserror::Error;
+use time::OffsetDateTime;
+use tracing::{debug, info};
+use zip::{result::ZipError, write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+/// The version of the backup format.
+pub const BACKUP_VERSION: u32 = 1;
+/// The manifest of the backup, in the root of the archive.
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// The environment of the coordinator, in the root of the archive.
+pub const ENVIRONMENT_FILE: &str = "environment.json";
+/// The directory of the archive containing the transcript directory of the coordinator.
+pub const TRANSCRIPT_DIRECTORY: &str = "transcript";
+/// The directory of the archive containing the token files of the coordinator.
+pub const TOKENS_DIRECTORY: &str = "tokens";
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("Couldn't read the archive {}: {}", .0.display(), .1)]
+    Archive(PathBuf, ZipError),
+    #[error("The archive was made for {archived:?}, the coordinator runs on {expected:?}")]
+    CircuitsMismatch { archived: CircuitSet, expected: CircuitSet },
+    #[error("{} already exists and is not empty", .0.display())]
+    DestinationNotEmpty(PathBuf),
+    #[error("{0} doesn't match the hash of the manifest")]
+    HashMismatch(String),
+    #[error("Invalid archive entry {0}")]
+    InvalidEntry(String),
+    #[error("Invalid record {}: {}", .0.display(), .1)]
+    InvalidRecord(PathBuf, String),
+    #[error("Couldn't access {}: {}", .0.display(), .1)]
+    Io(PathBuf, std::io::Error),
+    #[error("{0} is missing from the archive")]
+    MissingFile(String),
+    #[error("{0} is not listed in the manifest")]
+    UnexpectedFile(String),
+    #[error("Unsupported backup version {0}")]
+    UnsupportedVersion(u32),
+}
+
+/// The content of a backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: OffsetDateTime,
+    /// The round height of the ceremony when it was backed up, if it had started.
+    pub round_height: Option<u64>,
+    pub files: Vec<BackupFile>,
+}
+
+/// A file of a backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFile {
+    /// The name of the file in the archive, e.g. `transcript/round_1/state.json`.
+    pub path: String,
+    pub size: u64,
+    /// The BLAKE2b-512 hash of the file, in hexadecimal.
+    pub hash: String,
+}
+
+///
+/// Writes the transcript directory of the given environment and the token files in `tokens` to a new archive
+/// at `output`.
+///
+/// The leftovers of interrupted writes are skipped, the staged uploads are kept so that they can be resumed.
+///
+pub fn create_backup(environment: &Environment, tokens: &Path, output: &Path) -> Result<BackupManifest, BackupError> {
+    let base = Path::new(environment.local_base_directory());
+    if !base.is_dir() {
+        return Err(BackupError::MissingFile(base.display().to_string()));
+    }
+
+    let round_height_path = base.join("round_height");
+    let round_height = match round_height_path.exists() {
+        true => Some(parse_json::<u64>(&round_height_path)?),
+        false => None,
+    };
+
+    let file = fs::File::create(output).map_err(|e| BackupError::Io(output.to_path_buf(), e))?;
+    let mut archive = ZipWriter::new(file);
+    let mut files = Vec::new();
+
+    let environment_bytes = serde_json::to_vec_pretty(environment).expect("Environment serialization can't fail");
+    files.push(add_file(&mut archive, output, ENVIRONMENT_FILE, &environment_bytes)?);
+
+    let mut sources = vec![(TRANSCRIPT_DIRECTORY, base)];
+    if tokens.is_dir() {
+        sources.push((TOKENS_DIRECTORY, tokens));
+    }
+    for (directory, source) in sources {
+        for path in list_files(source)? {
+            if path.extension().map_or(false, |extension| extension == "tmp") {
+                continue;
+            }
+
+            let name = format!("{}/{}", directory, relative_name(source, &path)?);
+            let bytes = read(&path)?;
+            files.push(add_file(&mut archive, output, &name, &bytes)?);
+            debug!("Backed up {}", path.display());
+        }
+    }
+
+    let manifest = BackupManifest {
+        version: BACKUP_VERSION,
+        created_at: OffsetDateTime::now_utc(),
+        round_height,
+        files,
+    };
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest).expect("Manifest serialization can't fail");
+    archive
+        .start_file(MANIFEST_FILE, FileOptions::default())
+        .map_err(|e| BackupError::Archive(output.to_path_buf(), e))?;
+    archive
+        .write_all(&manifest_bytes)
+        .map_err(|e| BackupError::Io(output.to_path_buf(), e))?;
+    archive
+        .finish()
+        .map_err(|e| BackupError::Archive(output.to_path_buf(), e))?;
+
+    info!(
+        "Backed up {} files of round {:?} to {}",
+        manifest.files.len(),
+        manifest.round_height,
+        output.display()
+    );
+    Ok(manifest)
+}
+
+///
+/// Restores the archive at `input` to the transcript directory of the given environment and to `tokens`.
+///
+/// Nothing is moved in place unless every file of the archive matches the manifest, the archive was made for the
+/// circuits of the given environment and the records of the ceremony are consistent. The transcript and token
+/// directories must not contain any file.
+///
+pub fn restore_backup(environment: &Environment, tokens: &Path, input: &Path) -> Result<BackupManifest, BackupError> {
+    let base = Path::new(environment.local_base_directory());
+    for destination in [base, tokens].iter() {
+        if destination.exists() && !list_files(destination)?.is_empty() {
+            return Err(BackupError::DestinationNotEmpty(destination.to_path_buf()));
+        }
+    }
+
+    let file = fs::File::open(input).map_err(|e| BackupError::Io(input.to_path_buf(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| BackupError::Archive(input.to_path_buf(), e))?;
+
+    let manifest: BackupManifest = {
+        let mut entry = archive
+            .by_name(MANIFEST_FILE)
+            .map_err(|_| BackupError::MissingFile(MANIFEST_FILE.to_string()))?;
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|e| BackupError::Io(input.to_path_buf(), e))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| BackupError::InvalidRecord(PathBuf::from(MANIFEST_FILE), e.to_string()))?
+    };
+    if manifest.version != BACKUP_VERSION {
+        return Err(BackupError::UnsupportedVersion(manifest.version));
+    }
+
+    let staging_base = staging_path(base);
+    let staging_tokens = staging_path(tokens);
+    let result = extract(
+        &mut archive,
+        input,
+        &manifest,
+        environment,
+        &staging_base,
+        &staging_tokens,
+    )
+    .and_then(|()| check_transcript(&staging_base, manifest.round_height));
+    if let Err(e) = result {
+        remove_dir(&staging_base);
+        remove_dir(&staging_tokens);
+        return Err(e);
+    }
+
+    // Everything has been checked, move the staging directories in place.
+    for (staging, destination) in [(&staging_base, base), (&staging_tokens, tokens)].iter() {
+        if destination.exists() {
+            fs::remove_dir_all(destination).map_err(|e| BackupError::Io(destination.to_path_buf(), e))?;
+        }
+        if let Some(parent) = destination.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| BackupError::Io(parent.to_path_buf(), e))?;
+        }
+        if staging.exists() {
+            fs::rename(staging, destination).map_err(|e| BackupError::Io(destination.to_path_buf(), e))?;
+        }
+    }
+
+    info!(
+        "Restored {} files of round {:?} from {}",
+        manifest.files.len(),
+        manifest.round_height,
+        input.display()
+    );
+    Ok(manifest)
+}
+
+/// Extracts the files of the archive to the staging directories, checking them against the manifest.
+fn extract(
+    archive: &mut ZipArchive<fs::File>,
+    input: &Path,
+    manifest: &BackupManifest,
+    environment: &Environment,
+    staging_base: &Path,
+    staging_tokens: &Path,
+) -> Result<(), BackupError> {
+    let mut remaining: Vec<&BackupFile> = manifest.files.iter().collect();
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| BackupError::Archive(input.to_path_buf(), e))?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || name == MANIFEST_FILE {
+            continue;
+        }
+
+        let position = remaining
+            .iter()
+            .position(|file| file.path == name)
+            .ok_or_else(|| BackupError::UnexpectedFile(name.clone()))?;
+        let expected = remaining.swap_remove(position);
+
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|e| BackupError::Io(input.to_path_buf(), e))?;
+        if bytes.len() as u64 != expected.size || hex::encode(Blake2b512::digest(&bytes)) != expected.hash {
+            return Err(BackupError::HashMismatch(name));
+        }
+
+        if name == ENVIRONMENT_FILE {
+            let archived: Environment = serde_json::from_slice(&bytes)
+                .map_err(|e| BackupError::InvalidRecord(PathBuf::from(ENVIRONMENT_FILE), e.to_string()))?;
+            let (archived, expected) = (archived.parameters().circuits, environment.parameters().circuits);
+            if archived != expected {
+                return Err(BackupError::CircuitsMismatch { archived, expected });
+            }
+            continue;
+        }
+
+        let destination = match safe_path(&name)?.split_first() {
+            Some((&TRANSCRIPT_DIRECTORY, relative)) if !relative.is_empty() => staging_base.join(relative.join("/")),
+            Some((&TOKENS_DIRECTORY, relative)) if !relative.is_empty() => staging_tokens.join(relative.join("/")),
+            _ => return Err(BackupError::InvalidEntry(name.clone())),
+        };
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).map_err(|e| BackupError::Io(parent.to_path_buf(), e))?;
+        }
+        fs::write(&destination, &bytes).map_err(|e| BackupError::Io(destination.clone(), e))?;
+    }
+
+    match remaining.first() {
+        Some(file) => Err(BackupError::MissingFile(file.path.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the records of the restored transcript can be loaded and are consistent with each other.
+fn check_transcript(base: &Path, round_height: Option<u64>) -> Result<(), BackupError> {
+    let state_path = base.join("coordinator.json");
+    if state_path.exists() {
+        let state: CoordinatorState = parse_json(&state_path)?;
+        if state.current_round_height_opt() != round_height {
+            return Err(BackupError::InvalidRecord(
+                state_path,
+                format!("the round height doesn't match the manifest ({:?})", round_height),
+            ));
+        }
+    } else if !base.join("coordinator.db").exists() {
+        return Err(BackupError::MissingFile(format!(
+            "{}/coordinator.json",
+            TRANSCRIPT_DIRECTORY
+        )));
+    }
+
+    for path in list_files(base)? {
+        let name = relative_name(base, &path)?;
+        let components: Vec<&str> = name.split('/').collect();
+        match components.as_slice() {
+            [round_directory, "state.json"] => {
+                let round: Round = parse_json(&path)?;
+                let expected = round_directory
+                    .strip_prefix("round_")
+                    .and_then(|h| u64::from_str(h).ok());
+                if Some(round.round_height()) != expected {
+                    return Err(BackupError::InvalidRecord(
+                        path,
+                        format!("the round height {} doesn't match its directory", round.round_height()),
+                    ));
+                }
+            }
+            [round_directory, _, file] if round_directory.starts_with("round_") && file.ends_with(".signature") => {
+                parse_json::<ContributionFileSignature>(&path)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn add_file(
+    archive: &mut ZipWriter<fs::File>,
+    output: &Path,
+    name: &str,
+    bytes: &[u8],
+) -> Result<BackupFile, BackupError> {
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(bytes.len() as u64 >= u32::MAX as u64);
+    archive
+        .start_file(name, options)
+        .map_err(|e| BackupError::Archive(output.to_path_buf(), e))?;
+    archive
+        .write_all(bytes)
+        .map_err(|e| BackupError::Io(output.to_path_buf(), e))?;
+
+    Ok(BackupFile {
+        path: name.to_string(),
+        size: bytes.len() as u64,
+        hash: hex::encode(Blake2b512::digest(bytes)),
+    })
+}
+
+/// Splits the name of an archive entry into its components, rejecting the names escaping the archive.
+fn safe_path(name: &str) -> Result<Vec<&str>, BackupError> {
+    let components: Vec<&str> = name.split('/').collect();
+    let is_safe = Path::new(name)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        && components.iter().all(|component| !component.is_empty());
+
+    match is_safe {
+        true => Ok(components),
+        false => Err(BackupError::InvalidEntry(name.to_string())),
+    }
+}
+
+/// The staging directory of a destination lives next to it with the `.restore` extension.
+fn staging_path(destination: &Path) -> PathBuf {
+    let mut path = destination.as_os_str().to_owned();
+    path.push(".restore");
+    path.into()
+}
+
+/// Returns the paths of the files in the given directory and its subdirectories, in order.
+fn list_files(directory: &Path) -> Result<Vec<PathBuf>, BackupError> {
+    let mut files = Vec::new();
+    let entries = fs::read_dir(directory).map_err(|e| BackupError::Io(directory.to_path_buf(), e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| BackupError::Io(directory.to_path_buf(), e))?.path();
+        if path.is_dir() {
+            files.extend(list_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Returns the path of the given file relative to the given directory, with `/` separators.
+fn relative_name(directory: &Path, path: &Path) -> Result<String, BackupError> {
+    let relative = path
+        .strip_prefix(directory)
+        .map_err(|_| BackupError::InvalidEntry(path.display().to_string()))?;
+
+    Ok(relative
+        .iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, BackupError> {
+    fs::read(path).map_err(|e| BackupError::Io(path.to_path_buf(), e))
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, BackupError> {
+    serde_json::from_slice(&read(path)?).map_err(|e| BackupError::InvalidRecord(path.to_path_buf(), e.to_string()))
+}
+
+fn remove_dir(path: &Path) {
+    if path.exists() {
+        if let Err(e) = fs::remove_dir_all(path) {
+            tracing::warn!("Couldn't remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        environment::{CircuitSet, Parameters, Testing},
+        testing::prelude::*,
+    };
+
+    #[test]
+    #[serial]
+    fn test_backup_and_restore() {
+        let environment = initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+        let base = Path::new(environment.local_base_directory());
+        let dir = tempfile::tempdir().unwrap();
+        let tokens = dir.path().join("tokens");
+
+        // A ceremony at round 1
+        let mut state = CoordinatorState::new(environment.clone());
+        state.initialize(1);
+        let round = test_round_1_initial_json().unwrap();
+        fs::create_dir_all(base.join("round_1/chunk_0")).unwrap();
+        fs::write(base.join("coordinator.json"), serde_json::to_vec(&state).unwrap()).unwrap();
+        fs::write(base.join("coordinator.json.tmp"), b"").unwrap();
+        fs::write(base.join("round_height"), b"1").unwrap();
+        fs::write(base.join("round_1/state.json"), serde_json::to_vec(&round).unwrap()).unwrap();
+        fs::write(base.join("round_1/chunk_0/contribution_0.verified"), [1u8; 64]).unwrap();
+        fs::create_dir_all(&tokens).unwrap();
+        fs::write(tokens.join("namada_tokens_cohort_1.json"), b"[]").unwrap();
+
+        let archive = dir.path().join("backup.zip");
+        let manifest = create_backup(&environment, &tokens, &archive).unwrap();
+        assert_eq!(manifest.round_height, Some(1));
+        // The environment, the four records and contribution of the transcript and the token file
+        assert_eq!(manifest.files.len(), 6);
+
+        // The destination must be empty
+        assert!(matches!(
+            restore_backup(&environment, &tokens, &archive),
+            Err(BackupError::DestinationNotEmpty(_))
+        ));
+
+        clear_test_storage(&environment);
+        fs::remove_dir_all(&tokens).unwrap();
+        assert_eq!(
+            restore_backup(&environment, &tokens, &archive).unwrap().files,
+            manifest.files
+        );
+        assert_eq!(
+            fs::read(base.join("round_1/chunk_0/contribution_0.verified")).unwrap(),
+            vec![1u8; 64]
+        );
+        assert_eq!(fs::read(tokens.join("namada_tokens_cohort_1.json")).unwrap(), b"[]");
+        assert!(!base.join("coordinator.json.tmp").exists());
+
+        // The archive can't be restored on other circuits
+        clear_test_storage(&environment);
+        fs::remove_dir_all(&tokens).unwrap();
+        let other: Environment = Testing::from(Parameters::TestCustom {
+            number_of_chunks: 1,
+            power: 8,
+            batch_size: 128,
+        })
+        .circuits(CircuitSet::Custom(vec![String::from("other")]))
+        .into();
+        assert!(matches!(
+            restore_backup(&other, &tokens, &archive),
+            Err(BackupError::CircuitsMismatch { .. })
+        ));
+        assert!(!staging_path(base).exists());
+
+        // A tampered archive is rejected before anything is restored
+        let tampered = dir.path().join("tampered.zip");
+        let mut writer = ZipWriter::new(fs::File::create(&tampered).unwrap());
+        let mut original = ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+        for index in 0..original.len() {
+            let mut entry = original.by_index(index).unwrap();
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes).unwrap();
+            if entry.name().ends_with(".verified") {
+                bytes[0] ^= 1;
+            }
+            writer.start_file(entry.name(), FileOptions::default()).unwrap();
+            writer.write_all(&bytes).unwrap();
+        }
+        writer.finish().unwrap();
+        assert!(matches!(
+            restore_backup(&environment, &tokens, &tampered),
+            Err(BackupError::HashMismatch(_))
+        ));
+        assert!(!base.exists());
+        assert!(!tokens.exists());
+    }
+}
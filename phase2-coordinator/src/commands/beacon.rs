@@ -0,0 +1,240 @@
+//! Finalization of the ceremony with a public randomness beacon.
+//!
+//! The beacon value (e.g. the hash of a public block) is hashed 2^`iterations_exp` times with SHA256 to derive the
+//! seed of a final, deterministic contribution on top of the last verified contribution of the transcript. The
+//! beacon inputs are recorded in the transcript next to the final contribution, so that anyone can reproduce it.
+
+use crate::{
+    commands::{
+        transcript::{contribution_path, read, read_transcript_file, verified_contributions, write},
+        Computation, RandomSource, Seed, TranscriptError,
+    },
+    environment::CircuitSet,
+};
+use setup_utils::{calculate_hash, iterate_beacon_hash};
+
+use serde::{Deserialize, Serialize};
+use std::{convert::TryInto, path::Path};
+use tracing::{debug, info};
+
+/// The file of the transcript recording the beacon inputs.
+pub const BEACON_RECORD_FILE: &str = "beacon.json";
+/// The file of the transcript containing the final contribution, in the format of a response file.
+pub const BEACON_CONTRIBUTION_FILE: &str = "beacon.params";
+/// The maximum exponent of the number of hash iterations of the beacon.
+pub const MAX_BEACON_ITERATIONS_EXP: u32 = 42;
+
+/// A public randomness beacon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Beacon {
+    /// The public randomness, as 32 bytes in hexadecimal.
+    pub value: String,
+    /// The value is hashed 2^`iterations_exp` times to derive the seed.
+    pub iterations_exp: u32,
+}
+
+impl Beacon {
+    /// Derives the seed of the final contribution from the beacon.
+    pub fn seed(&self) -> Result<Seed, TranscriptError> {
+        if self.iterations_exp > MAX_BEACON_ITERATIONS_EXP {
+            return Err(TranscriptError::InvalidBeacon(format!(
+                "the iterations exponent can't be greater than {}",
+                MAX_BEACON_ITERATIONS_EXP
+            )));
+        }
+
+        let value: [u8; 32] = hex::decode(self.value.trim_start_matches("0x"))
+            .ok()
+            .and_then(|value| value.try_into().ok())
+            .ok_or_else(|| TranscriptError::InvalidBeacon(String::from("the value must be 32 bytes in hexadecimal")))?;
+
+        Ok(iterate_beacon_hash(value, self.iterations_exp))
+    }
+}
+
+/// The beacon inputs and outputs, as recorded in the transcript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeaconRecord {
+    pub beacon: Beacon,
+    /// The seed derived from the beacon, in hexadecimal.
+    pub seed: String,
+    /// The round height of the contribution the beacon was applied to.
+    pub round_height: u64,
+    /// The ID of the verified contribution the beacon was applied to.
+    pub contribution_id: u64,
+    pub challenge_hash: String,
+    pub response_hash: String,
+    /// The hash of the final parameters.
+    pub contribution_hash: String,
+}
+
+///
+/// Applies the beacon to the last verified contribution of the transcript stored in the given directory, with the
+/// seed derived from it by [`Beacon::seed`], whose hash iterations can be run beforehand.
+///
+/// The final contribution is written to [`BEACON_CONTRIBUTION_FILE`] and the beacon inputs to
+/// [`BEACON_RECORD_FILE`]. A transcript can be finalized only once.
+///
+pub fn apply_beacon(
+    path: &Path,
+    circuits: &CircuitSet,
+    beacon: &Beacon,
+    seed: &Seed,
+) -> Result<BeaconRecord, TranscriptError> {
+    let record_path = path.join(BEACON_RECORD_FILE);
+    if record_path.exists() {
+        return Err(TranscriptError::BeaconAlreadyApplied(record_path));
+    }
+    debug!("Beacon seed is 0x{}", hex::encode(seed));
+
+    let (round_height, contribution_id) = last_verified_contribution(path)?;
+    info!(
+        "Applying the beacon to round {} contribution {}",
+        round_height, contribution_id
+    );
+    let challenge = read_transcript_file(path, &contribution_path(path, round_height, contribution_id, true))?;
+    let response = contribute(circuits, &challenge, seed);
+
+    let record = BeaconRecord {
+        beacon: beacon.clone(),
+        seed: hex::encode(seed),
+        round_height,
+        contribution_id,
+        challenge_hash: hex::encode(calculate_hash(&challenge)),
+        response_hash: hex::encode(calculate_hash(&response)),
+        contribution_hash: hex::encode(calculate_hash(&response[64..])),
+    };
+    write(&path.join(BEACON_CONTRIBUTION_FILE), &response)?;
+    write(
+        &record_path,
+        &serde_json::to_vec_pretty(&record).expect("Beacon record serialization can't fail"),
+    )?;
+
+    Ok(record)
+}
+
+///
+/// Reproduces the final contribution of the transcript stored in the given directory from its beacon record.
+///
+/// Returns `None` if the beacon hasn't been applied yet.
+///
+pub fn verify_beacon(path: &Path, circuits: &CircuitSet) -> Result<Option<BeaconRecord>, TranscriptError> {
+    let record_path = path.join(BEACON_RECORD_FILE);
+    if !record_path.exists() {
+        return Ok(None);
+    }
+    let record: BeaconRecord = serde_json::from_slice(&read(&record_path)?)
+        .map_err(|e| TranscriptError::InvalidBeaconFile(record_path.clone(), e))?;
+
+    if (record.round_height, record.contribution_id) != last_verified_contribution(path)? {
+        return Err(TranscriptError::BeaconMismatch(String::from(
+            "the beacon wasn't applied to the last contribution",
+        )));
+    }
+
+    let seed = record.beacon.seed()?;
+    if hex::encode(seed) != record.seed {
+        return Err(TranscriptError::BeaconMismatch(String::from(
+            "the seed wasn't derived from the beacon",
+        )));
+    }
+
+    let challenge = read_transcript_file(
+        path,
+        &contribution_path(path, record.round_height, record.contribution_id, true),
+    )?;
+    if hex::encode(calculate_hash(&challenge)) != record.challenge_hash {
+        return Err(TranscriptError::BeaconMismatch(String::from(
+            "the challenge hash doesn't match the recorded one",
+        )));
+    }
+
+    debug!("Reproducing the final contribution");
+    let response = read(&path.join(BEACON_CONTRIBUTION_FILE))?;
+    if response != contribute(circuits, &challenge, &seed) {
+        return Err(TranscriptError::BeaconMismatch(String::from(
+            "the final contribution wasn't generated from the beacon",
+        )));
+    }
+    if hex::encode(calculate_hash(&response)) != record.response_hash
+        || hex::encode(calculate_hash(&response[64..])) != record.contribution_hash
+    {
+        return Err(TranscriptError::BeaconMismatch(String::from(
+            "the final contribution hashes don't match the recorded ones",
+        )));
+    }
+
+    Ok(Some(record))
+}
+
+/// Performs the deterministic contribution seeded by the beacon.
+fn contribute(circuits: &CircuitSet, challenge: &[u8], seed: &Seed) -> Vec<u8> {
+    let mut response = calculate_hash(challenge).to_vec();
+    Computation::contribute_circuits(circuits, challenge, &mut response, &RandomSource::Seed(*seed));
+
+    response
+}
+
+fn last_verified_contribution(path: &Path) -> Result<(u64, u64), TranscriptError> {
+    verified_contributions(path)?
+        .last()
+        .copied()
+        .ok_or(TranscriptError::ContributionMissing {
+            round_height: 0,
+            contribution_id: 0,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::transcript::initial_transcript;
+    use std::fs;
+
+    #[test]
+    fn test_apply_beacon() {
+        let circuits = CircuitSet::MaspTest;
+        let dir = tempfile::tempdir().unwrap();
+        let beacon = Beacon {
+            value: hex::encode([7u8; 32]),
+            iterations_exp: 4,
+        };
+
+        let initial = initial_transcript(&circuits).unwrap();
+        for round_height in 0..2 {
+            let path = contribution_path(dir.path(), round_height, 0, true);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, &initial).unwrap();
+        }
+        assert!(verify_beacon(dir.path(), &circuits).unwrap().is_none());
+
+        // The beacon is applied to the last verified contribution and can be reproduced
+        let seed = beacon.seed().unwrap();
+        let record = apply_beacon(dir.path(), &circuits, &beacon, &seed).unwrap();
+        assert_eq!((record.round_height, record.contribution_id), (1, 0));
+        assert_eq!(record.seed, hex::encode(iterate_beacon_hash([7u8; 32], 4)));
+        assert_eq!(verify_beacon(dir.path(), &circuits).unwrap(), Some(record));
+        assert!(matches!(
+            apply_beacon(dir.path(), &circuits, &beacon, &seed),
+            Err(TranscriptError::BeaconAlreadyApplied(_))
+        ));
+
+        // The final contribution has been tampered with
+        let contribution_path = dir.path().join(BEACON_CONTRIBUTION_FILE);
+        let mut response = fs::read(&contribution_path).unwrap();
+        response[100] ^= 1;
+        fs::write(&contribution_path, &response).unwrap();
+        assert!(matches!(
+            verify_beacon(dir.path(), &circuits),
+            Err(TranscriptError::BeaconMismatch(_))
+        ));
+
+        let invalid_beacon = Beacon {
+            value: String::from("0x1234"),
+            iterations_exp: 4,
+        };
+        assert!(matches!(invalid_beacon.seed(), Err(TranscriptError::InvalidBeacon(_))));
+    }
+}
@@ -1,8 +1,8 @@
 use crate::{
     authentication::Signature,
     commands::SigningKey,
-    environment::Environment,
-    storage::{Disk, Locator, StorageLocator, StorageObject},
+    environment::{CircuitSet, Environment},
+    storage::{Locator, Storage},
     CoordinatorError,
 };
 use phase2::helpers::CurveKind;
@@ -40,7 +40,7 @@ impl Computation {
     ///
     pub fn run(
         environment: &Environment,
-        storage: &mut Disk,
+        storage: &mut impl Storage,
         signature: Arc<dyn Signature>,
         contributor_signing_key: &SigningKey,
         challenge_locator: &Locator,
@@ -68,16 +68,20 @@ impl Computation {
         // Run computation on chunk.
         let settings = environment.parameters();
         let curve = settings.curve();
+        let circuits = settings.circuits();
         if let Err(error) = match curve {
             CurveKind::Bls12_381 => Self::contribute(
+                circuits,
                 storage.reader(challenge_locator)?.as_ref(),
                 storage.writer(response_locator)?.as_mut(),
             ),
             CurveKind::Bls12_377 => Self::contribute(
+                circuits,
                 storage.reader(challenge_locator)?.as_ref(),
                 storage.writer(response_locator)?.as_mut(),
             ),
             CurveKind::BW6 => Self::contribute(
+                circuits,
                 storage.reader(challenge_locator)?.as_ref(),
                 storage.writer(response_locator)?.as_mut(),
             ),
@@ -123,7 +127,11 @@ impl Computation {
         Ok(())
     }
 
-    fn contribute(challenge_reader: &[u8], mut response_writer: &mut [u8]) -> Result<(), CoordinatorError> {
+    fn contribute(
+        circuits: &CircuitSet,
+        challenge_reader: &[u8],
+        mut response_writer: &mut [u8],
+    ) -> Result<(), CoordinatorError> {
         trace!("Calculating previous contribution hash and writing it to the response");
 
         let challenge_hash = calculate_hash(&challenge_reader);
@@ -144,13 +152,9 @@ impl Computation {
         // Perform the transformation
         trace!("Computing and writing your contribution, this could take a while");
 
-        // Contribute to the MASP circuit
+        // Contribute to the circuits
         let rand_source = RandomSource::Entropy(String::from("entropy"));
-        #[cfg(debug_assertions)]
-        Self::contribute_test_masp(&challenge_reader, &mut response_writer, &rand_source);
-
-        #[cfg(not(debug_assertions))]
-        Self::contribute_masp(&challenge_reader, &mut response_writer, &rand_source);
+        Self::contribute_circuits(circuits, &challenge_reader, &mut response_writer, &rand_source);
 
         trace!("Finishing writing your contribution to response file");
 
@@ -159,10 +163,14 @@ impl Computation {
 
     // The [`ContributionFile`] has the following format
     // | previous_contribution_file_hash (64 bytes) |
-    // | masp_mpc_new_parameters_contribution |
+    // | circuit_1_mpc_new_parameters_contribution | ... | circuit_n_mpc_new_parameters_contribution |
     // The masp-mpc commands are executed at offset 64 of the [`ContributionFile`]
-    #[cfg(not(debug_assertions))]
-    pub fn contribute_masp<W: Write>(challenge_reader: &[u8], mut response_writer: W, rand_source: &RandomSource) {
+    pub fn contribute_circuits<W: Write>(
+        circuits: &CircuitSet,
+        challenge_reader: &[u8],
+        mut response_writer: W,
+        rand_source: &RandomSource,
+    ) {
         // Create an RNG as following:
         //  - if the user provides a seed, create the rng from that seed
         //  - if the user provides entropy, create the rng from the combination of OS randomness and user entropy
@@ -192,125 +200,35 @@ impl Computation {
             }
         };
 
-        let mut masp_challenge_reader = &challenge_reader[64..];
-        //
-        // MASP Spend circuit
-        //
-        trace!("Reading MASP Spend...");
-        let mut spend_params =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("unable to read MASP Spend params");
-
-        trace!("Contributing to MASP Spend...");
-        let progress_update_interval: u32 = 0;
-
-        let spend_hash = spend_params.contribute(&mut rng, &progress_update_interval);
-        debug!("MASP Spend hash is {}", pretty_hash!(&spend_hash));
-        trace!("Contributed to MASP Spend!");
-
-        //
-        // MASP Output circuit
-        //
-        trace!("Reading MASP Output...");
-        let mut output_params =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("unable to read MASP Output params");
-
-        trace!("Contributing to MASP Output...");
-        let progress_update_interval: u32 = 0;
-
-        let output_hash = output_params.contribute(&mut rng, &progress_update_interval);
-        debug!("MASP Output hash is {}", pretty_hash!(&output_hash));
-        trace!("Contributed to MASP Output!");
-
-        //
-        // MASP Convert circuit
-        //
-        trace!("Reading MASP Convert...");
-        let mut convert_params =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("unable to read MASP Convert params");
-
-        trace!("Contributing to MASP Convert...");
-        let progress_update_interval: u32 = 0;
-        let convert_hash = convert_params.contribute(&mut rng, &progress_update_interval);
-        debug!("MASP Convert hash is {}", pretty_hash!(&convert_hash));
-        trace!("Contributed to MASP Convert!");
-
+        let mut challenge_reader = &challenge_reader[64..];
+        let mut params = Vec::new();
         let mut h = Blake2b512::new();
-        h.update(&spend_hash);
-        h.update(&output_hash);
-        h.update(&convert_hash);
-        let h = h.finalize();
-        debug!("MASP Contribution hash is {}", pretty_hash!(&h));
-
-        info!("Contribution hash: 0x{:02x}", h.iter().format(""));
-
-        trace!("Writing MASP Spend parameters to file...");
-        spend_params
-            .write(&mut response_writer)
-            .expect("failed to write updated MASP Spend parameters");
-
-        trace!("Writing MASP Output parameters to file...");
-        output_params
-            .write(&mut response_writer)
-            .expect("failed to write updated MASP Output parameters");
-
-        trace!("Writing MASP Convert parameters to file...");
-        convert_params
-            .write(&mut response_writer)
-            .expect("failed to write updated MASP Convert parameters");
-
-        response_writer.flush().unwrap();
-    }
-
-    #[cfg(debug_assertions)]
-    pub fn contribute_test_masp<W: Write>(challenge_reader: &[u8], mut response_writer: W, rand_source: &RandomSource) {
-        // Create an RNG as following:
-        //  - if the user provides a seed, create the rng from that seed
-        //  - if the user provides entropy, create the rng from the combination of OS randomness and user entropy
-        let mut rng = {
-            use rand::{Rng, SeedableRng};
-            use rand_chacha::ChaChaRng;
-            use std::convert::TryInto;
-
-            match rand_source {
-                RandomSource::Entropy(e) => {
-                    let mut system_rng = rand::rngs::OsRng;
-                    let mut h = Blake2b512::new();
+        for name in circuits.names() {
+            trace!("Reading {}...", name);
+            let mut circuit_params = MPCParameters::read(&mut challenge_reader, false)
+                .unwrap_or_else(|_| panic!("unable to read {} params", name));
 
-                    // Gather 1024 bytes of entropy from the system
-                    for _ in 0..1024 {
-                        let r: u8 = system_rng.gen();
-                        h.update(&[r]);
-                    }
+            trace!("Contributing to {}...", name);
+            let progress_update_interval: u32 = 0;
 
-                    // Hash it all up to make a seed
-                    h.update(e.as_bytes());
-                    let digest = h.finalize();
+            let circuit_hash = circuit_params.contribute(&mut rng, &progress_update_interval);
+            debug!("{} hash is {}", name, pretty_hash!(&circuit_hash));
+            trace!("Contributed to {}!", name);
 
-                    ChaChaRng::from_seed(digest[0..32].try_into().unwrap())
-                }
-                RandomSource::Seed(s) => ChaChaRng::from_seed(*s),
-            }
-        };
-
-        let mut test_params =
-            MPCParameters::read(&challenge_reader[64..], false).expect("unable to read MASP Test params");
-
-        trace!("Contributing to Masp Test...");
-        let progress_update_interval: u32 = 0;
-
-        let test_hash = test_params.contribute(&mut rng, &progress_update_interval);
-
-        let mut h = Blake2b512::new();
-        h.update(&test_hash);
+            h.update(&circuit_hash);
+            params.push((name, circuit_params));
+        }
         let h = h.finalize();
+        debug!("Contribution hash is {}", pretty_hash!(&h));
 
-        debug!("Contribution hash: 0x{:02x}", h.iter().format(""));
-
-        trace!("Writing MASP Test parameters to file...");
+        info!("Contribution hash: 0x{:02x}", h.iter().format(""));
 
-        test_params
-            .write(&mut response_writer)
-            .expect("failed to write updated MASP Test parameters");
+        for (name, circuit_params) in params {
+            trace!("Writing {} parameters to file...", name);
+            circuit_params
+                .write(&mut response_writer)
+                .unwrap_or_else(|_| panic!("failed to write updated {} parameters", name));
+        }
 
         response_writer.flush().unwrap();
     }
@@ -321,7 +239,7 @@ mod tests {
     use crate::{
         authentication::{Dummy, Signature},
         commands::{Computation, Initialization, Seed, SEED_LENGTH},
-        storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object, StorageObject},
+        storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object, Storage, StorageObject},
         testing::prelude::*,
     };
     use setup_utils::calculate_hash;
@@ -372,7 +290,12 @@ mod tests {
             );
 
             if !storage.exists(response_locator) {
-                let expected_filesize = Object::anoma_contribution_file_size(round_height, 1);
+                let expected_filesize = Object::anoma_contribution_file_size(
+                    TEST_ENVIRONMENT_ANOMA.parameters().circuits(),
+                    round_height,
+                    1,
+                )
+                .unwrap();
                 storage.initialize(response_locator.clone(), expected_filesize).unwrap();
             }
             if !storage.exists(contribution_file_signature_locator) {
@@ -0,0 +1,175 @@
+//! Export of the final parameters of the ceremony in the format loaded by the Namada node.
+//!
+//! Each circuit gets a standalone `{circuit}.params` file containing its Groth16 [`Parameters`], as read by
+//! `masp_proofs::load_parameters`, and a `{circuit}.vk` file containing its serialized [`VerifyingKey`], the one
+//! embedded in the parameters. The node doesn't load a prepared key: it prepares the verifying key of the parameters
+//! with `prepare_verifying_key` when loading them, and bellman has no serialization for the prepared form. The same
+//! preparation step is done on an exported key by [`read_prepared_verifying_key`]. The BLAKE2b-512 checksums of the
+//! exported files are written to [`CHECKSUMS_FILE`], in the format of `b2sum`.
+
+use crate::{
+    commands::{transcript::write, TranscriptError, TranscriptFile, VerificationError},
+    environment::CircuitSet,
+};
+
+use bellman::groth16::{prepare_verifying_key, Parameters, PreparedVerifyingKey, VerifyingKey};
+use blake2::{Blake2b512, Digest};
+use bls12_381::Bls12;
+use masp_phase2::MPCParameters;
+use std::{fs, path::Path};
+use tracing::{debug, info};
+
+/// The file listing the BLAKE2b-512 checksums of the exported files. Can be checked with `b2sum -c`.
+pub const CHECKSUMS_FILE: &str = "checksums.b2";
+
+/// The files exported for a circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedCircuit {
+    /// The name of the circuit.
+    pub name: String,
+    /// The name of the parameters file.
+    pub params_file: String,
+    /// The BLAKE2b-512 hash of the parameters file, in hexadecimal.
+    pub params_hash: String,
+    /// The name of the verifying key file.
+    pub vk_file: String,
+    /// The BLAKE2b-512 hash of the verifying key file, in hexadecimal.
+    pub vk_hash: String,
+}
+
+///
+/// Splits the given contribution file into the parameters and verifying keys of each circuit and writes them,
+/// together with their checksums, to the output directory.
+///
+/// The contribution can be any verified contribution of the transcript or the final contribution generated by
+/// the beacon.
+///
+pub fn export_parameters(
+    contribution: &[u8],
+    circuits: &CircuitSet,
+    output: &Path,
+) -> Result<Vec<ExportedCircuit>, TranscriptError> {
+    // Skip the hash of the previous contribution
+    let mut reader = contribution.get(64..).ok_or_else(|| {
+        TranscriptError::InvalidParameters(VerificationError::TranscriptInconsistency(String::from(
+            "the contribution is shorter than its hash prefix",
+        )))
+    })?;
+
+    let mut params = Vec::new();
+    for name in circuits.names() {
+        debug!("Reading {} parameters", name);
+        let circuit_params = MPCParameters::read(&mut reader, true).map_err(|e| {
+            TranscriptError::InvalidParameters(VerificationError::Deserialization {
+                circuit: name.to_string(),
+                file: TranscriptFile::Response,
+                reason: e.to_string(),
+            })
+        })?;
+        params.push((name, circuit_params));
+    }
+    if !reader.is_empty() {
+        return Err(TranscriptError::InvalidParameters(
+            VerificationError::TranscriptInconsistency(format!(
+                "{} trailing bytes after the parameters of {}",
+                reader.len(),
+                circuits
+            )),
+        ));
+    }
+
+    fs::create_dir_all(output).map_err(|e| TranscriptError::Io(output.to_path_buf(), e))?;
+
+    let mut exported = Vec::new();
+    let mut checksums = String::new();
+    for (name, circuit_params) in params {
+        let groth16_params: &Parameters<Bls12> = circuit_params.get_params();
+
+        let mut params_bytes = Vec::new();
+        groth16_params
+            .write(&mut params_bytes)
+            .expect("Writing to a vector can't fail");
+        let mut vk_bytes = Vec::new();
+        groth16_params
+            .vk
+            .write(&mut vk_bytes)
+            .expect("Writing to a vector can't fail");
+
+        let circuit = ExportedCircuit {
+            name: name.to_string(),
+            params_file: format!("{}.params", name),
+            params_hash: hex::encode(Blake2b512::digest(&params_bytes)),
+            vk_file: format!("{}.vk", name),
+            vk_hash: hex::encode(Blake2b512::digest(&vk_bytes)),
+        };
+        write(&output.join(&circuit.params_file), &params_bytes)?;
+        write(&output.join(&circuit.vk_file), &vk_bytes)?;
+        info!("Exported {} parameters with hash {}", name, circuit.params_hash);
+
+        checksums.push_str(&format!("{}  {}\n", circuit.params_hash, circuit.params_file));
+        checksums.push_str(&format!("{}  {}\n", circuit.vk_hash, circuit.vk_file));
+        exported.push(circuit);
+    }
+    write(&output.join(CHECKSUMS_FILE), checksums.as_bytes())?;
+
+    Ok(exported)
+}
+
+///
+/// Reads an exported verifying key and prepares it for the verification of proofs, as the node does with the
+/// verifying key of the parameters it loads.
+///
+pub fn read_prepared_verifying_key(path: &Path) -> Result<PreparedVerifyingKey<Bls12>, TranscriptError> {
+    let vk = fs::read(path)
+        .and_then(|bytes| VerifyingKey::<Bls12>::read(&bytes[..]))
+        .map_err(|e| TranscriptError::Io(path.to_path_buf(), e))?;
+
+    Ok(prepare_verifying_key(&vk))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::transcript::initial_transcript;
+
+    #[test]
+    fn test_export_parameters() {
+        let circuits = CircuitSet::MaspTest;
+        let dir = tempfile::tempdir().unwrap();
+        let contribution = initial_transcript(&circuits).unwrap();
+
+        let exported = export_parameters(&contribution, &circuits, dir.path()).unwrap();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].params_file, "masp-test.params");
+
+        // The exported files can be loaded back and match their checksums
+        let params_bytes = fs::read(dir.path().join(&exported[0].params_file)).unwrap();
+        assert_eq!(hex::encode(Blake2b512::digest(&params_bytes)), exported[0].params_hash);
+        let params = Parameters::<Bls12>::read(&params_bytes[..], true).unwrap();
+
+        let vk_bytes = fs::read(dir.path().join(&exported[0].vk_file)).unwrap();
+        assert_eq!(hex::encode(Blake2b512::digest(&vk_bytes)), exported[0].vk_hash);
+        let vk = VerifyingKey::<Bls12>::read(&vk_bytes[..]).unwrap();
+        assert!(vk == params.vk);
+        read_prepared_verifying_key(&dir.path().join(&exported[0].vk_file)).unwrap();
+
+        let checksums = fs::read_to_string(dir.path().join(CHECKSUMS_FILE)).unwrap();
+        assert_eq!(
+            checksums,
+            format!(
+                "{}  masp-test.params\n{}  masp-test.vk\n",
+                exported[0].params_hash, exported[0].vk_hash
+            )
+        );
+
+        // Trailing bytes after the parameters
+        let mut contribution = contribution;
+        contribution.push(0);
+        assert!(matches!(
+            export_parameters(&contribution, &circuits, dir.path()),
+            Err(TranscriptError::InvalidParameters(
+                VerificationError::TranscriptInconsistency(_)
+            ))
+        ));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    environment::Environment,
-    storage::{ContributionLocator, Disk, Locator, Object, StorageObject},
+    environment::{CircuitSet, Environment},
+    storage::{ContributionLocator, Locator, Object, Storage},
     CoordinatorError,
 };
 
@@ -10,25 +10,6 @@ use setup_utils::{blank_hash, calculate_hash};
 use std::{io::Write, time::Instant};
 use tracing::{debug, error, info, trace};
 
-use masp_phase2::MPCParameters;
-
-use bellman::{Circuit, ConstraintSystem, SynthesisError};
-use bls12_381::Scalar;
-
-struct TestCircuit {
-    x: Option<Scalar>,
-}
-impl Circuit<Scalar> for TestCircuit {
-    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
-        let x_value = self.x;
-        let x = cs.alloc(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?;
-
-        cs.enforce(|| "x = x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x);
-
-        Ok(())
-    }
-}
-
 pub(crate) struct Initialization;
 
 impl Initialization {
@@ -40,7 +21,7 @@ impl Initialization {
     #[inline]
     pub(crate) fn run(
         environment: &Environment,
-        storage: &mut Disk,
+        storage: &mut impl Storage,
         round_height: u64,
         chunk_id: u64,
     ) -> anyhow::Result<Vec<u8>> {
@@ -48,7 +29,8 @@ impl Initialization {
         let start = Instant::now();
 
         // Determine the expected challenge size.
-        let expected_challenge_size = Object::anoma_contribution_file_size(0, 0);
+        let settings = environment.parameters();
+        let expected_challenge_size = Object::anoma_contribution_file_size(settings.circuits(), 0, 0)?;
         trace!("Expected challenge file size is {}", expected_challenge_size);
 
         // Initialize and fetch a writer for the contribution locator so the output is saved.
@@ -56,12 +38,11 @@ impl Initialization {
         storage.initialize(contribution_locator.clone(), expected_challenge_size as u64)?;
 
         // Run ceremony initialization on chunk.
-        let settings = environment.parameters();
-
+        let circuits = settings.circuits();
         if let Err(error) = match settings.curve() {
-            CurveKind::Bls12_381 => Self::initialization(storage.writer(&contribution_locator)?.as_mut()),
-            CurveKind::Bls12_377 => Self::initialization(storage.writer(&contribution_locator)?.as_mut()),
-            CurveKind::BW6 => Self::initialization(storage.writer(&contribution_locator)?.as_mut()),
+            CurveKind::Bls12_381 => Self::initialization(circuits, storage.writer(&contribution_locator)?.as_mut()),
+            CurveKind::Bls12_377 => Self::initialization(circuits, storage.writer(&contribution_locator)?.as_mut()),
+            CurveKind::BW6 => Self::initialization(circuits, storage.writer(&contribution_locator)?.as_mut()),
         } {
             error!("Initialization failed with {}", error);
             return Err(CoordinatorError::InitializationFailed.into());
@@ -86,7 +67,7 @@ impl Initialization {
 
     /// Runs Phase 2 initialization on the given parameters.
     #[inline]
-    fn initialization(mut writer: &mut [u8]) -> Result<(), CoordinatorError> {
+    fn initialization(circuits: &CircuitSet, mut writer: &mut [u8]) -> Result<(), CoordinatorError> {
         info!("Initializing Phase 2 Initialization");
         // The initialization contribution file contains [blank_hash, init.params]
         // The circuit parameters are appended to the blank_hash
@@ -99,14 +80,17 @@ impl Initialization {
 
         info!("Starting Phase 2 initialization operation");
 
-        //
-        // NOTE: Add your MPC Parameters initialization function below
-        //
-        #[cfg(debug_assertions)]
-        Self::initialize_test_masp(&mut writer);
+        // The parameters of the circuits are written one after the other, in order
+        for circuit in circuits.circuits()? {
+            trace!("Creating initial parameters for {}...", circuit.name());
+            let params = circuit
+                .initial_parameters()
+                .map_err(|_| CoordinatorError::CircuitSynthesisFailed(circuit.name().to_string()))?;
 
-        #[cfg(not(debug_assertions))]
-        Self::initialize_masp(&mut writer);
+            trace!("Writing initial {} parameters to file...", circuit.name());
+            params.write(&mut writer)?;
+        }
+        writer.flush()?;
 
         trace!("Completed Phase 2 initialization operation");
 
@@ -116,7 +100,7 @@ impl Initialization {
     /// Compute both contribution hashes and check for equivalence.
     #[inline]
     fn check_hash(
-        storage: &Disk,
+        storage: &impl Storage,
         contribution_locator: &Locator,
         next_contribution_locator: &Locator,
     ) -> anyhow::Result<Vec<u8>> {
@@ -132,95 +116,6 @@ impl Initialization {
 
         Ok(contribution_hash_1.to_vec())
     }
-
-    #[inline]
-    #[cfg(not(debug_assertions))]
-    fn initialize_masp(mut writer: &mut [u8]) {
-        //
-        // MASP spend circuit
-        //
-        trace!("Creating initial parameters for MASP Spend...");
-        let spend_params = MPCParameters::new(
-            masp_proofs::circuit::sapling::Spend {
-                value_commitment: None,
-                proof_generation_key: None,
-                payment_address: None,
-                commitment_randomness: None,
-                ar: None,
-                auth_path: vec![None; 32], // Tree depth is 32 for sapling
-                anchor: None,
-            },
-            //should_filter_points_at_infinity,
-            //radix_directory,
-        )
-        .unwrap();
-        trace!("Writing initial MASP Spend parameters to file...",);
-
-        spend_params
-            .write(&mut writer)
-            .expect("unable to write MASP Spend params");
-        //
-        // MASP output circuit
-        //
-        trace!("Creating initial parameters for MASP Output...");
-        let output_params = MPCParameters::new(
-            masp_proofs::circuit::sapling::Output {
-                value_commitment: None,
-                payment_address: None,
-                commitment_randomness: None,
-                esk: None,
-                asset_identifier: vec![None; 256],
-            },
-            //should_filter_points_at_infinity,
-            //radix_directory,
-        )
-        .unwrap();
-
-        trace!("Writing initial MASP Output parameters to file...",);
-
-        output_params
-            .write(&mut writer)
-            .expect("unable to write MASP Output params");
-
-        //
-        // MASP Convert circuit
-        //
-        trace!("Creating initial parameters for MASP Convert...");
-        let convert_params = MPCParameters::new(
-            masp_proofs::circuit::convert::Convert {
-                value_commitment: None,
-                auth_path: vec![None; 32], // Tree depth is 32 for sapling
-                anchor: None,
-            },
-            //should_filter_points_at_infinity,
-            //radix_directory,
-        )
-        .unwrap();
-
-        trace!("Writing initial MASP Convert parameters to file...",);
-
-        convert_params
-            .write(&mut writer)
-            .expect("unable to write MASP Convert params");
-
-        writer.flush().unwrap();
-    }
-
-    #[inline]
-    #[cfg(debug_assertions)]
-    fn initialize_test_masp(mut writer: &mut [u8]) {
-        // MASP Test circuit
-        trace!("Creating initial parameters for MASP Test Circuit...");
-        let instance = TestCircuit { x: Some(Scalar::one()) };
-        let test_params = MPCParameters::new(instance).unwrap();
-        trace!("Writing initial MASP Test Circuit parameters to file...",);
-
-        test_params
-            .write(&mut writer)
-            .expect("unable to write MASP Test Circuit params");
-
-        writer.flush().unwrap();
-    }
 }
 
 #[cfg(test)]
@@ -14,11 +14,34 @@ pub(crate) mod verification;
 #[cfg(any(test, feature = "operator"))]
 pub(crate) use verification::*;
 
+#[cfg(any(test, feature = "operator"))]
+pub mod backup;
+#[cfg(any(test, feature = "operator"))]
+pub use backup::*;
+
+#[cfg(any(test, feature = "operator"))]
+pub mod beacon;
+#[cfg(any(test, feature = "operator"))]
+pub use beacon::*;
+
+#[cfg(any(test, feature = "operator"))]
+pub mod export;
+#[cfg(any(test, feature = "operator"))]
+pub use export::*;
+
+#[cfg(any(test, feature = "operator"))]
+pub mod transcript;
+#[cfg(any(test, feature = "operator"))]
+pub use transcript::*;
+
+pub mod verification_error;
+pub use verification_error::*;
+
 #[cfg(any(test, feature = "operator"))]
 use crate::{
     authentication::Signature,
     objects::{ContributionFileSignature, ContributionState},
-    storage::{Disk, Locator, StorageLocator, StorageObject},
+    storage::{Locator, Storage},
     CoordinatorError,
 };
 
@@ -47,7 +70,7 @@ pub type SigningKey = String;
 #[cfg(any(test, feature = "operator"))]
 #[inline]
 pub(crate) fn write_contribution_file_signature(
-    storage: &mut Disk,
+    storage: &mut impl Storage,
     signature: Arc<dyn Signature>,
     signing_key: &SigningKey,
     challenge_locator: &Locator,
@@ -0,0 +1,544 @@
+//! Verification of the whole transcript of a ceremony, as stored by the [`Disk`](crate::storage::Disk) storage.
+//!
+//! The transcript is verified from the initial parameters of the circuits up to the last verified contribution:
+//! every link between two consecutive verified contribution files is checked against the hashes of its
+//! [`ContributionFileSignature`], whose signatures are verified for the participants recorded in the round state,
+//! and the contribution is verified again for every circuit. The contribution files of the archived rounds are read
+//! from the archive directory of the transcript.
+
+use crate::{
+    authentication::{Production, Signature},
+    commands::{TranscriptFile, Verification, VerificationError},
+    environment::CircuitSet,
+    objects::{ContributionFileSignature, Participant, Round},
+    storage::{archived_path, read_archived, ARCHIVE_DIRECTORY},
+    CoordinatorError,
+};
+use setup_utils::{blank_hash, calculate_hash};
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use thiserror::Error;
+use tracing::{debug, info};
+
+/// The only chunk of the ceremony.
+const CHUNK_ID: u64 = 0;
+
+#[derive(Debug, Error)]
+pub enum TranscriptError {
+    #[error("The beacon has already been applied, see {}", .0.display())]
+    BeaconAlreadyApplied(PathBuf),
+    #[error("The final contribution doesn't match the beacon: {0}")]
+    BeaconMismatch(String),
+    #[error("Couldn't read the archived copy of {}: {}", .0.display(), .1)]
+    Archive(PathBuf, CoordinatorError),
+    #[error("Couldn't generate the initial parameters: {0}")]
+    Circuits(CoordinatorError),
+    #[error("Round {round_height} contribution {contribution_id} is missing from the transcript")]
+    ContributionMissing { round_height: u64, contribution_id: u64 },
+    #[error("The transcript doesn't start from the initial parameters of {0}")]
+    InitialParametersMismatch(CircuitSet),
+    #[error("Invalid beacon: {0}")]
+    InvalidBeacon(String),
+    #[error("Couldn't parse the beacon record {}: {}", .0.display(), .1)]
+    InvalidBeaconFile(PathBuf, serde_json::Error),
+    #[error("Invalid parameters: {0}")]
+    InvalidParameters(VerificationError),
+    #[error("Couldn't parse the round state {}: {}", .0.display(), .1)]
+    InvalidRoundFile(PathBuf, serde_json::Error),
+    #[error("Couldn't parse the contribution signature {}: {}", .0.display(), .1)]
+    InvalidSignatureFile(PathBuf, serde_json::Error),
+    #[error("Couldn't read {}: {}", .0.display(), .1)]
+    Io(PathBuf, std::io::Error),
+    #[error("Round {round_height} contribution {contribution_id}: the {role} is missing from the round state")]
+    ParticipantMissing {
+        round_height: u64,
+        contribution_id: u64,
+        role: &'static str,
+    },
+    #[error(
+        "Round {round_height} contribution {contribution_id}: the contribution signature of {participant} is invalid"
+    )]
+    SignatureInvalid {
+        round_height: u64,
+        contribution_id: u64,
+        participant: Participant,
+    },
+    #[error(
+        "Round {round_height} contribution {contribution_id}: the {file} hash doesn't match the one of the contribution signature"
+    )]
+    SignatureMismatch {
+        round_height: u64,
+        contribution_id: u64,
+        file: &'static str,
+    },
+    #[error("Round {round_height} contribution {contribution_id}: {error}")]
+    Verification {
+        round_height: u64,
+        contribution_id: u64,
+        error: VerificationError,
+    },
+}
+
+/// A contribution of the transcript which passed verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedContribution {
+    /// The round height of the contribution.
+    pub round_height: u64,
+    /// The ID of the contribution in its round.
+    pub contribution_id: u64,
+    /// The hash of the contributed parameters, as published by the contributor.
+    pub contribution_hash: String,
+}
+
+///
+/// Verifies the transcript of a ceremony stored in the given directory, from the initial parameters
+/// of the given circuits to the last verified contribution.
+///
+/// On success, returns the verified contributions in order.
+///
+pub fn verify_transcript(path: &Path, circuits: &CircuitSet) -> Result<Vec<VerifiedContribution>, TranscriptError> {
+    let contributions = verified_contributions(path)?;
+    info!(
+        "Found {} verified contribution files in {}",
+        contributions.len(),
+        path.display()
+    );
+
+    // The transcript starts with the initial parameters, copied over to the first round.
+    let initial_transcript = initial_transcript(circuits)?;
+    for (index, &(round_height, contribution_id)) in [(0, 0), (1, 0)].iter().enumerate() {
+        if contributions.get(index) != Some(&(round_height, contribution_id)) {
+            return Err(TranscriptError::ContributionMissing {
+                round_height,
+                contribution_id,
+            });
+        }
+        let contribution = read_transcript_file(path, &contribution_path(path, round_height, contribution_id, true))?;
+        if contribution != initial_transcript {
+            return Err(TranscriptError::InitialParametersMismatch(circuits.clone()));
+        }
+    }
+
+    let mut verified = Vec::new();
+    for link in contributions.windows(2).skip(1) {
+        let (round_height, contribution_id) = link[0];
+        let response_id = contribution_id + 1;
+
+        // The next challenge is either the next one of the round or the first one of the next round.
+        if link[1] != (round_height, response_id) && link[1] != (round_height + 1, 0) {
+            return Err(TranscriptError::ContributionMissing {
+                round_height,
+                contribution_id: response_id,
+            });
+        }
+
+        debug!("Verifying round {} contribution {}", round_height, response_id);
+        let contribution_hash = verify_link(path, circuits, link[0], link[1])?;
+        info!(
+            "Round {} contribution {} is valid with hash {}",
+            round_height, response_id, contribution_hash
+        );
+
+        verified.push(VerifiedContribution {
+            round_height,
+            contribution_id: response_id,
+            contribution_hash,
+        });
+    }
+
+    Ok(verified)
+}
+
+/// Verifies the contribution turning the `challenge` verified contribution file into the `next_challenge` one
+/// and returns its hash.
+fn verify_link(
+    path: &Path,
+    circuits: &CircuitSet,
+    challenge: (u64, u64),
+    next_challenge: (u64, u64),
+) -> Result<String, TranscriptError> {
+    let (round_height, contribution_id) = (challenge.0, challenge.1 + 1);
+    let signature_mismatch = |file| TranscriptError::SignatureMismatch {
+        round_height,
+        contribution_id,
+        file,
+    };
+
+    let signature_invalid = |participant: &Participant| TranscriptError::SignatureInvalid {
+        round_height,
+        contribution_id,
+        participant: participant.clone(),
+    };
+
+    let challenge = read_transcript_file(path, &contribution_path(path, challenge.0, challenge.1, true))?;
+    let next_challenge_path = contribution_path(path, next_challenge.0, next_challenge.1, true);
+    let next_challenge = read_transcript_file(path, &next_challenge_path)?;
+    let signature = read_signature(path, &signature_path(&next_challenge_path))?;
+
+    // The next challenge is signed by the verifier, the response by the contributor.
+    let (contributor, verifier) = contribution_participants(path, round_height, contribution_id)?;
+    if !verify_signature(&signature, &verifier) {
+        return Err(signature_invalid(&verifier));
+    }
+
+    let challenge_hash = calculate_hash(&challenge);
+    if hex::encode(challenge_hash) != signature.get_challenge_hash() {
+        return Err(signature_mismatch("challenge"));
+    }
+    if Some(hex::encode(calculate_hash(&next_challenge))) != *signature.get_next_challenge_hash() {
+        return Err(signature_mismatch("next challenge"));
+    }
+
+    // The next challenge is the response of the contributor, prefixed with the hash of the response
+    // instead of the hash of the challenge.
+    let mut response = challenge_hash.to_vec();
+    response.extend_from_slice(next_challenge.get(64..).unwrap_or_default());
+
+    let response_hash =
+        Verification::transform_pok_and_correctness(circuits, &challenge, &response).map_err(|error| {
+            TranscriptError::Verification {
+                round_height,
+                contribution_id,
+                error,
+            }
+        })?;
+    if hex::encode(response_hash) != signature.get_response_hash() {
+        return Err(signature_mismatch("response"));
+    }
+    if next_challenge[0..64] != response_hash[..] {
+        return Err(TranscriptError::Verification {
+            round_height,
+            contribution_id,
+            error: VerificationError::HashMismatch {
+                file: TranscriptFile::Response,
+            },
+        });
+    }
+
+    // The unverified response of the contributor and its signature may not have been kept.
+    let response_path = contribution_path(path, round_height, contribution_id, false);
+    if response_path.exists() && read(&response_path)? != response {
+        return Err(signature_mismatch("response"));
+    }
+    let response_signature_path = signature_path(&response_path);
+    if response_signature_path.exists() {
+        let response_signature = read_signature(path, &response_signature_path)?;
+        if response_signature.get_challenge_hash() != signature.get_challenge_hash() {
+            return Err(signature_mismatch("challenge"));
+        }
+        if response_signature.get_response_hash() != signature.get_response_hash() {
+            return Err(signature_mismatch("response"));
+        }
+        if !verify_signature(&response_signature, &contributor) {
+            return Err(signature_invalid(&contributor));
+        }
+    }
+
+    Ok(hex::encode(calculate_hash(&next_challenge[64..])))
+}
+
+/// Returns the (round height, contribution ID) of the verified contribution files of the transcript, in order,
+/// including the ones of the archived rounds.
+pub(super) fn verified_contributions(path: &Path) -> Result<Vec<(u64, u64)>, TranscriptError> {
+    let mut contributions = round_contributions(path, ".verified")?;
+    let archive_path = path.join(ARCHIVE_DIRECTORY);
+    if archive_path.is_dir() {
+        contributions.extend(round_contributions(&archive_path, ".verified.zst")?);
+    }
+    contributions.sort_unstable();
+    contributions.dedup();
+
+    Ok(contributions)
+}
+
+/// Returns the (round height, contribution ID) of the contribution files with the given suffix in the round
+/// directories of the given directory.
+fn round_contributions(path: &Path, suffix: &str) -> Result<Vec<(u64, u64)>, TranscriptError> {
+    let mut contributions = Vec::new();
+    for round_entry in read_dir(path)? {
+        let round_height = match parse_suffix(&round_entry, "round_", "") {
+            Some(round_height) => round_height,
+            None => continue,
+        };
+
+        let chunk_path = round_entry.join(format!("chunk_{}", CHUNK_ID));
+        if !chunk_path.is_dir() {
+            continue;
+        }
+
+        for contribution_entry in read_dir(&chunk_path)? {
+            if let Some(contribution_id) = parse_suffix(&contribution_entry, "contribution_", suffix) {
+                contributions.push((round_height, contribution_id));
+            }
+        }
+    }
+
+    Ok(contributions)
+}
+
+/// Returns the content of the initial verified contribution file of the given circuits.
+pub(super) fn initial_transcript(circuits: &CircuitSet) -> Result<Vec<u8>, TranscriptError> {
+    let mut transcript = blank_hash().to_vec();
+    for circuit in circuits.circuits().map_err(TranscriptError::Circuits)? {
+        debug!("Generating the initial {} parameters", circuit.name());
+        circuit
+            .initial_parameters()
+            .map_err(|_| {
+                TranscriptError::Circuits(CoordinatorError::CircuitSynthesisFailed(circuit.name().to_string()))
+            })?
+            .write(&mut transcript)
+            .map_err(|e| TranscriptError::Circuits(e.into()))?;
+    }
+
+    Ok(transcript)
+}
+
+pub(super) fn contribution_path(path: &Path, round_height: u64, contribution_id: u64, verified: bool) -> PathBuf {
+    path.join(format!("round_{}", round_height))
+        .join(format!("chunk_{}", CHUNK_ID))
+        .join(format!(
+            "contribution_{}.{}",
+            contribution_id,
+            if verified { "verified" } else { "unverified" }
+        ))
+}
+
+fn signature_path(contribution_path: &Path) -> PathBuf {
+    let mut path = contribution_path.as_os_str().to_owned();
+    path.push(".signature");
+    path.into()
+}
+
+/// Parses the number in the file name of the given path, between the given prefix and suffix.
+fn parse_suffix(path: &Path, prefix: &str, suffix: &str) -> Option<u64> {
+    let name = path.file_name()?.to_str()?;
+    u64::from_str(name.strip_prefix(prefix)?.strip_suffix(suffix)?).ok()
+}
+
+pub(super) fn read(path: &Path) -> Result<Vec<u8>, TranscriptError> {
+    fs::read(path).map_err(|e| TranscriptError::Io(path.to_path_buf(), e))
+}
+
+/// Reads the given file of the transcript stored in `path`, from the archive if its round has been archived.
+pub(super) fn read_transcript_file(path: &Path, file: &Path) -> Result<Vec<u8>, TranscriptError> {
+    let relative = match file.strip_prefix(path) {
+        Ok(relative) if !file.exists() && archived_path(path, relative).exists() => relative,
+        _ => return read(file),
+    };
+
+    match read_archived(path, relative) {
+        Ok(Some(content)) => Ok(content),
+        Ok(None) => read(file),
+        Err(e) => Err(TranscriptError::Archive(file.to_path_buf(), e)),
+    }
+}
+
+pub(super) fn write(path: &Path, content: &[u8]) -> Result<(), TranscriptError> {
+    fs::write(path, content).map_err(|e| TranscriptError::Io(path.to_path_buf(), e))
+}
+
+fn read_dir(path: &Path) -> Result<Vec<PathBuf>, TranscriptError> {
+    fs::read_dir(path)
+        .and_then(|entries| entries.map(|entry| entry.map(|entry| entry.path())).collect())
+        .map_err(|e| TranscriptError::Io(path.to_path_buf(), e))
+}
+
+fn read_signature(path: &Path, file: &Path) -> Result<ContributionFileSignature, TranscriptError> {
+    serde_json::from_slice(&read_transcript_file(path, file)?)
+        .map_err(|e| TranscriptError::InvalidSignatureFile(file.to_path_buf(), e))
+}
+
+/// Returns the contributor and the verifier of the given contribution, from the state of its round.
+fn contribution_participants(
+    path: &Path,
+    round_height: u64,
+    contribution_id: u64,
+) -> Result<(Participant, Participant), TranscriptError> {
+    let round_path = path.join(format!("round_{}", round_height)).join("state.json");
+    let round: Round = serde_json::from_slice(&read(&round_path)?)
+        .map_err(|e| TranscriptError::InvalidRoundFile(round_path.clone(), e))?;
+    let contribution = round
+        .chunk(CHUNK_ID)
+        .and_then(|chunk| chunk.get_contribution(contribution_id))
+        .map_err(|_| TranscriptError::ContributionMissing {
+            round_height,
+            contribution_id,
+        })?;
+
+    let participant_missing = |role| TranscriptError::ParticipantMissing {
+        round_height,
+        contribution_id,
+        role,
+    };
+    let contributor = contribution
+        .get_contributor()
+        .clone()
+        .ok_or_else(|| participant_missing("contributor"))?;
+    let verifier = contribution
+        .get_verifier()
+        .clone()
+        .ok_or_else(|| participant_missing("verifier"))?;
+
+    Ok((contributor, verifier))
+}
+
+/// Checks that the contribution file signature has been signed with the key of the given participant.
+fn verify_signature(signature: &ContributionFileSignature, participant: &Participant) -> bool {
+    signature.get_state().signature_message().map_or(false, |message| {
+        Production.verify(&participant.address(), &message, signature.get_signature())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        authentication::KeyPair,
+        commands::{Computation, RandomSource},
+        objects::ContributionState,
+    };
+    use serde_json::json;
+
+    fn write(path: &Path, content: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn sign(keypair: &KeyPair, state: ContributionState) -> ContributionFileSignature {
+        let signature = Production
+            .sign(keypair.sigkey(), &state.signature_message().unwrap())
+            .unwrap();
+        ContributionFileSignature::new(signature, state).unwrap()
+    }
+
+    #[test]
+    fn test_verify_transcript() {
+        let circuits = CircuitSet::MaspTest;
+        let dir = tempfile::tempdir().unwrap();
+
+        // Initialization
+        let initial = initial_transcript(&circuits).unwrap();
+        write(&contribution_path(dir.path(), 0, 0, true), &initial);
+        write(&contribution_path(dir.path(), 1, 0, true), &initial);
+
+        // Contribution and verification of round 1
+        let challenge_hash = calculate_hash(&initial);
+        let mut response = challenge_hash.to_vec();
+        Computation::contribute_circuits(&circuits, &initial, &mut response, &RandomSource::Seed([0; 32]));
+        let response_hash = calculate_hash(&response);
+
+        let mut next_challenge = response_hash.to_vec();
+        next_challenge.extend_from_slice(&response[64..]);
+        let state = ContributionState::new(
+            challenge_hash.to_vec(),
+            response_hash.to_vec(),
+            Some(calculate_hash(&next_challenge).to_vec()),
+        )
+        .unwrap();
+        let response_state = ContributionState::new(challenge_hash.to_vec(), response_hash.to_vec(), None).unwrap();
+
+        // The participants of the contribution are recorded in the round state
+        let contributor_keypair = KeyPair::new();
+        let verifier_keypair = KeyPair::new();
+        let contributor = Participant::new_contributor(contributor_keypair.pubkey());
+        let verifier = Participant::new_verifier(verifier_keypair.pubkey());
+        let round = json!({
+            "version": 1,
+            "height": 1,
+            "startedAt": null,
+            "finishedAt": null,
+            "contributorIds": [contributor],
+            "verifierIds": [verifier],
+            "chunks": [{
+                "chunkId": 0,
+                "lockHolder": null,
+                "contributions": {
+                    "1": {
+                        "contributorId": contributor,
+                        "verifierId": verifier,
+                        "verified": true,
+                    },
+                },
+            }],
+        });
+        write(
+            &dir.path().join("round_1").join("state.json"),
+            &serde_json::to_vec(&round).unwrap(),
+        );
+
+        let next_challenge_path = contribution_path(dir.path(), 2, 0, true);
+        let response_signature_path = signature_path(&contribution_path(dir.path(), 1, 1, false));
+        write(&next_challenge_path, &next_challenge);
+        write(
+            &signature_path(&next_challenge_path),
+            &serde_json::to_vec(&sign(&verifier_keypair, state.clone())).unwrap(),
+        );
+        write(
+            &response_signature_path,
+            &serde_json::to_vec(&sign(&contributor_keypair, response_state.clone())).unwrap(),
+        );
+
+        let verified = verify_transcript(dir.path(), &circuits).unwrap();
+        assert_eq!(
+            verified,
+            vec![VerifiedContribution {
+                round_height: 1,
+                contribution_id: 1,
+                contribution_hash: hex::encode(calculate_hash(&response[64..])),
+            }]
+        );
+
+        // The contributor signature has been tampered with
+        let response_signature = sign(&contributor_keypair, response_state.clone());
+        let mut signature_bytes = hex::decode(response_signature.get_signature()).unwrap();
+        signature_bytes[0] ^= 1;
+        let tampered_signature = ContributionFileSignature::new(hex::encode(signature_bytes), response_state).unwrap();
+        write(
+            &response_signature_path,
+            &serde_json::to_vec(&tampered_signature).unwrap(),
+        );
+        assert!(matches!(
+            verify_transcript(dir.path(), &circuits),
+            Err(TranscriptError::SignatureInvalid { participant, .. }) if participant == contributor
+        ));
+        fs::remove_file(&response_signature_path).unwrap();
+
+        // The next challenge has been signed by another verifier
+        write(
+            &signature_path(&next_challenge_path),
+            &serde_json::to_vec(&sign(&KeyPair::new(), state.clone())).unwrap(),
+        );
+        assert!(matches!(
+            verify_transcript(dir.path(), &circuits),
+            Err(TranscriptError::SignatureInvalid { participant, .. }) if participant == verifier
+        ));
+        write(
+            &signature_path(&next_challenge_path),
+            &serde_json::to_vec(&sign(&verifier_keypair, state)).unwrap(),
+        );
+
+        // The next challenge doesn't match its signature anymore
+        next_challenge[100] ^= 1;
+        write(&next_challenge_path, &next_challenge);
+        assert!(matches!(
+            verify_transcript(dir.path(), &circuits),
+            Err(TranscriptError::SignatureMismatch {
+                file: "next challenge",
+                ..
+            })
+        ));
+
+        // The initial parameters have been tampered with
+        let mut initial = initial;
+        initial[100] ^= 1;
+        write(&contribution_path(dir.path(), 0, 0, true), &initial);
+        assert!(matches!(
+            verify_transcript(dir.path(), &circuits),
+            Err(TranscriptError::InitialParametersMismatch(_))
+        ));
+    }
+}
@@ -1,10 +1,8 @@
 use crate::{
     authentication::Signature,
-    commands::SigningKey,
-    environment::Environment,
-    storage::{
-        ContributionLocator, ContributionSignatureLocator, Disk, Locator, Object, StorageLocator, StorageObject,
-    },
+    commands::{SigningKey, TranscriptFile, VerificationError},
+    environment::{CircuitSet, Environment},
+    storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object, Storage},
     CoordinatorError,
 };
 use phase2::helpers::CurveKind;
@@ -17,6 +15,9 @@ use blake2::{Blake2b512, Digest};
 use itertools::Itertools;
 use masp_phase2::{verify_contribution, MPCParameters};
 
+/// The hash of a response checked by [`Verification::check`], or the reason it's invalid.
+pub(crate) type CheckedResponse = Result<GenericArray<u8, U64>, CoordinatorError>;
+
 pub(crate) struct Verification;
 
 impl Verification {
@@ -25,16 +26,20 @@ impl Verification {
     /// round height, chunk ID, and contribution ID of the
     /// unverified response file.
     ///
+    /// The response is checked against its challenge, unless the
+    /// result of [`Verification::check`] is given.
+    ///
     #[inline]
     pub(crate) fn run(
         environment: &Environment,
-        storage: &mut Disk,
+        storage: &mut impl Storage,
         signature: Arc<dyn Signature>,
         signing_key: &SigningKey,
         round_height: u64,
         chunk_id: u64,
         current_contribution_id: u64,
         is_final_contribution: bool,
+        checked_response: Option<CheckedResponse>,
     ) -> Result<(), CoordinatorError> {
         info!(
             "Starting verification of round {} chunk {} contribution {}",
@@ -111,6 +116,7 @@ impl Verification {
             next_challenge_locator.clone(),
             round_height,
             current_contribution_id,
+            checked_response,
         ) {
             error!("Verification failed with {}", error);
             return Err(error);
@@ -152,17 +158,27 @@ impl Verification {
         Ok(())
     }
 
-    #[inline]
-    fn verification(
+    ///
+    /// Checks the response of the given contribution against its challenge,
+    /// and returns the hash of the response. The check only reads the
+    /// contribution files, it can run without the lock of the coordinator.
+    ///
+    pub(crate) fn check(
         environment: &Environment,
-        storage: &mut Disk,
-        _chunk_id: u64,
-        challenge_locator: Locator,
-        response_locator: Locator,
-        next_challenge_locator: Locator,
+        storage: &impl Storage,
         round_height: u64,
+        chunk_id: u64,
         contribution_id: u64,
-    ) -> Result<(), CoordinatorError> {
+    ) -> CheckedResponse {
+        let challenge_locator = Locator::ContributionFile(ContributionLocator::new(
+            round_height,
+            chunk_id,
+            contribution_id - 1,
+            true,
+        ));
+        let response_locator =
+            Locator::ContributionFile(ContributionLocator::new(round_height, chunk_id, contribution_id, false));
+
         // Check that the previous and current locators exist in storage.
         if !storage.exists(&challenge_locator) || !storage.exists(&response_locator) {
             return Err(CoordinatorError::ContributionLocatorMissing);
@@ -172,26 +188,54 @@ impl Verification {
         let settings = environment.parameters();
         let result = match settings.curve() {
             CurveKind::Bls12_381 => Self::transform_pok_and_correctness(
+                settings.circuits(),
                 storage.reader(&challenge_locator)?.as_ref(),
                 storage.reader(&response_locator)?.as_ref(),
             ),
             CurveKind::Bls12_377 => Self::transform_pok_and_correctness(
+                settings.circuits(),
                 storage.reader(&challenge_locator)?.as_ref(),
                 storage.reader(&response_locator)?.as_ref(),
             ),
             CurveKind::BW6 => Self::transform_pok_and_correctness(
+                settings.circuits(),
                 storage.reader(&challenge_locator)?.as_ref(),
                 storage.reader(&response_locator)?.as_ref(),
             ),
         };
-        let response_hash = match result {
-            Ok(response_hash) => response_hash,
-            Err(error) => {
-                error!("Verification failed with {}", error);
-                return Err(CoordinatorError::VerificationFailed.into());
+        result.map_err(|error| {
+            error!("Verification failed with {}", error);
+            error.into()
+        })
+    }
+
+    #[inline]
+    fn verification(
+        environment: &Environment,
+        storage: &mut impl Storage,
+        chunk_id: u64,
+        challenge_locator: Locator,
+        response_locator: Locator,
+        next_challenge_locator: Locator,
+        round_height: u64,
+        contribution_id: u64,
+        checked_response: Option<CheckedResponse>,
+    ) -> Result<(), CoordinatorError> {
+        // Check that the previous and current locators exist in storage.
+        if !storage.exists(&challenge_locator) || !storage.exists(&response_locator) {
+            return Err(CoordinatorError::ContributionLocatorMissing);
+        }
+
+        // The response is checked again if it changed since it was checked.
+        let response_hash = match checked_response {
+            Some(Ok(response_hash)) if calculate_hash(storage.reader(&response_locator)?.as_ref()) == response_hash => {
+                response_hash
             }
+            Some(Err(error)) => return Err(error),
+            _ => Self::check(environment, &*storage, round_height, chunk_id, contribution_id)?,
         };
 
+        let settings = environment.parameters();
         trace!("Verification succeeded! Writing the next challenge file");
 
         // Fetch the compression settings.
@@ -213,7 +257,7 @@ impl Verification {
             if !storage.exists(&next_challenge_locator) {
                 storage.initialize(
                     next_challenge_locator.clone(),
-                    Object::anoma_contribution_file_size(round_height, contribution_id),
+                    Object::anoma_contribution_file_size(settings.circuits(), round_height, contribution_id)?,
                 )?;
             }
 
@@ -255,7 +299,10 @@ impl Verification {
             debug!("The saved response hash is {}", pretty_hash!(&saved_response_hash));
             if response_hash.as_slice() != saved_response_hash {
                 error!("Response hash does not match the saved response hash.");
-                return Err(CoordinatorError::ContributionHashMismatch);
+                return Err(VerificationError::HashMismatch {
+                    file: TranscriptFile::Response,
+                }
+                .into());
             }
         }
 
@@ -263,21 +310,30 @@ impl Verification {
     }
 
     #[inline]
-    fn transform_pok_and_correctness(
+    pub(crate) fn transform_pok_and_correctness(
+        circuits: &CircuitSet,
         challenge_reader: &[u8],
         response_reader: &[u8],
-    ) -> Result<GenericArray<u8, U64>, CoordinatorError> {
+    ) -> Result<GenericArray<u8, U64>, VerificationError> {
         debug!("Verifying challenges");
 
+        // Check that the response has the size expected from the challenge.
+        let expected_response_size = challenge_reader.len() as u64 + circuits.per_round_file_size_increase();
+        if response_reader.len() as u64 != expected_response_size {
+            return Err(VerificationError::TranscriptInconsistency(format!(
+                "expected a response of {} bytes, found {} bytes",
+                expected_response_size,
+                response_reader.len()
+            )));
+        }
+
         // Check that the challenge hashes match.
         let _challenge_hash = {
             // Compute the challenge hash using the challenge file.
             let challenge_hash = calculate_hash(challenge_reader.as_ref());
 
             // Fetch the challenge hash from the response file.
-            let saved_challenge_hash = &response_reader
-                .get(0..64)
-                .ok_or(CoordinatorError::StorageReaderFailed)?[..];
+            let saved_challenge_hash = &response_reader[0..64];
 
             // Check that the challenge hashes match.
             debug!("The challenge hash is {}", pretty_hash!(&challenge_hash));
@@ -286,7 +342,9 @@ impl Verification {
                 true => challenge_hash,
                 false => {
                     error!("Challenge hash does not match saved challenge hash.");
-                    return Err(CoordinatorError::ContributionHashMismatch);
+                    return Err(VerificationError::HashMismatch {
+                        file: TranscriptFile::Challenge,
+                    });
                 }
             }
         };
@@ -303,11 +361,7 @@ impl Verification {
 
         trace!("Starting verification");
 
-        #[cfg(debug_assertions)]
-        Self::verify_test_masp(&challenge_reader, &response_reader);
-
-        #[cfg(not(debug_assertions))]
-        Self::verify_masp(&challenge_reader, &response_reader);
+        Self::verify_circuits(circuits, &challenge_reader, &response_reader)?;
 
         trace!("Completed verification");
 
@@ -315,85 +369,57 @@ impl Verification {
     }
 
     #[inline]
-    #[cfg(not(debug_assertions))]
-    fn verify_masp(challenge_reader: &[u8], response_reader: &[u8]) {
-        trace!("Reading MASP Spend old parameters...");
-        let mut masp_challenge_reader = &challenge_reader[64..];
-        let mut masp_response_reader = &response_reader[64..];
-
-        let masp_spend =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("couldn't deserialize MASP Spend params");
-
-        trace!("Reading MASP Output old parameters...");
-        let masp_output =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("couldn't deserialize MASP Output params");
-
-        trace!("Reading MASP Convert old parameters...");
-        let masp_convert =
-            MPCParameters::read(&mut masp_challenge_reader, false).expect("couldn't deserialize MASP Convert params");
-
-        trace!("Reading MASP Spend new parameters...");
-        let new_masp_spend =
-            MPCParameters::read(&mut masp_response_reader, true).expect("couldn't deserialize MASP Spend new_params");
-
-        trace!("Reading MASP Output new parameters...");
-        let new_masp_output =
-            MPCParameters::read(&mut masp_response_reader, true).expect("couldn't deserialize MASP Output new_params");
-
-        trace!("Reading MASP Convert new parameters...");
-        let new_masp_convert =
-            MPCParameters::read(&mut masp_response_reader, true).expect("couldn't deserialize MASP Convert new_params");
-
-        trace!("Verifying MASP Spend...");
-        let spend_hash = match verify_contribution(&masp_spend, &new_masp_spend) {
-            Ok(hash) => hash,
-            Err(_) => panic!("invalid MASP Spend transformation!"),
-        };
-        debug!("MASP Spend hash is {}", pretty_hash!(&spend_hash));
+    fn verify_circuits(
+        circuits: &CircuitSet,
+        challenge_reader: &[u8],
+        response_reader: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut challenge_reader = &challenge_reader[64..];
+        let mut response_reader = &response_reader[64..];
 
-        trace!("Verifying MASP Output...");
-        let output_hash = match verify_contribution(&masp_output, &new_masp_output) {
-            Ok(hash) => hash,
-            Err(_) => panic!("invalid MASP Output transformation!"),
-        };
-        debug!("MASP Output hash is {}", pretty_hash!(&output_hash));
+        let mut h = Blake2b512::new();
+        for name in circuits.names() {
+            trace!("Reading {} old parameters...", name);
+            let params =
+                MPCParameters::read(&mut challenge_reader, false).map_err(|e| VerificationError::Deserialization {
+                    circuit: name.to_string(),
+                    file: TranscriptFile::Challenge,
+                    reason: e.to_string(),
+                })?;
+
+            trace!("Reading {} new parameters...", name);
+            let new_params =
+                MPCParameters::read(&mut response_reader, true).map_err(|e| VerificationError::Deserialization {
+                    circuit: name.to_string(),
+                    file: TranscriptFile::Response,
+                    reason: e.to_string(),
+                })?;
+
+            trace!("Verifying {}...", name);
+            let circuit_hash =
+                verify_contribution(&params, &new_params).map_err(|_| VerificationError::InvalidDelta {
+                    circuit: name.to_string(),
+                })?;
+            debug!("{} hash is {}", name, pretty_hash!(&circuit_hash));
+
+            h.update(&circuit_hash);
+        }
 
-        trace!("Verifying MASP Convert...");
-        let convert_hash = match verify_contribution(&masp_convert, &new_masp_convert) {
-            Ok(hash) => hash,
-            Err(_) => panic!("invalid MASP Convert transformation!"),
-        };
-        debug!("MASP Convert hash is {}", pretty_hash!(&convert_hash));
+        // Check that the parameters of all the circuits account for the whole response.
+        if !response_reader.is_empty() {
+            return Err(VerificationError::TranscriptInconsistency(format!(
+                "{} trailing bytes after the parameters of {}",
+                response_reader.len(),
+                circuits
+            )));
+        }
 
-        let mut h = Blake2b512::new();
-        h.update(&spend_hash);
-        h.update(&output_hash);
-        h.update(&convert_hash);
         let h = h.finalize();
 
         info!("Verification hash: 0x{:02x}", h.iter().format(""));
-        debug!("MASP Contribution hash is {}", pretty_hash!(&h));
-    }
-
-    #[inline]
-    #[cfg(debug_assertions)]
-    fn verify_test_masp(challenge_reader: &[u8], response_reader: &[u8]) {
-        let masp_test =
-            MPCParameters::read(&challenge_reader[64..], false).expect("couldn't deserialize MASP Test params");
-
-        let new_masp_test =
-            MPCParameters::read(&response_reader[64..], true).expect("couldn't deserialize MASP Spend new_params");
-
-        let test_hash = match verify_contribution(&masp_test, &new_masp_test) {
-            Ok(hash) => hash,
-            Err(_) => panic!("invalid MASP Spend transformation!"),
-        };
+        debug!("Contribution hash is {}", pretty_hash!(&h));
 
-        let mut h = Blake2b512::new();
-        h.update(&test_hash);
-        let h = h.finalize();
-
-        debug!("Verification hash: 0x{:02x}", h.iter().format(""));
+        Ok(())
     }
 
     #[inline]
@@ -416,14 +442,17 @@ mod tests {
 
     use crate::{
         authentication::Dummy,
-        commands::{Computation, Seed, Verification, SEED_LENGTH},
-        storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object},
+        circuits::{self, MASP_TEST},
+        commands::{Computation, RandomSource, Seed, TranscriptFile, Verification, VerificationError, SEED_LENGTH},
+        environment::CircuitSet,
+        storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object, Storage},
         testing::prelude::*,
         Coordinator,
     };
 
     use once_cell::sync::Lazy;
     use rand::RngCore;
+    use setup_utils::calculate_hash;
     use time::OffsetDateTime;
 
     #[test]
@@ -477,7 +506,12 @@ mod tests {
 
             if !storage.exists(response_locator) {
                 // let expected_filesize = Object::contribution_file_size(&TEST_ENVIRONMENT_ANOMA, chunk_id, false);
-                let expected_filesize = Object::anoma_contribution_file_size(round_height, 1);
+                let expected_filesize = Object::anoma_contribution_file_size(
+                    TEST_ENVIRONMENT_ANOMA.parameters().circuits(),
+                    round_height,
+                    1,
+                )
+                .unwrap();
                 storage.initialize(response_locator.clone(), expected_filesize).unwrap();
             }
             if !storage.exists(contribution_file_signature_locator) {
@@ -525,4 +559,49 @@ mod tests {
             assert!(storage.exists(&next));
         }
     }
+
+    #[test]
+    fn test_verification_errors() {
+        let circuits = CircuitSet::MaspTest;
+
+        let mut challenge = vec![0; 64];
+        circuits::get(MASP_TEST)
+            .unwrap()
+            .initial_parameters()
+            .unwrap()
+            .write(&mut challenge)
+            .unwrap();
+
+        let mut response = calculate_hash(&challenge).to_vec();
+        Computation::contribute_circuits(&circuits, &challenge, &mut response, &RandomSource::Seed([0; 32]));
+        assert!(Verification::transform_pok_and_correctness(&circuits, &challenge, &response).is_ok());
+
+        // The response was computed from another challenge.
+        let mut other_challenge = challenge.clone();
+        other_challenge[0] = 1;
+        assert_eq!(
+            Verification::transform_pok_and_correctness(&circuits, &other_challenge, &response).unwrap_err(),
+            VerificationError::HashMismatch {
+                file: TranscriptFile::Challenge
+            }
+        );
+
+        // The response is truncated.
+        assert!(matches!(
+            Verification::transform_pok_and_correctness(&circuits, &challenge, &response[..response.len() - 1])
+                .unwrap_err(),
+            VerificationError::TranscriptInconsistency(_)
+        ));
+
+        // The response carries the challenge parameters without any contribution.
+        let mut unchanged = calculate_hash(&challenge).to_vec();
+        unchanged.extend_from_slice(&challenge[64..]);
+        unchanged.resize(response.len(), 0);
+        assert_eq!(
+            Verification::transform_pok_and_correctness(&circuits, &challenge, &unchanged).unwrap_err(),
+            VerificationError::InvalidDelta {
+                circuit: MASP_TEST.to_string()
+            }
+        );
+    }
 }
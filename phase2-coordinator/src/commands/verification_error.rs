@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+/// The files of the transcript involved in the verification of a contribution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TranscriptFile {
+    Challenge,
+    Response,
+}
+
+impl fmt::Display for TranscriptFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranscriptFile::Challenge => write!(f, "challenge"),
+            TranscriptFile::Response => write!(f, "response"),
+        }
+    }
+}
+
+/// The reason a contribution failed verification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[serde(rename_all = "camelCase")]
+pub enum VerificationError {
+    #[error("Couldn't deserialize the {circuit} parameters of the {file}: {reason}")]
+    Deserialization {
+        circuit: String,
+        file: TranscriptFile,
+        reason: String,
+    },
+    #[error("The hash of the {file} doesn't match the one saved in the transcript")]
+    HashMismatch { file: TranscriptFile },
+    #[error("The {circuit} parameters are not a valid transformation of the challenge")]
+    InvalidDelta { circuit: String },
+    #[error("Inconsistent transcript: {0}")]
+    TranscriptInconsistency(String),
+}
+
+impl VerificationError {
+    /// Returns the name of the circuit that failed verification, if the failure is specific to one.
+    pub fn circuit(&self) -> Option<&str> {
+        match self {
+            VerificationError::Deserialization { circuit, .. } | VerificationError::InvalidDelta { circuit } => {
+                Some(circuit)
+            }
+            VerificationError::HashMismatch { .. } | VerificationError::TranscriptInconsistency(_) => None,
+        }
+    }
+}
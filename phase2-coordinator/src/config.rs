@@ -0,0 +1,871 @@
+//! Configuration of the coordinator: the [Environment] of the ceremony, the tokens, the auth settings and the
+//! object store.
+//!
+//! The configuration is read from a TOML file, every setting of which can be overridden by its env variable
+//! (see [ENV_VARIABLES]), and validated before the coordinator starts. The binary installs it once with
+//! [install], the library reads it with [Config::current], which falls back to the env variables alone when no
+//! configuration has been installed, e.g. in tests.
+
+use crate::{
+    authentication::KeyPair,
+    environment::{CircuitSet, Deployment, Development, Environment, Production, Testing},
+    object_store::ObjectStoreKind,
+    queue::QueuePolicy,
+    s3::CredentialsSource,
+    schedule::CohortSchedule,
+    secret::SecretSinkKind,
+    tokens::TokenIssuer,
+};
+
+use once_cell::sync::OnceCell;
+use rusoto_core::Region;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use thiserror::Error;
+use time::OffsetDateTime;
+
+/// The env variable holding the path of the configuration file, if not given on the command line.
+pub const CONFIG_PATH_ENV: &str = "NAMADA_CONFIG";
+
+/// The env variables overriding the settings of the configuration file.
+pub const ENV_VARIABLES: &[&str] = &[
+    "CEREMONY_START_TIMESTAMP",
+    "NAMADA_COHORT_TIME",
+    "NAMADA_COHORT_SCHEDULE",
+    "NAMADA_CIRCUITS",
+    "NAMADA_MPC_TIMEOUT_SECONDS",
+    "NAMADA_RETENTION_ROUNDS",
+    "NAMADA_AUDIT_INTERVAL_SECONDS",
+    "NAMADA_QUEUE_POLICY",
+    "NAMADA_INVITED_COHORTS",
+    "NAMADA_RESERVED_SLOTS",
+    "NAMADA_TOKENS_PATH",
+    "TOKENS_FILE_PREFIX",
+    "NAMADA_TOKEN_ISSUER",
+    "NAMADA_SIGNED_COHORTS",
+    "TOKEN_BLACKLIST",
+    "NAMADA_MPC_IP_BAN",
+    "HEALTH_PATH",
+    "NAMADA_OPERATORS_PATH",
+    "NAMADA_AUDIT_LOG",
+    "NAMADA_SIGNATURE_MAX_AGE",
+    "NAMADA_NONCE_CACHE_CAPACITY",
+    "NAMADA_OBJECT_STORE",
+    "NAMADA_PRESIGNED_URL_EXPIRY",
+    "NAMADA_LOCAL_STORE_PATH",
+    "NAMADA_LOCAL_STORE_URL",
+    "AWS_S3_PROD",
+    "AWS_S3_BUCKET",
+    "AWS_REGION",
+    "AWS_S3_ENDPOINT",
+    "AWS_S3_PATH_STYLE",
+    "AWS_CREDENTIALS_SOURCE",
+    "ACCESS_SECRET",
+    "NAMADA_SECRET_SINK",
+    "NAMADA_SECRET_FILE",
+    "NAMADA_SECRET_SSM_NAME",
+];
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Configuration has already been installed")]
+    AlreadyInstalled,
+    #[error("Invalid value {value:?} for env variable {name}: {reason}")]
+    InvalidEnv {
+        name: String,
+        value: String,
+        reason: String,
+    },
+    #[error("Invalid configuration: {0}")]
+    Invalid(String),
+    #[error("Error while reading {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Error while parsing {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    #[error("Error while serializing the configuration: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+type Result<T> = std::result::Result<T, ConfigError>;
+
+/// The configuration of the coordinator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The environment the ceremony is run with, [Testing] for debug builds and [Production] otherwise by default.
+    pub deployment: Deployment,
+    pub ceremony: CeremonyConfig,
+    pub tokens: TokensConfig,
+    pub auth: AuthConfig,
+    pub object_store: ObjectStoreConfig,
+    pub s3: S3Config,
+    pub secret: SecretConfig,
+}
+
+/// The settings of the ceremony, on top of the defaults of the deployment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CeremonyConfig {
+    /// The unix timestamp the ceremony starts at. Required in release builds, now if missing in
+    /// debug builds.
+    pub start_timestamp: Option<i64>,
+    /// The duration of a cohort.
+    pub cohort_time_seconds: u64,
+    /// The TOML file of the schedule of the cohorts (see [CohortSchedule]), replacing the cohorts of
+    /// `cohort_time_seconds` if set.
+    pub schedule_path: Option<String>,
+    /// The circuits of the ceremony, `masp-full`, `masp-test` or a comma separated list of circuit names, if not
+    /// the default ones of the deployment.
+    pub circuits: Option<String>,
+    /// The timeout of the contributors, the verifiers, the locks and the queue, if not the default ones.
+    pub timeout_seconds: Option<i64>,
+    pub minimum_contributors_per_round: Option<usize>,
+    pub maximum_contributors_per_round: Option<usize>,
+    /// The time a participant waits in the queue before being assigned a round.
+    pub queue_wait_time_seconds: Option<u64>,
+    /// The policy ordering the queued contributors in the next rounds: `fifo`, `reliability-weighted`,
+    /// `cohort-priority` or `reserved-slots`, see [QueuePolicy].
+    pub queue_policy: String,
+    /// The number of first cohorts the invited contributors join during, for the `reserved-slots` policy.
+    pub invited_cohorts: usize,
+    /// The number of slots of each round reserved to the invited contributors, for the `reserved-slots` policy.
+    pub reserved_slots: usize,
+    /// The directory of the transcript, if not the default one of the deployment.
+    pub base_directory: Option<String>,
+    /// The number of rounds kept in place in the transcript, the older ones are archived.
+    pub retention_rounds: Option<u64>,
+    /// The interval between two audits of the storage, which are disabled if missing.
+    pub audit_interval_seconds: Option<u64>,
+}
+
+/// The settings of the contribution tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokensConfig {
+    /// The directory the token files are extracted to.
+    pub path: String,
+    /// The prefix of the token files, followed by the number of the cohort.
+    pub file_prefix: String,
+    /// The hex encoded public key of the issuer of the signed tokens, which are only accepted if set.
+    pub issuer_pubkey: Option<String>,
+    /// The number of cohorts the issuer signs tokens for, in addition to the ones of the token files.
+    pub signed_cohorts: usize,
+}
+
+/// The settings of the authentication of the participants.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Whether a token can only be used once.
+    pub token_blacklist: bool,
+    /// Whether an IP can only be used by a single contributor.
+    pub ip_ban: bool,
+    /// The file the healthcheck endpoint responds with.
+    pub health_path: String,
+    /// The file of the operator keys, see [Operators](crate::operators::Operators).
+    pub operators_path: String,
+    /// The file the admin actions are recorded to, see [AuditLog](crate::audit_log::AuditLog).
+    pub audit_log_path: String,
+    /// The maximum age of a signed request, from its timestamp to the time of the coordinator.
+    pub signature_max_age_seconds: u64,
+    /// The number of nonces of signed requests kept to reject the replayed ones.
+    pub nonce_cache_capacity: usize,
+}
+
+/// The settings of the object store used to exchange files with the participants.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObjectStoreConfig {
+    /// Either `s3` or `local`.
+    pub kind: String,
+    /// The validity of the urls returned by the object store.
+    pub presigned_url_expiry_seconds: u64,
+    /// The directory of the local store.
+    pub local_path: String,
+    /// The url the local store is reachable at.
+    pub local_url: String,
+}
+
+/// The settings of the S3 object store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Config {
+    /// Whether the production folder of the bucket is used, rather than the master one.
+    pub production: bool,
+    pub bucket: String,
+    pub region: String,
+    /// The endpoint of the S3-compatible service.
+    pub endpoint: String,
+    /// Whether the requests are sent to the endpoint itself rather than to the bucket subdomain of it.
+    pub path_style: bool,
+    /// Where the credentials are taken from, see [CredentialsSource].
+    pub credentials_source: String,
+}
+
+/// The settings of the access secret of the reserved endpoints.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretConfig {
+    /// The secret to use, generated if missing. Never printed with the configuration.
+    #[serde(skip_serializing)]
+    pub value: Option<String>,
+    /// Where the generated and rotated secrets are published, either `ssm`, `file` or `stdout`.
+    pub sink: String,
+    /// The file of the `file` sink.
+    pub file_path: String,
+    /// The parameter of the `ssm` sink, `/namada/trusted-setup/{production|master}/secret` by default.
+    pub ssm_name: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        #[cfg(debug_assertions)]
+        let deployment = Deployment::Testing;
+        #[cfg(not(debug_assertions))]
+        let deployment = Deployment::Production;
+
+        Self {
+            deployment,
+            ceremony: CeremonyConfig::default(),
+            tokens: TokensConfig::default(),
+            auth: AuthConfig::default(),
+            object_store: ObjectStoreConfig::default(),
+            s3: S3Config::default(),
+            secret: SecretConfig::default(),
+        }
+    }
+}
+
+impl Default for CeremonyConfig {
+    fn default() -> Self {
+        Self {
+            start_timestamp: None,
+            cohort_time_seconds: 86400,
+            schedule_path: None,
+            circuits: None,
+            timeout_seconds: None,
+            minimum_contributors_per_round: None,
+            maximum_contributors_per_round: None,
+            queue_wait_time_seconds: None,
+            queue_policy: "fifo".to_string(),
+            invited_cohorts: 0,
+            reserved_slots: 0,
+            base_directory: None,
+            retention_rounds: None,
+            audit_interval_seconds: None,
+        }
+    }
+}
+
+impl Default for TokensConfig {
+    fn default() -> Self {
+        Self {
+            path: "./tokens".to_string(),
+            file_prefix: "namada_tokens_cohort".to_string(),
+            issuer_pubkey: None,
+            signed_cohorts: 0,
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            token_blacklist: false,
+            ip_ban: false,
+            health_path: "./health.json".to_string(),
+            operators_path: "./operators.toml".to_string(),
+            audit_log_path: "./audit_log.jsonl".to_string(),
+            signature_max_age_seconds: 300,
+            nonce_cache_capacity: 100_000,
+        }
+    }
+}
+
+impl Default for ObjectStoreConfig {
+    fn default() -> Self {
+        Self {
+            kind: "s3".to_string(),
+            presigned_url_expiry_seconds: 600,
+            local_path: "./object_store".to_string(),
+            local_url: "http://0.0.0.0:8080".to_string(),
+        }
+    }
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            production: false,
+            bucket: "bucket".to_string(),
+            region: Region::EuWest1.name().to_string(),
+            endpoint: "s3-accelerate.amazonaws.com".to_string(),
+            path_style: false,
+            credentials_source: "chain".to_string(),
+        }
+    }
+}
+
+impl Default for SecretConfig {
+    fn default() -> Self {
+        Self {
+            value: None,
+            sink: "ssm".to_string(),
+            file_path: "./access_secret".to_string(),
+            ssm_name: None,
+        }
+    }
+}
+
+impl Config {
+    ///
+    /// Loads the configuration from the given TOML file, or the defaults if missing, then applies the overrides
+    /// of the env variables and validates the result.
+    ///
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut config = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+                toml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env(|name| std::env::var(name).ok())?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    ///
+    /// Returns the configuration installed with [install], or the one of the env variables alone if none was. In
+    /// the latter case, the configuration is loaded on the first call and installed for the following ones, so
+    /// [install] must be called before.
+    ///
+    /// # Panics
+    /// If no configuration was installed and the env variables are invalid.
+    ///
+    pub fn current() -> Self {
+        CONFIG
+            .get_or_init(|| Self::load(None).unwrap_or_else(|e| panic!("{}", e)))
+            .clone()
+    }
+
+    /// Overrides the settings with the env variables returned by the given lookup.
+    fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<()> {
+        if let Some(value) = env("CEREMONY_START_TIMESTAMP") {
+            self.ceremony.start_timestamp = Some(parse_env("CEREMONY_START_TIMESTAMP", &value)?);
+        }
+        if let Some(value) = env("NAMADA_COHORT_TIME") {
+            self.ceremony.cohort_time_seconds = parse_env("NAMADA_COHORT_TIME", &value)?;
+        }
+        if let Some(value) = env("NAMADA_COHORT_SCHEDULE") {
+            self.ceremony.schedule_path = Some(value);
+        }
+        if let Some(value) = env("NAMADA_CIRCUITS") {
+            self.ceremony.circuits = Some(value);
+        }
+        if let Some(value) = env("NAMADA_MPC_TIMEOUT_SECONDS") {
+            self.ceremony.timeout_seconds = Some(parse_env("NAMADA_MPC_TIMEOUT_SECONDS", &value)?);
+        }
+        if let Some(value) = env("NAMADA_RETENTION_ROUNDS") {
+            self.ceremony.retention_rounds = Some(parse_env("NAMADA_RETENTION_ROUNDS", &value)?);
+        }
+        if let Some(value) = env("NAMADA_AUDIT_INTERVAL_SECONDS") {
+            self.ceremony.audit_interval_seconds = Some(parse_env("NAMADA_AUDIT_INTERVAL_SECONDS", &value)?);
+        }
+        if let Some(value) = env("NAMADA_QUEUE_POLICY") {
+            self.ceremony.queue_policy = value;
+        }
+        if let Some(value) = env("NAMADA_INVITED_COHORTS") {
+            self.ceremony.invited_cohorts = parse_env("NAMADA_INVITED_COHORTS", &value)?;
+        }
+        if let Some(value) = env("NAMADA_RESERVED_SLOTS") {
+            self.ceremony.reserved_slots = parse_env("NAMADA_RESERVED_SLOTS", &value)?;
+        }
+        if let Some(value) = env("NAMADA_TOKENS_PATH") {
+            self.tokens.path = value;
+        }
+        if let Some(value) = env("TOKENS_FILE_PREFIX") {
+            self.tokens.file_prefix = value;
+        }
+        if let Some(value) = env("NAMADA_TOKEN_ISSUER") {
+            self.tokens.issuer_pubkey = Some(value);
+        }
+        if let Some(value) = env("NAMADA_SIGNED_COHORTS") {
+            self.tokens.signed_cohorts = parse_env("NAMADA_SIGNED_COHORTS", &value)?;
+        }
+        // These two flags were only enabled by "true", any other value disables them
+        if let Some(value) = env("TOKEN_BLACKLIST") {
+            self.auth.token_blacklist = value == "true";
+        }
+        if let Some(value) = env("NAMADA_MPC_IP_BAN") {
+            self.auth.ip_ban = value == "true";
+        }
+        if let Some(value) = env("HEALTH_PATH") {
+            self.auth.health_path = value;
+        }
+        if let Some(value) = env("NAMADA_OPERATORS_PATH") {
+            self.auth.operators_path = value;
+        }
+        if let Some(value) = env("NAMADA_AUDIT_LOG") {
+            self.auth.audit_log_path = value;
+        }
+        if let Some(value) = env("NAMADA_SIGNATURE_MAX_AGE") {
+            self.auth.signature_max_age_seconds = parse_env("NAMADA_SIGNATURE_MAX_AGE", &value)?;
+        }
+        if let Some(value) = env("NAMADA_NONCE_CACHE_CAPACITY") {
+            self.auth.nonce_cache_capacity = parse_env("NAMADA_NONCE_CACHE_CAPACITY", &value)?;
+        }
+        if let Some(value) = env("NAMADA_OBJECT_STORE") {
+            self.object_store.kind = value;
+        }
+        if let Some(value) = env("NAMADA_PRESIGNED_URL_EXPIRY") {
+            self.object_store.presigned_url_expiry_seconds = parse_env("NAMADA_PRESIGNED_URL_EXPIRY", &value)?;
+        }
+        if let Some(value) = env("NAMADA_LOCAL_STORE_PATH") {
+            self.object_store.local_path = value;
+        }
+        if let Some(value) = env("NAMADA_LOCAL_STORE_URL") {
+            self.object_store.local_url = value;
+        }
+        if let Some(value) = env("AWS_S3_PROD") {
+            self.s3.production = value == "true";
+        }
+        if let Some(value) = env("AWS_S3_BUCKET") {
+            self.s3.bucket = value;
+        }
+        if let Some(value) = env("AWS_REGION") {
+            self.s3.region = value;
+        }
+        if let Some(value) = env("AWS_S3_ENDPOINT") {
+            self.s3.endpoint = value;
+        }
+        if let Some(value) = env("AWS_S3_PATH_STYLE") {
+            self.s3.path_style = parse_env("AWS_S3_PATH_STYLE", &value)?;
+        }
+        if let Some(value) = env("AWS_CREDENTIALS_SOURCE") {
+            self.s3.credentials_source = value;
+        }
+        if let Some(value) = env("ACCESS_SECRET") {
+            self.secret.value = Some(value);
+        }
+        if let Some(value) = env("NAMADA_SECRET_SINK") {
+            self.secret.sink = value;
+        }
+        if let Some(value) = env("NAMADA_SECRET_FILE") {
+            self.secret.file_path = value;
+        }
+        if let Some(value) = env("NAMADA_SECRET_SSM_NAME") {
+            self.secret.ssm_name = Some(value);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the settings are consistent and can be parsed.
+    pub fn validate(&self) -> Result<()> {
+        match self.ceremony.start_timestamp {
+            Some(timestamp) => {
+                OffsetDateTime::from_unix_timestamp(timestamp)
+                    .map_err(|e| ConfigError::Invalid(format!("ceremony.start_timestamp: {}", e)))?;
+            }
+            None if !cfg!(debug_assertions) => {
+                return Err(ConfigError::Invalid(
+                    "ceremony.start_timestamp is required in release builds".to_string(),
+                ));
+            }
+            None => (),
+        }
+        if self.ceremony.cohort_time_seconds == 0 {
+            return Err(ConfigError::Invalid(
+                "ceremony.cohort_time_seconds must be positive".to_string(),
+            ));
+        }
+        self.cohort_schedule()?;
+        if let Some(circuits) = self.circuits()? {
+            circuits
+                .circuits()
+                .map_err(|e| ConfigError::Invalid(format!("ceremony.circuits: {}", e)))?;
+        }
+        if matches!(self.ceremony.timeout_seconds, Some(timeout) if timeout <= 0) {
+            return Err(ConfigError::Invalid(
+                "ceremony.timeout_seconds must be positive".to_string(),
+            ));
+        }
+        if let (Some(minimum), Some(maximum)) = (
+            self.ceremony.minimum_contributors_per_round,
+            self.ceremony.maximum_contributors_per_round,
+        ) {
+            if minimum > maximum {
+                return Err(ConfigError::Invalid(
+                    "ceremony.minimum_contributors_per_round exceeds the maximum".to_string(),
+                ));
+            }
+        }
+        if self.ceremony.audit_interval_seconds == Some(0) {
+            return Err(ConfigError::Invalid(
+                "ceremony.audit_interval_seconds must be positive".to_string(),
+            ));
+        }
+        if let QueuePolicy::ReservedSlots { invited_cohorts, slots } = self.queue_policy()? {
+            if invited_cohorts == 0 || slots == 0 {
+                return Err(ConfigError::Invalid(
+                    "the reserved-slots queue policy requires ceremony.invited_cohorts and ceremony.reserved_slots"
+                        .to_string(),
+                ));
+            }
+        }
+
+        if self.tokens.path.is_empty() || self.tokens.file_prefix.is_empty() {
+            return Err(ConfigError::Invalid(
+                "tokens.path and tokens.file_prefix can't be empty".to_string(),
+            ));
+        }
+        match &self.tokens.issuer_pubkey {
+            Some(pubkey) if hex::decode(pubkey).map_or(true, |bytes| bytes.len() != 32) => {
+                return Err(ConfigError::Invalid(
+                    "tokens.issuer_pubkey must be a hex encoded ed25519 public key".to_string(),
+                ));
+            }
+            None if self.tokens.signed_cohorts > 0 => {
+                return Err(ConfigError::Invalid(
+                    "tokens.signed_cohorts requires tokens.issuer_pubkey".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        if self.auth.audit_log_path.is_empty() {
+            return Err(ConfigError::Invalid("auth.audit_log_path can't be empty".to_string()));
+        }
+        if self.auth.signature_max_age_seconds == 0 {
+            return Err(ConfigError::Invalid(
+                "auth.signature_max_age_seconds must be positive".to_string(),
+            ));
+        }
+        if self.auth.nonce_cache_capacity == 0 {
+            return Err(ConfigError::Invalid(
+                "auth.nonce_cache_capacity must be positive".to_string(),
+            ));
+        }
+
+        self.object_store_kind()?;
+        if self.object_store.presigned_url_expiry_seconds == 0 {
+            return Err(ConfigError::Invalid(
+                "object_store.presigned_url_expiry_seconds must be positive".to_string(),
+            ));
+        }
+        url::Url::parse(&self.object_store.local_url)
+            .map_err(|e| ConfigError::Invalid(format!("object_store.local_url: {}", e)))?;
+
+        if self.s3.bucket.is_empty() {
+            return Err(ConfigError::Invalid("s3.bucket can't be empty".to_string()));
+        }
+        self.region()?;
+        self.credentials_source()?;
+
+        if matches!(&self.secret.value, Some(secret) if secret.is_empty()) {
+            return Err(ConfigError::Invalid("secret.value can't be empty".to_string()));
+        }
+        self.secret_sink_kind()?;
+
+        Ok(())
+    }
+
+    /// Returns the circuits of the ceremony, if not the default ones of the deployment.
+    pub fn circuits(&self) -> Result<Option<CircuitSet>> {
+        self.ceremony
+            .circuits
+            .as_deref()
+            .map(CircuitSet::from_str)
+            .transpose()
+            .map_err(|e| ConfigError::Invalid(format!("ceremony.circuits: {}", e)))
+    }
+
+    /// Returns the schedule of the cohorts, if any.
+    pub fn cohort_schedule(&self) -> Result<Option<CohortSchedule>> {
+        self.ceremony
+            .schedule_path
+            .as_ref()
+            .map(CohortSchedule::load)
+            .transpose()
+            .map_err(|e| ConfigError::Invalid(format!("ceremony.schedule_path: {}", e)))
+    }
+
+    /// Returns the policy ordering the queued contributors.
+    pub fn queue_policy(&self) -> Result<QueuePolicy> {
+        let policy = QueuePolicy::from_str(&self.ceremony.queue_policy)
+            .map_err(|e| ConfigError::Invalid(format!("ceremony.queue_policy: {}", e)))?;
+
+        Ok(match policy {
+            QueuePolicy::ReservedSlots { .. } => QueuePolicy::ReservedSlots {
+                invited_cohorts: self.ceremony.invited_cohorts,
+                slots: self.ceremony.reserved_slots,
+            },
+            policy => policy,
+        })
+    }
+
+    /// Returns the issuer of the signed tokens, if any.
+    pub fn token_issuer(&self) -> Option<TokenIssuer> {
+        self.tokens.issuer_pubkey.as_ref().map(|pubkey| TokenIssuer {
+            pubkey: pubkey.clone(),
+            cohorts: self.tokens.signed_cohorts,
+        })
+    }
+
+    /// Returns the kind of object store.
+    pub fn object_store_kind(&self) -> Result<ObjectStoreKind> {
+        ObjectStoreKind::from_str(&self.object_store.kind)
+            .map_err(|e| ConfigError::Invalid(format!("object_store.kind: {}", e)))
+    }
+
+    /// Returns the region of the S3 bucket.
+    pub fn region(&self) -> Result<Region> {
+        Region::from_str(&self.s3.region).map_err(|e| ConfigError::Invalid(format!("s3.region: {}", e)))
+    }
+
+    /// Returns the source of the S3 credentials.
+    pub fn credentials_source(&self) -> Result<CredentialsSource> {
+        CredentialsSource::from_str(&self.s3.credentials_source)
+            .map_err(|e| ConfigError::Invalid(format!("s3.credentials_source: {}", e)))
+    }
+
+    /// Returns the kind of sink of the access secret.
+    pub fn secret_sink_kind(&self) -> Result<SecretSinkKind> {
+        SecretSinkKind::from_str(&self.secret.sink).map_err(|e| ConfigError::Invalid(format!("secret.sink: {}", e)))
+    }
+
+    /// Returns the name of the parameter of the `ssm` sink of the access secret.
+    pub fn secret_ssm_name(&self) -> String {
+        let folder = match self.s3.production {
+            true => "production",
+            false => "master",
+        };
+
+        self.secret
+            .ssm_name
+            .clone()
+            .unwrap_or_else(|| format!("/namada/trusted-setup/{}/secret", folder))
+    }
+
+    /// Returns the start time of the ceremony, if set.
+    pub fn start_time(&self) -> Option<OffsetDateTime> {
+        self.ceremony
+            .start_timestamp
+            .and_then(|timestamp| OffsetDateTime::from_unix_timestamp(timestamp).ok())
+    }
+
+    /// Builds the environment of the ceremony for the given coordinator keypair.
+    pub fn environment(&self, keypair: &KeyPair) -> Result<Environment> {
+        let ceremony = &self.ceremony;
+        let circuits = self.circuits()?;
+        let queue_policy = self.queue_policy()?;
+        let timeout = ceremony.timeout_seconds.map(time::Duration::seconds);
+
+        // The same settings are applied on top of the defaults of each deployment
+        macro_rules! configure {
+            ($deployment:expr) => {{
+                let mut deployment = $deployment
+                    .retention_rounds(ceremony.retention_rounds)
+                    .token_issuer(self.token_issuer())
+                    .queue_policy(queue_policy);
+                if let Some(circuits) = circuits {
+                    deployment = deployment.circuits(circuits);
+                }
+                if let Some(timeout) = timeout {
+                    deployment = deployment
+                        .contributor_seen_timeout(timeout)
+                        .verifier_seen_timeout(timeout)
+                        .participant_lock_timeout(timeout)
+                        .queue_seen_timeout(timeout);
+                }
+                if let Some(minimum) = ceremony.minimum_contributors_per_round {
+                    deployment = deployment.minimum_contributors_per_round(minimum);
+                }
+                if let Some(maximum) = ceremony.maximum_contributors_per_round {
+                    deployment = deployment.maximum_contributors_per_round(maximum);
+                }
+                if let Some(seconds) = ceremony.queue_wait_time_seconds {
+                    deployment = deployment.queue_wait_time(seconds);
+                }
+                if let Some(directory) = &ceremony.base_directory {
+                    deployment = deployment.local_base_directory(directory);
+                }
+
+                Environment::from(deployment)
+            }};
+        }
+
+        Ok(match self.deployment {
+            Deployment::Testing => configure!(Testing::new(keypair)),
+            Deployment::Development => configure!(Development::new(keypair)),
+            Deployment::Production => configure!(Production::new(keypair)),
+        })
+    }
+
+    /// Returns the configuration as TOML.
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+/// Installs the configuration read by [Config::current], once at startup before any call to it.
+pub fn install(config: Config) -> Result<()> {
+    CONFIG.set(config).map_err(|_| ConfigError::AlreadyInstalled)
+}
+
+/// Returns the path of the configuration file given in the [CONFIG_PATH_ENV] env variable, if any.
+pub fn path_from_env() -> Option<PathBuf> {
+    std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from)
+}
+
+fn parse_env<T: FromStr>(name: &str, value: &str) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e: T::Err| ConfigError::InvalidEnv {
+        name: name.to_string(),
+        value: value.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// The default configuration, with the start of the ceremony required by release builds.
+    fn valid_config() -> Config {
+        let mut config = Config::default();
+        config.ceremony.start_timestamp = Some(1660000000);
+        config
+    }
+
+    #[test]
+    fn test_config_file_and_env() {
+        let config: Config = toml::from_str(
+            r#"
+            deployment = "Production"
+
+            [ceremony]
+            start_timestamp = 1660000000
+            circuits = "masp-test"
+            timeout_seconds = 60
+            queue_wait_time_seconds = 10
+
+            [object_store]
+            kind = "local"
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.ceremony.cohort_time_seconds, 86400);
+        assert_eq!(config.object_store_kind().unwrap(), ObjectStoreKind::Local);
+
+        let environment = config.environment(&KeyPair::new()).unwrap();
+        assert_eq!(environment.deployment(), &Deployment::Production);
+        assert_eq!(environment.parameters().circuits(), &CircuitSet::MaspTest);
+        assert_eq!(environment.contributor_seen_timeout(), time::Duration::seconds(60));
+        assert_eq!(environment.queue_wait_time(), 10);
+        assert_eq!(environment.local_base_directory(), "./transcript");
+
+        // The env variables take precedence over the file
+        let env: HashMap<&str, &str> = [("NAMADA_COHORT_TIME", "15"), ("TOKEN_BLACKLIST", "true")]
+            .iter()
+            .cloned()
+            .collect();
+        let mut overridden = config.clone();
+        overridden
+            .apply_env(|name| env.get(name).map(|value| value.to_string()))
+            .unwrap();
+        assert_eq!(overridden.ceremony.cohort_time_seconds, 15);
+        assert!(overridden.auth.token_blacklist);
+        assert_eq!(overridden.ceremony.circuits, config.ceremony.circuits);
+
+        // The printed configuration can be read back
+        let printed: Config = toml::from_str(&overridden.to_toml().unwrap()).unwrap();
+        assert_eq!(printed, overridden);
+
+        // But never contains the access secret
+        overridden
+            .apply_env(|name| (name == "ACCESS_SECRET").then(|| "secret".to_string()))
+            .unwrap();
+        let printed: Config = toml::from_str(&overridden.to_toml().unwrap()).unwrap();
+        assert_eq!(printed.secret.value, None);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(toml::from_str::<Config>("[ceremony]\nunknown = 1").is_err());
+
+        let mut config = valid_config();
+        config.ceremony.circuits = Some("unknown".to_string());
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+        let mut config = valid_config();
+        config.object_store.kind = "ftp".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = valid_config();
+        config.secret.sink = "email".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = valid_config();
+        config.auth.nonce_cache_capacity = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = valid_config();
+        config.auth.audit_log_path = String::new();
+        assert!(config.validate().is_err());
+
+        let mut config = valid_config();
+        config.tokens.signed_cohorts = 2;
+        assert!(config.validate().is_err());
+        config.tokens.issuer_pubkey = Some("not hex".to_string());
+        assert!(config.validate().is_err());
+        config.tokens.issuer_pubkey = Some(KeyPair::new().pubkey().to_string());
+        config.validate().unwrap();
+        let environment = config.environment(&KeyPair::new()).unwrap();
+        assert_eq!(environment.token_issuer().map(|issuer| issuer.cohorts), Some(2));
+
+        let mut config = valid_config();
+        config.ceremony.queue_policy = "reserved-slots".to_string();
+        assert!(config.validate().is_err());
+        config.ceremony.invited_cohorts = 2;
+        config.ceremony.reserved_slots = 1;
+        config.validate().unwrap();
+        let environment = config.environment(&KeyPair::new()).unwrap();
+        assert_eq!(
+            environment.queue_policy(),
+            &QueuePolicy::ReservedSlots {
+                invited_cohorts: 2,
+                slots: 1
+            }
+        );
+        config.ceremony.queue_policy = "lifo".to_string();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+        let mut config = valid_config();
+        config.ceremony.schedule_path = Some("missing_schedule.toml".to_string());
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+        let mut config = valid_config();
+        config.ceremony.start_timestamp = Some(i64::MAX);
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+        config.ceremony.start_timestamp = None;
+        assert_eq!(config.validate().is_ok(), cfg!(debug_assertions));
+
+        let mut config = valid_config();
+        assert!(matches!(
+            config.apply_env(|name| (name == "NAMADA_COHORT_TIME").then(|| "a day".to_string())),
+            Err(ConfigError::InvalidEnv { .. })
+        ));
+    }
+}
@@ -4,7 +4,7 @@
 
 use crate::{
     authentication::Signature,
-    commands::{Aggregation, Initialization},
+    commands::{Aggregation, Initialization, VerificationError},
     coordinator_state::{
         CeremonyStorageAction, CoordinatorState, DropParticipant, ParticipantInfo, ResetCurrentRoundStorageAction,
         RoundMetrics, IP_BAN, TOKEN_BLACKLIST,
@@ -15,8 +15,8 @@ use crate::{
         Round, Task, TrimmedContributionInfo,
     },
     storage::{
-        ContributionLocator, ContributionSignatureLocator, Disk, Locator, LocatorPath, Object, StorageAction,
-        StorageLocator, StorageObject, UpdateAction,
+        self, AuditIssue, ContributionLocator, ContributionSignatureLocator, DefaultStorage, Locator, LocatorPath,
+        Object, RoundStates, Storage, StorageAction, UpdateAction,
     },
 };
 use setup_utils::calculate_hash;
@@ -25,6 +25,7 @@ use std::{
     collections::HashSet,
     fmt,
     net::IpAddr,
+    path::PathBuf,
     sync::{Arc, RwLock},
 };
 use time::OffsetDateTime;
@@ -36,7 +37,9 @@ use std::collections::HashMap;
 #[derive(Debug)]
 pub enum CoordinatorError {
     AggregateContributionFileSizeMismatch,
+    ArchivedFileCorrupted,
     CeremonyIsOver,
+    CeremonyNotLocked,
     ChallengeHashSizeInvalid,
     ChunkAlreadyComplete,
     ChunkAlreadyVerified,
@@ -50,6 +53,10 @@ pub enum CoordinatorError {
     ChunkMissingVerification,
     ChunkCannotLockZeroContributions { chunk_id: u64 },
     ChunkNotLockedOrByWrongParticipant,
+    CircuitAlreadyRegistered(String),
+    CircuitMissing(String),
+    CircuitSetEmpty,
+    CircuitSynthesisFailed(String),
     ComputationFailed,
     CompressedContributionHashingUnsupported,
     ContributorPendingTasksCannotBeEmpty(Participant),
@@ -78,6 +85,7 @@ pub enum CoordinatorError {
     ContributorSignatureInvalid,
     ContributorsMissing,
     CoordinatorContributorMissing,
+    CoordinatorStateCorrupted,
     CoordinatorStateNotInitialized,
     CurrentRoundAggregating,
     CurrentRoundAggregated,
@@ -87,6 +95,7 @@ pub enum CoordinatorError {
     DropParticipantFailed,
     ExpectedContributor,
     ExpectedVerifier,
+    DatabaseError(Box<dyn std::error::Error + Send + Sync>),
     Error(anyhow::Error),
     InitializationFailed,
     InitializationTranscriptsDiffer,
@@ -194,12 +203,15 @@ pub enum CoordinatorError {
     StorageReaderFailed,
     StorageSizeLookupFailed,
     StorageUpdateFailed,
+    StorageUploadIncomplete,
+    StorageUploadOffsetMismatch { expected: u64, actual: u64 },
+    StorageUploadTooLarge,
     TaskInitializationFailed(TaskInitializationError),
     PreviousContributionMissing { current_task: Task },
     TryFromSliceError(std::array::TryFromSliceError),
     UnauthorizedChunkContributor,
     UnauthorizedChunkVerifier,
-    VerificationFailed,
+    VerificationFailed(VerificationError),
     VerificationOnContributionIdZero,
     VerifierMissing,
     VerifierSignatureInvalid,
@@ -212,12 +224,25 @@ impl From<TaskInitializationError> for CoordinatorError {
     }
 }
 
+impl From<VerificationError> for CoordinatorError {
+    fn from(error: VerificationError) -> Self {
+        Self::VerificationFailed(error)
+    }
+}
+
 impl From<anyhow::Error> for CoordinatorError {
     fn from(error: anyhow::Error) -> Self {
         CoordinatorError::Error(error)
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for CoordinatorError {
+    fn from(error: rusqlite::Error) -> Self {
+        CoordinatorError::DatabaseError(Box::new(error))
+    }
+}
+
 impl From<hex::FromHexError> for CoordinatorError {
     fn from(error: hex::FromHexError) -> Self {
         CoordinatorError::Hex(error)
@@ -329,14 +354,15 @@ impl TimeSource for MockTimeSource {
 
 /// A core structure for operating the Phase 1 ceremony. This struct
 /// is designed to be [Send] + [Sync]. The state of the ceremony is
-/// stored in a [CoordinatorState] object.
-pub struct Coordinator {
+/// stored in a [CoordinatorState] object, the contributions and rounds
+/// in a [Storage], [DefaultStorage] by default.
+pub struct Coordinator<S = DefaultStorage> {
     /// The parameters and settings of this coordinator.
     environment: Environment,
     /// The signature scheme for contributors & verifiers with this coordinator.
     signature: Arc<dyn Signature>,
     /// The storage of contributions and rounds for this coordinator.
-    storage: Disk,
+    storage: S,
     /// The current round and participant self.
     state: CoordinatorState,
     /// The source of time, allows mocking system time for testing.
@@ -345,7 +371,7 @@ pub struct Coordinator {
     aggregation_callback: Arc<dyn Fn(Vec<Participant>) -> () + Send + Sync>,
 }
 
-impl Coordinator {
+impl Coordinator<DefaultStorage> {
     ///
     /// Creates a new instance of the `Coordinator`, for a given environment.
     ///
@@ -367,6 +393,25 @@ impl Coordinator {
     ) -> Result<Self, CoordinatorError> {
         // Load an instance of storage.
         let storage = environment.storage()?;
+
+        Self::new_with_storage(environment, signature, storage, time)
+    }
+}
+
+impl<S: Storage> Coordinator<S> {
+    ///
+    /// Creates a new instance of the `Coordinator`, for a given environment,
+    /// on top of the given storage.
+    ///
+    pub fn new_with_storage(
+        environment: Environment,
+        signature: Arc<dyn Signature>,
+        storage: S,
+        time: Arc<dyn TimeSource>,
+    ) -> Result<Self, CoordinatorError> {
+        // Check that the circuits of the ceremony are registered.
+        environment.parameters().circuits().circuits()?;
+
         // Load an instance of coordinator self.
         let state = match storage.get(&Locator::CoordinatorState)? {
             Object::CoordinatorState(state) => state,
@@ -393,7 +438,7 @@ impl Coordinator {
     }
 }
 
-impl Coordinator {
+impl<S: Storage> Coordinator<S> {
     ///
     /// Runs a set of operations to initialize state and start the coordinator.
     ///
@@ -427,6 +472,10 @@ impl Coordinator {
 
         info!("Current round height is {}", current_round_height);
         info!("{}", serde_json::to_string_pretty(&self.current_round()?)?);
+
+        // Archive the rounds which left the retention window while the coordinator was down.
+        self.apply_retention_policy(current_round_height, true);
+
         info!("Coordinator has booted up");
 
         Ok(())
@@ -535,7 +584,7 @@ impl Coordinator {
         }
 
         // If cohorts are over, shut the coordinator down
-        if self.state.get_current_cohort_index() >= self.state.get_number_of_cohorts() {
+        if self.state.get_current_cohort_index() >= self.number_of_cohorts() {
             info!("Completed all the scheduled cohorts");
             // Return an error to force the calling task to request a graceful shutdown of the server
             return Err(CoordinatorError::CeremonyIsOver);
@@ -570,6 +619,19 @@ impl Coordinator {
         self.state.update_tokens(tokens)
     }
 
+    ///
+    /// Returns the number of cohorts of the ceremony: the ones of the schedule if any, otherwise the ones of the
+    /// cohort files, or of the issuer of the signed tokens if more.
+    ///
+    pub fn number_of_cohorts(&self) -> usize {
+        if let Some(schedule) = self.state.cohort_schedule() {
+            return schedule.len();
+        }
+        let signed_cohorts = self.environment.token_issuer().map_or(0, |issuer| issuer.cohorts);
+
+        self.state.get_number_of_cohorts().max(signed_cohorts)
+    }
+
     ///
     /// Returns `true` if the given participant is a contributor in the queue.
     ///
@@ -618,6 +680,14 @@ impl Coordinator {
         self.state.current_round_metrics()
     }
 
+    ///
+    /// Returns the reliability score of the given participant joining the queue, from its past rounds.
+    ///
+    #[inline]
+    pub fn reliability_score(&self, participant: &Participant) -> u8 {
+        self.state.reliability_score(participant)
+    }
+
     ///
     /// Adds the given participant to the queue if they are permitted to participate.
     ///
@@ -658,6 +728,24 @@ impl Coordinator {
         Ok(())
     }
 
+    ///
+    /// Moves the given contributor to the given position of the queue, starting from 0, and assigns the rounds of
+    /// the new order.
+    ///
+    #[inline]
+    pub fn move_in_queue(&mut self, participant: &Participant, position: usize) -> Result<(), CoordinatorError> {
+        // Attempt to move the participant in the queue.
+        self.state.move_in_queue(participant, position)?;
+
+        // Update the state of the queue.
+        self.state.update_queue()?;
+
+        // Save the coordinator state in storage.
+        self.save_state()?;
+
+        Ok(())
+    }
+
     ///
     /// Drops the given participant from the ceremony.
     ///
@@ -679,12 +767,12 @@ impl Coordinator {
     }
 
     ///
-    /// Bans the given participant from the ceremony.
+    /// Bans the given participant from the ceremony for the given reason.
     ///
     #[inline]
-    pub fn ban_participant(&mut self, participant: &Participant) -> Result<(), CoordinatorError> {
+    pub fn ban_participant(&mut self, participant: &Participant, reason: &str) -> Result<(), CoordinatorError> {
         // Ban the participant from the ceremony.
-        let drop = self.state.ban_participant(participant, self.time.as_ref())?;
+        let drop = self.state.ban_participant(participant, reason, self.time.as_ref())?;
 
         // Update the round on disk to reflect the coordinator state change.
         self.drop_participant_from_storage(&drop)?;
@@ -718,6 +806,15 @@ impl Coordinator {
         self.state.is_manual_lock_enabled()
     }
 
+    ///
+    /// Returns `true` if no contributor can join the ceremony anymore, either because the manual lock is
+    /// enabled or because all the cohorts are over.
+    ///
+    #[inline]
+    pub fn is_ceremony_locked(&self) -> bool {
+        self.state.is_manual_lock_enabled() || self.state.get_current_cohort_index() >= self.number_of_cohorts()
+    }
+
     ///
     /// Sets the manual lock for transitioning to the next round to `true`.
     ///
@@ -779,6 +876,20 @@ impl Coordinator {
         self.state.is_finished_contributor_at_round(participant, round)
     }
 
+    ///
+    /// Marks the contribution of the given contributor as being verified. Returns `false` if it already was.
+    ///
+    pub fn start_verifying_contributor(&mut self, participant: &Participant) -> bool {
+        self.state.start_verifying_contributor(participant)
+    }
+
+    ///
+    /// Marks the verification of the contribution of the given contributor as done.
+    ///
+    pub fn stop_verifying_contributor(&mut self, participant: &Participant) {
+        self.state.stop_verifying_contributor(participant)
+    }
+
     ///
     /// Returns `true` if the given participant has finished contributing in the current round
     ///
@@ -893,6 +1004,24 @@ impl Coordinator {
         }
     }
 
+    ///
+    /// Audits the contribution files referenced by the rounds of the ceremony in storage,
+    /// and returns the inconsistencies found.
+    ///
+    /// See [storage::audit_storage] for the checks run on each contribution.
+    ///
+    pub fn audit_storage(&self) -> Result<Vec<AuditIssue>, CoordinatorError> {
+        storage::audit_storage(&self.storage, &self.environment, self.signature.as_ref())
+    }
+
+    ///
+    /// Reads the round states of the ceremony in storage, for their contribution files to be audited with
+    /// [storage::audit_files] once the coordinator is released.
+    ///
+    pub fn round_states(&self) -> Result<RoundStates, CoordinatorError> {
+        RoundStates::read(&self.storage)
+    }
+
     /// Lets the coordinator know that the participant is still alive
     /// and participating (or waiting to participate) in the ceremony.
     pub fn heartbeat(&mut self, participant: &Participant) -> Result<(), CoordinatorError> {
@@ -1386,6 +1515,7 @@ impl Coordinator {
                         // If success, update coordinator state to next round.
                         info!("Coordinator has advanced to round {}", next_round_height);
                         self.state.commit_next_round();
+                        self.apply_retention_policy(next_round_height, false);
                         Ok(next_round_height)
                     }
                     // Case 1b - Coordinator failed to advance the round.
@@ -1414,6 +1544,29 @@ impl Coordinator {
         result
     }
 
+    ///
+    /// Archives the rounds out of the retention window of the environment: the one which just left it,
+    /// or all of them if `catch_up` is set. The rounds which can't be archived stay in place.
+    ///
+    fn apply_retention_policy(&mut self, current_round_height: u64, catch_up: bool) {
+        // The current round is always kept in place.
+        let retention_rounds = match self.environment.retention_rounds() {
+            Some(rounds) => rounds.max(1),
+            None => return,
+        };
+        let last_archived_round = match current_round_height.checked_sub(retention_rounds) {
+            Some(round_height) => round_height,
+            None => return,
+        };
+
+        let first_archived_round = if catch_up { 0 } else { last_archived_round };
+        for round_height in first_archived_round..=last_archived_round {
+            if let Err(e) = self.storage.archive_round(round_height) {
+                warn!("Failed to archive round {}: {}", round_height, e);
+            }
+        }
+    }
+
     ///
     /// Returns the chunk ID from the given contribution file locator path.
     ///
@@ -1783,6 +1936,95 @@ impl Coordinator {
         )
     }
 
+    /// Returns the path of the contribution file at the given locator, so that it can be streamed
+    /// without being loaded in memory.
+    pub(crate) fn contribution_file_path(&self, locator: ContributionLocator) -> Result<PathBuf, CoordinatorError> {
+        let locator = Locator::ContributionFile(locator);
+        if !self.storage.exists(&locator) {
+            return Err(CoordinatorError::ContributionLocatorMissing);
+        }
+
+        Ok(self.storage.to_path(&locator)?.into())
+    }
+
+    /// Returns the number of bytes of the contribution at the given locator
+    /// which have been uploaded directly to the coordinator so far.
+    pub(crate) fn contribution_upload_size(&self, locator: ContributionLocator) -> Result<u64, CoordinatorError> {
+        self.storage.staged_size(&Locator::ContributionFile(locator))
+    }
+
+    /// Appends a part of a contribution uploaded directly to the coordinator.
+    /// Returns the number of bytes received so far.
+    pub(crate) fn append_contribution_upload(
+        &mut self,
+        locator: ContributionLocator,
+        offset: u64,
+        bytes: &[u8],
+    ) -> Result<u64, CoordinatorError> {
+        let expected_size = Object::anoma_contribution_file_size(
+            self.environment.parameters().circuits(),
+            locator.round_height(),
+            locator.contribution_id(),
+        )?;
+        if offset + bytes.len() as u64 > expected_size {
+            return Err(CoordinatorError::StorageUploadTooLarge);
+        }
+
+        self.storage.stage(&Locator::ContributionFile(locator), offset, bytes)
+    }
+
+    /// Stages the signature of a contribution uploaded directly to the coordinator.
+    pub(crate) fn upload_contribution_file_signature(
+        &mut self,
+        locator: ContributionSignatureLocator,
+        contribution_file_signature: ContributionFileSignature,
+    ) -> Result<(), CoordinatorError> {
+        let bytes = serde_json::to_vec(&contribution_file_signature)?;
+        self.storage
+            .stage(&Locator::ContributionFileSignature(locator), 0, &bytes)?;
+
+        Ok(())
+    }
+
+    /// Returns true if the contribution at the given locator has been entirely uploaded
+    /// directly to the coordinator.
+    pub(crate) fn is_contribution_upload_complete(
+        &self,
+        locator: ContributionLocator,
+    ) -> Result<bool, CoordinatorError> {
+        let expected_size = Object::anoma_contribution_file_size(
+            self.environment.parameters().circuits(),
+            locator.round_height(),
+            locator.contribution_id(),
+        )?;
+
+        Ok(self.contribution_upload_size(locator)? == expected_size)
+    }
+
+    /// Moves a contribution and its signature uploaded directly to the coordinator to
+    /// their locators, in place of the ones fetched from the object store.
+    pub(crate) fn commit_contribution_upload(
+        &mut self,
+        contribution_locator: ContributionLocator,
+        signature_locator: ContributionSignatureLocator,
+    ) -> Result<(), CoordinatorError> {
+        if !self.is_contribution_upload_complete(contribution_locator)? {
+            return Err(CoordinatorError::StorageUploadIncomplete);
+        }
+
+        let staged_contribution = Locator::ContributionFile(contribution_locator);
+        let staged_signature = Locator::ContributionFileSignature(signature_locator);
+        let contribution = self.storage.get_staged(&staged_contribution)?;
+        let signature: ContributionFileSignature =
+            serde_json::from_slice(&self.storage.get_staged(&staged_signature)?)?;
+
+        self.write_contribution(contribution_locator, contribution)?;
+        self.write_contribution_file_signature(signature_locator, signature)?;
+
+        self.storage.clear_staged(&staged_contribution)?;
+        self.storage.clear_staged(&staged_signature)
+    }
+
     ///
     /// Attempts to run verification in the current round for a given
     /// chunk ID and participant.
@@ -2393,7 +2635,7 @@ impl Coordinator {
     }
 
     #[inline]
-    fn load_current_round_height(storage: &Disk) -> Result<u64, CoordinatorError> {
+    fn load_current_round_height(storage: &S) -> Result<u64, CoordinatorError> {
         if storage.exists(&Locator::RoundHeight) {
             // Fetch the current round height from storage.
             match storage.get(&Locator::RoundHeight)? {
@@ -2408,7 +2650,7 @@ impl Coordinator {
     }
 
     #[inline]
-    fn load_current_round(storage: &Disk) -> Result<Round, CoordinatorError> {
+    fn load_current_round(storage: &S) -> Result<Round, CoordinatorError> {
         // Fetch the current round height from storage.
         let current_round_height = Self::load_current_round_height(storage)?;
 
@@ -2417,7 +2659,7 @@ impl Coordinator {
     }
 
     #[inline]
-    fn load_round(storage: &Disk, round_height: u64) -> Result<Round, CoordinatorError> {
+    fn load_round(storage: &S, round_height: u64) -> Result<Round, CoordinatorError> {
         // Fetch the current round height from storage.
         let current_round_height = Self::load_current_round_height(storage)?;
 
@@ -2440,7 +2682,7 @@ impl Coordinator {
     /// coordinator is using.
     ///
     #[inline]
-    pub(super) fn storage(&self) -> &Disk {
+    pub(super) fn storage(&self) -> &S {
         &self.storage
     }
 
@@ -2450,7 +2692,7 @@ impl Coordinator {
     ///
     #[cfg(test)]
     #[inline]
-    pub(super) fn storage_mut(&mut self) -> &mut Disk {
+    pub(super) fn storage_mut(&mut self) -> &mut S {
         &mut self.storage
     }
 
@@ -2458,7 +2700,6 @@ impl Coordinator {
     /// Returns a reference to the instantiation of `Signature` that this
     /// coordinator is using.
     ///
-    #[cfg(test)]
     #[inline]
     pub(super) fn signature(&self) -> Arc<dyn Signature> {
         self.signature.clone()
@@ -2510,6 +2751,18 @@ impl Coordinator {
             let new_round_height = current_round_height - 1;
             tracing::debug!("Rolling back to round {} in storage.", new_round_height);
 
+            // Keep the failed verifications of the removed round in the state of the previous one.
+            if !round.failed_verifications().is_empty() {
+                let mut previous_round = Self::load_round(&self.storage, new_round_height)?;
+                previous_round.add_failed_verifications(round.failed_verifications());
+                self.storage.update(
+                    &Locator::RoundState {
+                        round_height: new_round_height,
+                    },
+                    Object::RoundState(previous_round),
+                )?;
+            }
+
             self.storage.remove(&Locator::RoundState {
                 round_height: current_round_height,
             })?;
@@ -2524,10 +2777,10 @@ impl Coordinator {
 }
 
 #[cfg(any(test, feature = "operator"))]
-use crate::commands::{Computation, Seed, SigningKey, Verification};
+use crate::commands::{Beacon, BeaconRecord, CheckedResponse, Computation, Seed, SigningKey, Verification};
 
 #[cfg(any(test, feature = "operator"))]
-impl Coordinator {
+impl<S: Storage> Coordinator<S> {
     #[tracing::instrument(
         skip(self, contributor, contributor_signing_key, contributor_seed),
         fields(contributor = %contributor),
@@ -2592,7 +2845,7 @@ impl Coordinator {
     /// # Error
     /// This function assumes that the given task has been indeed assigned to the
     /// default verifier.
-    pub fn default_verify(&mut self, task: &Task) -> anyhow::Result<()> {
+    pub fn default_verify(&mut self, task: &Task) -> Result<(), CoordinatorError> {
         let verifier = self
             .environment
             .coordinator_verifiers()
@@ -2604,6 +2857,27 @@ impl Coordinator {
         self.verify(&verifier, &sigkey, task)
     }
 
+    /// Verifies a contribution using the coordinator's default verifier, like [`default_verify`], from the result of
+    /// [`Coordinator::check_contribution`]. Only the next challenge and the contribution file signature are written
+    /// here.
+    pub(crate) fn default_verify_checked(
+        &mut self,
+        task: &Task,
+        checked_response: CheckedResponse,
+    ) -> Result<(), CoordinatorError> {
+        let verifier = self
+            .environment
+            .coordinator_verifiers()
+            .first()
+            .ok_or_else(|| CoordinatorError::VerifierMissing)?
+            .clone();
+        let sigkey = self.environment.default_verifier_signing_key();
+
+        self.checked_verify(&verifier, &sigkey, task, Some(checked_response))
+    }
+
+    /// Verifies the contribution of the given task. An invalid contribution is recorded
+    /// in the coordinator and round states and reported as [`CoordinatorError::VerificationFailed`].
     #[tracing::instrument(
         skip(self, verifier, verifier_signing_key),
         fields(verifier = %verifier),
@@ -2613,14 +2887,45 @@ impl Coordinator {
         verifier: &Participant,
         verifier_signing_key: &SigningKey,
         task: &Task,
-    ) -> anyhow::Result<()> {
+    ) -> Result<(), CoordinatorError> {
+        self.checked_verify(verifier, verifier_signing_key, task, None)
+    }
+
+    fn checked_verify(
+        &mut self,
+        verifier: &Participant,
+        verifier_signing_key: &SigningKey,
+        task: &Task,
+        checked_response: Option<CheckedResponse>,
+    ) -> Result<(), CoordinatorError> {
         let round_height = self.current_round_height()?;
         debug!(
             "Running verification for round {} chunk {}",
             round_height,
             task.chunk_id()
         );
-        let _next_challenge = self.run_verification(round_height, task, verifier, verifier_signing_key)?;
+        let _next_challenge =
+            match self.run_checked_verification(round_height, task, verifier, verifier_signing_key, checked_response) {
+                Err(CoordinatorError::VerificationFailed(reason)) => {
+                    // Record the failure in the coordinator and round states before reporting it.
+                    let mut round = self.current_round()?;
+                    let contributor = round
+                        .chunk(task.chunk_id())?
+                        .get_contribution(task.contribution_id())?
+                        .get_contributor()
+                        .clone();
+                    let failed_verification =
+                        self.state
+                            .add_failed_verification(*task, contributor, reason.clone(), self.time.as_ref())?;
+                    round.add_failed_verifications(&[failed_verification]);
+                    self.storage
+                        .update(&Locator::RoundState { round_height }, Object::RoundState(round))?;
+                    self.save_state()?;
+
+                    return Err(CoordinatorError::VerificationFailed(reason));
+                }
+                result => result?,
+            };
         self.try_verify(verifier, task)?;
         debug!(
             "Successful verification for round {} chunk {}",
@@ -2733,6 +3038,17 @@ impl Coordinator {
         task: &Task,
         participant: &Participant,
         participant_signing_key: &SigningKey,
+    ) -> Result<LocatorPath, CoordinatorError> {
+        self.run_checked_verification(round_height, task, participant, participant_signing_key, None)
+    }
+
+    fn run_checked_verification(
+        &mut self,
+        round_height: u64,
+        task: &Task,
+        participant: &Participant,
+        participant_signing_key: &SigningKey,
+        checked_response: Option<CheckedResponse>,
     ) -> Result<LocatorPath, CoordinatorError> {
         let chunk_id = task.chunk_id();
         let contribution_id = task.contribution_id();
@@ -2804,6 +3120,7 @@ impl Coordinator {
             chunk_id,
             contribution_id,
             is_final_contribution,
+            checked_response,
         )?;
         info!(
             "Completed verification on round {} chunk {} contribution {} as {}",
@@ -2858,6 +3175,59 @@ impl Coordinator {
     }
 }
 
+#[cfg(any(test, feature = "operator"))]
+impl Coordinator<DefaultStorage> {
+    ///
+    /// Checks the contribution of the given task of the given round against its challenge. The contribution files
+    /// are read from the base directory of the environment, so the check can run without the lock of the coordinator.
+    /// Its result is recorded with [`Coordinator::default_verify_checked`].
+    ///
+    pub(crate) fn check_contribution(environment: &Environment, round_height: u64, task: &Task) -> CheckedResponse {
+        // Check that the contribution ID is valid.
+        if task.contribution_id() == 0 {
+            return Err(CoordinatorError::ContributionIdMustBeNonzero);
+        }
+
+        Verification::check(
+            environment,
+            &storage::Disk::new(environment),
+            round_height,
+            task.chunk_id(),
+            task.contribution_id(),
+        )
+    }
+
+    ///
+    /// Finalizes the ceremony by applying the given randomness beacon, with the seed derived from it, to the last
+    /// verified contribution.
+    ///
+    /// The beacon can be applied only once the ceremony is locked with the manual lock or over, when no
+    /// contribution is in progress, and only once.
+    ///
+    pub fn apply_beacon(&mut self, beacon: &Beacon, seed: &Seed) -> Result<BeaconRecord, CoordinatorError> {
+        if !self.is_ceremony_locked() {
+            return Err(CoordinatorError::CeremonyNotLocked);
+        }
+        if !self.state.current_contributors().is_empty() || !self.get_pending_verifications().is_empty() {
+            return Err(CoordinatorError::CurrentRoundNotFinished);
+        }
+
+        let record = crate::commands::apply_beacon(
+            self.environment.local_base_directory().as_ref(),
+            self.environment.parameters().circuits(),
+            beacon,
+            seed,
+        )
+        .map_err(anyhow::Error::from)?;
+        info!(
+            "Applied the beacon to round {} contribution {}, final contribution hash is {}",
+            record.round_height, record.contribution_id, record.contribution_hash
+        );
+
+        Ok(record)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -2865,8 +3235,10 @@ mod tests {
         commands::{Seed, SigningKey, SEED_LENGTH},
         environment::*,
         objects::{Participant, Task},
+        storage::{audit_files, AuditIssueKind, ContributionLocator, Locator, Object, Storage, StorageLocator},
         testing::prelude::*,
         Coordinator,
+        CoordinatorError,
     };
 
     use once_cell::sync::Lazy;
@@ -3230,6 +3602,209 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    fn coordinator_audit_storage() -> anyhow::Result<()> {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+
+        let contributor = Lazy::force(&TEST_CONTRIBUTOR_ID);
+        let contributor_signing_key: SigningKey = "secret_key".to_string();
+        let verifier = Lazy::force(&TEST_VERIFIER_ID).clone();
+        let verifier_signing_key: SigningKey = "secret_key".to_string();
+
+        let mut coordinator = Coordinator::new(TEST_ENVIRONMENT_ANOMA.clone(), Arc::new(Dummy))?;
+        initialize_coordinator(&mut coordinator)?;
+
+        // Add and verify round 1 chunk 0 contribution 1.
+        let round_height = coordinator.current_round_height()?;
+        let chunk_id = 0;
+        let contribution_id = 1;
+        {
+            coordinator.try_lock_chunk(chunk_id, &contributor)?;
+            let mut seed: Seed = [0; SEED_LENGTH];
+            rand::thread_rng().fill_bytes(&mut seed[..]);
+            coordinator.run_computation(
+                round_height,
+                chunk_id,
+                contribution_id,
+                contributor,
+                &contributor_signing_key,
+                &seed,
+            )?;
+            coordinator.add_contribution(chunk_id, &contributor)?;
+
+            let task = Task::new(chunk_id, contribution_id);
+            coordinator.run_verification(round_height, &task, &verifier, &verifier_signing_key)?;
+            coordinator.verify_contribution(&task, &verifier)?;
+        }
+
+        // The transcript is consistent.
+        assert_eq!(coordinator.audit_storage()?, vec![]);
+
+        // Tamper with the response of the contributor, keeping its size.
+        let response = Locator::ContributionFile(ContributionLocator::new(
+            round_height,
+            chunk_id,
+            contribution_id,
+            false,
+        ));
+        let path = coordinator.storage().to_path(&response)?;
+        let size = coordinator.storage().size(&response)?;
+        std::fs::write(path.as_path(), vec![0u8; size as usize])?;
+
+        // Both signatures of the contribution sign the hash of the original response.
+        let issues = coordinator.audit_storage()?;
+        assert_eq!(issues.len(), 2);
+        for issue in issues {
+            assert_eq!(issue.round_height, round_height);
+            assert_eq!(issue.chunk_id, Some(chunk_id));
+            assert_eq!(issue.contribution_id, Some(contribution_id));
+            assert!(matches!(issue.kind, AuditIssueKind::HashMismatch { file, .. } if file == path));
+        }
+
+        // The files are audited the same from the round states read beforehand.
+        let round_states = coordinator.round_states()?;
+        let signature = coordinator.signature();
+        let issues = audit_files(coordinator.environment(), signature.as_ref(), round_states);
+        assert_eq!(issues, coordinator.audit_storage()?);
+
+        // A missing file is reported as such.
+        std::fs::remove_file(path.as_path())?;
+        let issues = coordinator.audit_storage()?;
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, AuditIssueKind::FileMissing { path });
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn coordinator_verifier_records_failed_verification() -> anyhow::Result<()> {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+
+        let contributor = Lazy::force(&TEST_CONTRIBUTOR_ID);
+        let contributor_signing_key: SigningKey = "secret_key".to_string();
+        let verifier = Lazy::force(&TEST_VERIFIER_ID).clone();
+        let verifier_signing_key: SigningKey = "secret_key".to_string();
+
+        let mut coordinator = Coordinator::new(TEST_ENVIRONMENT_ANOMA.clone(), Arc::new(Dummy))?;
+        initialize_coordinator_single_contributor(&mut coordinator)?;
+
+        // Add round 1 chunk 0 contribution 1.
+        let round_height = coordinator.current_round_height()?;
+        let chunk_id = 0;
+        let contribution_id = 1;
+        coordinator.try_lock_chunk(chunk_id, &contributor)?;
+        let mut seed: Seed = [0; SEED_LENGTH];
+        rand::thread_rng().fill_bytes(&mut seed[..]);
+        coordinator.run_computation(
+            round_height,
+            chunk_id,
+            contribution_id,
+            contributor,
+            &contributor_signing_key,
+            &seed,
+        )?;
+        coordinator.add_contribution(chunk_id, &contributor)?;
+
+        // Tamper with the response of the contributor.
+        let response = Locator::ContributionFile(ContributionLocator::new(
+            round_height,
+            chunk_id,
+            contribution_id,
+            false,
+        ));
+        let path = coordinator.storage().to_path(&response)?;
+        let size = coordinator.storage().size(&response)?;
+        std::fs::write(path.as_path(), vec![0u8; size as usize])?;
+
+        let task = Task::new(chunk_id, contribution_id);
+        let reason = match coordinator.verify(&verifier, &verifier_signing_key, &task) {
+            Err(CoordinatorError::VerificationFailed(reason)) => reason,
+            result => panic!("Unexpected verification result: {:?}", result),
+        };
+
+        // The failure is persisted in the state of the round.
+        let failed_verifications = |coordinator: &Coordinator, round_height| -> anyhow::Result<_> {
+            match coordinator.storage().get(&Locator::RoundState { round_height })? {
+                Object::RoundState(round) => Ok(round.failed_verifications().to_vec()),
+                _ => panic!("Unexpected object for the round state"),
+            }
+        };
+        let failed = failed_verifications(&coordinator, round_height)?;
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].round_height(), round_height);
+        assert_eq!(failed[0].task(), &task);
+        assert_eq!(failed[0].contributor(), Some(contributor));
+        assert_eq!(failed[0].reason(), &reason);
+        assert_eq!(coordinator.state.failed_verifications(), failed.as_slice());
+
+        // It is kept in the state of the previous round when the round is rolled back.
+        coordinator.reset_round()?;
+        assert_eq!(coordinator.current_round_height()?, round_height - 1);
+        assert_eq!(failed_verifications(&coordinator, round_height - 1)?, failed);
+
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn coordinator_verifier_verify_checked_contribution() -> anyhow::Result<()> {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+
+        let contributor = Lazy::force(&TEST_CONTRIBUTOR_ID);
+        let contributor_signing_key: SigningKey = "secret_key".to_string();
+
+        let mut coordinator = Coordinator::new(TEST_ENVIRONMENT_ANOMA.clone(), Arc::new(Dummy))?;
+        initialize_coordinator_single_contributor(&mut coordinator)?;
+
+        // Add round 1 chunk 0 contribution 1.
+        let round_height = coordinator.current_round_height()?;
+        let chunk_id = 0;
+        let contribution_id = 1;
+        coordinator.try_lock_chunk(chunk_id, &contributor)?;
+        let mut seed: Seed = [0; SEED_LENGTH];
+        rand::thread_rng().fill_bytes(&mut seed[..]);
+        coordinator.run_computation(
+            round_height,
+            chunk_id,
+            contribution_id,
+            contributor,
+            &contributor_signing_key,
+            &seed,
+        )?;
+        coordinator.add_contribution(chunk_id, &contributor)?;
+
+        // The contribution is checked apart from the coordinator, its contributor is reported as verifying meanwhile.
+        assert!(coordinator.start_verifying_contributor(contributor));
+        assert!(!coordinator.start_verifying_contributor(contributor));
+        assert!(coordinator.state().is_verifying_contributor(contributor));
+        let task = Task::new(chunk_id, contribution_id);
+        let checked_response = Coordinator::check_contribution(coordinator.environment(), round_height, &task);
+        assert!(checked_response.is_ok());
+        coordinator.stop_verifying_contributor(contributor);
+        assert!(!coordinator.state().is_verifying_contributor(contributor));
+
+        // A response which changed since it was checked is checked again.
+        let response = Locator::ContributionFile(ContributionLocator::new(
+            round_height,
+            chunk_id,
+            contribution_id,
+            false,
+        ));
+        let path = coordinator.storage().to_path(&response)?;
+        let size = coordinator.storage().size(&response)?;
+        std::fs::write(path.as_path(), vec![0u8; size as usize])?;
+
+        match coordinator.default_verify_checked(&task, checked_response) {
+            Err(CoordinatorError::VerificationFailed(_)) => (),
+            result => panic!("Unexpected verification result: {:?}", result),
+        }
+        assert_eq!(coordinator.state().failed_verifications().len(), 1);
+
+        Ok(())
+    }
+
     #[test]
     #[serial]
     // This test runs a round with a single coordinator and single verifier
@@ -3485,14 +4060,8 @@ mod tests {
         );
     }
 
-    #[test]
-    #[serial]
-    fn coordinator_next_round() -> anyhow::Result<()> {
-        initialize_test_environment(&TEST_ENVIRONMENT_3);
-
-        let mut coordinator = Coordinator::new(TEST_ENVIRONMENT_3.clone(), Arc::new(Dummy))?;
-        initialize_coordinator_single_contributor(&mut coordinator)?;
-
+    /// Runs computation and verification on each contribution in each chunk of round 1.
+    fn complete_round_1(coordinator: &mut Coordinator) -> anyhow::Result<()> {
         let round_height = 1;
         assert_eq!(round_height, coordinator.current_round_height()?);
 
@@ -3593,6 +4162,20 @@ mod tests {
             }
         }
 
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn coordinator_next_round() -> anyhow::Result<()> {
+        initialize_test_environment(&TEST_ENVIRONMENT_3);
+
+        let mut coordinator = Coordinator::new(TEST_ENVIRONMENT_3.clone(), Arc::new(Dummy))?;
+        initialize_coordinator_single_contributor(&mut coordinator)?;
+        complete_round_1(&mut coordinator)?;
+
+        let contributor = Lazy::force(&TEST_CONTRIBUTOR_ID).clone();
+
         info!(
             "Starting aggregation with this transcript {}",
             serde_json::to_string_pretty(&coordinator.current_round()?)?
@@ -3617,6 +4200,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[serial]
+    fn coordinator_next_round_after_interrupted_advance() -> anyhow::Result<()> {
+        initialize_test_environment(&TEST_ENVIRONMENT_3);
+
+        let mut coordinator = Coordinator::new(TEST_ENVIRONMENT_3.clone(), Arc::new(Dummy))?;
+        initialize_coordinator_single_contributor(&mut coordinator)?;
+        complete_round_1(&mut coordinator)?;
+
+        // Advance to round 2 without saving the coordinator state, as if the coordinator stopped right after, and
+        // leave a contribution file of round 2 behind.
+        let contributor = Lazy::force(&TEST_CONTRIBUTOR_ID).clone();
+        coordinator.aggregate_contributions()?;
+        coordinator.next_round(OffsetDateTime::now_utc(), vec![contributor.clone()])?;
+        assert_eq!(2, coordinator.current_round_height()?);
+        let contribution = Locator::ContributionFile(ContributionLocator::new(2, 0, 1, false));
+        coordinator.storage.initialize(contribution.clone(), 1)?;
+        drop(coordinator);
+
+        // The coordinator is rolled back to round 1 on restart, the files of round 2 are removed except its initial
+        // challenges.
+        let mut coordinator = Coordinator::new(TEST_ENVIRONMENT_3.clone(), Arc::new(Dummy))?;
+        assert_eq!(1, coordinator.current_round_height()?);
+        assert!(!coordinator.storage.exists(&contribution));
+        assert!(coordinator
+            .storage
+            .exists(&Locator::ContributionFile(ContributionLocator::new(2, 0, 0, true))));
+
+        // The ceremony advances to round 2 again.
+        coordinator.aggregate_contributions()?;
+        coordinator.next_round(OffsetDateTime::now_utc(), vec![contributor])?;
+        assert_eq!(2, coordinator.current_round_height()?);
+        coordinator.storage.initialize(contribution, 1)?;
+
+        Ok(())
+    }
+
     #[test]
     #[serial]
     #[ignore]
@@ -1,10 +1,15 @@
 use crate::{
+    commands::VerificationError,
+    config::Config,
     environment::Environment,
     objects::{
         participant::*,
         task::{initialize_tasks, Task},
     },
-    storage::{Disk, Locator, Object},
+    queue::{self, QueueEntry},
+    schedule::CohortSchedule,
+    storage::{Locator, Object, Storage},
+    tokens::{self, TokenError},
     CoordinatorError, TimeSource,
 };
 use anyhow::anyhow;
@@ -21,17 +26,16 @@ use time::{Duration, OffsetDateTime};
 use tracing::*;
 
 lazy_static! {
-    pub static ref TOKENS_PATH: String = std::env::var("NAMADA_TOKENS_PATH").unwrap_or_else(|_| "./tokens".to_string());
-    pub(crate) static ref TOKEN_BLACKLIST: bool = match std::env::var("TOKEN_BLACKLIST") {
-        Ok(s) if s == "true" => true,
-        _ => false,
-    };
-    pub(crate) static ref IP_BAN: bool = match std::env::var("NAMADA_MPC_IP_BAN") {
-        Ok(s) if s == "true" => true,
-        _ => false,
-    };
+    pub static ref TOKENS_PATH: String = Config::current().tokens.path;
+    pub(crate) static ref TOKEN_BLACKLIST: bool = Config::current().auth.token_blacklist;
+    pub(crate) static ref IP_BAN: bool = Config::current().auth.ip_ban;
 }
 
+/// The reliability score of a contributor joining the queue for the first time.
+const INITIAL_RELIABILITY: u8 = 5;
+/// The highest reliability score of a contributor.
+const MAX_RELIABILITY: u8 = 10;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(super) enum CoordinatorStatus {
     Initializing,
@@ -945,6 +949,48 @@ impl Default for RoundMetrics {
     }
 }
 
+/// A contribution which failed verification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedVerification {
+    /// The round height of the contribution.
+    round_height: u64,
+    /// The task of the contribution.
+    task: Task,
+    /// The participant who produced the contribution, if known.
+    contributor: Option<Participant>,
+    /// The reason the verification failed.
+    reason: VerificationError,
+    /// The timestamp of the failed verification.
+    failed_at: OffsetDateTime,
+}
+
+impl FailedVerification {
+    /// Returns the round height of the contribution.
+    pub fn round_height(&self) -> u64 {
+        self.round_height
+    }
+
+    /// Returns the task of the contribution.
+    pub fn task(&self) -> &Task {
+        &self.task
+    }
+
+    /// Returns the participant who produced the contribution, if known.
+    pub fn contributor(&self) -> Option<&Participant> {
+        self.contributor.as_ref()
+    }
+
+    /// Returns the reason the verification failed.
+    pub fn reason(&self) -> &VerificationError {
+        &self.reason
+    }
+
+    /// Returns the timestamp of the failed verification.
+    pub fn failed_at(&self) -> &OffsetDateTime {
+        &self.failed_at
+    }
+}
+
 /// A runtime state holding values which are specific to the current ceremony run. This state must not be persisted to
 /// storage to allow a reset of it in case of a ceremony restart
 #[derive(Debug, Clone)]
@@ -955,6 +1001,8 @@ struct RuntimeState {
     tokens_in_use: HashMap<String, Participant>,
     /// The map of ip addresses currently in ceremony
     current_ips: HashMap<IpAddr, Participant>,
+    /// The contributors whose contribution is currently being verified
+    verifying_contributors: HashSet<Participant>,
 }
 
 impl Default for RuntimeState {
@@ -965,6 +1013,7 @@ impl Default for RuntimeState {
             tokens: CoordinatorState::load_tokens(),
             tokens_in_use: Default::default(),
             current_ips: Default::default(),
+            verifying_contributors: Default::default(),
         }
     }
 }
@@ -1000,12 +1049,27 @@ pub struct CoordinatorState {
     dropped: Vec<ParticipantInfo>,
     /// The list of participants that are banned from all current and future rounds.
     banned: HashSet<Participant>,
+    /// The map of banned participants to the reason of their ban.
+    #[serde(default)]
+    ban_reasons: HashMap<Participant, String>,
+    /// The list of contributions that failed verification in current and past rounds.
+    #[serde(default)]
+    failed_verifications: Vec<FailedVerification>,
     /// The manual lock to hold the coordinator from transitioning to the next round.
     manual_lock: bool,
     /// The ceremony start time.
     ceremony_start_time: OffsetDateTime,
     /// Duration, in seconds, of each cohort
     cohort_duration: u64,
+    /// The schedule of the cohorts, replacing the ones of `cohort_duration` if set
+    #[serde(default)]
+    cohort_schedule: Option<CohortSchedule>,
+    /// The number of contributors who joined the queue during each cohort, starting from 0
+    #[serde(default)]
+    cohort_contributors: HashMap<usize, usize>,
+    /// The positions of the queue, starting from 0, the operators moved contributors to
+    #[serde(default)]
+    queue_positions: HashMap<Participant, usize>,
     /// Map of tokens which have been used in the ceremony
     blacklisted_tokens: HashMap<String, Participant>,
     /// Temporary runtime state, should not be persisted to storage to reset it in case of restart
@@ -1019,38 +1083,30 @@ impl CoordinatorState {
     /// # Panics
     /// If folder, file names or content don't respect the specified format.
     pub(super) fn load_tokens() -> Vec<HashSet<String>> {
-        let tokens_file_prefix = std::env::var("TOKENS_FILE_PREFIX").unwrap_or("namada_tokens_cohort".to_string());
         let tokens_dir =
             std::fs::read_dir(TOKENS_PATH.as_str()).expect(format!("Error with path {}", &*TOKENS_PATH).as_str());
-        let number_of_cohorts = tokens_dir.count();
-        let mut tokens = vec![HashSet::default(); number_of_cohorts];
+        let mut cohorts = HashMap::new();
 
-        for cohort in 1..=number_of_cohorts {
-            let path = format!("{}/{}_{}.json", *TOKENS_PATH, tokens_file_prefix, cohort);
-            let file = std::fs::read(path).unwrap();
-            let token_set: HashSet<String> = serde_json::from_slice(&file).unwrap();
-            tokens[cohort - 1] = token_set;
+        for entry in tokens_dir {
+            let path = entry.unwrap().path();
+            let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+            cohorts.insert(file_name, std::fs::read(&path).unwrap());
         }
 
-        tokens
+        Self::load_tokens_from_bytes(&cohorts).unwrap()
     }
 
-    /// Reads tokens from bytes and generates a vector of them.
     ///
-    /// # Panics
-    /// If the files' name don't respect the expected format of if the bytes don't represent a valid HashSet<String>
-    pub(super) fn load_tokens_from_bytes(cohorts: &HashMap<String, Vec<u8>>) -> Vec<HashSet<String>> {
-        let mut tokens = vec![HashSet::default(); cohorts.len()];
-
-        for (file_name, bytes) in cohorts {
-            let split: Vec<&str> = file_name.rsplit(".json").collect();
-            let index_str = split[1].rsplit("_").collect::<Vec<&str>>()[0];
-            let index = index_str.parse::<usize>().unwrap() - 1;
-            let token: HashSet<String> = serde_json::from_slice(bytes.as_ref()).unwrap();
-            tokens[index] = token;
-        }
+    /// Reads tokens from bytes, indexed by file name, and generates a vector of them.
+    ///
+    /// On file names or content not respecting the format of the cohort files, this function returns a `TokenError`.
+    ///
+    pub(super) fn load_tokens_from_bytes(
+        cohorts: &HashMap<String, Vec<u8>>,
+    ) -> Result<Vec<HashSet<String>>, TokenError> {
+        let tokens = tokens::parse_cohort_files(&Config::current().tokens.file_prefix, cohorts)?;
 
-        tokens
+        Ok(tokens.into_iter().map(HashSet::from_iter).collect())
     }
 
     ///
@@ -1060,15 +1116,14 @@ impl CoordinatorState {
         self.runtime_state.tokens = tokens
     }
 
+    /// The ceremony starts at the configured time. Debug builds start it now if none is configured.
     fn get_ceremony_start_time() -> OffsetDateTime {
         #[cfg(debug_assertions)]
-        let ceremony_start_time = OffsetDateTime::now_utc();
+        let ceremony_start_time = Config::current().start_time().unwrap_or_else(OffsetDateTime::now_utc);
         #[cfg(not(debug_assertions))]
-        let ceremony_start_time = {
-            let timestamp_env = std::env::var("CEREMONY_START_TIMESTAMP").unwrap();
-            let timestamp = timestamp_env.parse::<i64>().unwrap();
-            OffsetDateTime::from_unix_timestamp(timestamp).unwrap()
-        };
+        let ceremony_start_time = Config::current()
+            .start_time()
+            .expect("ceremony.start_timestamp is required in release builds");
 
         ceremony_start_time
     }
@@ -1077,17 +1132,17 @@ impl CoordinatorState {
     /// Creates a new instance of `CoordinatorState`.
     ///
     /// NOTE: At startup the coordinator will try to recover this state from disk instead of calling this initializer
-    /// So we need to clear the coordinator.json file if we want to reset the following variables:
-    ///     - CEREMONY_START_TIMESTAMP
-    ///     - NAMADA_COHORT_TIME
-    /// These two parameters are meant to stay constant during the entire ceremony.
+    /// So we need to clear the coordinator.json file if we want to reset the following settings:
+    ///     - ceremony.start_timestamp (CEREMONY_START_TIMESTAMP)
+    ///     - ceremony.cohort_time_seconds (NAMADA_COHORT_TIME)
+    ///     - ceremony.schedule_path (NAMADA_COHORT_SCHEDULE)
+    /// These parameters are meant to stay constant during the entire ceremony.
     /// The tokens are instead reloaded from files when restarting a coordinator to support a token update
     #[inline]
     pub(super) fn new(environment: Environment) -> Self {
-        let cohort_duration = match std::env::var("NAMADA_COHORT_TIME") {
-            Ok(n) => n.parse::<u64>().unwrap(),
-            Err(_) => 86400,
-        };
+        let config = Config::current();
+        let cohort_duration = config.ceremony.cohort_time_seconds;
+        let cohort_schedule = config.cohort_schedule().expect("The configuration has been validated");
 
         let ceremony_start_time = CoordinatorState::get_ceremony_start_time();
 
@@ -1106,9 +1161,14 @@ impl CoordinatorState {
             finished_verifiers: HashMap::default(),
             dropped: Vec::new(),
             banned: HashSet::new(),
+            ban_reasons: HashMap::default(),
+            failed_verifications: Vec::new(),
             manual_lock: false,
             ceremony_start_time,
             cohort_duration,
+            cohort_schedule,
+            cohort_contributors: HashMap::default(),
+            queue_positions: HashMap::default(),
             blacklisted_tokens: HashMap::default(),
             runtime_state: RuntimeState::default(),
         }
@@ -1216,11 +1276,16 @@ impl CoordinatorState {
             *self = Self {
                 ceremony_start_time: std::mem::replace(&mut self.ceremony_start_time, OffsetDateTime::now_utc()),
                 cohort_duration: std::mem::take(&mut self.cohort_duration),
+                cohort_schedule: std::mem::take(&mut self.cohort_schedule),
+                cohort_contributors: std::mem::take(&mut self.cohort_contributors),
                 current_metrics,
                 current_round_height: Some(new_round_height),
                 blacklisted_ips: std::mem::take(&mut self.blacklisted_ips),
                 queue,
+                queue_positions: std::mem::take(&mut self.queue_positions),
                 banned: std::mem::take(&mut self.banned),
+                ban_reasons: std::mem::take(&mut self.ban_reasons),
+                failed_verifications: std::mem::take(&mut self.failed_verifications),
                 blacklisted_tokens: std::mem::take(&mut self.blacklisted_tokens),
                 runtime_state: std::mem::take(&mut self.runtime_state),
                 ..Self::new(self.environment.clone())
@@ -1261,11 +1326,16 @@ impl CoordinatorState {
             *self = Self {
                 ceremony_start_time: std::mem::replace(&mut self.ceremony_start_time, OffsetDateTime::now_utc()),
                 cohort_duration: std::mem::take(&mut self.cohort_duration),
+                cohort_schedule: std::mem::take(&mut self.cohort_schedule),
+                cohort_contributors: std::mem::take(&mut self.cohort_contributors),
                 current_contributors,
                 current_verifiers: Default::default(),
                 blacklisted_ips: std::mem::take(&mut self.blacklisted_ips),
                 queue: std::mem::take(&mut self.queue),
+                queue_positions: std::mem::take(&mut self.queue_positions),
                 banned: std::mem::take(&mut self.banned),
+                ban_reasons: std::mem::take(&mut self.ban_reasons),
+                failed_verifications: std::mem::take(&mut self.failed_verifications),
                 dropped: std::mem::take(&mut self.dropped),
                 blacklisted_tokens: std::mem::take(&mut self.blacklisted_tokens),
                 runtime_state: std::mem::take(&mut self.runtime_state),
@@ -1344,6 +1414,20 @@ impl CoordinatorState {
         self.banned.contains(participant)
     }
 
+    ///
+    /// Returns the reason the given participant was banned for, if any.
+    ///
+    pub fn ban_reason(&self, participant: &Participant) -> Option<&str> {
+        self.ban_reasons.get(participant).map(String::as_str)
+    }
+
+    ///
+    /// Returns the contributions that failed verification in current and past rounds.
+    ///
+    pub fn failed_verifications(&self) -> &[FailedVerification] {
+        &self.failed_verifications
+    }
+
     ///
     /// Returns `true` if the given participant is dropped.
     ///
@@ -1490,6 +1574,14 @@ impl CoordinatorState {
         self.current_round_height.unwrap_or_default()
     }
 
+    ///
+    /// Returns the current round height stored in the coordinator state, if it has been set.
+    ///
+    #[inline]
+    pub(crate) fn current_round_height_opt(&self) -> Option<u64> {
+        self.current_round_height
+    }
+
     ///
     /// Returns the metrics for the current round and current round participants.
     ///
@@ -1499,16 +1591,66 @@ impl CoordinatorState {
     }
 
     ///
-    /// Computes the current ceremony cohort, starting from 0, depending on the cohort duration.
+    /// Computes the current ceremony cohort, starting from 0, depending on the cohort duration or on the schedule.
+    /// Between two cohorts of the schedule, returns the next one.
     ///
     pub fn get_current_cohort_index(&self) -> usize {
+        self.get_cohort_index_at(OffsetDateTime::now_utc())
+    }
+
+    ///
+    /// Computes the ceremony cohort, starting from 0, at the given time.
+    ///
+    pub fn get_cohort_index_at(&self, time: OffsetDateTime) -> usize {
+        if let Some(schedule) = &self.cohort_schedule {
+            return schedule.index_at(time.unix_timestamp());
+        }
+
         let ceremony_start_time = self.ceremony_start_time;
-        let now = OffsetDateTime::now_utc();
-        let timestamp_diff = (now.unix_timestamp() - ceremony_start_time.unix_timestamp()) as u64;
+        let timestamp_diff = (time.unix_timestamp() - ceremony_start_time.unix_timestamp()) as u64;
 
         (timestamp_diff / self.cohort_duration) as usize
     }
 
+    ///
+    /// Returns `true` if the current cohort has started: the cohorts of the cohort duration follow each other, while
+    /// the schedule can leave gaps between them.
+    ///
+    pub fn is_current_cohort_in_progress(&self) -> bool {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        match &self.cohort_schedule {
+            Some(schedule) => schedule
+                .get(schedule.index_at(now))
+                .map_or(false, |cohort| cohort.is_in_window(now)),
+            None => true,
+        }
+    }
+
+    ///
+    /// Returns the schedule of the cohorts, if any.
+    ///
+    #[inline]
+    pub fn cohort_schedule(&self) -> Option<&CohortSchedule> {
+        self.cohort_schedule.as_ref()
+    }
+
+    ///
+    /// Returns `true` if the given cohort, starting from 0, has reached the maximum number of contributors of the
+    /// schedule.
+    ///
+    pub fn is_cohort_full(&self, cohort: usize) -> bool {
+        let max_contributors = self
+            .cohort_schedule
+            .as_ref()
+            .and_then(|schedule| schedule.get(cohort))
+            .and_then(|cohort| cohort.max_contributors);
+
+        match max_contributors {
+            Some(max) => self.cohort_contributors.get(&cohort).copied().unwrap_or_default() >= max,
+            None => false,
+        }
+    }
+
     ///
     /// Returns the number of scheduled cohorts for the ceremony.
     ///
@@ -1593,6 +1735,27 @@ impl CoordinatorState {
         }
     }
 
+    ///
+    /// Marks the contribution of the given contributor as being verified. Returns `false` if it already was.
+    ///
+    pub(super) fn start_verifying_contributor(&mut self, participant: &Participant) -> bool {
+        self.runtime_state.verifying_contributors.insert(participant.clone())
+    }
+
+    ///
+    /// Marks the verification of the contribution of the given contributor as done.
+    ///
+    pub(super) fn stop_verifying_contributor(&mut self, participant: &Participant) {
+        self.runtime_state.verifying_contributors.remove(participant);
+    }
+
+    ///
+    /// Returns true if the contribution of the given contributor is being verified
+    ///
+    pub fn is_verifying_contributor(&self, participant: &Participant) -> bool {
+        self.runtime_state.verifying_contributors.contains(participant)
+    }
+
     ///
     /// Returns true if the token is currently in use
     ///
@@ -1785,6 +1948,29 @@ impl CoordinatorState {
         Ok(())
     }
 
+    ///
+    /// Returns the reliability score of the given participant joining the queue, from its past rounds:
+    /// [`INITIAL_RELIABILITY`], raised by one for each round it finished and lowered by one for each
+    /// time it was dropped or one of its contributions failed verification, up to [`MAX_RELIABILITY`].
+    ///
+    #[inline]
+    pub fn reliability_score(&self, participant: &Participant) -> u8 {
+        let finished = self
+            .finished_contributors
+            .values()
+            .filter(|contributors| contributors.contains_key(participant))
+            .count();
+        let dropped = self.dropped.iter().filter(|info| info.id() == participant).count();
+        let failed = self
+            .failed_verifications
+            .iter()
+            .filter(|failed| failed.contributor() == Some(participant))
+            .count();
+
+        let score = INITIAL_RELIABILITY as i64 + finished as i64 - (dropped + failed) as i64;
+        score.clamp(0, MAX_RELIABILITY as i64) as u8
+    }
+
     ///
     /// Adds the given participant to the queue if they are permitted to participate.
     ///
@@ -1804,6 +1990,12 @@ impl CoordinatorState {
             (reliability_score, None, time.now_utc(), time.now_utc()),
         );
 
+        // Count the contributor in the current cohort, for its maximum number of contributors
+        *self
+            .cohort_contributors
+            .entry(self.get_current_cohort_index())
+            .or_default() += 1;
+
         // Add ip (if env set and if any) to the set of currently known addresses
         if *IP_BAN {
             if let Some(ip) = participant_ip {
@@ -1836,6 +2028,28 @@ impl CoordinatorState {
 
         // Remove the participant from the queue.
         self.queue.remove(participant);
+        self.queue_positions.remove(participant);
+
+        Ok(())
+    }
+
+    ///
+    /// Moves the given contributor to the given position of the queue, starting from 0, ahead of the order of the
+    /// queue policy. The rounds are assigned on the next update of the queue.
+    ///
+    #[inline]
+    pub(super) fn move_in_queue(&mut self, participant: &Participant, position: usize) -> Result<(), CoordinatorError> {
+        // Check that the participant is not already in precommit for the next round.
+        if self.next.contains_key(participant) {
+            return Err(CoordinatorError::ParticipantAlreadyPrecommitted);
+        }
+
+        // Check that the participant is exists in the queue.
+        if !self.queue.contains_key(participant) {
+            return Err(CoordinatorError::ParticipantMissing);
+        }
+
+        self.queue_positions.insert(participant.clone(), position);
 
         Ok(())
     }
@@ -2610,6 +2824,40 @@ impl CoordinatorState {
         Ok(DropParticipant::DropCurrent(drop_data))
     }
 
+    ///
+    /// Records that the contribution of the given task failed verification in the current round,
+    /// and returns the record.
+    ///
+    #[inline]
+    pub(super) fn add_failed_verification(
+        &mut self,
+        task: Task,
+        contributor: Option<Participant>,
+        reason: VerificationError,
+        time: &dyn TimeSource,
+    ) -> Result<FailedVerification, CoordinatorError> {
+        let round_height = self.current_round_height.ok_or(CoordinatorError::RoundHeightNotSet)?;
+
+        warn!(
+            "Contribution of round {} chunk {} contribution {} failed verification: {}",
+            round_height,
+            task.chunk_id(),
+            task.contribution_id(),
+            reason
+        );
+
+        let failed_verification = FailedVerification {
+            round_height,
+            task,
+            contributor,
+            reason,
+            failed_at: time.now_utc(),
+        };
+        self.failed_verifications.push(failed_verification.clone());
+
+        Ok(failed_verification)
+    }
+
     ///
     /// Bans the given participant from the queue, precommit, and current round.
     ///
@@ -2617,6 +2865,7 @@ impl CoordinatorState {
     pub(super) fn ban_participant(
         &mut self,
         participant: &Participant,
+        reason: &str,
         time: &dyn TimeSource,
     ) -> Result<DropParticipant, CoordinatorError> {
         // Check that the participant is not already banned from participating.
@@ -2632,10 +2881,11 @@ impl CoordinatorState {
 
         // Add the participant to the banned list.
         self.banned.insert(participant.clone());
+        self.ban_reasons.insert(participant.clone(), reason.to_string());
 
         // NOTE: token of the participant has already been blacklisted at the end of the contribution, no need to take actions here
 
-        info!("{} was banned from the ceremony", participant);
+        info!("{} was banned from the ceremony: {}", participant, reason);
 
         Ok(drop)
     }
@@ -2652,6 +2902,7 @@ impl CoordinatorState {
             .into_par_iter()
             .filter(|p| p != participant)
             .collect();
+        self.ban_reasons.remove(participant);
 
         // Unban ip
         self.blacklisted_ips.retain(|_, part| part != participant);
@@ -2757,43 +3008,53 @@ impl CoordinatorState {
             _ => return Err(CoordinatorError::RoundHeightNotSet),
         };
 
-        // Sort the participants in the queue by time joined.
-        let mut queue: Vec<_> = self
-            .queue
-            .clone()
-            .into_par_iter()
-            .map(|(p, (r, _, ls, j))| (p, r, ls, j))
-            .collect();
-        queue.par_sort_by(|a, b| (a.3).cmp(&b.3));
-
         // Parse the queue participants into contributors and verifiers,
         // and check that they are not banned participants.
-        let contributors: Vec<(_, _, _, _)> = queue
-            .clone()
-            .into_par_iter()
-            .filter(|(p, _, _, _)| p.is_contributor() && !self.banned.contains(&p))
+        let mut contributors: Vec<QueueEntry> = self
+            .queue
+            .iter()
+            .filter(|(p, _)| p.is_contributor() && !self.banned.contains(p))
+            .map(|(p, (r, _, _, j))| QueueEntry {
+                participant: p.clone(),
+                reliability: *r,
+                joined: *j,
+                cohort: self.get_cohort_index_at(*j),
+            })
             .collect();
 
+        // Sort the contributors in the queue by time joined.
+        contributors.par_sort_by(|a, b| a.joined.cmp(&b.joined));
+
         // Fetch the permitted number of contributors
         let maximum_contributors = self.environment.maximum_contributors_per_round();
 
+        // Order the contributors with the queue policy, then the positions set by the operators.
+        let policy = self.environment.queue_policy();
+        let mut contributors = policy.scheduler().schedule(contributors, maximum_contributors);
+        self.queue_positions
+            .retain(|participant, _| contributors.iter().any(|entry| &entry.participant == participant));
+        let positions: Vec<_> = self.queue_positions.clone().into_iter().collect();
+        queue::apply_positions(&mut contributors, &positions);
+
         // Initialize the updated queue.
         let mut updated_queue = HashMap::with_capacity(contributors.len());
 
         // Update assigned round height for each contributor.
         for (index, round) in contributors.chunks(maximum_contributors).enumerate() {
-            for (contributor, reliability, last_seen, joined) in round.into_iter() {
+            for entry in round.iter() {
                 let assigned_round = next_round + index as u64;
                 trace!(
-                    "Assigning contributor {} who joined at {} with reliability {} in queue to round {}",
-                    contributor,
-                    joined,
-                    reliability,
-                    assigned_round
+                    "Assigning contributor {} who joined at {} with reliability {} in queue to round {} ({} policy)",
+                    entry.participant,
+                    entry.joined,
+                    entry.reliability,
+                    assigned_round,
+                    policy
                 );
+                let (_, _, last_seen, _) = self.queue[&entry.participant];
                 updated_queue.insert(
-                    contributor.clone(),
-                    (*reliability, Some(assigned_round), *last_seen, *joined),
+                    entry.participant.clone(),
+                    (entry.reliability, Some(assigned_round), last_seen, entry.joined),
                 );
             }
         }
@@ -3016,6 +3277,8 @@ impl CoordinatorState {
                 // Check if the participant meets the ban threshold.
                 if count > self.environment.participant_ban_threshold() as usize {
                     self.banned.insert(participant_info.id.clone());
+                    self.ban_reasons
+                        .insert(participant_info.id.clone(), format!("Dropped {} times", count));
 
                     debug!("{} is being banned", participant_info.id);
                 }
@@ -3569,7 +3832,7 @@ impl CoordinatorState {
 
     /// Save the coordinator state in storage.
     #[inline]
-    pub(crate) fn save(&self, storage: &mut Disk) -> Result<(), CoordinatorError> {
+    pub(crate) fn save(&self, storage: &mut impl Storage) -> Result<(), CoordinatorError> {
         storage.update(&Locator::CoordinatorState, Object::CoordinatorState(self.clone()))
     }
 }
@@ -3730,6 +3993,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cohort_schedule() {
+        let time = SystemTimeSource::new();
+        let environment = TEST_ENVIRONMENT.clone();
+        let contributor = test_coordinator_contributor(&environment).unwrap();
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        // Between two cohorts, the next one hasn't started yet
+        let mut state = CoordinatorState::new(environment.clone());
+        state.cohort_schedule = Some(CohortSchedule {
+            cohorts: vec![
+                crate::schedule::Cohort {
+                    start: now - 200,
+                    end: now - 100,
+                    max_contributors: None,
+                    label: None,
+                },
+                crate::schedule::Cohort {
+                    start: now + 100,
+                    end: now + 200,
+                    max_contributors: None,
+                    label: Some("later".to_string()),
+                },
+            ],
+        });
+        assert_eq!(1, state.get_current_cohort_index());
+        assert!(!state.is_current_cohort_in_progress());
+
+        // The cohort in progress is full once its maximum number of contributors joined
+        state.cohort_schedule = Some(CohortSchedule {
+            cohorts: vec![crate::schedule::Cohort {
+                start: now - 100,
+                end: now + 100,
+                max_contributors: Some(1),
+                label: None,
+            }],
+        });
+        assert_eq!(0, state.get_current_cohort_index());
+        assert!(state.is_current_cohort_in_progress());
+        assert!(!state.is_cohort_full(0));
+
+        state
+            .add_to_queue(contributor, None, String::from("test_token"), 10, &time)
+            .unwrap();
+        assert!(state.is_cohort_full(0));
+        assert!(!state.is_cohort_full(1));
+    }
+
     #[test]
     fn test_add_duplicate_ip_to_queue_contributor() {
         let time = SystemTimeSource::new();
@@ -3958,6 +4269,113 @@ mod tests {
         assert_eq!(Some(current_round_height), state.current_round_height);
     }
 
+    #[test]
+    fn test_move_in_queue() {
+        let time = MockTimeSource::new(OffsetDateTime::now_utc());
+        let environment: Environment = Testing::from(Parameters::Test8Chunks)
+            .maximum_contributors_per_round(1)
+            .queue_policy(crate::queue::QueuePolicy::ReliabilityWeighted)
+            .into();
+
+        // Initialize a new coordinator state.
+        let mut state = CoordinatorState::new(environment);
+        state.initialize(5);
+
+        // Add three contributors, the last one being the most reliable.
+        let contributors: Vec<_> = (1..=3).map(|id| Participant::Contributor(id.to_string())).collect();
+        for (contributor, reliability) in contributors.iter().zip([5, 5, 9]) {
+            let token = format!("test_token_{}", contributor);
+            state
+                .add_to_queue(contributor.clone(), None, token, reliability, &time)
+                .unwrap();
+            time.update(|now| now + Duration::seconds(1));
+        }
+        let rounds = |state: &CoordinatorState| -> Vec<Option<u64>> {
+            contributors
+                .iter()
+                .map(|contributor| state.queue[contributor].1)
+                .collect()
+        };
+
+        // The policy orders the contributors.
+        state.update_queue().unwrap();
+        assert_eq!(rounds(&state), vec![Some(7), Some(8), Some(6)]);
+
+        // The moved contributor goes ahead of the policy.
+        state.move_in_queue(&contributors[1], 0).unwrap();
+        state.update_queue().unwrap();
+        assert_eq!(rounds(&state), vec![Some(8), Some(6), Some(7)]);
+        assert!(state
+            .move_in_queue(&Participant::Contributor("4".to_string()), 0)
+            .is_err());
+
+        // The position is forgotten once the contributor leaves the queue.
+        state.remove_from_queue(&contributors[1]).unwrap();
+        state.update_queue().unwrap();
+        assert!(state.queue_positions.is_empty());
+        assert_eq!(state.queue[&contributors[0]].1, Some(7));
+        assert_eq!(state.queue[&contributors[2]].1, Some(6));
+    }
+
+    #[test]
+    fn test_reliability_score() {
+        let time = MockTimeSource::new(OffsetDateTime::now_utc());
+        let mut state = CoordinatorState::new(TEST_ENVIRONMENT.clone());
+        let contributor = Participant::Contributor("1".to_string());
+
+        // A new contributor starts from the initial score.
+        assert_eq!(state.reliability_score(&contributor), INITIAL_RELIABILITY);
+
+        // Each finished round raises it.
+        for round_height in 1..=3 {
+            let info = ParticipantInfo::new(contributor.clone(), round_height, 0, 0, &time);
+            state
+                .finished_contributors
+                .entry(round_height)
+                .or_default()
+                .insert(contributor.clone(), info);
+        }
+        assert_eq!(state.reliability_score(&contributor), INITIAL_RELIABILITY + 3);
+
+        // Each drop and failed verification lowers it.
+        state
+            .dropped
+            .push(ParticipantInfo::new(contributor.clone(), 4, 0, 0, &time));
+        state.failed_verifications.push(FailedVerification {
+            round_height: 4,
+            task: Task::new(0, 1),
+            contributor: Some(contributor.clone()),
+            reason: VerificationError::InvalidDelta {
+                circuit: "masp-spend".to_string(),
+            },
+            failed_at: time.now_utc(),
+        });
+        assert_eq!(state.reliability_score(&contributor), INITIAL_RELIABILITY + 1);
+
+        // The score is bounded.
+        for round_height in 5..20 {
+            state
+                .dropped
+                .push(ParticipantInfo::new(contributor.clone(), round_height, 0, 0, &time));
+        }
+        assert_eq!(state.reliability_score(&contributor), 0);
+        for round_height in 20..40 {
+            let info = ParticipantInfo::new(contributor.clone(), round_height, 0, 0, &time);
+            state
+                .finished_contributors
+                .entry(round_height)
+                .or_default()
+                .insert(contributor.clone(), info);
+        }
+        assert_eq!(state.reliability_score(&contributor), MAX_RELIABILITY);
+
+        // The record of other contributors is not taken into account.
+        assert_eq!(
+            state.reliability_score(&Participant::Contributor("2".to_string())),
+            INITIAL_RELIABILITY
+        );
+    }
+
     #[test]
     fn test_remove_from_queue_contributor() {
         let time = SystemTimeSource::new();
@@ -1,15 +1,119 @@
-use crate::{authentication::KeyPair, objects::Participant, storage::Disk};
+use crate::{
+    authentication::KeyPair,
+    circuits::{self, CeremonyCircuit},
+    objects::Participant,
+    queue::QueuePolicy,
+    storage::DefaultStorage,
+    tokens::TokenIssuer,
+    CoordinatorError,
+};
 pub use phase2::{helpers::CurveKind, ContributionMode, ProvingSystem};
 use setup_utils::{CheckForCorrectness, UseCompression};
 
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 type BatchSize = usize;
 type ChunkSize = usize;
 type NumberOfChunks = usize;
 type Power = usize;
 
+/// The ordered list of circuits the ceremony is run on. The circuits must be registered in the
+/// [circuit registry](crate::circuits).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitSet {
+    /// The MASP Spend, Output and Convert circuits.
+    MaspFull,
+    /// A single toy circuit, for testing purposes only.
+    MaspTest,
+    /// Any list of registered circuits, by name.
+    Custom(Vec<String>),
+}
+
+impl CircuitSet {
+    /// Returns the names of the circuits, in the order their parameters appear in the transcript.
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            CircuitSet::MaspFull => vec![circuits::MASP_SPEND, circuits::MASP_OUTPUT, circuits::MASP_CONVERT],
+            CircuitSet::MaspTest => vec![circuits::MASP_TEST],
+            CircuitSet::Custom(names) => names.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// Returns the circuits from the registry. Fails if a circuit is not registered.
+    pub fn circuits(&self) -> Result<Vec<Arc<dyn CeremonyCircuit>>, CoordinatorError> {
+        let names = self.names();
+        if names.is_empty() {
+            return Err(CoordinatorError::CircuitSetEmpty);
+        }
+
+        names.into_iter().map(circuits::get).collect()
+    }
+
+    /// Returns the size of the initial contribution file. The parameters of the circuits are
+    /// prefixed by the 64 bytes hash of the previous contribution.
+    pub fn base_file_size(&self) -> Result<u64, CoordinatorError> {
+        let names = self.names();
+        if names.is_empty() {
+            return Err(CoordinatorError::CircuitSetEmpty);
+        }
+
+        // Only the total size of the MASP circuits is known, measuring them generates their parameters.
+        if let CircuitSet::MaspFull = self {
+            return Ok(64 + circuits::MASP_FULL_PARAMS_SIZE);
+        }
+
+        let mut size = 64;
+        for name in names {
+            size += circuits::params_size(name)?;
+        }
+
+        Ok(size)
+    }
+
+    /// Returns the number of bytes the contribution file grows by on each new contribution.
+    pub fn per_round_file_size_increase(&self) -> u64 {
+        self.names().len() as u64 * circuits::CONTRIBUTION_PUBLIC_KEY_SIZE
+    }
+}
+
+impl Default for CircuitSet {
+    fn default() -> Self {
+        CircuitSet::MaspFull
+    }
+}
+
+impl std::fmt::Display for CircuitSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitSet::MaspFull => write!(f, "masp-full"),
+            CircuitSet::MaspTest => write!(f, "masp-test"),
+            CircuitSet::Custom(names) => write!(f, "{}", names.join(",")),
+        }
+    }
+}
+
+impl std::str::FromStr for CircuitSet {
+    type Err = String;
+
+    /// Parses either a preset (`masp-full` or `masp-test`) or a comma separated list of circuit names.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "masp-full" => Ok(CircuitSet::MaspFull),
+            "masp-test" => Ok(CircuitSet::MaspTest),
+            _ => {
+                let names: Vec<String> = s.split(',').map(|name| name.trim().to_string()).collect();
+                if names.iter().any(String::is_empty) {
+                    return Err(format!("Invalid circuit set: {}", s));
+                }
+
+                Ok(CircuitSet::Custom(names))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub contribution_mode: ContributionMode,
@@ -18,6 +122,8 @@ pub struct Settings {
     pub power: Power,
     pub batch_size: BatchSize,
     pub chunk_size: ChunkSize,
+    #[serde(default)]
+    pub circuits: CircuitSet,
 }
 
 impl Settings {
@@ -43,9 +149,16 @@ impl Settings {
             power,
             batch_size,
             chunk_size,
+            circuits: CircuitSet::default(),
         }
     }
 
+    /// Sets the circuit set of these settings.
+    pub fn with_circuits(mut self, circuits: CircuitSet) -> Self {
+        self.circuits = circuits;
+        self
+    }
+
     pub fn contribution_mode(&self) -> ContributionMode {
         self.contribution_mode
     }
@@ -69,6 +182,10 @@ impl Settings {
     pub fn chunk_size(&self) -> ChunkSize {
         self.chunk_size
     }
+
+    pub fn circuits(&self) -> &CircuitSet {
+        &self.circuits
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -98,6 +215,7 @@ pub enum Parameters {
         number_of_chunks: usize,
         power: usize,
         batch_size: usize,
+        circuits: CircuitSet,
     },
 }
 
@@ -121,7 +239,8 @@ impl Parameters {
                 number_of_chunks,
                 power,
                 batch_size,
-            } => Self::namada(number_of_chunks, power, batch_size),
+                circuits,
+            } => Self::namada(number_of_chunks, power, batch_size, circuits),
         }
     }
 
@@ -167,6 +286,7 @@ impl Parameters {
             BatchSize::from(64_usize),
             ChunkSize::from(172_usize),
         )
+        .with_circuits(CircuitSet::MaspTest)
     }
 
     fn test_8_chunks() -> Settings {
@@ -178,6 +298,7 @@ impl Parameters {
             BatchSize::from(64_usize),
             ChunkSize::from(4096_usize),
         )
+        .with_circuits(CircuitSet::MaspTest)
     }
 
     fn test_chunks(number_of_chunks: &NumberOfChunks) -> Settings {
@@ -192,6 +313,7 @@ impl Parameters {
             BatchSize::from(batch_size),
             chunk_size!(number_of_chunks, proving_system, power),
         )
+        .with_circuits(CircuitSet::MaspTest)
     }
 
     fn test_custom(number_of_chunks: &NumberOfChunks, power: &Power, batch_size: &BatchSize) -> Settings {
@@ -204,9 +326,15 @@ impl Parameters {
             *batch_size,
             chunk_size!(number_of_chunks, proving_system, power),
         )
+        .with_circuits(CircuitSet::MaspTest)
     }
 
-    fn namada(number_of_chunks: &NumberOfChunks, power: &Power, batch_size: &BatchSize) -> Settings {
+    fn namada(
+        number_of_chunks: &NumberOfChunks,
+        power: &Power,
+        batch_size: &BatchSize,
+        circuits: &CircuitSet,
+    ) -> Settings {
         let proving_system = ProvingSystem::Groth16;
         Settings::new(
             ContributionMode::Full,
@@ -216,6 +344,7 @@ impl Parameters {
             *batch_size,
             chunk_size!(number_of_chunks, proving_system, power),
         )
+        .with_circuits(circuits.clone())
     }
 }
 
@@ -279,6 +408,16 @@ pub struct Environment {
     deployment: Deployment,
     /// The base directory for disk storage of this coordinator.
     local_base_directory: String,
+    /// The number of latest rounds kept in their round directory, the older ones are archived.
+    /// Every round is kept if unset.
+    #[serde(default)]
+    retention_rounds: Option<u64>,
+    /// The issuer of the signed contribution tokens. Only the tokens of the cohort files are accepted if unset.
+    #[serde(default)]
+    token_issuer: Option<TokenIssuer>,
+    /// The policy ordering the queued contributors in the next rounds.
+    #[serde(default)]
+    queue_policy: QueuePolicy,
 
     disable_reliability_zeroing: bool,
 }
@@ -464,6 +603,27 @@ impl Environment {
         &self.local_base_directory
     }
 
+    ///
+    /// Returns the number of latest rounds kept in their round directory, if the older ones are archived.
+    ///
+    pub const fn retention_rounds(&self) -> Option<u64> {
+        self.retention_rounds
+    }
+
+    ///
+    /// Returns the issuer of the signed contribution tokens, if any.
+    ///
+    pub const fn token_issuer(&self) -> Option<&TokenIssuer> {
+        self.token_issuer.as_ref()
+    }
+
+    ///
+    /// Returns the policy ordering the queued contributors in the next rounds.
+    ///
+    pub const fn queue_policy(&self) -> &QueuePolicy {
+        &self.queue_policy
+    }
+
     ///
     /// Returns the appropriate number of chunks for the coordinator
     /// to run given a proof system, power and chunk size.
@@ -477,8 +637,8 @@ impl Environment {
     }
 
     /// Returns the storage system of the coordinator.
-    pub(crate) fn storage(&self) -> anyhow::Result<Disk> {
-        Ok(Disk::load(self)?)
+    pub(crate) fn storage(&self) -> anyhow::Result<DefaultStorage> {
+        Ok(DefaultStorage::load(self)?)
     }
 }
 
@@ -522,6 +682,41 @@ impl Testing {
         self
     }
 
+    pub fn circuits(mut self, circuits: CircuitSet) -> Self {
+        self.environment.parameters.circuits = circuits;
+        self
+    }
+
+    pub fn retention_rounds(mut self, rounds: Option<u64>) -> Self {
+        self.environment.retention_rounds = rounds;
+        self
+    }
+
+    pub fn token_issuer(mut self, issuer: Option<TokenIssuer>) -> Self {
+        self.environment.token_issuer = issuer;
+        self
+    }
+
+    pub fn queue_policy(mut self, policy: QueuePolicy) -> Self {
+        self.environment.queue_policy = policy;
+        self
+    }
+
+    pub fn verifier_seen_timeout(mut self, timeout: time::Duration) -> Self {
+        self.environment.verifier_seen_timeout = timeout;
+        self
+    }
+
+    pub fn queue_wait_time(mut self, seconds: u64) -> Self {
+        self.environment.queue_wait_time = seconds;
+        self
+    }
+
+    pub fn local_base_directory(mut self, directory: &str) -> Self {
+        self.environment.local_base_directory = directory.to_string();
+        self
+    }
+
     #[inline]
     pub fn coordinator_contributors(&self, contributors: &[Participant]) -> Self {
         // Check that all participants are contributors.
@@ -571,6 +766,7 @@ impl Testing {
                     number_of_chunks: 1,
                     power: 6,
                     batch_size: 16,
+                    circuits: CircuitSet::MaspTest,
                 }
                 .to_settings(),
                 compressed_inputs: UseCompression::No,
@@ -599,6 +795,9 @@ impl Testing {
                 software_version: 1,
                 deployment: Deployment::Testing,
                 local_base_directory: "./transcript/testing".to_string(),
+                retention_rounds: None,
+                token_issuer: None,
+                queue_policy: QueuePolicy::Fifo,
 
                 disable_reliability_zeroing: false,
             },
@@ -667,6 +866,46 @@ impl Development {
         self
     }
 
+    pub fn circuits(mut self, circuits: CircuitSet) -> Self {
+        self.environment.parameters.circuits = circuits;
+        self
+    }
+
+    pub fn retention_rounds(mut self, rounds: Option<u64>) -> Self {
+        self.environment.retention_rounds = rounds;
+        self
+    }
+
+    pub fn token_issuer(mut self, issuer: Option<TokenIssuer>) -> Self {
+        self.environment.token_issuer = issuer;
+        self
+    }
+
+    pub fn queue_policy(mut self, policy: QueuePolicy) -> Self {
+        self.environment.queue_policy = policy;
+        self
+    }
+
+    pub fn verifier_seen_timeout(mut self, timeout: time::Duration) -> Self {
+        self.environment.verifier_seen_timeout = timeout;
+        self
+    }
+
+    pub fn queue_seen_timeout(mut self, timeout: time::Duration) -> Self {
+        self.environment.queue_seen_timeout = timeout;
+        self
+    }
+
+    pub fn queue_wait_time(mut self, seconds: u64) -> Self {
+        self.environment.queue_wait_time = seconds;
+        self
+    }
+
+    pub fn local_base_directory(mut self, directory: &str) -> Self {
+        self.environment.local_base_directory = directory.to_string();
+        self
+    }
+
     #[inline]
     pub fn coordinator_contributors(&self, contributors: &[Participant]) -> Self {
         // Check that all participants are contributors.
@@ -698,6 +937,7 @@ impl Development {
                     number_of_chunks: 1,
                     power: 6,
                     batch_size: 16,
+                    circuits: CircuitSet::MaspFull,
                 }
                 .to_settings(),
                 compressed_inputs: UseCompression::No,
@@ -726,6 +966,9 @@ impl Development {
                 software_version: 1,
                 deployment: Deployment::Development,
                 local_base_directory: "./transcript/development".to_string(),
+                retention_rounds: None,
+                token_issuer: None,
+                queue_policy: QueuePolicy::Fifo,
 
                 disable_reliability_zeroing: false,
             },
@@ -805,6 +1048,41 @@ impl Production {
         self
     }
 
+    pub fn circuits(mut self, circuits: CircuitSet) -> Self {
+        self.environment.parameters.circuits = circuits;
+        self
+    }
+
+    pub fn retention_rounds(mut self, rounds: Option<u64>) -> Self {
+        self.environment.retention_rounds = rounds;
+        self
+    }
+
+    pub fn token_issuer(mut self, issuer: Option<TokenIssuer>) -> Self {
+        self.environment.token_issuer = issuer;
+        self
+    }
+
+    pub fn queue_policy(mut self, policy: QueuePolicy) -> Self {
+        self.environment.queue_policy = policy;
+        self
+    }
+
+    pub fn verifier_seen_timeout(mut self, timeout: time::Duration) -> Self {
+        self.environment.verifier_seen_timeout = timeout;
+        self
+    }
+
+    pub fn queue_wait_time(mut self, seconds: u64) -> Self {
+        self.environment.queue_wait_time = seconds;
+        self
+    }
+
+    pub fn local_base_directory(mut self, directory: &str) -> Self {
+        self.environment.local_base_directory = directory.to_string();
+        self
+    }
+
     #[inline]
     pub fn coordinator_contributors(&self, contributors: &[Participant]) -> Self {
         // Check that all participants are contributors.
@@ -830,17 +1108,13 @@ impl Production {
     }
 
     fn generate_namada_env(keypair: &KeyPair) -> Self {
-        let test_timeout = match std::env::var("NAMADA_MPC_TIMEOUT_SECONDS") {
-            Ok(t) => Some(time::Duration::seconds(t.parse::<i64>().unwrap())),
-            Err(_) => None,
-        };
-
         Self {
             environment: Environment {
                 parameters: Parameters::Namada {
                     number_of_chunks: 1,
                     power: 6,
                     batch_size: 16,
+                    circuits: CircuitSet::MaspFull,
                 }
                 .to_settings(),
                 compressed_inputs: UseCompression::No,
@@ -853,10 +1127,10 @@ impl Production {
                 maximum_verifiers_per_round: 5,
                 contributor_lock_chunk_limit: 1,
                 verifier_lock_chunk_limit: 5,
-                contributor_seen_timeout: test_timeout.unwrap_or_else(|| time::Duration::seconds(150)),
-                verifier_seen_timeout: test_timeout.unwrap_or_else(|| time::Duration::days(7)),
-                participant_lock_timeout: test_timeout.unwrap_or_else(|| time::Duration::minutes(20)),
-                queue_seen_timeout: test_timeout.unwrap_or_else(|| time::Duration::seconds(150)),
+                contributor_seen_timeout: time::Duration::seconds(150),
+                verifier_seen_timeout: time::Duration::days(7),
+                participant_lock_timeout: time::Duration::minutes(20),
+                queue_seen_timeout: time::Duration::seconds(150),
                 participant_ban_threshold: 5,
                 allow_current_contributors_in_queue: false,
                 allow_current_verifiers_in_queue: true,
@@ -869,6 +1143,9 @@ impl Production {
                 software_version: 1,
                 deployment: Deployment::Production,
                 local_base_directory: "./transcript".to_string(),
+                retention_rounds: None,
+                token_issuer: None,
+                queue_policy: QueuePolicy::Fifo,
 
                 disable_reliability_zeroing: false,
             },
@@ -966,4 +1243,33 @@ mod tests {
         assert_eq!(ChunkSize::from(1639_usize), chunk_size);
         assert_eq!(number_of_chunks as u64, Testing::from(parameters).number_of_chunks());
     }
+
+    #[test]
+    fn test_circuits() {
+        assert_eq!(&CircuitSet::MaspTest, Parameters::Test3Chunks.to_settings().circuits());
+        assert_eq!(&CircuitSet::MaspTest, Testing::default().parameters().circuits());
+        assert_eq!(&CircuitSet::MaspFull, Production::default().parameters().circuits());
+        assert_eq!(
+            &CircuitSet::MaspTest,
+            Production::default()
+                .circuits(CircuitSet::MaspTest)
+                .parameters()
+                .circuits()
+        );
+
+        let custom = CircuitSet::Custom(vec!["masp-output".to_string(), "masp-test".to_string()]);
+        for circuits in [CircuitSet::MaspFull, CircuitSet::MaspTest, custom] {
+            assert_eq!(circuits, circuits.to_string().parse().unwrap());
+        }
+        assert!("masp-test,".parse::<CircuitSet>().is_err());
+
+        assert_eq!(CircuitSet::MaspFull.names().len(), 3);
+        assert_eq!(CircuitSet::MaspFull.per_round_file_size_increase(), 1_632);
+        assert_eq!(CircuitSet::MaspFull.base_file_size().unwrap(), 84_720_244);
+        assert_eq!(CircuitSet::MaspTest.base_file_size().unwrap(), 2_332);
+        assert!(CircuitSet::Custom(vec![]).circuits().is_err());
+        assert!(CircuitSet::Custom(vec!["unknown".to_string()])
+            .base_file_size()
+            .is_err());
+    }
 }
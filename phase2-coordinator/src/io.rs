@@ -1,4 +1,4 @@
-use std::{fmt::Display, io::Write, ops::Deref};
+use std::{fmt::Display, io::Write, ops::Deref, path::Path};
 
 #[cfg(not(debug_assertions))]
 use std::process;
@@ -150,9 +150,10 @@ pub fn seed_from_string(input: &str) -> Result<[u8; 64]> {
     Ok(mnemonic.to_seed_normalized(""))
 }
 
-/// Generates a new [`KeyPair`] from a mnemonic retrieved from the coordinator.mnemonic file in the current working directory.
-pub fn keypair_from_mnemonic() -> Result<KeyPair> {
-    let mnemonic_str = std::fs::read_to_string(COORDINATOR_MNEMONIC_FILE)?;
+/// Generates a new [`KeyPair`] from a mnemonic retrieved from the given file, the coordinator.mnemonic file of the
+/// coordinator or the one of an operator.
+pub fn keypair_from_mnemonic<P: AsRef<Path>>(path: P) -> Result<KeyPair> {
+    let mnemonic_str = std::fs::read_to_string(path)?;
     let seed = seed_from_string(&mnemonic_str)?;
 
     Ok(KeyPair::try_from_seed(&seed)?)
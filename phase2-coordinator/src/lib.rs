@@ -28,10 +28,16 @@
 #[macro_use]
 pub mod macros;
 
+pub mod audit_log;
+
 pub mod authentication;
 
+pub mod circuits;
+
 pub mod commands;
 
+pub mod config;
+
 pub mod coordinator;
 pub use coordinator::*;
 
@@ -48,9 +54,15 @@ pub use coordinator_state::CoordinatorState;
 pub mod environment;
 pub mod io;
 
+pub mod object_store;
+
 pub mod objects;
 pub use objects::{ContributionFileSignature, ContributionState, Participant, Round};
 
+pub mod operators;
+
+pub mod queue;
+
 pub mod storage;
 
 pub mod rest;
@@ -58,6 +70,12 @@ pub mod rest_utils;
 
 pub mod s3;
 
+pub mod schedule;
+
+pub mod secret;
+
+pub mod tokens;
+
 #[cfg(any(test, feature = "testing"))]
 pub mod testing;
 
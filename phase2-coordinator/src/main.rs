@@ -1,18 +1,16 @@
 use phase2_coordinator::{
-    authentication::Production as ProductionSig,
+    audit_log::{AuditLog, SharedAuditLog},
+    authentication::{KeyPair, Production as ProductionSig},
+    commands,
+    config::{self, Config},
     io::{self, KeyPairUser},
+    object_store::{self, ObjectStore, SharedObjectStore},
+    operators::{self, Operators, SharedOperators},
     rest,
     rest_utils::{self, ResponseError, TOKENS_PATH, TOKENS_ZIP_FILE, UPDATE_TIME},
-    s3::{S3Ctx, REGION},
-    Coordinator,
+    secret, Coordinator,
 };
 
-#[cfg(debug_assertions)]
-use phase2_coordinator::environment::Testing;
-
-#[cfg(not(debug_assertions))]
-use phase2_coordinator::environment::Production;
-
 use rocket::{
     self, catchers, routes,
     tokio::{
@@ -25,9 +23,13 @@ use rocket::{
 };
 
 use anyhow::Result;
-use rand::Rng;
-use rusoto_ssm::{Ssm, SsmClient};
-use std::{convert::TryInto, io::Write, sync::Arc};
+use std::{
+    convert::TryInto,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use tracing::{error, info, warn};
 
@@ -62,18 +64,19 @@ async fn update_coordinator(coordinator: Arc<RwLock<Coordinator>>, recv: Receive
 
 /// Periodically verifies the pending contributions. Pending contributions are added to the queue by the try_contribute function,
 /// no need to call an update on the coordinator.
-/// NOTE: a possible improvement could be to perform the verification when the try_contribute function gets called, allowing us to remove this task and
-/// speed up the verification process. This would also allow us to immediately provide to a client the state of validity of its contribution. This improvement could
-/// be possible because we only have one contribution per round and one verifier (the coordinator's one). To implement this logic though, it would require a major rework of the phase2_coordinator logic.
-async fn verify_contributions(coordinator: Arc<RwLock<Coordinator>>, recv: Receiver<bool>) -> Result<()> {
-    let s3_ctx = S3Ctx::new().await?;
-
+/// NOTE: contributions are verified as soon as they are submitted to the contribute_chunk endpoint, this task only picks up the ones left
+/// pending, e.g. because of a restart of the coordinator during the verification.
+async fn verify_contributions(
+    coordinator: Arc<RwLock<Coordinator>>,
+    object_store: SharedObjectStore,
+    recv: Receiver<bool>,
+) -> Result<()> {
     loop {
         tokio::time::sleep(UPDATE_TIME).await;
 
         info!("Verifying contributions...");
         let start = std::time::Instant::now();
-        rest_utils::perform_verify_chunks(coordinator.clone(), &s3_ctx).await?;
+        rest_utils::perform_verify_chunks(coordinator.clone(), object_store.as_ref()).await?;
         info!(
             "Verification of contributions completed in {:#?}. {:#?} to the next verification round...",
             start.elapsed(),
@@ -88,27 +91,64 @@ async fn verify_contributions(coordinator: Arc<RwLock<Coordinator>>, recv: Recei
     }
 }
 
-/// Checks and prints the env variables of interest for the ceremony
-macro_rules! print_env {
-    ($($env:expr),*) => {
-        info!("ENV VARIABLES STATE:");
-        $(info!(
-            "{}: {}",
-            $env,
-            std::env::var($env).unwrap_or("MISSING".to_string())
-        );)*
-    };
+/// Periodically audits the contribution files in storage and logs the inconsistencies found
+async fn audit_storage(coordinator: Arc<RwLock<Coordinator>>, interval: Duration, recv: Receiver<bool>) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        // Return if shutdown signal has been received on the channel
+        if *recv.borrow() {
+            info!("Received shutdown signal, exiting audit task");
+            return;
+        }
+
+        info!("Auditing storage...");
+        let start = std::time::Instant::now();
+        match rest_utils::perform_storage_audit(coordinator.clone()).await {
+            Ok(issues) => {
+                for issue in &issues {
+                    warn!("Storage audit: {}", issue);
+                }
+                info!(
+                    "Audit of storage completed in {:#?} with {} issues. {:#?} to the next audit...",
+                    start.elapsed(),
+                    issues.len(),
+                    interval
+                );
+            }
+            Err(e) => error!("Audit of storage failed: {}", e),
+        }
+    }
 }
 
-/// Download tokens from S3, decompress and store them locally.
-async fn download_tokens() -> Result<()> {
-    let s3_ctx = S3Ctx::new().await?;
+/// Periodically reloads the operator keys if their file has changed
+async fn refresh_operators(operators: SharedOperators, recv: Receiver<bool>) {
+    loop {
+        tokio::time::sleep(operators::REFRESH_INTERVAL).await;
+
+        // Return if shutdown signal has been received on the channel
+        if *recv.borrow() {
+            info!("Received shutdown signal, exiting operators task");
+            return;
+        }
+
+        let shared_operators = operators.clone();
+        match tokio::task::spawn_blocking(move || shared_operators.refresh()).await {
+            Ok(Ok(_)) => (),
+            Ok(Err(e)) => error!("No operator key accepted until their file is fixed: {}", e),
+            Err(e) => error!("Refresh of the operator keys panicked: {}", e),
+        }
+    }
+}
+
+/// Download tokens from the object store, decompress and store them locally.
+async fn download_tokens(object_store: &dyn ObjectStore) -> Result<()> {
     let mut zip_file = std::fs::File::options()
         .read(true)
         .write(true)
         .create(true)
         .open(TOKENS_ZIP_FILE)?;
-    zip_file.write_all(&s3_ctx.get_tokens().await?)?;
+    zip_file.write_all(&object_store.get_tokens().await?)?;
 
     let mut zip = zip::ZipArchive::new(zip_file)?;
     zip.extract(TOKENS_PATH.as_str())?;
@@ -116,40 +156,10 @@ async fn download_tokens() -> Result<()> {
     Ok(())
 }
 
-/// Generate the random secret to access reserved endpoints and exports it as env. Publish this secret to Amazon Parameter Store.
-async fn generate_secret() -> Result<()> {
-    let mut secret_bytes = [0u8; 32];
-    rand::thread_rng().fill(&mut secret_bytes[..]);
-    let secret = hex::encode(secret_bytes);
-    std::env::set_var("ACCESS_SECRET", &secret);
-    let env = match std::env::var("AWS_S3_PROD") {
-        Ok(val) if val == "true" => "production",
-        _ => "master",
-    };
-
-    let aws_client = SsmClient::new(REGION.clone());
-    let put_request = rusoto_ssm::PutParameterRequest {
-        description: Some("Trusted setup endpoints secret".to_string()),
-        key_id: None,
-        name: format!("/namada/trusted-setup/{}/secret", env),
-        overwrite: Some(true),
-        policies: None,
-        tags: None,
-        tier: None,
-        type_: Some("SecureString".to_string()),
-        value: secret.clone(),
-        allowed_pattern: None,
-        data_type: None,
-    };
-    aws_client.put_parameter(put_request).await?;
-
-    Ok(())
-}
-
 /// Perform the steps to finalize the ceremony state before shut down
-async fn finalize_ceremony(coordinator: Arc<RwLock<Coordinator>>) -> Result<()> {
+async fn finalize_ceremony(coordinator: Arc<RwLock<Coordinator>>, object_store: &dyn ObjectStore) -> Result<()> {
     info!("Performing last contribution verification (if any)...");
-    if let Err(e) = rest_utils::perform_verify_chunks(coordinator.clone(), &S3Ctx::new().await?).await {
+    if let Err(e) = rest_utils::perform_verify_chunks(coordinator.clone(), object_store).await {
         // Log any error without interrupting the shutdown procedure
         warn!("Ignoring error while performing last verification: {}", e);
     }
@@ -166,27 +176,118 @@ async fn finalize_ceremony(coordinator: Arc<RwLock<Coordinator>>) -> Result<()>
     Ok(())
 }
 
+/// Runs the `backup <archive>` or `restore <archive>` command on the transcript and the tokens of the coordinator,
+/// which must not be running.
+fn run_command(config: &Config, args: &[String]) -> Result<()> {
+    let environment = config.environment(&KeyPair::new())?;
+    let tokens = Path::new(&config.tokens.path);
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["backup", archive] => {
+            commands::create_backup(&environment, tokens, Path::new(archive))?;
+        }
+        ["restore", archive] => {
+            commands::restore_backup(&environment, tokens, Path::new(archive))?;
+        }
+        ["audit"] => {
+            let coordinator = Coordinator::new(environment, Arc::new(ProductionSig))?;
+            let issues = coordinator.audit_storage()?;
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            if !issues.is_empty() {
+                anyhow::bail!("Found {} inconsistencies in storage", issues.len());
+            }
+        }
+        _ => anyhow::bail!(
+            "Usage: phase2-coordinator [--config <file>] [--print-config | backup <archive> | restore <archive> | audit]"
+        ),
+    }
+
+    Ok(())
+}
+
+///
+/// Loads the configuration from the file given with `--config`, or in the `NAMADA_CONFIG` env variable, and
+/// removes the options of the configuration from the given arguments. Returns the configuration and whether it
+/// should be printed instead of running the coordinator.
+///
+fn load_config(args: &mut Vec<String>) -> Result<(Config, bool)> {
+    let path = match args.iter().position(|arg| arg == "--config") {
+        Some(index) if index + 1 < args.len() => {
+            let path = args.remove(index + 1);
+            args.remove(index);
+            Some(PathBuf::from(path))
+        }
+        Some(_) => anyhow::bail!("Missing path of the configuration file after --config"),
+        None => config::path_from_env(),
+    };
+    let print_config = match args.iter().position(|arg| arg == "--print-config") {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    };
+
+    Ok((Config::load(path.as_deref())?, print_config))
+}
+
 /// Rocket main function using the [`tokio`] runtime
 #[rocket::main]
 pub async fn main() {
     let tracing_enable_color = std::env::var("RUST_LOG_COLOR").is_ok();
     tracing_subscriber::fmt().with_ansi(tracing_enable_color).init();
-    print_env!(
-        "AWS_S3_PROD",
-        "AWS_S3_BUCKET",
-        "AWS_REGION",
-        "NAMADA_MPC_IP_BAN",
-        "NAMADA_MPC_TIMEOUT_SECONDS",
-        "HEALTH_PATH",
-        "NAMADA_TOKENS_PATH",
-        "CEREMONY_START_TIMESTAMP",
-        "TOKENS_FILE_PREFIX",
-        "NAMADA_COHORT_TIME",
-        "TOKEN_BLACKLIST"
+
+    // Load and validate the configuration before anything else reads it
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let config = match load_config(&mut args) {
+        Ok((config, false)) => config,
+        Ok((config, true)) => {
+            print!(
+                "{}",
+                config.to_toml().expect("Error while serializing the configuration")
+            );
+            return;
+        }
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    config::install(config.clone()).expect("Error while installing the configuration");
+
+    // Run the maintenance commands instead of the ceremony, if any
+    if !args.is_empty() {
+        if let Err(e) = run_command(&config, &args) {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    info!(
+        "CONFIGURATION:\n{}",
+        config.to_toml().expect("Error while serializing the configuration")
     );
+    let overrides: Vec<&str> = config::ENV_VARIABLES
+        .iter()
+        .copied()
+        .filter(|name| std::env::var_os(name).is_some())
+        .collect();
+    info!("Overridden by env variables: {:?}", overrides);
+
+    // Use the configured access secret of the reserved endpoints, or generate and publish one
+    let access_secret = secret::from_config(&config)
+        .await
+        .expect("Error while generating secret token");
+
+    // Load the keys of the operators, which are reloaded when their file changes
+    let operators: SharedOperators =
+        Arc::new(Operators::load(&config.auth.operators_path).expect("Error while loading the operator keys"));
 
-    // Generate, publish and export the secret token
-    generate_secret().await.expect("Error while generating secret token");
+    // Record the admin actions
+    let audit_log: SharedAuditLog = Arc::new(AuditLog::new(&config.auth.audit_log_path));
 
     // Set the environment
     let keypair = tokio::task::spawn_blocking(|| io::generate_keypair(KeyPairUser::Coordinator))
@@ -194,21 +295,30 @@ pub async fn main() {
         .unwrap()
         .expect("Error while generating the keypair");
 
+    let environment = config
+        .environment(&keypair)
+        .expect("Error while building the environment");
+
     #[cfg(debug_assertions)]
-    let environment: Testing = {
-        phase2_coordinator::testing::clear_test_storage(&Testing::default().into());
-        Testing::new(&keypair)
-    };
+    {
+        if *environment.deployment() == phase2_coordinator::environment::Deployment::Testing {
+            phase2_coordinator::testing::clear_test_storage(&environment);
+        }
+    }
 
-    #[cfg(not(debug_assertions))]
-    let environment: Production = { Production::new(&keypair) };
+    // Select the object store used to exchange files with the contributors
+    let (object_store, local_store) = object_store::from_env()
+        .await
+        .expect("Error while initializing the object store");
 
-    // Always download token files from S3 to check for updates
-    download_tokens().await.expect("Error while retrieving tokens");
+    // Always download token files from the object store to check for updates
+    download_tokens(object_store.as_ref())
+        .await
+        .expect("Error while retrieving tokens");
 
     // Initialize the coordinator
     let coordinator =
-        Coordinator::new(environment.into(), Arc::new(ProductionSig)).expect("Failed to instantiate coordinator");
+        Coordinator::new(environment, Arc::new(ProductionSig)).expect("Failed to instantiate coordinator");
     let coordinator: Arc<RwLock<Coordinator>> = Arc::new(RwLock::new(coordinator));
     let up_coordinator = coordinator.clone();
     let verify_coordinator = coordinator.clone();
@@ -227,16 +337,33 @@ pub async fn main() {
         rest::get_challenge_url,
         rest::get_contribution_url,
         rest::contribute_chunk,
+        rest::get_challenge,
+        rest::get_upload_offset,
+        rest::post_contribution_part,
+        rest::post_contribution_signature,
         rest::update_coordinator,
         rest::heartbeat,
         rest::stop_coordinator,
         rest::verify_chunks,
         rest::get_contributor_queue_status,
+        rest::get_contribution_status,
         rest::post_contribution_info,
         rest::get_contributions_info,
         rest::get_coordinator_state,
+        rest::rotate_secret,
         rest::get_healthcheck,
+        rest::get_circuits,
         rest::update_cohorts,
+        rest::move_in_queue,
+        rest::ban_participant,
+        rest::unban_participant,
+        rest::drop_participant,
+        rest::remove_from_queue,
+        rest::enable_manual_lock,
+        rest::disable_manual_lock,
+        rest::reset_round,
+        rest::get_audit_log,
+        rest::finalize_ceremony,
         rest::post_attestation
     ];
 
@@ -247,17 +374,50 @@ pub async fn main() {
         rest::get_challenge_url,
         rest::get_contribution_url,
         rest::contribute_chunk,
+        rest::get_challenge,
+        rest::get_upload_offset,
+        rest::post_contribution_part,
+        rest::post_contribution_signature,
         rest::heartbeat,
         rest::stop_coordinator,
         rest::get_contributor_queue_status,
+        rest::get_contribution_status,
         rest::post_contribution_info,
         rest::get_coordinator_state,
+        rest::rotate_secret,
         rest::get_healthcheck,
+        rest::get_circuits,
         rest::update_cohorts,
+        rest::move_in_queue,
+        rest::ban_participant,
+        rest::unban_participant,
+        rest::drop_participant,
+        rest::remove_from_queue,
+        rest::enable_manual_lock,
+        rest::disable_manual_lock,
+        rest::reset_round,
+        rest::get_audit_log,
+        rest::finalize_ceremony,
         rest::post_attestation
     ];
 
-    let build_rocket = rocket::build().mount("/", routes).manage(coordinator.clone()).register(
+    let build_rocket = rocket::build()
+        .mount("/", routes)
+        .manage(coordinator.clone())
+        .manage(object_store.clone())
+        .manage(access_secret)
+        .manage(operators.clone())
+        .manage(audit_log);
+
+    // Objects of the local store are served by the coordinator itself
+    let build_rocket = match local_store {
+        Some(local_store) => build_rocket
+            .mount("/", routes![rest::get_local_object, rest::put_local_object])
+            .manage(local_store),
+        None => build_rocket,
+    };
+
+    let build_rocket = build_rocket.register(
         "/",
         catchers![
             rest_utils::invalid_signature,
@@ -272,14 +432,7 @@ pub async fn main() {
     let ignite_rocket = build_rocket.ignite().await.expect("Coordinator server didn't ignite");
 
     // Sleep until ceremony start time has been reached
-    #[cfg(not(debug_assertions))]
-    {
-        let ceremony_start_time = {
-            let timestamp_env = std::env::var("CEREMONY_START_TIMESTAMP").unwrap();
-            let timestamp = timestamp_env.parse::<i64>().unwrap();
-            time::OffsetDateTime::from_unix_timestamp(timestamp).unwrap()
-        };
-
+    if let Some(ceremony_start_time) = config.start_time() {
         let now = time::OffsetDateTime::now_utc();
 
         if now < ceremony_start_time {
@@ -302,8 +455,17 @@ pub async fn main() {
     // Spawn task to update the coordinator periodically
     let mut update_handle = rocket::tokio::spawn(update_coordinator(up_coordinator, rx.clone()));
 
+    // Spawn task to audit the storage periodically, if enabled
+    if let Some(seconds) = config.ceremony.audit_interval_seconds {
+        let interval = Duration::from_secs(seconds);
+        rocket::tokio::spawn(audit_storage(coordinator.clone(), interval, rx.clone()));
+    }
+
+    // Spawn task to reload the operator keys when their file changes
+    rocket::tokio::spawn(refresh_operators(operators, rx.clone()));
+
     // Spawn task to verify the contributions periodically
-    let mut verify_handle = rocket::tokio::spawn(verify_contributions(verify_coordinator, rx));
+    let mut verify_handle = rocket::tokio::spawn(verify_contributions(verify_coordinator, object_store.clone(), rx));
 
     // Spawn Rocket server task
     let mut rocket_handle = rocket::tokio::spawn(ignite_rocket.launch());
@@ -338,7 +500,7 @@ pub async fn main() {
 
                     info!("Concurrent tasks terminated");
 
-                    finalize_ceremony(coordinator).await.expect("Failed ceremony state finalize");
+                    finalize_ceremony(coordinator, object_store.as_ref()).await.expect("Failed ceremony state finalize");
                 },
                 Err(e) => error!("Update of Coordinator failed: {}", e),
             }
@@ -374,7 +536,7 @@ pub async fn main() {
 
                     info!("Concurrent tasks terminated");
 
-                    finalize_ceremony(coordinator).await.expect("Failed ceremony state finalize");
+                    finalize_ceremony(coordinator, object_store.as_ref()).await.expect("Failed ceremony state finalize");
                 },
                 Err(e) => error!("Rocket failed: {}", e)
             }
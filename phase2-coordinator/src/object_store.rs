@@ -0,0 +1,322 @@
+//! Object stores used to exchange the ceremony files (challenges, contributions, tokens and the contributions
+//! summary) with the participants and the frontend.
+
+use crate::{
+    config::Config,
+    s3::{S3Ctx, S3Error},
+};
+use blake2::{Blake2b512, Digest};
+use lazy_static::lazy_static;
+use rand::{rngs::OsRng, RngCore};
+use rocket::{
+    data::{Data, ToByteUnit},
+    tokio::fs,
+};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use time::OffsetDateTime;
+
+pub const TOKENS_ZIP_FILE: &str = "tokens.zip";
+pub(crate) const CONTRIBUTIONS_INFO_FILE: &str = "contributors.json";
+/// Upper bound on the size of an object uploaded to the [`LocalStore`]
+const LOCAL_STORE_MAX_OBJECT_SIZE_GIB: u64 = 1;
+
+lazy_static! {
+    /// Validity of the urls returned by the object stores, 10 minutes by default
+    pub(crate) static ref PRESIGNED_URL_EXPIRY: Duration =
+        Duration::from_secs(Config::current().object_store.presigned_url_expiry_seconds);
+    pub static ref OBJECT_STORE: ObjectStoreKind = Config::current()
+        .object_store_kind()
+        .expect("The configuration has been validated");
+    static ref LOCAL_STORE_PATH: String = Config::current().object_store.local_path;
+    static ref LOCAL_STORE_URL: String = Config::current().object_store.local_url;
+}
+
+#[derive(Error, Debug)]
+pub enum ObjectStoreError {
+    #[error("Object {0} is present but empty")]
+    EmptyObject(String),
+    #[error("Error in IO: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Object {0} doesn't exist")]
+    MissingObject(String),
+    #[error("Object {0} exceeds the maximum allowed size")]
+    ObjectTooLarge(String),
+    #[error("Error with S3: {0}")]
+    S3Error(#[from] S3Error),
+    #[error("Unknown object store kind: {0}")]
+    UnknownKind(String),
+}
+
+type Result<T> = std::result::Result<T, ObjectStoreError>;
+
+/// The backends available to store the ceremony objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectStoreKind {
+    /// Amazon S3, see [`S3Ctx`]
+    S3,
+    /// Local filesystem served by the coordinator itself, see [`LocalStore`]
+    Local,
+}
+
+impl FromStr for ObjectStoreKind {
+    type Err = ObjectStoreError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "s3" => Ok(Self::S3),
+            "local" => Ok(Self::Local),
+            _ => Err(ObjectStoreError::UnknownKind(s.to_string())),
+        }
+    }
+}
+
+/// Operations on the ceremony objects that are exchanged outside of the coordinator's [`Disk`](`crate::storage::Disk`).
+///
+/// Keys are relative paths mirroring the layout of the coordinator storage, e.g.
+/// `round_1/chunk_0/contribution_0.verified`.
+#[rocket::async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Get the url to download a challenge, if the challenge is already in the store.
+    async fn get_challenge_url(&self, key: String) -> Result<Option<String>>;
+
+    /// Upload a challenge to the store. Returns the url to download it.
+    async fn upload_challenge(&self, key: String, challenge: Vec<u8>) -> Result<String>;
+
+    /// Get the urls where to upload a contribution and its signature.
+    async fn get_contribution_urls(&self, contrib_key: String, contrib_sig_key: String) -> Result<(String, String)>;
+
+    /// Retrieve a contribution and its signature.
+    async fn get_contribution(&self, round_height: u64) -> Result<(Vec<u8>, Vec<u8>)>;
+
+    /// Retrieve the compressed token folder.
+    async fn get_tokens(&self) -> Result<Vec<u8>>;
+
+    /// Publish the contributors.json file for the frontend.
+    async fn upload_contributions_info(&self, contributions_info: Vec<u8>) -> Result<()>;
+}
+
+/// The object store shared among the rest server and the coordinator tasks.
+pub type SharedObjectStore = Arc<dyn ObjectStore>;
+
+/// Key of the contribution uploaded by the contributor of the given round.
+pub(crate) fn contribution_key(round_height: u64) -> String {
+    format!("round_{}/chunk_0/contribution_1.unverified", round_height)
+}
+
+/// Key of the signature of the contribution uploaded by the contributor of the given round.
+pub(crate) fn contribution_signature_key(round_height: u64) -> String {
+    format!("round_{}/chunk_0/contribution_1.unverified.signature", round_height)
+}
+
+/// Key of the compressed token folder.
+pub(crate) fn tokens_key() -> String {
+    match Config::current().s3.production {
+        true => format!("production/{}", TOKENS_ZIP_FILE),
+        false => format!("master/{}", TOKENS_ZIP_FILE),
+    }
+}
+
+/// Instantiate the object store selected by the configuration, see [`Config`]. If the [`LocalStore`] is
+/// selected, it is also returned on its own since its routes need to be mounted on the rest server.
+pub async fn from_env() -> Result<(SharedObjectStore, Option<Arc<LocalStore>>)> {
+    match *OBJECT_STORE {
+        ObjectStoreKind::S3 => Ok((Arc::new(S3Ctx::new()?), None)),
+        ObjectStoreKind::Local => {
+            let store = Arc::new(LocalStore::new(LOCAL_STORE_PATH.as_str(), LOCAL_STORE_URL.as_str()).await?);
+
+            Ok((store.clone(), Some(store)))
+        }
+    }
+}
+
+/// Object store backed by the local filesystem. Objects are served by the coordinator itself through the
+/// [`get_local_object`](`crate::rest::get_local_object`) and [`put_local_object`](`crate::rest::put_local_object`)
+/// endpoints, which only accept urls signed by this store.
+pub struct LocalStore {
+    root: PathBuf,
+    base_url: String,
+    secret: [u8; 32],
+}
+
+impl LocalStore {
+    /// Creates a new store rooted at `root`. The `base_url` is the public address of the coordinator.
+    pub async fn new<P: AsRef<Path>>(root: P, base_url: &str) -> Result<Self> {
+        fs::create_dir_all(root.as_ref()).await?;
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+
+        Ok(Self {
+            root: root.as_ref().to_path_buf(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            secret,
+        })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+
+    fn sign(&self, method: &str, key: &str, expires: i64) -> String {
+        let mut hasher = Blake2b512::new();
+        hasher.update(&self.secret);
+        hasher.update(method.as_bytes());
+        hasher.update(key.as_bytes());
+        hasher.update(expires.to_le_bytes());
+
+        hex::encode(hasher.finalize())
+    }
+
+    /// Returns a url to access the object with the given method, valid for [`PRESIGNED_URL_EXPIRY`].
+    fn presign(&self, method: &str, key: &str) -> String {
+        let expires = OffsetDateTime::now_utc().unix_timestamp() + PRESIGNED_URL_EXPIRY.as_secs() as i64;
+
+        format!(
+            "{}/storage/{}?expires={}&signature={}",
+            self.base_url,
+            key,
+            expires,
+            self.sign(method, key, expires)
+        )
+    }
+
+    /// Checks that a url was signed by this store for the given method and that it has not expired.
+    pub(crate) fn verify(&self, method: &str, key: &str, expires: i64, signature: &str) -> bool {
+        if expires < OffsetDateTime::now_utc().unix_timestamp() {
+            return false;
+        }
+
+        self.sign(method, key, expires)
+            .as_bytes()
+            .ct_eq(signature.as_bytes())
+            .into()
+    }
+
+    /// Returns the path of an existing object.
+    pub(crate) async fn object_path(&self, key: &str) -> Result<PathBuf> {
+        let path = self.path(key);
+
+        match fs::metadata(&path).await {
+            Ok(m) if m.is_file() => Ok(path),
+            _ => Err(ObjectStoreError::MissingObject(key.to_string())),
+        }
+    }
+
+    /// Streams the body of a request to the object with the given key, overwriting it.
+    pub(crate) async fn write_object(&self, key: &str, data: Data<'_>) -> Result<()> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let written = data
+            .open(LOCAL_STORE_MAX_OBJECT_SIZE_GIB.gibibytes())
+            .into_file(&path)
+            .await?;
+
+        if !written.is_complete() {
+            fs::remove_file(&path).await?;
+            return Err(ObjectStoreError::ObjectTooLarge(key.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn read_object(&self, key: &str) -> Result<Vec<u8>> {
+        let object = fs::read(self.object_path(key).await?).await?;
+
+        if object.is_empty() {
+            return Err(ObjectStoreError::EmptyObject(key.to_string()));
+        }
+
+        Ok(object)
+    }
+
+    async fn write_bytes(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        fs::write(path, bytes).await?;
+
+        Ok(())
+    }
+}
+
+#[rocket::async_trait]
+impl ObjectStore for LocalStore {
+    async fn get_challenge_url(&self, key: String) -> Result<Option<String>> {
+        match self.object_path(&key).await {
+            Ok(_) => Ok(Some(self.presign("GET", &key))),
+            Err(ObjectStoreError::MissingObject(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn upload_challenge(&self, key: String, challenge: Vec<u8>) -> Result<String> {
+        self.write_bytes(&key, &challenge).await?;
+
+        Ok(self.presign("GET", &key))
+    }
+
+    async fn get_contribution_urls(&self, contrib_key: String, contrib_sig_key: String) -> Result<(String, String)> {
+        Ok((self.presign("PUT", &contrib_key), self.presign("PUT", &contrib_sig_key)))
+    }
+
+    async fn get_contribution(&self, round_height: u64) -> Result<(Vec<u8>, Vec<u8>)> {
+        let contribution = self.read_object(&contribution_key(round_height)).await?;
+        let signature = self.read_object(&contribution_signature_key(round_height)).await?;
+
+        Ok((contribution, signature))
+    }
+
+    async fn get_tokens(&self) -> Result<Vec<u8>> {
+        self.read_object(&tokens_key()).await
+    }
+
+    async fn upload_contributions_info(&self, contributions_info: Vec<u8>) -> Result<()> {
+        self.write_bytes(CONTRIBUTIONS_INFO_FILE, &contributions_info).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rocket::async_test]
+    async fn test_local_store() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let store = LocalStore::new(tmp_dir.path(), "http://localhost:8080/").await.unwrap();
+        let key = "round_1/chunk_0/contribution_0.verified".to_string();
+
+        assert!(store.get_challenge_url(key.clone()).await.unwrap().is_none());
+        let url = store.upload_challenge(key.clone(), vec![1, 2, 3]).await.unwrap();
+        assert!(url.starts_with("http://localhost:8080/storage/round_1/chunk_0/contribution_0.verified?"));
+        assert!(store.get_challenge_url(key.clone()).await.unwrap().is_some());
+
+        // Signatures are bound to the method, the key and the expiry
+        let expires = OffsetDateTime::now_utc().unix_timestamp() + 10;
+        let signature = store.sign("GET", &key, expires);
+        assert!(store.verify("GET", &key, expires, &signature));
+        assert!(!store.verify("PUT", &key, expires, &signature));
+        assert!(!store.verify("GET", "round_1/chunk_0/contribution_1.verified", expires, &signature));
+        assert!(!store.verify("GET", &key, expires + 1, &signature));
+
+        let expired = OffsetDateTime::now_utc().unix_timestamp() - 1;
+        let signature = store.sign("GET", &key, expired);
+        assert!(!store.verify("GET", &key, expired, &signature));
+
+        // Missing contributions are reported as such
+        match store.get_contribution(1).await {
+            Err(ObjectStoreError::MissingObject(k)) => assert_eq!(k, contribution_key(1)),
+            _ => panic!("Expected missing object"),
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{
+    coordinator_state::FailedVerification,
     environment::Environment,
     objects::{participant::*, Chunk},
     storage::{
-        ContributionLocator, ContributionSignatureLocator, Disk, Locator, LocatorPath, Object, StorageAction,
-        StorageLocator, UpdateAction,
+        ContributionLocator, ContributionSignatureLocator, Locator, LocatorPath, Object, Storage, StorageAction,
+        UpdateAction,
     },
     CoordinatorError,
 };
@@ -75,6 +76,9 @@ pub struct Round {
     contributor_ids: Vec<Participant>,
     verifier_ids: Vec<Participant>,
     chunks: Vec<Chunk>,
+    #[serde_diff(opaque)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    failed_verifications: Vec<FailedVerification>,
 }
 
 impl Round {
@@ -82,7 +86,7 @@ impl Round {
     #[inline]
     pub(crate) fn new(
         environment: &Environment,
-        storage: &mut Disk,
+        storage: &mut impl Storage,
         round_height: u64,
         started_at: OffsetDateTime,
         contributor_ids: Vec<Participant>,
@@ -159,6 +163,7 @@ impl Round {
             contributor_ids,
             verifier_ids: vec![],
             chunks,
+            failed_verifications: vec![],
         })
     }
 
@@ -242,6 +247,23 @@ impl Round {
         &self.chunks
     }
 
+    ///
+    /// Returns the contributions of this round which failed verification,
+    /// including those of a later round rolled back to this one.
+    ///
+    #[inline]
+    pub fn failed_verifications(&self) -> &[FailedVerification] {
+        &self.failed_verifications
+    }
+
+    ///
+    /// Records contributions which failed verification.
+    ///
+    #[inline]
+    pub(crate) fn add_failed_verifications(&mut self, failed_verifications: &[FailedVerification]) {
+        self.failed_verifications.extend_from_slice(failed_verifications);
+    }
+
     ///
     /// Returns the expected number of contributions.
     ///
@@ -338,7 +360,7 @@ impl Round {
     )]
     pub(crate) fn current_contribution_locator(
         &self,
-        storage: &Disk,
+        storage: &impl Storage,
         chunk_id: u64,
         verified: bool,
     ) -> Result<ContributionLocator, CoordinatorError> {
@@ -399,7 +421,7 @@ impl Round {
     )]
     pub(crate) fn next_contribution_locator(
         &self,
-        storage: &Disk,
+        storage: &impl Storage,
         chunk_id: u64,
     ) -> Result<ContributionLocator, CoordinatorError> {
         // Fetch the current round height.
@@ -455,7 +477,7 @@ impl Round {
     #[inline]
     pub(crate) fn next_contribution_file_signature_locator(
         &self,
-        storage: &Disk,
+        storage: &impl Storage,
         chunk_id: u64,
     ) -> Result<ContributionSignatureLocator, CoordinatorError> {
         // Fetch the current round height.
@@ -506,7 +528,7 @@ impl Round {
     pub(crate) fn try_lock_chunk(
         &mut self,
         environment: &Environment,
-        storage: &mut Disk,
+        storage: &mut impl Storage,
         chunk_id: u64,
         participant: &Participant,
     ) -> Result<LockedLocators, CoordinatorError> {
@@ -615,9 +637,10 @@ impl Round {
                 storage.initialize(
                     Locator::ContributionFile(locked_locators.next_contribution.clone()),
                     Object::anoma_contribution_file_size(
+                        environment.parameters().circuits(),
                         locked_locators.next_contribution.round_height(),
                         locked_locators.next_contribution.contribution_id(),
-                    ),
+                    )?,
                 )?;
 
                 // Initialize the contribution file signature.
@@ -638,8 +661,8 @@ impl Round {
     /// Initialize the files for the next challenge
     pub fn initialize_verifier_response_files(
         &self,
-        _environment: &Environment,
-        storage: &mut Disk,
+        environment: &Environment,
+        storage: &mut impl Storage,
         participant: &Participant,
         _chunk_id: u64,
         locators: &LockedLocators,
@@ -651,9 +674,10 @@ impl Round {
         storage.initialize(
             Locator::ContributionFile(locators.next_contribution.clone()),
             Object::anoma_contribution_file_size(
+                environment.parameters().circuits(),
                 locators.next_contribution.round_height(),
                 locators.next_contribution.contribution_id(),
-            ),
+            )?,
         )?;
 
         // Initialize the contribution file signature.
@@ -668,7 +692,7 @@ impl Round {
     /// Returns previous contribution, current contribution and next contribution paths
     pub(crate) fn get_chunk_locators_for_verifier(
         &self,
-        storage: &Disk,
+        storage: &impl Storage,
         participant: &Participant,
         chunk_id: u64,
         contribution_id: u64,
@@ -786,7 +810,7 @@ impl Round {
     /// Remove a contributor from the round.
     pub(crate) fn remove_contributor_unsafe(
         &mut self,
-        storage: &mut Disk,
+        storage: &mut impl Storage,
         contributor: &Participant,
         locked_chunks: &[u64],
         tasks: &[Task],
@@ -823,7 +847,7 @@ impl Round {
     #[inline]
     pub(crate) fn remove_locks_unsafe(
         &mut self,
-        storage: &mut Disk,
+        storage: &mut impl Storage,
         participant: &Participant,
         locked_chunks: &[u64],
     ) -> Result<(), CoordinatorError> {
@@ -997,7 +1021,7 @@ impl Round {
     )]
     pub(crate) fn remove_chunk_contributions_unsafe(
         &mut self,
-        storage: &mut Disk,
+        storage: &mut impl Storage,
         participant: &Participant,
         tasks: &[Task],
     ) -> Result<(), CoordinatorError> {
@@ -0,0 +1,238 @@
+//! The public keys of the operators of the ceremony and their roles, which grant access to the privileged endpoints
+//! (see [`OperatorAuth`](`crate::rest_utils::OperatorAuth`) and the other operator guards).
+//!
+//! The keys are read from a TOML file, checked for changes every [REFRESH_INTERVAL], so that a key can be added or
+//! revoked while the ceremony is running:
+//!
+//! ```toml
+//! [[operators]]
+//! name = "alice"
+//! pubkey = "<hex encoded ed25519 public key>"
+//! role = "admin"
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Debug, Error)]
+pub enum OperatorsError {
+    #[error("Operator key {0} is declared more than once")]
+    DuplicateKey(String),
+    #[error("Error while reading {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Error while parsing {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+}
+
+type Result<T> = std::result::Result<T, OperatorsError>;
+
+/// The interval between two checks of the file of the operator keys.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The roles of the operators, each one granting the permissions of the previous ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperatorRole {
+    /// Reads the state of the ceremony
+    Observer,
+    /// Runs the ceremony, e.g. updates the cohorts
+    Operator,
+    /// Closes and finalizes the ceremony
+    Admin,
+}
+
+impl fmt::Display for OperatorRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Observer => write!(f, "observer"),
+            Self::Operator => write!(f, "operator"),
+            Self::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// The public key of an operator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OperatorKey {
+    /// The name of the operator, for the logs.
+    pub name: String,
+    /// The hex encoded public key the operator signs the requests with.
+    pub pubkey: String,
+    pub role: OperatorRole,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OperatorsFile {
+    #[serde(default)]
+    operators: Vec<OperatorKey>,
+}
+
+/// The operator keys, indexed by public key, and the content of the file they were parsed from.
+#[derive(Default)]
+struct Cache {
+    content: Option<String>,
+    keys: HashMap<String, OperatorKey>,
+}
+
+/// The operator keys of a file, reloaded by [Operators::refresh] when it is modified.
+pub struct Operators {
+    path: PathBuf,
+    cache: RwLock<Cache>,
+}
+
+/// The operator keys shared among the rest server and the coordinator tasks.
+pub type SharedOperators = Arc<Operators>;
+
+impl Operators {
+    ///
+    /// Loads the operator keys of the given file. A missing file declares no operator.
+    ///
+    /// On failure to read or parse the file, this function returns an `OperatorsError`.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let operators = Self {
+            path: path.as_ref().to_path_buf(),
+            cache: Default::default(),
+        };
+        let count = operators.reload()?;
+        info!("Loaded {} operator keys from {}", count, operators.path.display());
+
+        Ok(operators)
+    }
+
+    /// Returns the operator of the given public key, if any, as of the last load of the file.
+    pub fn get(&self, pubkey: &str) -> Option<OperatorKey> {
+        self.cache
+            .read()
+            .expect("Lock should not be poisoned")
+            .keys
+            .get(pubkey)
+            .cloned()
+    }
+
+    ///
+    /// Reloads the file if it was modified, and returns true if it was. The file is small enough to be compared
+    /// with the loaded one, which doesn't depend on the precision of its modification time.
+    ///
+    /// If the file can't be reloaded, no key is accepted until it is fixed, so that a revocation can't be missed.
+    ///
+    pub fn refresh(&self) -> Result<bool> {
+        let content = self.read();
+        let modified = match &content {
+            Ok(content) => *content != self.cache.read().expect("Lock should not be poisoned").content,
+            Err(_) => true,
+        };
+        if !modified {
+            return Ok(false);
+        }
+
+        let count = self.reload()?;
+        info!("Reloaded {} operator keys from {}", count, self.path.display());
+
+        Ok(true)
+    }
+
+    /// Reads the file into the cache, which is emptied on failure. Returns the number of keys.
+    fn reload(&self) -> Result<usize> {
+        let mut cache = self.cache.write().expect("Lock should not be poisoned");
+        *cache = Cache::default();
+
+        let content = self.read()?;
+        let file: OperatorsFile = match &content {
+            Some(content) => toml::from_str(content).map_err(|e| OperatorsError::Parse(self.path.clone(), e))?,
+            None => {
+                warn!("Missing operators file {}, no operator declared", self.path.display());
+                OperatorsFile::default()
+            }
+        };
+
+        let mut keys = HashMap::with_capacity(file.operators.len());
+        for key in file.operators {
+            if keys.contains_key(&key.pubkey) {
+                return Err(OperatorsError::DuplicateKey(key.pubkey));
+            }
+            keys.insert(key.pubkey.clone(), key);
+        }
+
+        *cache = Cache { content, keys };
+
+        Ok(cache.keys.len())
+    }
+
+    /// Returns the content of the file, or `None` if it doesn't exist.
+    fn read(&self) -> Result<Option<String>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(OperatorsError::Io(self.path.clone(), e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_operators() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("operators.toml");
+
+        // A missing file declares no operator
+        let operators = Operators::load(&path).unwrap();
+        assert_eq!(operators.get("alice_key"), None);
+        assert!(!operators.refresh().unwrap());
+
+        std::fs::write(
+            &path,
+            "[[operators]]\nname = \"alice\"\npubkey = \"alice_key\"\nrole = \"operator\"\n\n\
+             [[operators]]\nname = \"bob\"\npubkey = \"bob_key\"\nrole = \"observer\"\n",
+        )
+        .unwrap();
+        // The keys are only reloaded by a refresh
+        assert_eq!(operators.get("alice_key"), None);
+        assert!(operators.refresh().unwrap());
+        assert!(!operators.refresh().unwrap());
+        let alice = operators.get("alice_key").unwrap();
+        assert_eq!(alice.name, "alice");
+        assert!(alice.role >= OperatorRole::Observer && alice.role < OperatorRole::Admin);
+
+        // Revoke bob
+        std::fs::write(
+            &path,
+            "[[operators]]\nname = \"alice\"\npubkey = \"alice_key\"\nrole = \"admin\"\n",
+        )
+        .unwrap();
+        assert!(operators.refresh().unwrap());
+        assert_eq!(operators.get("bob_key"), None);
+        assert_eq!(operators.get("alice_key").unwrap().role, OperatorRole::Admin);
+
+        // An invalid file revokes every key until it is fixed
+        std::fs::write(
+            &path,
+            "[[operators]]\nname = \"alice\"\npubkey = \"alice_key\"\nrole = \"root\"\n",
+        )
+        .unwrap();
+        assert!(matches!(operators.refresh(), Err(OperatorsError::Parse(..))));
+        assert_eq!(operators.get("alice_key"), None);
+
+        std::fs::write(
+            &path,
+            "[[operators]]\nname = \"alice\"\npubkey = \"key\"\nrole = \"admin\"\n\n\
+             [[operators]]\nname = \"bob\"\npubkey = \"key\"\nrole = \"observer\"\n",
+        )
+        .unwrap();
+        assert!(matches!(operators.refresh(), Err(OperatorsError::DuplicateKey(_))));
+        assert_eq!(operators.get("key"), None);
+    }
+}
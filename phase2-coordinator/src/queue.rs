@@ -0,0 +1,239 @@
+//! The scheduling of the queue: the order in which the queued contributors are assigned to the next rounds, given by
+//! the [QueuePolicy] of the [Environment](crate::environment::Environment). The operators can also move a
+//! contributor to a position of the queue, which takes precedence over the policy.
+
+use crate::objects::Participant;
+
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fmt, str::FromStr};
+use time::OffsetDateTime;
+
+/// A contributor waiting in the queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueEntry {
+    pub participant: Participant,
+    /// The reliability score the contributor joined with.
+    pub reliability: u8,
+    /// The time the contributor joined the queue at.
+    pub joined: OffsetDateTime,
+    /// The cohort the contributor joined the queue during, starting from 0.
+    pub cohort: usize,
+}
+
+/// The order in which the queued contributors are assigned to the next rounds.
+pub trait QueueScheduler {
+    /// Returns the given contributors, sorted by time joined, in the order of their rounds of `round_size`
+    /// contributors.
+    fn schedule(&self, queue: Vec<QueueEntry>, round_size: usize) -> Vec<QueueEntry>;
+}
+
+/// First come, first served.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fifo;
+
+impl QueueScheduler for Fifo {
+    fn schedule(&self, queue: Vec<QueueEntry>, _round_size: usize) -> Vec<QueueEntry> {
+        queue
+    }
+}
+
+/// The most reliable contributors first, then the first ones to have joined.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReliabilityWeighted;
+
+impl QueueScheduler for ReliabilityWeighted {
+    fn schedule(&self, mut queue: Vec<QueueEntry>, _round_size: usize) -> Vec<QueueEntry> {
+        // The sort is stable, the contributors of the same reliability keep their order
+        queue.sort_by_key(|entry| std::cmp::Reverse(entry.reliability));
+        queue
+    }
+}
+
+/// The contributors of the earlier cohorts first, then the first ones to have joined.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CohortPriority;
+
+impl QueueScheduler for CohortPriority {
+    fn schedule(&self, mut queue: Vec<QueueEntry>, _round_size: usize) -> Vec<QueueEntry> {
+        queue.sort_by_key(|entry| entry.cohort);
+        queue
+    }
+}
+
+/// The first `slots` of each round go to the invited contributors, who joined during the first `invited_cohorts`
+/// cohorts, and the other ones to the first contributors to have joined. The reserved slots left without an invited
+/// contributor go to the other ones, so that no round waits for them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReservedSlots {
+    pub invited_cohorts: usize,
+    pub slots: usize,
+}
+
+impl QueueScheduler for ReservedSlots {
+    fn schedule(&self, queue: Vec<QueueEntry>, round_size: usize) -> Vec<QueueEntry> {
+        let round_size = round_size.max(1);
+        let slots = self.slots.min(round_size);
+        let mut scheduled = Vec::with_capacity(queue.len());
+        let (mut invited, mut others): (VecDeque<_>, VecDeque<_>) =
+            queue.into_iter().partition(|entry| entry.cohort < self.invited_cohorts);
+
+        while !invited.is_empty() || !others.is_empty() {
+            let round_start = scheduled.len();
+            let reserved = slots.min(invited.len());
+            scheduled.extend(invited.drain(..reserved));
+
+            while scheduled.len() - round_start < round_size {
+                let next = match (invited.front(), others.front()) {
+                    (Some(i), Some(o)) if i.joined <= o.joined => invited.pop_front(),
+                    (_, Some(_)) => others.pop_front(),
+                    (Some(_), None) => invited.pop_front(),
+                    (None, None) => break,
+                };
+                scheduled.extend(next);
+            }
+        }
+
+        scheduled
+    }
+}
+
+/// The built-in [QueueScheduler]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueuePolicy {
+    Fifo,
+    ReliabilityWeighted,
+    CohortPriority,
+    ReservedSlots { invited_cohorts: usize, slots: usize },
+}
+
+impl Default for QueuePolicy {
+    fn default() -> Self {
+        Self::Fifo
+    }
+}
+
+impl QueuePolicy {
+    /// Returns the scheduler of the policy.
+    pub fn scheduler(&self) -> Box<dyn QueueScheduler + Send + Sync> {
+        match *self {
+            Self::Fifo => Box::new(Fifo),
+            Self::ReliabilityWeighted => Box::new(ReliabilityWeighted),
+            Self::CohortPriority => Box::new(CohortPriority),
+            Self::ReservedSlots { invited_cohorts, slots } => Box::new(ReservedSlots { invited_cohorts, slots }),
+        }
+    }
+}
+
+/// Parses the name of a policy, `fifo`, `reliability-weighted`, `cohort-priority` or `reserved-slots`, the latter
+/// without any reserved slot.
+impl FromStr for QueuePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fifo" => Ok(Self::Fifo),
+            "reliability-weighted" => Ok(Self::ReliabilityWeighted),
+            "cohort-priority" => Ok(Self::CohortPriority),
+            "reserved-slots" => Ok(Self::ReservedSlots {
+                invited_cohorts: 0,
+                slots: 0,
+            }),
+            _ => Err(format!("unknown queue policy {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for QueuePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fifo => write!(f, "fifo"),
+            Self::ReliabilityWeighted => write!(f, "reliability-weighted"),
+            Self::CohortPriority => write!(f, "cohort-priority"),
+            Self::ReservedSlots { .. } => write!(f, "reserved-slots"),
+        }
+    }
+}
+
+///
+/// Moves the contributors of the given positions, starting from 0, to them in the given queue, in order of
+/// position. A position past the end of the queue moves the contributor to the end of it.
+///
+pub fn apply_positions(queue: &mut Vec<QueueEntry>, positions: &[(Participant, usize)]) {
+    let mut positions = positions.to_vec();
+    positions.sort_by_key(|(_, position)| *position);
+
+    for (participant, position) in positions {
+        if let Some(index) = queue.iter().position(|entry| entry.participant == participant) {
+            let entry = queue.remove(index);
+            queue.insert(position.min(queue.len()), entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(cohorts: &[usize]) -> Vec<QueueEntry> {
+        let start = OffsetDateTime::now_utc();
+        cohorts
+            .iter()
+            .enumerate()
+            .map(|(i, cohort)| QueueEntry {
+                participant: Participant::new_contributor(&format!("contributor-{}", i)),
+                reliability: (i % 3) as u8,
+                joined: start + time::Duration::seconds(i as i64),
+                cohort: *cohort,
+            })
+            .collect()
+    }
+
+    /// Returns the addresses of the contributors of the given queue, in its order.
+    fn order(queue: &[QueueEntry]) -> Vec<String> {
+        queue.iter().map(|entry| entry.participant.address()).collect()
+    }
+
+    fn contributors(indexes: &[usize]) -> Vec<String> {
+        indexes.iter().map(|i| format!("contributor-{}", i)).collect()
+    }
+
+    #[test]
+    fn test_queue_policies() {
+        let entries = queue(&[1, 0, 1, 1, 0]);
+
+        assert_eq!(Fifo.schedule(entries.clone(), 2), entries);
+        assert_eq!(
+            order(&ReliabilityWeighted.schedule(entries.clone(), 2)),
+            contributors(&[2, 1, 4, 0, 3])
+        );
+        assert_eq!(
+            order(&CohortPriority.schedule(entries.clone(), 2)),
+            contributors(&[1, 4, 0, 2, 3])
+        );
+
+        // One reserved slot per round of two, for the invited contributors of cohort 0
+        let reserved = ReservedSlots {
+            invited_cohorts: 1,
+            slots: 1,
+        };
+        assert_eq!(order(&reserved.schedule(entries, 2)), contributors(&[1, 0, 4, 2, 3]));
+
+        assert_eq!(
+            "reserved-slots".parse::<QueuePolicy>().unwrap().to_string(),
+            "reserved-slots"
+        );
+        assert!("lifo".parse::<QueuePolicy>().is_err());
+    }
+
+    #[test]
+    fn test_apply_positions() {
+        let mut entries = queue(&[0, 0, 0, 0]);
+        let positions = vec![
+            (entries[1].participant.clone(), 10),
+            (entries[3].participant.clone(), 0),
+        ];
+
+        apply_positions(&mut entries, &positions);
+        assert_eq!(order(&entries), contributors(&[3, 0, 2, 1]));
+    }
+}
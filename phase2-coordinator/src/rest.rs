@@ -3,21 +3,30 @@
 use std::{
     collections::{HashMap, HashSet},
     io::{Cursor, Read, Write},
+    path::PathBuf,
+    sync::Arc,
 };
 use tracing::warn;
 
 use crate::{
-    objects::{ContributionInfo, LockedLocators},
+    audit_log::{AdminAction, AuditEntry, SharedAuditLog},
+    commands::{Beacon, BeaconRecord},
+    environment::CircuitSet,
+    object_store::{contribution_key, contribution_signature_key, LocalStore, SharedObjectStore},
+    objects::{ContributionFileSignature, ContributionInfo, LockedLocators},
     rest_utils::{
-        self, ContributorStatus, Coordinator, CurrentContributor, LazyJson, NewParticipant, PostChunkRequest,
-        ResponseError, Result, Secret, ServerAuth, HEALTH_PATH, TOKENS_PATH, TOKENS_ZIP_FILE,
+        self, AdminAuth, BanRequest, CheckedBytes, ContributionStatus, ContributorStatus, Coordinator,
+        CurrentContributor, LazyJson, MoveInQueueRequest, NewParticipant, ObserverAuth, OperatorAuth,
+        ParticipantRequest, PostChunkRequest, ResponseError, Result, Secret, HEALTH_PATH, TOKENS_PATH, TOKENS_ZIP_FILE,
     },
-    s3::S3Ctx,
-    storage::{Locator, Object},
-    CoordinatorState, Participant,
+    secret::SharedAccessSecret,
+    storage::{ContributionLocator, ContributionSignatureLocator, Locator, Object, Storage},
+    CoordinatorError, CoordinatorState, Participant,
 };
 use rocket::{
-    get, post,
+    data::Data,
+    fs::NamedFile,
+    get, post, put,
     serde::json::Json,
     tokio::{fs, task},
     Shutdown, State,
@@ -35,15 +44,16 @@ pub async fn join_queue(
     // NOTE: check on the token happens only here meaning that a contributor can join the ceremony at the very last moment of a cohort and
     // contribute effectively in the following cohort. Forcing the contribution to happen in the correct cohort would take more complicated checks
     // and could lower the amount of contributions received
-    let cohort = rest_utils::token_check((*coordinator).clone(), token.as_str()).await?;
+    let (cohort, token_key) = rest_utils::token_check((*coordinator).clone(), token.as_str()).await?;
     let mut write_lock = (*coordinator).clone().write_owned().await;
 
     task::spawn_blocking(move || {
+        let reliability = write_lock.reliability_score(&new_participant.participant);
         write_lock.add_to_queue(
             new_participant.participant,
             new_participant.ip_address,
-            token.clone(),
-            10,
+            token_key,
+            reliability,
         )
     })
     .await?
@@ -65,18 +75,18 @@ pub async fn lock_chunk(
     }
 }
 
-/// Get the challenge key on Amazon S3 from the [Coordinator](`crate::Coordinator`).
+/// Get the challenge url on the object store from the [Coordinator](`crate::Coordinator`).
 #[post("/contributor/challenge", format = "json", data = "<round_height>")]
 pub async fn get_challenge_url(
     coordinator: &State<Coordinator>,
+    object_store: &State<SharedObjectStore>,
     _participant: CurrentContributor,
     round_height: LazyJson<u64>,
 ) -> Result<Json<String>> {
-    let s3_ctx = S3Ctx::new().await?;
     let key = format!("round_{}/chunk_0/contribution_0.verified", *round_height);
 
-    // If challenge is already on S3 (round rollback) immediately return the key
-    if let Some(url) = s3_ctx.get_challenge_url(key.clone()).await {
+    // If challenge is already on the object store (round rollback) immediately return the url
+    if let Some(url) = object_store.get_challenge_url(key.clone()).await? {
         return Ok(Json(url));
     }
 
@@ -88,8 +98,8 @@ pub async fn get_challenge_url(
         Err(e) => return Err(ResponseError::CoordinatorError(e)),
     };
 
-    // Upload challenge to S3 and return url
-    let url = s3_ctx.upload_challenge(key, challenge).await?;
+    // Upload challenge to the object store and return url
+    let url = object_store.upload_challenge(key, challenge).await?;
 
     Ok(Json(url))
 }
@@ -97,20 +107,114 @@ pub async fn get_challenge_url(
 /// Request the urls where to upload a [Chunk](`crate::objects::Chunk`) contribution and the ContributionFileSignature.
 #[post("/upload/chunk", format = "json", data = "<round_height>")]
 pub async fn get_contribution_url(
+    object_store: &State<SharedObjectStore>,
     _participant: CurrentContributor,
     round_height: LazyJson<u64>,
 ) -> Result<Json<(String, String)>> {
-    let contrib_key = format!("round_{}/chunk_0/contribution_1.unverified", *round_height);
-    let contrib_sig_key = format!("round_{}/chunk_0/contribution_1.unverified.signature", *round_height);
+    let contrib_key = contribution_key(*round_height);
+    let contrib_sig_key = contribution_signature_key(*round_height);
 
     // Prepare urls for the upload
-    let s3_ctx = S3Ctx::new().await?;
-    let urls = s3_ctx.get_contribution_urls(contrib_key, contrib_sig_key);
+    let urls = object_store.get_contribution_urls(contrib_key, contrib_sig_key).await?;
 
     Ok(Json(urls))
 }
 
+/// Stream the challenge of the given round straight from the [Coordinator](`crate::Coordinator`) storage, without going through the object store.
+#[get("/contributor/challenge/<round_height>")]
+pub async fn get_challenge(
+    coordinator: &State<Coordinator>,
+    _participant: CurrentContributor,
+    round_height: u64,
+) -> Result<NamedFile> {
+    let read_lock = (*coordinator).clone().read_owned().await;
+    let path = task::spawn_blocking(move || {
+        read_lock.contribution_file_path(ContributionLocator::new(round_height, 0, 0, true))
+    })
+    .await?
+    .map_err(|e| ResponseError::CoordinatorError(e))?;
+
+    NamedFile::open(path)
+        .await
+        .map_err(|e| ResponseError::IoError(e.to_string()))
+}
+
+/// Get the number of bytes of the [Chunk](`crate::objects::Chunk`) contribution already uploaded directly to the [Coordinator](`crate::Coordinator`). A contributor resumes an interrupted upload from this offset.
+#[get("/contributor/upload/<round_height>")]
+pub async fn get_upload_offset(
+    coordinator: &State<Coordinator>,
+    _participant: CurrentContributor,
+    round_height: u64,
+) -> Result<Json<u64>> {
+    let read_lock = (*coordinator).clone().read_owned().await;
+
+    task::spawn_blocking(move || {
+        check_round_height(&read_lock, round_height)?;
+        read_lock.contribution_upload_size(ContributionLocator::new(round_height, 0, 1, false))
+    })
+    .await?
+    .map_or_else(|e| Err(ResponseError::CoordinatorError(e)), |size| Ok(Json(size)))
+}
+
+/// Upload a part of a [Chunk](`crate::objects::Chunk`) contribution directly to the [Coordinator](`crate::Coordinator`), starting at the given offset. Returns the number of bytes received so far.
+#[post("/contributor/upload/<round_height>/<offset>", data = "<part>")]
+pub async fn post_contribution_part(
+    coordinator: &State<Coordinator>,
+    _participant: CurrentContributor,
+    round_height: u64,
+    offset: u64,
+    part: CheckedBytes,
+) -> Result<Json<u64>> {
+    let mut write_lock = (*coordinator).clone().write_owned().await;
+
+    task::spawn_blocking(move || {
+        check_round_height(&write_lock, round_height)?;
+        write_lock.append_contribution_upload(ContributionLocator::new(round_height, 0, 1, false), offset, &part)
+    })
+    .await?
+    .map_or_else(|e| Err(ResponseError::CoordinatorError(e)), |size| Ok(Json(size)))
+}
+
+/// Upload the ContributionFileSignature of a [Chunk](`crate::objects::Chunk`) contribution directly to the [Coordinator](`crate::Coordinator`).
+#[post(
+    "/contributor/upload_signature/<round_height>",
+    format = "json",
+    data = "<signature>"
+)]
+pub async fn post_contribution_signature(
+    coordinator: &State<Coordinator>,
+    _participant: CurrentContributor,
+    round_height: u64,
+    signature: LazyJson<ContributionFileSignature>,
+) -> Result<()> {
+    let mut write_lock = (*coordinator).clone().write_owned().await;
+
+    task::spawn_blocking(move || {
+        check_round_height(&write_lock, round_height)?;
+        write_lock.upload_contribution_file_signature(
+            ContributionSignatureLocator::new(round_height, 0, 1, false),
+            signature.0,
+        )
+    })
+    .await?
+    .map_err(|e| ResponseError::CoordinatorError(e))
+}
+
+/// Direct uploads are only accepted for the round currently running.
+fn check_round_height(
+    coordinator: &crate::Coordinator,
+    round_height: u64,
+) -> std::result::Result<(), CoordinatorError> {
+    if coordinator.current_round_height()? != round_height {
+        return Err(CoordinatorError::RoundHeightMismatch);
+    }
+
+    Ok(())
+}
+
 /// Notify the [Coordinator](`crate::Coordinator`) of a finished and uploaded [Contribution](`crate::objects::Contribution`). This will unlock the given [Chunk](`crate::objects::Chunk`).
+/// If the contribution has been uploaded directly to the coordinator it's used in place of the one on the object store.
+/// The contribution is verified before returning, its outcome can be queried with [`get_contribution_status`].
 #[post(
     "/contributor/contribute_chunk",
     format = "json",
@@ -118,30 +222,60 @@ pub async fn get_contribution_url(
 )]
 pub async fn contribute_chunk(
     coordinator: &State<Coordinator>,
+    object_store: &State<SharedObjectStore>,
     participant: CurrentContributor,
     contribute_chunk_request: LazyJson<PostChunkRequest>,
 ) -> Result<()> {
-    // Download contribution and its signature from S3 to local disk from the provided Urls
-    let s3_ctx = S3Ctx::new().await?;
-    let (contribution, contribution_sig) = s3_ctx.get_contribution(contribute_chunk_request.round_height).await?;
+    let contribution_locator = contribute_chunk_request.contribution_locator;
+    let read_lock = (*coordinator).clone().read_owned().await;
+    let direct_upload = task::spawn_blocking(move || read_lock.is_contribution_upload_complete(contribution_locator))
+        .await?
+        .map_err(|e| ResponseError::CoordinatorError(e))?;
+
+    // Download contribution and its signature from the object store to local disk, unless they were uploaded directly
+    let object_store_contribution = if direct_upload {
+        None
+    } else {
+        Some(
+            object_store
+                .get_contribution(contribute_chunk_request.round_height)
+                .await?,
+        )
+    };
     let mut write_lock = (*coordinator).clone().write_owned().await;
 
-    task::spawn_blocking(move || {
-        write_lock.write_contribution(contribute_chunk_request.contribution_locator, contribution)?;
-        write_lock.write_contribution_file_signature(
-            contribute_chunk_request.contribution_signature_locator,
-            serde_json::from_slice(&contribution_sig)?,
-        )?;
+    task::spawn_blocking(move || -> std::result::Result<_, CoordinatorError> {
+        // The direct upload may have changed since it was checked under the read lock. A complete one is always
+        // committed, the commit failing if it's no longer complete
+        let direct_upload = write_lock.is_contribution_upload_complete(contribution_locator)?;
+        match object_store_contribution {
+            Some((contribution, contribution_sig)) if !direct_upload => {
+                write_lock.write_contribution(contribute_chunk_request.contribution_locator, contribution)?;
+                write_lock.write_contribution_file_signature(
+                    contribute_chunk_request.contribution_signature_locator,
+                    serde_json::from_slice(&contribution_sig)?,
+                )?;
+            }
+            _ => write_lock.commit_contribution_upload(
+                contribute_chunk_request.contribution_locator,
+                contribute_chunk_request.contribution_signature_locator,
+            )?,
+        }
         write_lock.try_contribute(&participant, 0) // Only 1 chunk per round, chunk_id is always 0
     })
     .await?
-    .map_or_else(|e| Err(ResponseError::CoordinatorError(e)), |_| Ok(()))
+    .map_err(|e| ResponseError::CoordinatorError(e))?;
+
+    // Verify the contribution right away, without waiting for the periodic verification
+    let contributions_info = rest_utils::verify_pending_contributions((*coordinator).clone()).await?;
+
+    rest_utils::upload_contributions_info(object_store.inner().as_ref(), contributions_info).await
 }
 
-/// Update the [Coordinator](`crate::Coordinator`) state. This endpoint is accessible only by the coordinator itself.
+/// Update the [Coordinator](`crate::Coordinator`) state. This endpoint is accessible only by the operators.
 #[cfg(debug_assertions)]
 #[get("/update")]
-pub async fn update_coordinator(coordinator: &State<Coordinator>, _auth: ServerAuth) -> Result<()> {
+pub async fn update_coordinator(coordinator: &State<Coordinator>, _auth: OperatorAuth) -> Result<()> {
     rest_utils::perform_coordinator_update((*coordinator).clone()).await
 }
 
@@ -155,25 +289,54 @@ pub async fn heartbeat(coordinator: &State<Coordinator>, participant: Participan
         .map_err(|e| ResponseError::CoordinatorError(e))
 }
 
-/// Stop the [Coordinator](`crate::Coordinator`) and shuts the rest server down. This endpoint is accessible only by the coordinator itself.
+/// Stop the [Coordinator](`crate::Coordinator`) and shuts the rest server down. This endpoint is accessible only by the admins.
 #[get("/stop")]
-pub async fn stop_coordinator(_auth: ServerAuth, shutdown: Shutdown) {
+pub async fn stop_coordinator(_auth: AdminAuth, shutdown: Shutdown) {
     // Shut Rocket server down
     shutdown.notify();
 }
 
-/// Verify all the pending contributions. This endpoint is accessible only by the coordinator itself.
+/// Verify all the pending contributions. This endpoint is accessible only by the operators.
 #[cfg(debug_assertions)]
 #[get("/verify")]
-pub async fn verify_chunks(coordinator: &State<Coordinator>, _auth: ServerAuth) -> Result<()> {
-    rest_utils::perform_verify_chunks((*coordinator).clone(), &S3Ctx::new().await?).await
+pub async fn verify_chunks(
+    coordinator: &State<Coordinator>,
+    object_store: &State<SharedObjectStore>,
+    _auth: OperatorAuth,
+) -> Result<()> {
+    rest_utils::perform_verify_chunks((*coordinator).clone(), object_store.inner().as_ref()).await
+}
+
+/// Finalize the ceremony by applying a public randomness beacon to the last contribution. This endpoint is accessible only by the admins.
+#[post("/finalize", format = "json", data = "<beacon>")]
+pub async fn finalize_ceremony(
+    coordinator: &State<Coordinator>,
+    _auth: AdminAuth,
+    beacon: LazyJson<Beacon>,
+) -> Result<Json<BeaconRecord>> {
+    if !coordinator.read().await.is_ceremony_locked() {
+        return Err(ResponseError::CoordinatorError(CoordinatorError::CeremonyNotLocked));
+    }
+
+    // The hash iterations of the beacon can be long, the seed is derived without locking the coordinator
+    let beacon = beacon.0;
+    let (beacon, seed) = task::spawn_blocking(move || beacon.seed().map(|seed| (beacon, seed)))
+        .await?
+        .map_err(|e| ResponseError::CoordinatorError(CoordinatorError::Error(e.into())))?;
+
+    let mut write_lock = (*coordinator).clone().write_owned().await;
+    let record = task::spawn_blocking(move || write_lock.apply_beacon(&beacon, &seed))
+        .await?
+        .map_err(|e| ResponseError::CoordinatorError(e))?;
+
+    Ok(Json(record))
 }
 
-/// Load new tokens to update the future cohorts. The `tokens` parameter is the serialized zip folder
+/// Load new tokens to update the future cohorts. The `tokens` parameter is the serialized zip folder. This endpoint is accessible only by the operators.
 #[post("/update_cohorts", format = "json", data = "<tokens>")]
 pub async fn update_cohorts(
     coordinator: &State<Coordinator>,
-    _auth: ServerAuth,
+    _auth: OperatorAuth,
     tokens: LazyJson<Vec<u8>>,
 ) -> Result<()> {
     let reader = Cursor::new(tokens.clone());
@@ -195,26 +358,28 @@ pub async fn update_cohorts(
         }
 
         // NOTE: tokens currently in use are still in memory, no need to take care of those
-        Ok(CoordinatorState::load_tokens_from_bytes(&cohorts))
+        Ok(CoordinatorState::load_tokens_from_bytes(&cohorts)?)
     })
     .await
     .unwrap()?;
 
-    // Check that the new tokens for the current cohort match the old ones (to prevent inconsistencies during contributions in the current cohort)
     let read_lock = coordinator.read().await;
     let cohort = read_lock.state().get_current_cohort_index();
-    let old_tokens = match read_lock.state().tokens(cohort) {
-        Some(t) => t,
-        None => return Err(ResponseError::CeremonyIsOver),
-    };
+    if cohort >= read_lock.number_of_cohorts() {
+        return Err(ResponseError::CeremonyIsOver);
+    }
 
-    match new_tokens.get(cohort) {
-        Some(new_tokens) => {
-            if new_tokens != old_tokens {
-                return Err(ResponseError::InvalidNewTokens);
-            }
+    // Check that the new tokens for the current cohort match the old ones (to prevent inconsistencies during contributions in the current cohort).
+    // The current cohort of the schedule can still be updated until it starts
+    if read_lock.state().is_current_cohort_in_progress() && new_tokens.get(cohort) != read_lock.state().tokens(cohort) {
+        return Err(ResponseError::InvalidNewTokens);
+    }
+
+    // With a schedule, the cohort files can't list tokens for the cohorts out of it
+    if let Some(schedule) = read_lock.state().cohort_schedule() {
+        if new_tokens.iter().skip(schedule.len()).any(|tokens| !tokens.is_empty()) {
+            return Err(ResponseError::InvalidNewTokens);
         }
-        _ => return Err(ResponseError::InvalidNewTokens),
     }
     drop(read_lock);
 
@@ -251,6 +416,163 @@ pub async fn update_cohorts(
     Ok(())
 }
 
+/// Move a contributor to the given position of the queue, ahead of the order of the queue policy. This endpoint is accessible only by the admins.
+#[post("/queue/move", format = "json", data = "<request>")]
+pub async fn move_in_queue(
+    coordinator: &State<Coordinator>,
+    audit_log: &State<SharedAuditLog>,
+    auth: AdminAuth,
+    request: LazyJson<MoveInQueueRequest>,
+) -> Result<()> {
+    let participant = Participant::new_contributor(&request.pubkey);
+    let position = request.position;
+    let action = AdminAction::MoveInQueue {
+        participant: participant.clone(),
+        position,
+    };
+
+    rest_utils::perform_admin_action((*coordinator).clone(), audit_log, &auth.0, action, move |coordinator| {
+        coordinator.move_in_queue(&participant, position)
+    })
+    .await
+}
+
+/// Ban a participant from the ceremony, dropping it from the current round. This endpoint is accessible only by the admins.
+#[post("/admin/ban", format = "json", data = "<request>")]
+pub async fn ban_participant(
+    coordinator: &State<Coordinator>,
+    audit_log: &State<SharedAuditLog>,
+    auth: AdminAuth,
+    request: LazyJson<BanRequest>,
+) -> Result<()> {
+    let BanRequest { participant, reason } = request.clone();
+    let action = AdminAction::Ban {
+        participant: participant.clone(),
+        reason: reason.clone(),
+    };
+
+    rest_utils::perform_admin_action((*coordinator).clone(), audit_log, &auth.0, action, move |coordinator| {
+        coordinator.ban_participant(&participant, &reason)
+    })
+    .await
+}
+
+/// Lift the ban of a participant. This endpoint is accessible only by the admins.
+#[post("/admin/unban", format = "json", data = "<request>")]
+pub async fn unban_participant(
+    coordinator: &State<Coordinator>,
+    audit_log: &State<SharedAuditLog>,
+    auth: AdminAuth,
+    request: LazyJson<ParticipantRequest>,
+) -> Result<()> {
+    let participant = request.participant.clone();
+    let action = AdminAction::Unban {
+        participant: participant.clone(),
+    };
+
+    rest_utils::perform_admin_action((*coordinator).clone(), audit_log, &auth.0, action, move |coordinator| {
+        coordinator.unban_participant(&participant)
+    })
+    .await
+}
+
+/// Drop a participant from the current round. This endpoint is accessible only by the admins.
+#[post("/admin/drop", format = "json", data = "<request>")]
+pub async fn drop_participant(
+    coordinator: &State<Coordinator>,
+    audit_log: &State<SharedAuditLog>,
+    auth: AdminAuth,
+    request: LazyJson<ParticipantRequest>,
+) -> Result<()> {
+    let participant = request.participant.clone();
+    let action = AdminAction::Drop {
+        participant: participant.clone(),
+    };
+
+    rest_utils::perform_admin_action((*coordinator).clone(), audit_log, &auth.0, action, move |coordinator| {
+        coordinator.drop_participant(&participant)
+    })
+    .await
+}
+
+/// Remove a participant from the queue. This endpoint is accessible only by the admins.
+#[post("/admin/remove_from_queue", format = "json", data = "<request>")]
+pub async fn remove_from_queue(
+    coordinator: &State<Coordinator>,
+    audit_log: &State<SharedAuditLog>,
+    auth: AdminAuth,
+    request: LazyJson<ParticipantRequest>,
+) -> Result<()> {
+    let participant = request.participant.clone();
+    let action = AdminAction::RemoveFromQueue {
+        participant: participant.clone(),
+    };
+
+    rest_utils::perform_admin_action((*coordinator).clone(), audit_log, &auth.0, action, move |coordinator| {
+        coordinator.remove_from_queue(&participant)
+    })
+    .await
+}
+
+/// Lock the ceremony, preventing the start of a new round. This endpoint is accessible only by the admins.
+#[post("/admin/enable_manual_lock")]
+pub async fn enable_manual_lock(
+    coordinator: &State<Coordinator>,
+    audit_log: &State<SharedAuditLog>,
+    auth: AdminAuth,
+) -> Result<()> {
+    rest_utils::perform_admin_action(
+        (*coordinator).clone(),
+        audit_log,
+        &auth.0,
+        AdminAction::EnableManualLock,
+        |coordinator| coordinator.enable_manual_lock(),
+    )
+    .await
+}
+
+/// Unlock the ceremony. This endpoint is accessible only by the admins.
+#[post("/admin/disable_manual_lock")]
+pub async fn disable_manual_lock(
+    coordinator: &State<Coordinator>,
+    audit_log: &State<SharedAuditLog>,
+    auth: AdminAuth,
+) -> Result<()> {
+    rest_utils::perform_admin_action(
+        (*coordinator).clone(),
+        audit_log,
+        &auth.0,
+        AdminAction::DisableManualLock,
+        |coordinator| coordinator.disable_manual_lock(),
+    )
+    .await
+}
+
+/// Reset the current round, discarding its contributions. This endpoint is accessible only by the admins.
+#[post("/admin/reset_round")]
+pub async fn reset_round(
+    coordinator: &State<Coordinator>,
+    audit_log: &State<SharedAuditLog>,
+    auth: AdminAuth,
+) -> Result<()> {
+    rest_utils::perform_admin_action(
+        (*coordinator).clone(),
+        audit_log,
+        &auth.0,
+        AdminAction::ResetRound,
+        |coordinator| coordinator.reset_round(),
+    )
+    .await
+}
+
+/// Get the entries of the audit log of the admin actions. This endpoint is accessible by all the operators.
+#[get("/admin/audit_log", format = "json")]
+pub async fn get_audit_log(audit_log: &State<SharedAuditLog>, _auth: ObserverAuth) -> Result<Json<Vec<AuditEntry>>> {
+    let audit_log = (*audit_log).clone();
+
+    Ok(Json(task::spawn_blocking(move || audit_log.entries()).await??))
+}
+
 /// Get the queue status of the contributor.
 #[get("/contributor/queue_status", format = "json")]
 pub async fn get_contributor_queue_status(
@@ -294,6 +616,45 @@ pub async fn get_contributor_queue_status(
     Json(ContributorStatus::Other)
 }
 
+/// Get the status of the contribution of the contributor.
+#[get("/contributor/contribution_status", format = "json")]
+pub async fn get_contribution_status(
+    coordinator: &State<Coordinator>,
+    participant: Participant,
+) -> Json<ContributionStatus> {
+    let read_lock = coordinator.read().await;
+    let state = read_lock.state();
+
+    if state.is_verifying_contributor(&participant) {
+        return Json(ContributionStatus::Verifying);
+    }
+
+    if let Some(failed_verification) = state
+        .failed_verifications()
+        .iter()
+        .rev()
+        .find(|failed| failed.contributor() == Some(&participant))
+    {
+        return Json(ContributionStatus::Invalid(failed_verification.reason().to_string()));
+    }
+
+    if state.is_finished_contributor(&participant) {
+        // Only one contribution per round, a pending verification belongs to this contributor
+        if state.get_pending_verifications().is_empty() {
+            return Json(ContributionStatus::Valid);
+        } else {
+            return Json(ContributionStatus::Pending);
+        }
+    }
+
+    if (1..state.current_round_height()).any(|round| state.is_finished_contributor_at_round(&participant, round)) {
+        return Json(ContributionStatus::Valid);
+    }
+
+    // No contribution submitted yet
+    Json(ContributionStatus::Other)
+}
+
 /// Write [`ContributionInfo`] to disk
 #[post("/contributor/contribution_info", format = "json", data = "<request>")]
 pub async fn post_contribution_info(
@@ -411,6 +772,15 @@ pub async fn get_coordinator_state(coordinator: &State<Coordinator>, _auth: Secr
     Ok(state)
 }
 
+/// Replace the access secret with a new one, published to the configured sink. The current secret stops working as
+/// soon as the request succeeds.
+#[post("/rotate_secret")]
+pub async fn rotate_secret(access_secret: &State<SharedAccessSecret>, _auth: Secret) -> Result<()> {
+    access_secret.rotate().await?;
+
+    Ok(())
+}
+
 /// Retrieve healthcheck info. This endpoint is accessible by anyone and does not require a signed request.
 #[get("/healthcheck", format = "json")]
 pub async fn get_healthcheck() -> Result<String> {
@@ -420,3 +790,50 @@ pub async fn get_healthcheck() -> Result<String> {
 
     Ok(content)
 }
+
+/// Retrieve the set of circuits the ceremony is run on. This endpoint is accessible by anyone and does not require a signed request.
+#[get("/circuits", format = "json")]
+pub async fn get_circuits(coordinator: &State<Coordinator>) -> Json<CircuitSet> {
+    let read_lock = coordinator.read().await;
+
+    Json(read_lock.environment().parameters().circuits().clone())
+}
+
+/// Download an object from the [`LocalStore`]. The url must have been signed by the store.
+#[get("/storage/<key..>?<expires>&<signature>")]
+pub async fn get_local_object(
+    local_store: &State<Arc<LocalStore>>,
+    key: PathBuf,
+    expires: i64,
+    signature: String,
+) -> Result<NamedFile> {
+    let key = key.to_string_lossy();
+    if !local_store.verify("GET", &key, expires, &signature) {
+        return Err(ResponseError::InvalidSignature);
+    }
+
+    let path = local_store.object_path(&key).await?;
+
+    NamedFile::open(path)
+        .await
+        .map_err(|e| ResponseError::IoError(e.to_string()))
+}
+
+/// Upload an object to the [`LocalStore`]. The url must have been signed by the store.
+#[put("/storage/<key..>?<expires>&<signature>", data = "<object>")]
+pub async fn put_local_object(
+    local_store: &State<Arc<LocalStore>>,
+    key: PathBuf,
+    expires: i64,
+    signature: String,
+    object: Data<'_>,
+) -> Result<()> {
+    let key = key.to_string_lossy();
+    if !local_store.verify("PUT", &key, expires, &signature) {
+        return Err(ResponseError::InvalidSignature);
+    }
+
+    local_store.write_object(&key, object).await?;
+
+    Ok(())
+}
@@ -1,15 +1,20 @@
 // Utility types for the rest API
 
 use crate::{
+    audit_log::{AdminAction, AuditEntry, AuditLog, AuditLogError},
     authentication::{Production, Signature},
+    config::Config,
     coordinator_state::TOKEN_BLACKLIST,
+    object_store::{ObjectStore, ObjectStoreError},
     objects::Task,
-    s3::{S3Ctx, S3Error},
-    storage::{ContributionLocator, ContributionSignatureLocator},
+    operators::{OperatorKey, OperatorRole, SharedOperators},
+    secret::{SecretError, SharedAccessSecret},
+    storage::{self, AuditIssue, ContributionLocator, ContributionSignatureLocator, Storage},
+    tokens::{SignedToken, TokenError},
     CoordinatorError, Participant,
 };
 
-pub use crate::{coordinator_state::TOKENS_PATH, s3::TOKENS_ZIP_FILE};
+pub use crate::{coordinator_state::TOKENS_PATH, object_store::TOKENS_ZIP_FILE};
 use blake2::Digest;
 use rocket::{
     catch,
@@ -24,14 +29,24 @@ use rocket::{
 };
 
 use anyhow::anyhow;
+use rand::{rngs::OsRng, RngCore};
 
 use sha2::Sha256;
-use subtle::ConstantTimeEq;
 
 use lazy_static::lazy_static;
-use std::{borrow::Cow, convert::TryFrom, io::Cursor, net::IpAddr, ops::Deref, sync::Arc, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    convert::TryFrom,
+    io::Cursor,
+    net::IpAddr,
+    ops::Deref,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use thiserror::Error;
-use tracing::warn;
+use time::OffsetDateTime;
+use tracing::{info, warn};
 
 #[cfg(debug_assertions)]
 pub const UPDATE_TIME: Duration = Duration::from_secs(5);
@@ -39,22 +54,31 @@ pub const UPDATE_TIME: Duration = Duration::from_secs(5);
 pub const UPDATE_TIME: Duration = Duration::from_secs(60);
 
 pub const UNKNOWN: &str = "Unknown";
+/// The tokens of the cohort files, base58 encoded.
 pub const TOKEN_REGEX: &str = r"^[A-HJ-NP-Za-km-z1-9]{115}$";
+/// The signed tokens, longer with their signature.
+pub const SIGNED_TOKEN_REGEX: &str = r"^[A-HJ-NP-Za-km-z1-9]{116,400}$";
 
 // Headers
 pub const BODY_DIGEST_HEADER: &str = "Digest";
 pub const PUBKEY_HEADER: &str = "ATS-Pubkey";
 pub const SIGNATURE_HEADER: &str = "ATS-Signature";
+pub const TIMESTAMP_HEADER: &str = "ATS-Timestamp";
+pub const NONCE_HEADER: &str = "ATS-Nonce";
 pub const CONTENT_LENGTH_HEADER: &str = "Content-Length";
 pub const ACCESS_SECRET_HEADER: &str = "Access-Secret";
 
+/// Length in bytes of the nonce of the signed requests, hex encoded.
+const NONCE_LENGTH: usize = 16;
+
 lazy_static! {
-    pub(crate) static ref HEALTH_PATH: String = match std::env::var("HEALTH_PATH") {
-        Ok(path) => path,
-        Err(_) => "./health.json".to_string(),
+    pub(crate) static ref HEALTH_PATH: String = Config::current().auth.health_path;
+    /// The nonces of the signed requests received lately.
+    static ref REPLAY_CACHE: Mutex<ReplayCache> = {
+        let auth = Config::current().auth;
+        let max_age = i64::try_from(auth.signature_max_age_seconds).unwrap_or(i64::MAX);
+        Mutex::new(ReplayCache::new(max_age, auth.nonce_cache_capacity))
     };
-    pub(crate) static ref ACCESS_SECRET: String =
-        std::env::var("ACCESS_SECRET").expect("Missing required env ACCESS_SECRET");
 }
 
 pub(crate) type Coordinator = Arc<RwLock<crate::Coordinator>>;
@@ -62,8 +86,14 @@ pub(crate) type Coordinator = Arc<RwLock<crate::Coordinator>>;
 /// Server errors. Also includes errors generated by the managed [Coordinator](`crate::Coordinator`).
 #[derive(Error, Debug)]
 pub enum ResponseError {
+    #[error("Error with the audit log: {0}")]
+    AuditLogError(#[from] AuditLogError),
     #[error("Ceremony is over, no more contributions are allowed")]
     CeremonyIsOver,
+    #[error("Cohort {0} has reached its maximum number of contributors")]
+    CohortIsFull(usize),
+    #[error("Cohort {0} hasn't started yet")]
+    CohortNotStarted(usize),
     #[error("Coordinator failed: {0}")]
     CoordinatorError(CoordinatorError),
     #[error("Contribution info is not valid: {0}")]
@@ -84,26 +114,34 @@ pub enum ResponseError {
     MismatchingChecksum(String, String),
     #[error("The required {0} header was missing from the incoming request")]
     MissingRequiredHeader(&'static str),
+    #[error("The {0} is not managed by the server")]
+    MissingState(&'static str),
     #[error("Couldn't verify signature because of missing signing key")]
     MissingSigningKey,
+    #[error("Error with the object store: {0}")]
+    ObjectStoreError(#[from] ObjectStoreError),
     #[error("Couldn't parse string to int: {0}")]
     ParseError(#[from] std::num::ParseIntError),
     #[error("Thread panicked: {0}")]
     RuntimeError(#[from] task::JoinError),
-    #[error("Error with S3: {0}")]
-    S3Error(#[from] S3Error),
+    #[error("Error with the access secret: {0}")]
+    SecretError(#[from] SecretError),
     #[error("Error with Serde: {0}")]
     SerdeError(String),
     #[error("Error while terminating the ceremony: {0}")]
     ShutdownError(String),
     #[error("The provided token is currently being used in the ceremony")]
     TokenAlreadyInUse,
+    #[error("The cohort files are not valid: {0}")]
+    TokenError(#[from] TokenError),
     #[error("The provided token has already been used in the ceremony")]
     BlacklistedToken,
     #[error("The participant {0} is not allowed to access the endpoint {1} because of: {2}")]
     UnauthorizedParticipant(Participant, String, String),
     #[error("Could not find contributor with public key {0}")]
     UnknownContributor(String),
+    #[error("Signed request rejected: {0}")]
+    ReplayedRequest(String),
     #[error("Could not find the provided Task {0} in coordinator state")]
     UnknownTask(Task),
     #[error("Digest of request's body is not base64 encoded: {0}")]
@@ -118,6 +156,14 @@ impl<'r> Responder<'r, 'static> for ResponseError {
         let response_code = match self {
             ResponseError::BlacklistedToken => Status::Unauthorized,
             ResponseError::CeremonyIsOver => Status::Unauthorized,
+            ResponseError::CohortIsFull(_) => Status::Unauthorized,
+            ResponseError::CohortNotStarted(_) => Status::Unauthorized,
+            ResponseError::CoordinatorError(CoordinatorError::CeremonyNotLocked) => Status::Conflict,
+            ResponseError::CoordinatorError(CoordinatorError::ParticipantMissing) => Status::NotFound,
+            ResponseError::CoordinatorError(CoordinatorError::RoundHeightMismatch) => Status::Conflict,
+            ResponseError::CoordinatorError(CoordinatorError::StorageUploadIncomplete) => Status::BadRequest,
+            ResponseError::CoordinatorError(CoordinatorError::StorageUploadOffsetMismatch { .. }) => Status::Conflict,
+            ResponseError::CoordinatorError(CoordinatorError::StorageUploadTooLarge) => Status::PayloadTooLarge,
             ResponseError::InvalidHeader(_) => Status::BadRequest,
             ResponseError::InvalidSecret => Status::Unauthorized,
             ResponseError::InvalidSignature => Status::BadRequest,
@@ -126,8 +172,11 @@ impl<'r> Responder<'r, 'static> for ResponseError {
             ResponseError::MissingRequiredHeader(h) if h == CONTENT_LENGTH_HEADER => Status::LengthRequired,
             ResponseError::MissingRequiredHeader(_) => Status::BadRequest,
             ResponseError::MissingSigningKey => Status::BadRequest,
+            ResponseError::ReplayedRequest(_) => Status::Unauthorized,
+            ResponseError::ObjectStoreError(ObjectStoreError::MissingObject(_)) => Status::NotFound,
             ResponseError::SerdeError(_) => Status::UnprocessableEntity,
             ResponseError::TokenAlreadyInUse => Status::Unauthorized,
+            ResponseError::TokenError(_) => Status::UnprocessableEntity,
             ResponseError::UnauthorizedParticipant(_, _, _) => Status::Unauthorized,
             ResponseError::WrongDigestEncoding(_) => Status::BadRequest,
             _ => Status::InternalServerError,
@@ -146,8 +195,11 @@ pub type Result<T> = std::result::Result<T, ResponseError>;
 // Custom catchers for Request/Data Guards. These remap custom error codes to the standard ones and call the ResponseError Responder to produce the response. The default catcher is mantained for non-custom errors
 
 #[catch(452)]
-pub fn invalid_signature() -> ResponseError {
-    ResponseError::InvalidSignature
+pub fn invalid_signature(req: &Request) -> ResponseError {
+    match req.local_cache(|| Option::<ReplayCause>::None) {
+        Some(ReplayCause(cause)) => ResponseError::ReplayedRequest(cause.to_owned()),
+        None => ResponseError::InvalidSignature,
+    }
 }
 
 #[catch(453)]
@@ -189,6 +241,7 @@ pub fn io_error(req: &Request) -> ResponseError {
 }
 
 /// Content info
+#[derive(Clone)]
 pub struct RequestContent<'a> {
     len: usize,
     digest: Cow<'a, str>,
@@ -231,9 +284,18 @@ impl<'a> RequestContent<'a> {
 }
 
 /// The headers involved in the signature of the request.
+///
+/// The signature binds the request to its method, its path, the time it was sent at and a random nonce, so that it
+/// can't be replayed: the coordinator rejects the stale timestamps and the nonces it has already received.
 #[derive(Default)]
 pub struct SignatureHeaders<'r> {
     pub pubkey: &'r str,
+    pub method: &'r str,
+    /// The path of the request, with its query if any.
+    pub path: Cow<'r, str>,
+    /// The unix timestamp the request was signed at.
+    pub timestamp: i64,
+    pub nonce: Cow<'r, str>,
     pub content: Option<RequestContent<'r>>,
     pub signature: Option<Cow<'r, str>>,
 }
@@ -241,15 +303,34 @@ pub struct SignatureHeaders<'r> {
 impl<'r> SignatureHeaders<'r> {
     /// Produces the message on which to compute the signature
     pub fn to_string(&self) -> Cow<'_, str> {
+        let message = format!(
+            "{} {} {} {} {}",
+            self.method, self.path, self.timestamp, self.nonce, self.pubkey
+        );
+
         match &self.content {
-            Some(content) => format!("{}{}{}", self.pubkey, content.len, content.digest).into(),
-            None => self.pubkey.into(),
+            Some(content) => format!("{} {} {}", message, content.len, content.digest).into(),
+            None => message.into(),
         }
     }
 
-    pub fn new(pubkey: &'r str, content: Option<RequestContent<'r>>, signature: Option<Cow<'r, str>>) -> Self {
+    /// Creates the headers of a new request, signed now with a random nonce.
+    pub fn new(
+        pubkey: &'r str,
+        method: &'r str,
+        path: Cow<'r, str>,
+        content: Option<RequestContent<'r>>,
+        signature: Option<Cow<'r, str>>,
+    ) -> Self {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+
         Self {
             pubkey,
+            method,
+            path,
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            nonce: hex::encode(nonce).into(),
             content,
             signature,
         }
@@ -276,6 +357,13 @@ impl<'r> TryFrom<&'r Request<'_>> for SignatureHeaders<'r> {
         let sig = headers
             .get_one(SIGNATURE_HEADER)
             .ok_or(ResponseError::InvalidHeader(SIGNATURE_HEADER))?;
+        let timestamp = headers
+            .get_one(TIMESTAMP_HEADER)
+            .and_then(|timestamp| timestamp.parse().ok())
+            .ok_or(ResponseError::InvalidHeader(TIMESTAMP_HEADER))?;
+        let nonce = headers
+            .get_one(NONCE_HEADER)
+            .ok_or(ResponseError::InvalidHeader(NONCE_HEADER))?;
 
         // If post request, also get the hash of body from header (if any and if base64 encoded)
         if request.method() == rocket::http::Method::Post {
@@ -289,7 +377,147 @@ impl<'r> TryFrom<&'r Request<'_>> for SignatureHeaders<'r> {
             }
         }
 
-        Ok(SignatureHeaders::new(pubkey, body, Some(sig.into())))
+        Ok(SignatureHeaders {
+            pubkey,
+            method: request.method().as_str(),
+            path: request.uri().to_string().into(),
+            timestamp,
+            nonce: nonce.into(),
+            content: body,
+            signature: Some(sig.into()),
+        })
+    }
+}
+
+/// The reason a signed request was rejected as replayed, for the error catcher.
+struct ReplayCause(String);
+
+/// The number of seconds a request can be signed ahead of the time of the coordinator, to allow for clock drift.
+const MAX_FUTURE_SKEW: i64 = 5;
+
+///
+/// The nonces of the signed requests received in the last `max_age` seconds, which are the only ones accepted.
+///
+/// When full, the key holding the most nonces is the offending one: its own requests are refused, or its oldest nonce
+/// is evicted to make room for the request of another key. The requests of a key signed at or before its last evicted
+/// nonce are rejected too, so that the eviction never allows a replay, without affecting the other keys.
+///
+struct ReplayCache {
+    max_age: i64,
+    capacity: usize,
+    /// The timestamp, the key and the nonce of the requests received, in the order they were signed.
+    received: BTreeSet<(i64, String, String)>,
+    /// The timestamp and the nonce of the requests received from each key.
+    keys: HashMap<String, BTreeSet<(i64, String)>>,
+    /// The timestamp of the last nonce evicted from each key.
+    evicted_until: HashMap<String, i64>,
+    /// The timestamp of the last nonce evicted from each key, in order.
+    evictions: BTreeSet<(i64, String)>,
+}
+
+impl ReplayCache {
+    fn new(max_age: i64, capacity: usize) -> Self {
+        Self {
+            max_age,
+            capacity,
+            received: BTreeSet::new(),
+            keys: HashMap::new(),
+            evicted_until: HashMap::new(),
+            evictions: BTreeSet::new(),
+        }
+    }
+
+    /// Records the nonce of a request signed by the given key at the given timestamp, unless the request is stale or
+    /// the nonce was already received.
+    fn check(&mut self, pubkey: &str, nonce: &str, timestamp: i64, now: i64) -> std::result::Result<(), String> {
+        if now - timestamp > self.max_age {
+            return Err(format!(
+                "timestamp {} is more than {} seconds older than the time of the coordinator, {}",
+                timestamp, self.max_age, now
+            ));
+        }
+        if timestamp - now > MAX_FUTURE_SKEW {
+            return Err(format!(
+                "timestamp {} is more than {} seconds ahead of the time of the coordinator, {}",
+                timestamp, MAX_FUTURE_SKEW, now
+            ));
+        }
+        self.prune(now);
+
+        if matches!(self.evicted_until.get(pubkey), Some(evicted_until) if timestamp <= *evicted_until) {
+            return Err(String::from(
+                "too many requests were received from the key since the request was signed",
+            ));
+        }
+        let entry = (timestamp, nonce.to_owned());
+        if matches!(self.keys.get(pubkey), Some(nonces) if nonces.contains(&entry)) {
+            return Err(String::from("the nonce was already used"));
+        }
+
+        if self.received.len() >= self.capacity {
+            // The key holding the most nonces gives up its oldest one, unless it is the key of the request
+            let offending = self
+                .keys
+                .iter()
+                .max_by_key(|(key, nonces)| (nonces.len(), key.as_str() == pubkey))
+                .map(|(key, _)| key.clone());
+            match offending {
+                Some(key) if key != pubkey => self.evict(key),
+                _ => return Err(String::from("too many requests were received from the key")),
+            }
+        }
+
+        self.received.insert((timestamp, pubkey.to_owned(), nonce.to_owned()));
+        self.keys.entry(pubkey.to_owned()).or_default().insert(entry);
+
+        Ok(())
+    }
+
+    /// Forgets the nonces and evictions which are stale now, since their requests can't be replayed anymore.
+    fn prune(&mut self, now: i64) {
+        while let Some((timestamp, key, nonce)) = self.received.iter().next().cloned() {
+            if now - timestamp <= self.max_age {
+                break;
+            }
+            self.received.remove(&(timestamp, key.clone(), nonce.clone()));
+            if let Some(nonces) = self.keys.get_mut(&key) {
+                nonces.remove(&(timestamp, nonce));
+                if nonces.is_empty() {
+                    self.keys.remove(&key);
+                }
+            }
+        }
+
+        while let Some((timestamp, key)) = self.evictions.iter().next().cloned() {
+            if now - timestamp <= self.max_age {
+                break;
+            }
+            self.evictions.remove(&(timestamp, key.clone()));
+            if self.evicted_until.get(&key) == Some(&timestamp) {
+                self.evicted_until.remove(&key);
+            }
+        }
+    }
+
+    /// Evicts the oldest nonce of the given key.
+    fn evict(&mut self, key: String) {
+        let nonces = match self.keys.get_mut(&key) {
+            Some(nonces) => nonces,
+            None => return,
+        };
+        if let Some((timestamp, nonce)) = nonces.iter().next().cloned() {
+            nonces.remove(&(timestamp, nonce.clone()));
+            if nonces.is_empty() {
+                self.keys.remove(&key);
+            }
+            self.received.remove(&(timestamp, key.clone(), nonce));
+
+            // The nonces of a key are evicted in the order they were signed
+            if let Some(previous) = self.evicted_until.insert(key.clone(), timestamp) {
+                self.evictions.remove(&(previous, key.clone()));
+            }
+            self.evictions.insert((timestamp, key));
+        }
     }
 }
 
@@ -299,14 +527,34 @@ trait VerifySignature<'r> {
 }
 
 impl<'r> VerifySignature<'r> for Request<'_> {
-    /// Check signature of request and return the pubkey of the participant
+    /// Check signature of request, and that it is not replayed, and return the pubkey of the participant
     fn verify_signature(&'r self) -> Result<&str> {
         let headers = SignatureHeaders::try_from(self)?;
 
-        match headers.try_verify_signature()? {
-            true => Ok(headers.pubkey),
-            false => Err(ResponseError::InvalidSignature),
+        if !headers.try_verify_signature()? {
+            return Err(ResponseError::InvalidSignature);
         }
+
+        // Only the requests with a valid signature are recorded, so that the cache can't be filled with forged ones
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let replay = REPLAY_CACHE.lock().expect("Lock should not be poisoned").check(
+            headers.pubkey,
+            &headers.nonce,
+            headers.timestamp,
+            now,
+        );
+        if let Err(cause) = replay {
+            warn!(
+                "Rejected a request of {} to {}: {}",
+                headers.pubkey, headers.path, cause
+            );
+            // Cache error data for the error catcher
+            self.local_cache(|| Some(ReplayCause(cause.clone())));
+
+            return Err(ResponseError::ReplayedRequest(cause));
+        }
+
+        Ok(headers.pubkey)
     }
 }
 
@@ -427,7 +675,7 @@ impl<'r> FromRequest<'r> for CurrentContributor {
 }
 
 /// Implements the secret token verification on the incoming server request via [`FromRequest`]. Used to restrict access to endpoints only when headers contain the valid secret.
-/// Can be used as an alternative to [`ServerAuth`] when the body of the request carries no data (and thus doesn't need a signature on that)
+/// Can be used as an alternative to [`OperatorAuth`] when the body of the request carries no data (and thus doesn't need a signature on that)
 pub struct Secret;
 
 #[rocket::async_trait]
@@ -435,48 +683,187 @@ impl<'r> FromRequest<'r> for Secret {
     type Error = ResponseError;
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let access_secret = match request.guard::<&State<SharedAccessSecret>>().await.succeeded() {
+            Some(access_secret) => access_secret,
+            None => {
+                return Outcome::Failure((
+                    Status::InternalServerError,
+                    ResponseError::MissingState("access secret"),
+                ))
+            }
+        };
+
         match request.headers().get_one(ACCESS_SECRET_HEADER) {
-            Some(secret) if secret.as_bytes().ct_eq(&*ACCESS_SECRET.as_bytes()).into() => Outcome::Success(Self),
+            Some(secret) if access_secret.verify(secret).await => Outcome::Success(Self),
             _ => Outcome::Failure((Status::new(401), ResponseError::InvalidSecret)),
         }
     }
 }
 
-/// Implements the signature verification on the incoming server request via [`FromRequest`].
-pub struct ServerAuth;
+///
+/// Checks that the incoming request is signed by the coordinator itself or by an operator with at least the given
+/// role, and returns the key it is signed with. The coordinator is an admin.
+///
+async fn authorize_operator(request: &Request<'_>, role: OperatorRole) -> Outcome<OperatorKey, ResponseError> {
+    let pubkey = match request.verify_signature() {
+        Ok(h) => h,
+        Err(e) => return Outcome::Failure((Status::new(452), e)),
+    };
 
-#[rocket::async_trait]
-impl<'r> FromRequest<'r> for ServerAuth {
-    type Error = ResponseError;
+    let coordinator = request
+        .guard::<&State<Coordinator>>()
+        .await
+        .succeeded()
+        .expect("Managed state should always be retrievable");
+    let verifier = Participant::new_verifier(pubkey);
+    if verifier == coordinator.read().await.environment().coordinator_verifiers()[0] {
+        return Outcome::Success(OperatorKey {
+            name: String::from("coordinator"),
+            pubkey: pubkey.to_owned(),
+            role: OperatorRole::Admin,
+        });
+    }
 
-    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        let pubkey = match request.verify_signature() {
-            Ok(h) => h,
-            Err(e) => return Outcome::Failure((Status::new(452), e)),
-        };
+    let operators = match request.guard::<&State<SharedOperators>>().await.succeeded() {
+        Some(operators) => operators,
+        None => {
+            return Outcome::Failure((
+                Status::InternalServerError,
+                ResponseError::MissingState("operator keys"),
+            ))
+        }
+    };
+    let error_msg = match operators.get(pubkey) {
+        Some(operator) if operator.role >= role => {
+            info!(
+                "Operator {} ({}) authorized to access {}",
+                operator.name,
+                operator.role,
+                request.uri()
+            );
+            return Outcome::Success(operator);
+        }
+        Some(operator) => format!(
+            "The {} role is required, {} has the {} role",
+            role, operator.name, operator.role
+        ),
+        None => String::from("Not an operator"),
+    };
 
-        // Check that the signature comes from the coordinator by matching the default verifier key
-        let coordinator = request
-            .guard::<&State<Coordinator>>()
-            .await
-            .succeeded()
-            .expect("Managed state should always be retrievable");
-        let verifier = Participant::new_verifier(pubkey);
+    // Cache error data for the error catcher
+    request.local_cache(|| verifier.clone());
+    request.local_cache(|| (request.uri().to_string(), error_msg.clone()));
+
+    Outcome::Failure((
+        Status::new(453),
+        ResponseError::UnauthorizedParticipant(verifier, request.uri().to_string(), error_msg),
+    ))
+}
+
+/// Implements the request guard of the endpoints reserved to the operators with the given role, see [authorize_operator].
+macro_rules! operator_guard {
+    ($(#[$doc:meta])* $name:ident, $role:expr) => {
+        $(#[$doc])*
+        pub struct $name(pub OperatorKey);
+
+        #[rocket::async_trait]
+        impl<'r> FromRequest<'r> for $name {
+            type Error = ResponseError;
+
+            async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+                authorize_operator(request, $role).await.map(Self)
+            }
+        }
+    };
+}
 
-        if verifier != coordinator.read().await.environment().coordinator_verifiers()[0] {
+operator_guard!(
+    /// Implements the signature verification of the requests of the observers, operators and admins via [`FromRequest`].
+    ObserverAuth,
+    OperatorRole::Observer
+);
+operator_guard!(
+    /// Implements the signature verification of the requests of the operators and admins via [`FromRequest`].
+    OperatorAuth,
+    OperatorRole::Operator
+);
+operator_guard!(
+    /// Implements the signature verification of the requests of the admins via [`FromRequest`].
+    AdminAuth,
+    OperatorRole::Admin
+);
+
+/// Reads the body of a request, checking it against the Digest and Content-Length headers.
+async fn read_checked_body<'r>(
+    req: &'r Request<'_>,
+    data: rocket::data::Data<'r>,
+) -> Result<Vec<u8>, (Status, ResponseError)> {
+    // Check that digest of body is the expected one
+    let headers = req.headers();
+    let expected_digest = match headers.get_one(BODY_DIGEST_HEADER) {
+        Some(h) => h,
+        None => {
             // Cache error data for the error catcher
-            let error_msg = String::from("Not the coordinator's verifier");
-            request.local_cache(|| verifier.clone());
-            request.local_cache(|| (request.uri().to_string(), error_msg.clone()));
+            req.local_cache(|| BODY_DIGEST_HEADER.to_string());
 
-            return Outcome::Failure((
-                Status::new(453),
-                ResponseError::UnauthorizedParticipant(verifier, request.uri().to_string(), error_msg),
+            return Err((
+                Status::new(454),
+                ResponseError::MissingRequiredHeader(BODY_DIGEST_HEADER),
             ));
         }
+    };
+
+    let content_length = match headers.get_one(CONTENT_LENGTH_HEADER) {
+        Some(h) => h,
+        None => {
+            // Cache error data for the error catcher
+            req.local_cache(|| CONTENT_LENGTH_HEADER.to_string());
+
+            return Err((
+                Status::new(454),
+                ResponseError::MissingRequiredHeader(CONTENT_LENGTH_HEADER),
+            ));
+        }
+    };
+
+    let expected_content = match RequestContent::try_from_header(content_length, expected_digest) {
+        Ok(c) => c,
+        Err(e) => {
+            // Cache error data for the error catcher
+            let header = match e {
+                ResponseError::InvalidHeader(h) => h,
+                _ => UNKNOWN,
+            };
+            req.local_cache(|| header);
+
+            return Err((Status::new(457), e));
+        }
+    };
+
+    let body = match data.open(expected_content.len.into()).into_bytes().await {
+        Ok(bytes) => bytes.into_inner(),
+        Err(e) => {
+            // Cache error data for the error catcher
+            req.local_cache(|| e.to_string());
 
-        Outcome::Success(Self)
+            return Err((Status::new(512), ResponseError::IoError(e.to_string())));
+        }
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(&body);
+    let digest = base64::encode(hasher.finalize());
+    if digest != expected_content.digest {
+        // Cache error data for the error catcher
+        req.local_cache(|| (expected_digest.to_owned(), expected_content.digest.to_string()));
+
+        return Err((
+            Status::new(456),
+            ResponseError::MismatchingChecksum(expected_digest.to_owned(), expected_content.digest.to_string()),
+        ));
     }
+
+    Ok(body)
 }
 
 /// Type to handle lazy deserialization of json encoded inputs.
@@ -501,71 +888,11 @@ impl<'r, T: DeserializeOwned> FromData<'r> for LazyJson<T> {
     type Error = ResponseError;
 
     async fn from_data(req: &'r Request<'_>, data: rocket::data::Data<'r>) -> rocket::data::Outcome<'r, Self> {
-        // Check that digest of body is the expected one
-        let headers = req.headers();
-        let expected_digest = match headers.get_one(BODY_DIGEST_HEADER) {
-            Some(h) => h,
-            None => {
-                // Cache error data for the error catcher
-                req.local_cache(|| BODY_DIGEST_HEADER.to_string());
-
-                return rocket::data::Outcome::Failure((
-                    Status::new(454),
-                    ResponseError::MissingRequiredHeader(BODY_DIGEST_HEADER),
-                ));
-            }
+        let body = match read_checked_body(req, data).await {
+            Ok(body) => body,
+            Err(e) => return rocket::data::Outcome::Failure(e),
         };
 
-        let content_length = match headers.get_one(CONTENT_LENGTH_HEADER) {
-            Some(h) => h,
-            None => {
-                // Cache error data for the error catcher
-                req.local_cache(|| CONTENT_LENGTH_HEADER.to_string());
-
-                return rocket::data::Outcome::Failure((
-                    Status::new(454),
-                    ResponseError::MissingRequiredHeader(CONTENT_LENGTH_HEADER),
-                ));
-            }
-        };
-
-        let expected_content = match RequestContent::try_from_header(content_length, expected_digest) {
-            Ok(c) => c,
-            Err(e) => {
-                // Cache error data for the error catcher
-                let header = match e {
-                    ResponseError::InvalidHeader(h) => h,
-                    _ => UNKNOWN,
-                };
-                req.local_cache(|| header);
-
-                return rocket::data::Outcome::Failure((Status::new(457), e));
-            }
-        };
-
-        let body = match data.open(expected_content.len.into()).into_bytes().await {
-            Ok(bytes) => bytes.into_inner(),
-            Err(e) => {
-                // Cache error data for the error catcher
-                req.local_cache(|| e.to_string());
-
-                return rocket::data::Outcome::Failure((Status::new(512), ResponseError::IoError(e.to_string())));
-            }
-        };
-
-        let mut hasher = Sha256::new();
-        hasher.update(&body);
-        let digest = base64::encode(hasher.finalize());
-        if digest != expected_content.digest {
-            // Cache error data for the error catcher
-            req.local_cache(|| (expected_digest.to_owned(), expected_content.digest.to_string()));
-
-            return rocket::data::Outcome::Failure((
-                Status::new(456),
-                ResponseError::MismatchingChecksum(expected_digest.to_owned(), expected_content.digest.to_string()),
-            ));
-        }
-
         // Deserialize data and pass it to the request handler
         match serde_json::from_slice::<T>(&body) {
             Ok(obj) => rocket::data::Outcome::Success(LazyJson(obj)),
@@ -578,6 +905,44 @@ impl<'r, T: DeserializeOwned> FromData<'r> for LazyJson<T> {
     }
 }
 
+/// Type to handle raw binary inputs, checked against their digest.
+pub struct CheckedBytes(pub Vec<u8>);
+
+impl Deref for CheckedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for CheckedBytes {
+    type Error = ResponseError;
+
+    async fn from_data(req: &'r Request<'_>, data: rocket::data::Data<'r>) -> rocket::data::Outcome<'r, Self> {
+        match read_checked_body(req, data).await {
+            Ok(body) => rocket::data::Outcome::Success(CheckedBytes(body)),
+            Err(e) => rocket::data::Outcome::Failure(e),
+        }
+    }
+}
+
+/// The status of the contribution of a contributor.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum ContributionStatus {
+    /// The contribution is waiting to be verified.
+    Pending,
+    /// The contribution is being verified.
+    Verifying,
+    /// The contribution has been verified and accepted.
+    Valid,
+    /// The contribution failed verification for the given reason.
+    Invalid(String),
+    /// The contributor has not submitted any contribution.
+    Other,
+}
+
 /// The status of the contributor related to the current round.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum ContributorStatus {
@@ -610,67 +975,164 @@ impl PostChunkRequest {
     }
 }
 
+/// Request to move a contributor to a position of the queue.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct MoveInQueueRequest {
+    /// The public key of the contributor.
+    pub pubkey: String,
+    /// The position in the queue, starting from 0.
+    pub position: usize,
+}
+
+/// Request of an admin action on a participant.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ParticipantRequest {
+    pub participant: Participant,
+}
+
+/// Request to ban a participant.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct BanRequest {
+    pub participant: Participant,
+    /// The reason of the ban, kept in the state of the coordinator.
+    pub reason: String,
+}
+
+/// Returns the key the usage of the token is tracked by: the identifier of a signed token, whose claims and signature
+/// can be encoded in several ways, or the token itself, which must be listed as is in the cohort files.
+fn token_usage_key(token: &str) -> String {
+    match SignedToken::decode(token) {
+        Ok(SignedToken {
+            claims,
+            signature: Some(_),
+        }) => claims.id,
+        _ => token.to_owned(),
+    }
+}
+
 /// Checks the validity of the token for the ceremony.
-/// Returns the current cohort index
-pub(crate) async fn token_check(coordinator: Coordinator, token: &str) -> Result<u64> {
-    // Check that token is not in use nor blacklisted (only if env is set)
+/// Returns the current cohort index and the key the usage of the token is tracked by
+pub(crate) async fn token_check(coordinator: Coordinator, token: &str) -> Result<(u64, String)> {
     let read_lock = coordinator.read().await;
+    let key = token_usage_key(token);
 
     // Check that token is not in use nor blacklisted (only if env is set)
     if *TOKEN_BLACKLIST {
-        if read_lock.state().is_token_in_use(token) {
+        if read_lock.state().is_token_in_use(&key) {
             return Err(ResponseError::TokenAlreadyInUse);
         }
 
-        if read_lock.state().is_token_blacklisted(token) {
+        if read_lock.state().is_token_blacklisted(&key) {
             return Err(ResponseError::BlacklistedToken);
         }
     }
 
     // Check that the token is correct for the current cohort number
     let cohort = read_lock.state().get_current_cohort_index();
-    let tokens = match read_lock.state().tokens(cohort) {
-        Some(t) => t,
-        None => return Err(ResponseError::CeremonyIsOver),
-    };
+    if cohort >= read_lock.number_of_cohorts() {
+        return Err(ResponseError::CeremonyIsOver);
+    }
+
+    // No token is valid between two cohorts of the schedule, nor once the cohort is full
+    if !read_lock.state().is_current_cohort_in_progress() {
+        return Err(ResponseError::CohortNotStarted(cohort + 1));
+    }
+    if read_lock.state().is_cohort_full(cohort) {
+        return Err(ResponseError::CohortIsFull(cohort + 1));
+    }
+
+    // The signed tokens are valid on their own, in the window of their cohort
+    if let Some(issuer) = read_lock.environment().token_issuer() {
+        if let Ok(signed_token) = SignedToken::decode(token) {
+            if signed_token.signature.is_some() {
+                let now = OffsetDateTime::now_utc().unix_timestamp() as u64;
+
+                if signed_token.verify(&issuer.pubkey)
+                    && signed_token.claims.index == (cohort + 1) as u64
+                    && signed_token.claims.is_in_window(now)
+                {
+                    return Ok(((cohort + 1) as u64, key));
+                }
 
-    if !tokens.contains(token) {
-        return Err(ResponseError::InvalidToken(cohort + 1));
+                return Err(ResponseError::InvalidToken(cohort + 1));
+            }
+        }
     }
 
-    Ok((cohort + 1) as u64)
+    // The other tokens must be listed in the file of the cohort
+    match read_lock.state().tokens(cohort) {
+        Some(tokens) if tokens.contains(token) => Ok(((cohort + 1) as u64, key)),
+        _ => Err(ResponseError::InvalidToken(cohort + 1)),
+    }
 }
 
-/// Performs the verification of the pending contributions
+/// Verifies the pending contributions with the default verifier of the coordinator and returns the updated
+/// contributions summary. An invalid contribution resets the round and bans the participant who produced it.
 ///
-/// # Cancel safety
-///
-/// https://docs.rs/tokio/latest/tokio/macro.select.html#cancellation-safety
-///
-/// Because of the use of [`tokio::sync::rwlock::RwLock::write_owned`], which is not cancel safe, and a spawned blocking
-/// task, which cannot be cancelled, this function is not cancel safe.
-pub async fn perform_verify_chunks(coordinator: Coordinator, s3_ctx: &S3Ctx) -> Result<()> {
-    // Get all the pending verifications, loop on each one of them and perform verification
+/// The contributions are checked without holding the lock of the coordinator, which is taken again only to record the
+/// results. Meanwhile, their contributors are reported as verifying.
+pub(crate) async fn verify_pending_contributions(coordinator: Coordinator) -> Result<Vec<u8>> {
     // Technically, since we don't chunk contributions and we only have one contribution per round, we will always get
     // one pending verification at max.
+    let mut write_lock = coordinator.clone().write_owned().await;
+    let (environment, round_height, tasks) = task::spawn_blocking(move || -> Result<_> {
+        let round_height = write_lock
+            .current_round_height()
+            .map_err(|e| ResponseError::CoordinatorError(e))?;
+        let mut tasks = Vec::new();
+        for (task, _) in write_lock.get_pending_verifications().to_owned() {
+            // Get the participant who produced the contribution
+            let finished_contributor = write_lock
+                .state()
+                .current_round_finished_contributors()
+                .map_err(|e| ResponseError::CoordinatorError(CoordinatorError::Error(e)))?
+                .first()
+                .cloned()
+                .ok_or(ResponseError::CoordinatorError(
+                    CoordinatorError::RoundContributorMissing,
+                ))?;
+
+            // Skip the contributions which are already being verified
+            if write_lock.start_verifying_contributor(&finished_contributor) {
+                tasks.push((task, finished_contributor));
+            }
+        }
+
+        Ok((write_lock.environment().clone(), round_height, tasks))
+    })
+    .await??;
+
+    let contributors: Vec<Participant> = tasks.iter().map(|(_, contributor)| contributor.clone()).collect();
+    let checks = task::spawn_blocking(move || {
+        tasks
+            .into_iter()
+            .map(|(task, contributor)| {
+                let checked_response = crate::Coordinator::check_contribution(&environment, round_height, &task);
+                (task, contributor, checked_response)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await;
+
     let mut write_lock = coordinator.write_owned().await;
+    task::spawn_blocking(move || {
+        for contributor in &contributors {
+            write_lock.stop_verifying_contributor(contributor);
+        }
 
-    // NOTE: we are going to rely on the single default verifier built in the coordinator itself,
-    //  no external verifiers
-    let contributions_info = task::spawn_blocking(move || -> Result<Vec<u8>> {
-        for (task, _) in write_lock.get_pending_verifications().to_owned() {
-            if let Err(e) = write_lock.default_verify(&task) {
-                warn!("Error while verifying a contribution: {}. Restarting the round...", e);
-                // FIXME: the verify_masp function may panic but the program doesn't shut down because we are executing it on a separate thread. It would be better though to make that function return a Result instead of panicking. Revert of round should be moved inside default_verify
+        for (task, finished_contributor, checked_response) in checks? {
+            // The round may have been reset while the contribution was checked
+            if !write_lock.get_pending_verifications().contains_key(&task) {
+                continue;
+            }
 
-                // Get the participant who produced the contribution
-                let finished_contributor = write_lock
-                    .state()
-                    .current_round_finished_contributors()
-                    .unwrap()
-                    .first()
-                    .unwrap()
-                    .clone();
+            if let Err(e) = write_lock.default_verify_checked(&task, checked_response) {
+                warn!("Error while verifying a contribution: {}. Restarting the round...", e);
+                // TODO: revert of round should be moved inside default_verify
+                let reason = match e {
+                    CoordinatorError::VerificationFailed(reason) => reason.to_string(),
+                    e => format!("Verification error: {:?}", e),
+                };
 
                 // Reset the round to prevent a coordinator stall (the corrupted contribution is not automatically dropped)
                 write_lock
@@ -679,7 +1141,7 @@ pub async fn perform_verify_chunks(coordinator: Coordinator, s3_ctx: &S3Ctx) ->
 
                 // Ban the participant who produced the invalid contribution. Must be banned after the reset beacuse one can't ban a finished contributor
                 write_lock
-                    .ban_participant(&finished_contributor)
+                    .ban_participant(&finished_contributor, &reason)
                     .map_err(|e| ResponseError::CoordinatorError(e))?;
             }
         }
@@ -689,15 +1151,77 @@ pub async fn perform_verify_chunks(coordinator: Coordinator, s3_ctx: &S3Ctx) ->
             .get_contributions_summary()
             .map_err(|e| ResponseError::CoordinatorError(e))
     })
-    .await??;
+    .await?
+}
 
-    // Upload json file to S3
-    s3_ctx
+/// Publishes the contributions summary on the object store.
+pub(crate) async fn upload_contributions_info(
+    object_store: &dyn ObjectStore,
+    contributions_info: Vec<u8>,
+) -> Result<()> {
+    object_store
         .upload_contributions_info(contributions_info)
         .await
         .map_err(|e| ResponseError::CoordinatorError(CoordinatorError::Error(anyhow!(e.to_string()))))
 }
 
+/// Performs the verification of the pending contributions
+///
+/// # Cancel safety
+///
+/// https://docs.rs/tokio/latest/tokio/macro.select.html#cancellation-safety
+///
+/// Because of the use of [`tokio::sync::rwlock::RwLock::write_owned`], which is not cancel safe, and a spawned blocking
+/// task, which cannot be cancelled, this function is not cancel safe.
+pub async fn perform_verify_chunks(coordinator: Coordinator, object_store: &dyn ObjectStore) -> Result<()> {
+    // NOTE: we are going to rely on the single default verifier built in the coordinator itself,
+    //  no external verifiers
+    let contributions_info = verify_pending_contributions(coordinator).await?;
+
+    // Publish json file on the object store
+    upload_contributions_info(object_store, contributions_info).await
+}
+
+/// Audits the contribution files in the storage of the [Coordinator](`crate::Coordinator`). Only the round states
+/// are read under a read lock, the files are hashed once it's released.
+pub async fn perform_storage_audit(coordinator: Coordinator) -> Result<Vec<AuditIssue>> {
+    let read_lock = coordinator.read_owned().await;
+    let (round_states, environment, signature) = task::spawn_blocking(move || {
+        read_lock
+            .round_states()
+            .map(|round_states| (round_states, read_lock.environment().clone(), read_lock.signature()))
+    })
+    .await?
+    .map_err(|e| ResponseError::CoordinatorError(e))?;
+
+    Ok(task::spawn_blocking(move || storage::audit_files(&environment, signature.as_ref(), round_states)).await?)
+}
+
+///
+/// Performs the given admin action on the [Coordinator](`crate::Coordinator`), then records it in the audit log with
+/// its outcome. The action is applied even if it can't be recorded, in which case the error of the audit log is
+/// returned.
+///
+pub(crate) async fn perform_admin_action<F>(
+    coordinator: Coordinator,
+    audit_log: &AuditLog,
+    operator: &OperatorKey,
+    action: AdminAction,
+    f: F,
+) -> Result<()>
+where
+    F: FnOnce(&mut crate::Coordinator) -> std::result::Result<(), CoordinatorError> + Send + 'static,
+{
+    info!("Operator {} requested to {}", operator.name, action);
+    let mut write_lock = coordinator.write_owned().await;
+    let result = task::spawn_blocking(move || f(&mut write_lock)).await?;
+
+    let error = result.as_ref().err().map(ToString::to_string);
+    audit_log.record(&AuditEntry::new(operator, action, error))?;
+
+    result.map_err(|e| ResponseError::CoordinatorError(e))
+}
+
 /// Performs the update of the [Coordinator](`crate::Coordinator`)
 ///
 /// # Cancel safety
@@ -713,3 +1237,49 @@ pub async fn perform_coordinator_update(coordinator: Coordinator) -> Result<()>
         .await?
         .map_err(|e| ResponseError::CoordinatorError(e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_cache() {
+        let now = 1_000_000;
+        let mut cache = ReplayCache::new(300, 4);
+
+        // Replayed, stale and future-dated requests
+        cache.check("honest", "0", now, now).unwrap();
+        assert!(cache.check("honest", "0", now, now).unwrap_err().contains("nonce"));
+        assert!(cache.check("honest", "1", now - 301, now).is_err());
+        assert!(cache.check("honest", "1", now + MAX_FUTURE_SKEW + 1, now).is_err());
+
+        // A flooding key only gets the room left
+        cache.check("flooder", "0", now - 10, now).unwrap();
+        cache.check("flooder", "1", now - 5, now).unwrap();
+        cache.check("flooder", "2", now + MAX_FUTURE_SKEW, now).unwrap();
+        assert!(cache.check("flooder", "3", now, now).unwrap_err().contains("too many"));
+
+        // Then gives up its oldest nonces to the other keys, which can't be replayed
+        cache.check("other", "0", now, now).unwrap();
+        assert_eq!(cache.keys["flooder"].len(), 2);
+        assert!(cache
+            .check("flooder", "0", now - 10, now)
+            .unwrap_err()
+            .contains("too many"));
+        cache.check("another", "0", now, now).unwrap();
+        assert!(cache.check("flooder", "1", now - 5, now).is_err());
+
+        // The evictions only affect the flooding key
+        assert_eq!(cache.evicted_until.len(), 1);
+        assert_eq!(cache.evicted_until["flooder"], now - 5);
+        assert_eq!(cache.received.len(), 4);
+
+        // The stale nonces and evictions are forgotten, in the order the requests were signed
+        let later = now + 301;
+        cache.check("honest", "1", later, later).unwrap();
+        assert_eq!(cache.keys["flooder"].len(), 1);
+        assert_eq!(cache.received.len(), 2);
+        assert!(cache.evicted_until.is_empty() && cache.evictions.is_empty());
+        cache.check("flooder", "0", later - 10, later).unwrap();
+    }
+}
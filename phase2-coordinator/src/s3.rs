@@ -1,7 +1,29 @@
+use crate::{
+    config::Config,
+    object_store::{
+        contribution_key,
+        contribution_signature_key,
+        tokens_key,
+        ObjectStore,
+        ObjectStoreError,
+        CONTRIBUTIONS_INFO_FILE,
+        PRESIGNED_URL_EXPIRY,
+    },
+};
 use lazy_static::lazy_static;
 use rocket::tokio::{io::AsyncReadExt, time};
-use rusoto_core::{region::Region, request::TlsError};
-use rusoto_credential::{AwsCredentials, ChainProvider, CredentialsError, ProvideAwsCredentials};
+use rusoto_core::{region::Region, request::TlsError, HttpClient};
+use rusoto_credential::{
+    AutoRefreshingProvider,
+    AwsCredentials,
+    ChainProvider,
+    ContainerProvider,
+    CredentialsError,
+    EnvironmentProvider,
+    InstanceMetadataProvider,
+    ProfileProvider,
+    ProvideAwsCredentials,
+};
 use rusoto_s3::{
     util::{PreSignedRequest, PreSignedRequestOption},
     DeleteObjectRequest,
@@ -16,24 +38,38 @@ use std::str::FromStr;
 use thiserror::Error;
 use tracing::warn;
 
-pub const TOKENS_ZIP_FILE: &str = "tokens.zip";
 const BACKOFF_SLEEP_TIME_MILLISECS: u32 = 100;
 const MAX_REQUEST_RETRY: u32 = 8; // This gives max 50 seconds before giving up and returning an error
 
 lazy_static! {
-    static ref BUCKET: String = std::env::var("AWS_S3_BUCKET").unwrap_or("bucket".to_string());
-    pub static ref REGION: Region = {
-        match std::env::var("AWS_REGION") {
-            Ok(region) => Region::from_str(&region).expect("Region must be a valid region"),
-            Err(_) => Region::EuWest1,
-        }
-    };
+    static ref BUCKET: String = Config::current().s3.bucket;
+    pub static ref REGION: Region = Config::current().region().expect("The configuration has been validated");
+    /// Endpoint of the S3-compatible service, the transfer accelerated endpoint of AWS by default
+    static ref ENDPOINT: String = Config::current().s3.endpoint;
+    /// Whether to send the requests to the endpoint itself rather than to the bucket subdomain of it (virtual-hosted
+    /// style), as required by most self-hosted services like MinIO
+    static ref PATH_STYLE: bool = Config::current().s3.path_style;
+    static ref CREDENTIALS_SOURCE: CredentialsSource = Config::current()
+        .credentials_source()
+        .expect("The configuration has been validated");
     static ref S3_REGION: Region = Region::Custom {
         name: REGION.name().to_string(),
-        endpoint: format!("{}.s3-accelerate.amazonaws.com", *BUCKET),
+        endpoint: endpoint(&ENDPOINT, &BUCKET, *PATH_STYLE),
     };
 }
 
+/// Builds the endpoint of the bucket, prepending the bucket name to the host when not using path-style addressing.
+fn endpoint(endpoint: &str, bucket: &str, path_style: bool) -> String {
+    if path_style {
+        return endpoint.to_string();
+    }
+
+    match endpoint.split_once("://") {
+        Some((scheme, host)) => format!("{}://{}.{}", scheme, bucket, host),
+        None => format!("{}.{}", bucket, endpoint),
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum S3Error {
     #[error("Error while creating the http client: {0}")]
@@ -50,27 +86,100 @@ pub enum S3Error {
     EmptyContributionSignature,
     #[error("Error in IO: {0}")]
     IOError(#[from] std::io::Error),
+    #[error("Unknown credentials source: {0}")]
+    UnknownCredentialsSource(String),
     #[error("Upload of file to S3 failed: {0}")]
     UploadError(String),
 }
 
 type Result<T> = std::result::Result<T, S3Error>;
 
+/// Where the credentials to access S3 are taken from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CredentialsSource {
+    /// Environment, then profile file, then container and instance metadata
+    Chain,
+    /// The AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY env variables
+    Environment,
+    /// The AWS credentials file
+    Profile,
+    /// The ECS container metadata
+    Container,
+    /// The EC2 instance metadata
+    InstanceMetadata,
+}
+
+impl FromStr for CredentialsSource {
+    type Err = S3Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "chain" => Ok(Self::Chain),
+            "environment" => Ok(Self::Environment),
+            "profile" => Ok(Self::Profile),
+            "container" => Ok(Self::Container),
+            "instance-metadata" => Ok(Self::InstanceMetadata),
+            _ => Err(S3Error::UnknownCredentialsSource(s.to_string())),
+        }
+    }
+}
+
+/// Provider of the credentials from the configured [`CredentialsSource`].
+// NOTE: the context lives for the whole ceremony, credentials must be refreshed when they expire
+enum CredentialsProvider {
+    Chain(AutoRefreshingProvider<ChainProvider>),
+    Environment(EnvironmentProvider),
+    Profile(AutoRefreshingProvider<ProfileProvider>),
+    Container(AutoRefreshingProvider<ContainerProvider>),
+    InstanceMetadata(AutoRefreshingProvider<InstanceMetadataProvider>),
+}
+
+impl CredentialsProvider {
+    fn new(source: CredentialsSource) -> Result<Self> {
+        Ok(match source {
+            CredentialsSource::Chain => Self::Chain(AutoRefreshingProvider::new(ChainProvider::new())?),
+            CredentialsSource::Environment => Self::Environment(EnvironmentProvider::default()),
+            CredentialsSource::Profile => Self::Profile(AutoRefreshingProvider::new(ProfileProvider::new()?)?),
+            CredentialsSource::Container => Self::Container(AutoRefreshingProvider::new(ContainerProvider::new())?),
+            CredentialsSource::InstanceMetadata => {
+                Self::InstanceMetadata(AutoRefreshingProvider::new(InstanceMetadataProvider::new())?)
+            }
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl ProvideAwsCredentials for CredentialsProvider {
+    async fn credentials(&self) -> std::result::Result<AwsCredentials, CredentialsError> {
+        match self {
+            Self::Chain(provider) => provider.credentials().await,
+            Self::Environment(provider) => provider.credentials().await,
+            Self::Profile(provider) => provider.credentials().await,
+            Self::Container(provider) => provider.credentials().await,
+            Self::InstanceMetadata(provider) => provider.credentials().await,
+        }
+    }
+}
+
 pub struct S3Ctx {
     client: S3Client,
     bucket: &'static String,
     region: &'static Region,
     options: PreSignedRequestOption,
-    credentials: AwsCredentials,
+    credentials: CredentialsProvider,
 }
 
 impl S3Ctx {
-    pub async fn new() -> Result<Self> {
-        let provider = ChainProvider::new();
-        let credentials = provider.credentials().await?;
-        let client = S3Client::new(S3_REGION.clone());
+    /// Creates the context from the AWS_S3_ENDPOINT, AWS_S3_PATH_STYLE and AWS_CREDENTIALS_SOURCE env variables.
+    pub fn new() -> Result<Self> {
+        let credentials = CredentialsProvider::new(*CREDENTIALS_SOURCE)?;
+        let client = S3Client::new_with(
+            HttpClient::new()?,
+            CredentialsProvider::new(*CREDENTIALS_SOURCE)?,
+            S3_REGION.clone(),
+        );
         let options = PreSignedRequestOption {
-            expires_in: std::time::Duration::from_secs(600),
+            expires_in: *PRESIGNED_URL_EXPIRY,
         };
 
         Ok(Self {
@@ -82,12 +191,54 @@ impl S3Ctx {
         })
     }
 
-    /// Upload contributors.json file to S3 for the frontend
-    pub(crate) async fn upload_contributions_info(&self, contributions_info: Vec<u8>) -> Result<()> {
+    /// Download an object from S3 as bytes.
+    async fn get_object(&self, get_request: GetObjectRequest) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+
+        let mut attempt = 0u32;
+
+        let stream = loop {
+            match self.client.get_object(get_request.clone()).await {
+                Ok(i) => break i.body.ok_or(S3Error::EmptyContribution)?,
+                Err(e) => match e {
+                    rusoto_core::RusotoError::Unknown(ref inner) => {
+                        match inner.status.as_u16() {
+                            429 | 500 | 502 | 503 | 504 => {
+                                // If enough attempts return
+                                if attempt >= MAX_REQUEST_RETRY {
+                                    return Err(S3Error::DownloadError(e.to_string()));
+                                }
+
+                                // Exponential backoff, https://docs.aws.amazon.com/elastictranscoder/latest/developerguide/error-handling.html#api-retries
+                                warn!("Retrying s3 get object request because of: {}", e);
+                                let sleep_time = 2u32.pow(attempt) * BACKOFF_SLEEP_TIME_MILLISECS;
+                                attempt += 1;
+                                time::sleep(std::time::Duration::from_millis(sleep_time.into())).await;
+                            }
+                            _ => return Err(S3Error::DownloadError(e.to_string())),
+                        }
+                    }
+                    _ => return Err(S3Error::DownloadError(e.to_string())),
+                },
+            }
+        };
+
+        stream.into_async_read().read_to_end(&mut buffer).await?;
+
+        Ok(buffer)
+    }
+}
+
+#[rocket::async_trait]
+impl ObjectStore for S3Ctx {
+    async fn upload_contributions_info(
+        &self,
+        contributions_info: Vec<u8>,
+    ) -> std::result::Result<(), ObjectStoreError> {
         // First delete the old file to allow triggering the lambda
         let delete_object_request = DeleteObjectRequest {
             bucket: self.bucket.clone(),
-            key: "contributors.json".to_string(),
+            key: CONTRIBUTIONS_INFO_FILE.to_string(),
             ..Default::default()
         };
 
@@ -100,7 +251,7 @@ impl S3Ctx {
                         429 | 500 | 502 | 503 | 504 => {
                             // If enough attempts return
                             if attempt >= MAX_REQUEST_RETRY {
-                                return Err(S3Error::DeleteError(e.to_string()));
+                                return Err(S3Error::DeleteError(e.to_string()).into());
                             }
 
                             // Exponential backoff, https://docs.aws.amazon.com/elastictranscoder/latest/developerguide/error-handling.html#api-retries
@@ -109,10 +260,10 @@ impl S3Ctx {
                             attempt += 1;
                             time::sleep(std::time::Duration::from_millis(sleep_time.into())).await;
                         }
-                        _ => return Err(S3Error::DeleteError(e.to_string())),
+                        _ => return Err(S3Error::DeleteError(e.to_string()).into()),
                     }
                 }
-                _ => return Err(S3Error::DeleteError(e.to_string())),
+                _ => return Err(S3Error::DeleteError(e.to_string()).into()),
             }
         }
 
@@ -121,7 +272,7 @@ impl S3Ctx {
         // Upload the updated file
         let mut put_object_request = PutObjectRequest {
             bucket: self.bucket.clone(),
-            key: "contributors.json".to_string(),
+            key: CONTRIBUTIONS_INFO_FILE.to_string(),
             body: Some(StreamingBody::from(contributions_info.clone())),
             ..Default::default()
         };
@@ -133,13 +284,13 @@ impl S3Ctx {
                         429 | 500 | 502 | 503 | 504 => {
                             // If enough attempts return
                             if attempt >= MAX_REQUEST_RETRY {
-                                return Err(S3Error::UploadError(e.to_string()));
+                                return Err(S3Error::UploadError(e.to_string()).into());
                             }
 
                             // Exponential backoff, https://docs.aws.amazon.com/elastictranscoder/latest/developerguide/error-handling.html#api-retries
                             put_object_request = PutObjectRequest {
                                 bucket: self.bucket.clone(),
-                                key: "contributors.json".to_string(),
+                                key: CONTRIBUTIONS_INFO_FILE.to_string(),
                                 body: Some(StreamingBody::from(contributions_info.clone())),
                                 ..Default::default()
                             };
@@ -149,18 +300,17 @@ impl S3Ctx {
                             attempt += 1;
                             time::sleep(std::time::Duration::from_millis(sleep_time.into())).await;
                         }
-                        _ => return Err(S3Error::UploadError(e.to_string())),
+                        _ => return Err(S3Error::UploadError(e.to_string()).into()),
                     }
                 }
-                _ => return Err(S3Error::UploadError(e.to_string())),
+                _ => return Err(S3Error::UploadError(e.to_string()).into()),
             }
         }
 
         Ok(())
     }
 
-    /// Get the url of a challenge on S3.
-    pub(crate) async fn get_challenge_url(&self, key: String) -> Option<String> {
+    async fn get_challenge_url(&self, key: String) -> std::result::Result<Option<String>, ObjectStoreError> {
         let head = HeadObjectRequest {
             bucket: self.bucket.clone(),
             key: key.clone(),
@@ -174,14 +324,15 @@ impl S3Ctx {
                 ..Default::default()
             };
 
-            Some(get.get_presigned_url(self.region, &self.credentials, &self.options))
+            let credentials = self.credentials.credentials().await.map_err(S3Error::from)?;
+
+            Ok(Some(get.get_presigned_url(self.region, &credentials, &self.options)))
         } else {
-            None
+            Ok(None)
         }
     }
 
-    /// Upload a challenge to S3. Returns the presigned url to get it.
-    pub(crate) async fn upload_challenge(&self, key: String, challenge: Vec<u8>) -> Result<String> {
+    async fn upload_challenge(&self, key: String, challenge: Vec<u8>) -> std::result::Result<String, ObjectStoreError> {
         let mut put_object_request = PutObjectRequest {
             bucket: self.bucket.clone(),
             key: key.clone(),
@@ -198,7 +349,7 @@ impl S3Ctx {
                         429 | 500 | 502 | 503 | 504 => {
                             // If enough attempts return
                             if attempt >= MAX_REQUEST_RETRY {
-                                return Err(S3Error::UploadError(e.to_string()));
+                                return Err(S3Error::UploadError(e.to_string()).into());
                             }
 
                             // Exponential backoff, https://docs.aws.amazon.com/elastictranscoder/latest/developerguide/error-handling.html#api-retries
@@ -214,10 +365,10 @@ impl S3Ctx {
                             attempt += 1;
                             time::sleep(std::time::Duration::from_millis(sleep_time.into())).await;
                         }
-                        _ => return Err(S3Error::UploadError(e.to_string())),
+                        _ => return Err(S3Error::UploadError(e.to_string()).into()),
                     }
                 }
-                _ => return Err(S3Error::UploadError(e.to_string())),
+                _ => return Err(S3Error::UploadError(e.to_string()).into()),
             }
         }
 
@@ -227,11 +378,16 @@ impl S3Ctx {
             ..Default::default()
         };
 
-        Ok(get.get_presigned_url(self.region, &self.credentials, &self.options))
+        let credentials = self.credentials.credentials().await.map_err(S3Error::from)?;
+
+        Ok(get.get_presigned_url(self.region, &credentials, &self.options))
     }
 
-    /// Get the urls of a contribution and its signature.
-    pub(crate) fn get_contribution_urls(&self, contrib_key: String, contrib_sig_key: String) -> (String, String) {
+    async fn get_contribution_urls(
+        &self,
+        contrib_key: String,
+        contrib_sig_key: String,
+    ) -> std::result::Result<(String, String), ObjectStoreError> {
         let get_contrib = PutObjectRequest {
             bucket: self.bucket.clone(),
             key: contrib_key,
@@ -243,80 +399,71 @@ impl S3Ctx {
             ..Default::default()
         };
 
-        // NOTE: urls live for 5 minutes so we cannot cache them for reuse because there's a high chance they expired, we
+        // NOTE: urls live for PRESIGNED_URL_EXPIRY (10 minutes by default) so we cannot cache them for reuse because there's a high chance they expired, we
         //  need to regenerate them every time
-        let contrib_url = get_contrib.get_presigned_url(self.region, &self.credentials, &self.options);
-        let contrib_sig_url = get_sig.get_presigned_url(self.region, &self.credentials, &self.options);
+        let credentials = self.credentials.credentials().await.map_err(S3Error::from)?;
+        let contrib_url = get_contrib.get_presigned_url(self.region, &credentials, &self.options);
+        let contrib_sig_url = get_sig.get_presigned_url(self.region, &credentials, &self.options);
 
-        (contrib_url, contrib_sig_url)
+        Ok((contrib_url, contrib_sig_url))
     }
 
-    /// Download an object from S3 as bytes.
-    async fn get_object(&self, get_request: GetObjectRequest) -> Result<Vec<u8>> {
-        let mut buffer = Vec::new();
-
-        let mut attempt = 0u32;
-
-        let stream = loop {
-            match self.client.get_object(get_request.clone()).await {
-                Ok(i) => break i.body.ok_or(S3Error::EmptyContribution)?,
-                Err(e) => match e {
-                    rusoto_core::RusotoError::Unknown(ref inner) => {
-                        match inner.status.as_u16() {
-                            429 | 500 | 502 | 503 | 504 => {
-                                // If enough attempts return
-                                if attempt >= MAX_REQUEST_RETRY {
-                                    return Err(S3Error::DownloadError(e.to_string()));
-                                }
-
-                                // Exponential backoff, https://docs.aws.amazon.com/elastictranscoder/latest/developerguide/error-handling.html#api-retries
-                                warn!("Retrying s3 get object request because of: {}", e);
-                                let sleep_time = 2u32.pow(attempt) * BACKOFF_SLEEP_TIME_MILLISECS;
-                                attempt += 1;
-                                time::sleep(std::time::Duration::from_millis(sleep_time.into())).await;
-                            }
-                            _ => return Err(S3Error::DownloadError(e.to_string())),
-                        }
-                    }
-                    _ => return Err(S3Error::DownloadError(e.to_string())),
-                },
-            }
-        };
-
-        stream.into_async_read().read_to_end(&mut buffer).await?;
-
-        Ok(buffer)
-    }
-
-    /// Retrieve a contribution and its signature from S3.
-    pub(crate) async fn get_contribution(&self, round_height: u64) -> Result<(Vec<u8>, Vec<u8>)> {
+    async fn get_contribution(&self, round_height: u64) -> std::result::Result<(Vec<u8>, Vec<u8>), ObjectStoreError> {
         let get_contrib = GetObjectRequest {
             bucket: self.bucket.clone(),
-            key: format!("round_{}/chunk_0/contribution_1.unverified", round_height),
+            key: contribution_key(round_height),
             ..Default::default()
         };
         let get_sig = GetObjectRequest {
             bucket: self.bucket.clone(),
-            key: format!("round_{}/chunk_0/contribution_1.unverified.signature", round_height),
+            key: contribution_signature_key(round_height),
             ..Default::default()
         };
 
-        rocket::tokio::try_join!(self.get_object(get_contrib), self.get_object(get_sig))
+        Ok(rocket::tokio::try_join!(
+            self.get_object(get_contrib),
+            self.get_object(get_sig)
+        )?)
     }
 
-    /// Retrieve the compressed token folder.
-    pub async fn get_tokens(&self) -> Result<Vec<u8>> {
-        let key = match std::env::var("AWS_S3_PROD") {
-            Ok(t) if t == "true" => format!("production/{}", TOKENS_ZIP_FILE),
-            _ => format!("master/{}", TOKENS_ZIP_FILE),
-        };
-
+    async fn get_tokens(&self) -> std::result::Result<Vec<u8>, ObjectStoreError> {
         let get_tokens = GetObjectRequest {
             bucket: self.bucket.clone(),
-            key,
+            key: tokens_key(),
             ..Default::default()
         };
 
-        self.get_object(get_tokens).await
+        Ok(self.get_object(get_tokens).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(
+            endpoint("s3-accelerate.amazonaws.com", "bucket", false),
+            "bucket.s3-accelerate.amazonaws.com"
+        );
+        assert_eq!(
+            endpoint("https://s3.amazonaws.com", "bucket", false),
+            "https://bucket.s3.amazonaws.com"
+        );
+        assert_eq!(
+            endpoint("http://localhost:9000", "bucket", true),
+            "http://localhost:9000"
+        );
+    }
+
+    #[test]
+    fn test_credentials_source() {
+        assert_eq!("chain".parse::<CredentialsSource>().unwrap(), CredentialsSource::Chain);
+        assert_eq!(
+            "instance-metadata".parse::<CredentialsSource>().unwrap(),
+            CredentialsSource::InstanceMetadata
+        );
+        assert!("static".parse::<CredentialsSource>().is_err());
     }
 }
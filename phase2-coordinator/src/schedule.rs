@@ -0,0 +1,166 @@
+//! The schedule of the cohorts of the ceremony. By default the cohorts follow each other from the start of the
+//! ceremony and all last `ceremony.cohort_time_seconds`. A schedule file instead lists the window of each cohort,
+//! which can have its own length and be separated from the previous one by a gap, during which no token is valid:
+//!
+//! ```toml
+//! [[cohorts]]
+//! label = "invited"
+//! start = 1668848400
+//! end = 1668934800
+//! max_contributors = 50
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ScheduleError {
+    #[error("The schedule has no cohort")]
+    Empty,
+    #[error("Cohort {0} doesn't end after its start")]
+    InvalidWindow(usize),
+    #[error("Error while reading {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Cohort {0} starts before the end of the previous one")]
+    Overlap(usize),
+    #[error("Error while parsing {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+}
+
+type Result<T> = std::result::Result<T, ScheduleError>;
+
+/// A cohort of the schedule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Cohort {
+    /// The unix timestamp of the start of the cohort.
+    pub start: i64,
+    /// The unix timestamp of the end of the cohort, excluded.
+    pub end: i64,
+    /// The maximum number of contributors joining the ceremony during the cohort, unlimited if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_contributors: Option<usize>,
+    /// The name of the cohort, for the logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl Cohort {
+    /// Returns true if the given unix timestamp falls in the window of the cohort.
+    pub fn is_in_window(&self, timestamp: i64) -> bool {
+        self.start <= timestamp && timestamp < self.end
+    }
+}
+
+/// The cohorts of a schedule file, in chronological order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CohortSchedule {
+    pub cohorts: Vec<Cohort>,
+}
+
+impl CohortSchedule {
+    ///
+    /// Loads the schedule of the given TOML file.
+    ///
+    /// On failure to read or parse the file, or on an invalid schedule, this function returns a `ScheduleError`.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| ScheduleError::Io(path.to_path_buf(), e))?;
+        let schedule: Self = toml::from_str(&content).map_err(|e| ScheduleError::Parse(path.to_path_buf(), e))?;
+        schedule.validate()?;
+
+        Ok(schedule)
+    }
+
+    /// Checks that the schedule has cohorts, which end after their start and don't overlap.
+    pub fn validate(&self) -> Result<()> {
+        if self.cohorts.is_empty() {
+            return Err(ScheduleError::Empty);
+        }
+
+        for (i, cohort) in self.cohorts.iter().enumerate() {
+            // The cohorts are numbered from 1 in the errors, as in the tokens
+            if cohort.end <= cohort.start {
+                return Err(ScheduleError::InvalidWindow(i + 1));
+            }
+            if i > 0 && cohort.start < self.cohorts[i - 1].end {
+                return Err(ScheduleError::Overlap(i + 1));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of cohorts.
+    pub fn len(&self) -> usize {
+        self.cohorts.len()
+    }
+
+    /// Returns true if the schedule has no cohort.
+    pub fn is_empty(&self) -> bool {
+        self.cohorts.is_empty()
+    }
+
+    /// Returns the cohort of the given index, starting from 0.
+    pub fn get(&self, index: usize) -> Option<&Cohort> {
+        self.cohorts.get(index)
+    }
+
+    ///
+    /// Returns the index, starting from 0, of the cohort in progress at the given unix timestamp or, before it or
+    /// between two cohorts, of the next one. After the last cohort, returns the number of cohorts.
+    ///
+    pub fn index_at(&self, timestamp: i64) -> usize {
+        self.cohorts
+            .iter()
+            .position(|cohort| timestamp < cohort.end)
+            .unwrap_or(self.cohorts.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cohort_schedule() {
+        let schedule: CohortSchedule = toml::from_str(
+            r#"
+            [[cohorts]]
+            label = "invited"
+            start = 100
+            end = 200
+            max_contributors = 2
+
+            [[cohorts]]
+            start = 300
+            end = 350
+            "#,
+        )
+        .unwrap();
+        schedule.validate().unwrap();
+        assert_eq!(schedule.get(0).unwrap().max_contributors, Some(2));
+        assert_eq!(schedule.get(1).unwrap().label, None);
+
+        // The index of the next cohort before it and during the gaps
+        assert_eq!(schedule.index_at(0), 0);
+        assert_eq!(schedule.index_at(199), 0);
+        assert_eq!(schedule.index_at(200), 1);
+        assert!(!schedule.get(1).unwrap().is_in_window(250));
+        assert!(schedule.get(1).unwrap().is_in_window(300));
+        assert_eq!(schedule.index_at(350), 2);
+
+        let mut invalid = schedule.clone();
+        invalid.cohorts[1].start = 150;
+        assert!(matches!(invalid.validate(), Err(ScheduleError::Overlap(2))));
+        invalid.cohorts[1].start = 350;
+        assert!(matches!(invalid.validate(), Err(ScheduleError::InvalidWindow(2))));
+        invalid.cohorts.clear();
+        assert!(matches!(invalid.validate(), Err(ScheduleError::Empty)));
+
+        assert!(toml::from_str::<CohortSchedule>("[[cohorts]]\nstart = 1\nend = 2\nmax = 3").is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! The access secret of the endpoints reserved to the operators of the ceremony, and the sinks it is published to.
+
+use crate::config::Config;
+use rand::{rngs::OsRng, RngCore};
+use rocket::tokio::{fs, io::AsyncWriteExt, sync::RwLock};
+use rusoto_core::Region;
+use rusoto_ssm::{PutParameterRequest, Ssm, SsmClient};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use tracing::info;
+
+/// Length in bytes of the generated secrets, hex encoded.
+const SECRET_LENGTH: usize = 32;
+
+#[derive(Error, Debug)]
+pub enum SecretError {
+    #[error("Error in IO: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Publication of the secret to the Parameter Store failed: {0}")]
+    SsmError(String),
+    #[error("Unknown secret sink kind: {0}")]
+    UnknownKind(String),
+}
+
+type Result<T> = std::result::Result<T, SecretError>;
+
+/// The destinations the access secret can be published to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretSinkKind {
+    /// Amazon SSM Parameter Store, see [`SsmSink`]
+    Ssm,
+    /// A file only readable by its owner, see [`FileSink`]
+    File,
+    /// The standard output of the coordinator, see [`StdoutSink`]
+    Stdout,
+}
+
+impl FromStr for SecretSinkKind {
+    type Err = SecretError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ssm" => Ok(Self::Ssm),
+            "file" => Ok(Self::File),
+            "stdout" => Ok(Self::Stdout),
+            _ => Err(SecretError::UnknownKind(s.to_string())),
+        }
+    }
+}
+
+/// A destination the operators read the access secret from.
+#[rocket::async_trait]
+pub trait SecretSink: Send + Sync {
+    /// Publish the given secret, replacing the previous one.
+    async fn publish(&self, secret: &str) -> Result<()>;
+}
+
+/// Publishes the secret as a `SecureString` parameter of the Amazon SSM Parameter Store.
+pub struct SsmSink {
+    client: SsmClient,
+    name: String,
+}
+
+impl SsmSink {
+    /// Creates a sink to the parameter of the given name.
+    pub fn new(region: Region, name: String) -> Self {
+        Self {
+            client: SsmClient::new(region),
+            name,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl SecretSink for SsmSink {
+    async fn publish(&self, secret: &str) -> Result<()> {
+        let put_request = PutParameterRequest {
+            description: Some("Trusted setup endpoints secret".to_string()),
+            key_id: None,
+            name: self.name.clone(),
+            overwrite: Some(true),
+            policies: None,
+            tags: None,
+            tier: None,
+            type_: Some("SecureString".to_string()),
+            value: secret.to_string(),
+            allowed_pattern: None,
+            data_type: None,
+        };
+        self.client
+            .put_parameter(put_request)
+            .await
+            .map_err(|e| SecretError::SsmError(e.to_string()))?;
+        info!("Published the access secret to the parameter {}", self.name);
+
+        Ok(())
+    }
+}
+
+/// Writes the secret to a file with the 0600 permissions. The file is replaced atomically, so that readers never
+/// see a partial secret.
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl SecretSink for FileSink {
+    async fn publish(&self, secret: &str) -> Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        // The permissions are only set when the file is created
+        fs::remove_file(&tmp_path).await.ok();
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp_path).await?;
+        file.write_all(secret.as_bytes()).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp_path, &self.path).await?;
+        info!("Published the access secret to {}", self.path.display());
+
+        Ok(())
+    }
+}
+
+/// Prints the secret on the standard output, out of the logs.
+pub struct StdoutSink;
+
+#[rocket::async_trait]
+impl SecretSink for StdoutSink {
+    async fn publish(&self, secret: &str) -> Result<()> {
+        println!("ACCESS_SECRET={}", secret);
+
+        Ok(())
+    }
+}
+
+///
+/// Returns the access secret of the given configuration, if any, or a new one published to the configured sink.
+/// The rotated secrets are published to the same sink.
+///
+pub async fn from_config(config: &Config) -> Result<SharedAccessSecret> {
+    let sink: Box<dyn SecretSink> = match config.secret_sink_kind().expect("The configuration has been validated") {
+        SecretSinkKind::Ssm => Box::new(SsmSink::new(
+            config.region().expect("The configuration has been validated"),
+            config.secret_ssm_name(),
+        )),
+        SecretSinkKind::File => Box::new(FileSink::new(&config.secret.file_path)),
+        SecretSinkKind::Stdout => Box::new(StdoutSink),
+    };
+
+    let access_secret = match &config.secret.value {
+        Some(secret) => AccessSecret::new(secret.clone(), sink),
+        None => AccessSecret::generate(sink).await?,
+    };
+
+    Ok(Arc::new(access_secret))
+}
+
+/// Generates a random secret, hex encoded.
+pub fn generate_secret() -> String {
+    let mut secret_bytes = [0u8; SECRET_LENGTH];
+    OsRng.fill_bytes(&mut secret_bytes);
+
+    hex::encode(secret_bytes)
+}
+
+/// The secret granting access to the reserved endpoints, see [`Secret`](`crate::rest_utils::Secret`).
+pub struct AccessSecret {
+    secret: RwLock<String>,
+    sink: Box<dyn SecretSink>,
+}
+
+/// The access secret shared among the rest server and the coordinator tasks.
+pub type SharedAccessSecret = Arc<AccessSecret>;
+
+impl AccessSecret {
+    /// Uses the given secret, which is only published to the sink once rotated.
+    pub fn new(secret: String, sink: Box<dyn SecretSink>) -> Self {
+        Self {
+            secret: RwLock::new(secret),
+            sink,
+        }
+    }
+
+    /// Generates a new secret and publishes it to the given sink.
+    pub async fn generate(sink: Box<dyn SecretSink>) -> Result<Self> {
+        let secret = generate_secret();
+        sink.publish(&secret).await?;
+
+        Ok(Self::new(secret, sink))
+    }
+
+    /// Checks, in constant time, that the given secret is the current one.
+    pub async fn verify(&self, secret: &str) -> bool {
+        secret.as_bytes().ct_eq(self.secret.read().await.as_bytes()).into()
+    }
+
+    ///
+    /// Replaces the secret with a new one, once published to the sink. The requests are held back until then, and
+    /// the previous secret is kept if the publication fails.
+    ///
+    pub async fn rotate(&self) -> Result<()> {
+        let mut current = self.secret.write().await;
+        let secret = generate_secret();
+        self.sink.publish(&secret).await?;
+        *current = secret;
+        info!("Rotated the access secret");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+//! Archival of the contribution files of the rounds out of the retention window of the coordinator.
+//!
+//! The verified contributions of an archived round and their signatures are compressed with zstd from
+//! `{base}/round_{N}` to `{base}/archive/round_{N}`, next to a [`MANIFEST_FILE`] listing the BLAKE2b-512 hashes of
+//! the original files. The unverified leftovers are removed, the round state stays in the round directory.
+
+use crate::CoordinatorError;
+
+use blake2::{Blake2b512, Digest};
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{debug, error, info};
+
+use super::disk::write_atomically;
+
+/// The directory of the archived rounds, in the base directory of the storage.
+pub const ARCHIVE_DIRECTORY: &str = "archive";
+/// The manifest of an archived round, written once all its files are archived.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The zstd compression level of the archived files.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The files of an archived round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub round_height: u64,
+    pub files: Vec<ArchivedFile>,
+}
+
+/// A file of an archived round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedFile {
+    /// The path of the file in the round directory, e.g. `chunk_0/contribution_1.verified`.
+    pub path: String,
+    /// The size of the file before compression.
+    pub size: u64,
+    /// The BLAKE2b-512 hash of the file before compression, in hexadecimal.
+    pub hash: String,
+}
+
+///
+/// Archives the verified contributions of the given round and removes its unverified ones.
+///
+/// Archiving a round again only removes the files left in the round directory, e.g. after a crash.
+///
+pub fn archive_round(base: &Path, round_height: u64) -> Result<ArchiveManifest, CoordinatorError> {
+    let round_directory = base.join(format!("round_{}", round_height));
+    let archive_directory = archive_directory(base, round_height);
+
+    let manifest = match read_manifest(&archive_directory)? {
+        Some(manifest) => manifest,
+        None => {
+            let mut files = Vec::new();
+            for path in round_files(&round_directory)? {
+                let relative = relative_path(&round_directory, &path)?;
+                if !is_verified(&relative) {
+                    continue;
+                }
+
+                let data = fs::read(&path)?;
+                let archived_path = archive_directory.join(format!("{}.zst", relative));
+                if let Some(parent) = archived_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&archived_path, zstd::encode_all(&data[..], COMPRESSION_LEVEL)?)?;
+                debug!("Archived {}", path.display());
+
+                files.push(ArchivedFile {
+                    path: relative,
+                    size: data.len() as u64,
+                    hash: hex::encode(Blake2b512::digest(&data)),
+                });
+            }
+
+            let manifest = ArchiveManifest { round_height, files };
+            fs::create_dir_all(&archive_directory)?;
+            write_atomically(
+                &archive_directory.join(MANIFEST_FILE),
+                &serde_json::to_vec_pretty(&manifest)?,
+            )?;
+            info!("Archived {} files of round {}", manifest.files.len(), round_height);
+
+            manifest
+        }
+    };
+
+    // The files are removed once the manifest is written, so that they can always be read from either place.
+    for path in round_files(&round_directory)? {
+        let relative = relative_path(&round_directory, &path)?;
+        if is_verified(&relative) || is_leftover(&relative) {
+            fs::remove_file(&path)?;
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Checks whether the given round has been archived.
+pub fn is_archived(base: &Path, round_height: u64) -> bool {
+    archive_directory(base, round_height).join(MANIFEST_FILE).exists()
+}
+
+/// Returns the path of the archived copy of the file at the given path, relative to the base directory.
+pub fn archived_path(base: &Path, relative: &Path) -> PathBuf {
+    let mut path = base.join(ARCHIVE_DIRECTORY).join(relative).into_os_string();
+    path.push(".zst");
+    path.into()
+}
+
+///
+/// Reads the archived copy of the file at the given path, relative to the base directory, and checks it
+/// against the manifest of its round. Returns `None` if the file has not been archived.
+///
+pub fn read_archived(base: &Path, relative: &Path) -> Result<Option<Vec<u8>>, CoordinatorError> {
+    let path = archived_path(base, relative);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let mut components = relative.iter();
+    let round_directory = components
+        .next()
+        .ok_or(CoordinatorError::StorageLocatorFormatIncorrect)?;
+    let file = components
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let manifest = read_manifest(&base.join(ARCHIVE_DIRECTORY).join(round_directory))?
+        .ok_or(CoordinatorError::ArchivedFileCorrupted)?;
+    let entry = manifest.files.iter().find(|entry| entry.path == file).ok_or_else(|| {
+        error!("{} is missing from the manifest of its round", path.display());
+        CoordinatorError::ArchivedFileCorrupted
+    })?;
+
+    let data = zstd::decode_all(fs::File::open(&path)?)?;
+    if hex::encode(Blake2b512::digest(&data)) != entry.hash {
+        error!("{} doesn't match the hash of its manifest", path.display());
+        return Err(CoordinatorError::ArchivedFileCorrupted);
+    }
+
+    Ok(Some(data))
+}
+
+///
+/// Removes the archived copy of the file at the given path, relative to the base directory, and its entry
+/// in the manifest of its round. Returns `false` if the file has not been archived.
+///
+pub fn remove_archived(base: &Path, relative: &Path) -> Result<bool, CoordinatorError> {
+    let path = archived_path(base, relative);
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    let mut components = relative.iter();
+    if let Some(round_directory) = components.next() {
+        let archive_directory = base.join(ARCHIVE_DIRECTORY).join(round_directory);
+        if let Some(mut manifest) = read_manifest(&archive_directory)? {
+            let file = components
+                .map(|component| component.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            manifest.files.retain(|entry| entry.path != file);
+            write_atomically(
+                &archive_directory.join(MANIFEST_FILE),
+                &serde_json::to_vec_pretty(&manifest)?,
+            )?;
+        }
+    }
+    fs::remove_file(&path)?;
+
+    Ok(true)
+}
+
+fn archive_directory(base: &Path, round_height: u64) -> PathBuf {
+    base.join(ARCHIVE_DIRECTORY).join(format!("round_{}", round_height))
+}
+
+fn read_manifest(archive_directory: &Path) -> Result<Option<ArchiveManifest>, CoordinatorError> {
+    let path = archive_directory.join(MANIFEST_FILE);
+    match path.exists() {
+        true => Ok(Some(serde_json::from_slice(&fs::read(path)?)?)),
+        false => Ok(None),
+    }
+}
+
+/// Returns the paths of the files in the given round directory and its chunk directories.
+fn round_files(round_directory: &Path) -> Result<Vec<PathBuf>, CoordinatorError> {
+    if !round_directory.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(round_directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(round_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+fn relative_path(round_directory: &Path, path: &Path) -> Result<String, CoordinatorError> {
+    let relative = path
+        .strip_prefix(round_directory)
+        .map_err(|_| CoordinatorError::StorageLocatorFormatIncorrect)?;
+
+    Ok(relative
+        .iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+fn is_verified(file: &str) -> bool {
+    file.ends_with(".verified") || file.ends_with(".verified.signature")
+}
+
+/// The unverified contributions and the staging files of interrupted uploads and writes.
+fn is_leftover(file: &str) -> bool {
+    file.ends_with(".unverified")
+        || file.ends_with(".unverified.signature")
+        || file.ends_with(".part")
+        || file.ends_with(".tmp")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_round() {
+        let dir = tempfile::tempdir().unwrap();
+        let chunk_directory = dir.path().join("round_1").join("chunk_0");
+        fs::create_dir_all(&chunk_directory).unwrap();
+        fs::write(chunk_directory.join("contribution_0.verified"), [1u8; 64]).unwrap();
+        fs::write(chunk_directory.join("contribution_0.verified.signature"), b"{}").unwrap();
+        fs::write(chunk_directory.join("contribution_1.unverified"), [2u8; 64]).unwrap();
+        fs::write(dir.path().join("round_1").join("state.json"), b"{}").unwrap();
+
+        let manifest = archive_round(dir.path(), 1).unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert!(is_archived(dir.path(), 1));
+
+        // Only the round state is left in the round directory
+        assert_eq!(
+            round_files(&dir.path().join("round_1")).unwrap(),
+            vec![dir.path().join("round_1").join("state.json")]
+        );
+
+        let relative = Path::new("round_1/chunk_0/contribution_0.verified");
+        assert_eq!(read_archived(dir.path(), relative).unwrap(), Some(vec![1u8; 64]));
+        let unverified = Path::new("round_1/chunk_0/contribution_1.unverified");
+        assert_eq!(read_archived(dir.path(), unverified).unwrap(), None);
+
+        // Archiving again is a no-op
+        assert_eq!(archive_round(dir.path(), 1).unwrap(), manifest);
+
+        // A tampered archive is detected
+        fs::write(
+            archived_path(dir.path(), relative),
+            zstd::encode_all(&[3u8; 64][..], COMPRESSION_LEVEL).unwrap(),
+        )
+        .unwrap();
+        assert!(matches!(
+            read_archived(dir.path(), relative),
+            Err(CoordinatorError::ArchivedFileCorrupted)
+        ));
+
+        assert!(remove_archived(dir.path(), relative).unwrap());
+        assert_eq!(read_archived(dir.path(), relative).unwrap(), None);
+        assert_eq!(archive_round(dir.path(), 1).unwrap().files.len(), 1);
+    }
+}
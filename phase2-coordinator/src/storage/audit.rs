@@ -0,0 +1,411 @@
+//! Integrity audit of the contribution files referenced by the round states of a storage.
+//!
+//! The audit never modifies the storage: it reports the missing files, the files of an unexpected size, the
+//! hashes which don't match the contribution file signatures and the signatures which don't verify.
+
+use crate::{
+    authentication::Signature,
+    environment::Environment,
+    objects::{ContributionFileSignature, Participant, Round},
+    storage::{Disk, Locator, LocatorPath, Object, Storage},
+    CoordinatorError,
+};
+
+use serde::Serialize;
+use setup_utils::calculate_hash;
+use std::fmt;
+use tracing::{debug, info, warn};
+
+/// An inconsistency found by [audit_storage].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditIssue {
+    pub round_height: u64,
+    /// The chunk of the inconsistency, if it concerns a contribution.
+    pub chunk_id: Option<u64>,
+    /// The contribution of the inconsistency, if it concerns a contribution.
+    pub contribution_id: Option<u64>,
+    pub kind: AuditIssueKind,
+}
+
+/// The kinds of inconsistencies found by [audit_storage].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum AuditIssueKind {
+    /// The round state is missing or can't be read.
+    RoundStateUnreadable { reason: String },
+    /// A file referenced by the round state is missing.
+    FileMissing { path: LocatorPath },
+    /// A contribution file doesn't have the size expected at its round and contribution.
+    FileSizeMismatch {
+        path: LocatorPath,
+        expected: u64,
+        found: u64,
+    },
+    /// A file referenced by the round state can't be read or parsed.
+    FileUnreadable { path: LocatorPath, reason: String },
+    /// A hash signed in a contribution file signature doesn't match the file it refers to.
+    HashMismatch { path: LocatorPath, file: LocatorPath },
+    /// A contribution file signature doesn't verify for its participant.
+    SignatureInvalid {
+        path: LocatorPath,
+        participant: Participant,
+    },
+}
+
+impl fmt::Display for AuditIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "round {}", self.round_height)?;
+        if let Some(chunk_id) = self.chunk_id {
+            write!(f, " chunk {}", chunk_id)?;
+        }
+        if let Some(contribution_id) = self.contribution_id {
+            write!(f, " contribution {}", contribution_id)?;
+        }
+
+        match &self.kind {
+            AuditIssueKind::RoundStateUnreadable { reason } => {
+                write!(f, ": the round state can't be read ({})", reason)
+            }
+            AuditIssueKind::FileMissing { path } => write!(f, ": {} is missing", path),
+            AuditIssueKind::FileSizeMismatch { path, expected, found } => {
+                write!(f, ": {} should be {} bytes but is {}", path, expected, found)
+            }
+            AuditIssueKind::FileUnreadable { path, reason } => write!(f, ": {} can't be read ({})", path, reason),
+            AuditIssueKind::HashMismatch { path, file } => {
+                write!(f, ": {} doesn't match the hash of {}", path, file)
+            }
+            AuditIssueKind::SignatureInvalid { path, participant } => {
+                write!(f, ": {} is not a valid signature of {}", path, participant)
+            }
+        }
+    }
+}
+
+///
+/// Audits the contribution files referenced by the round states of the given storage, from round 0 up to the
+/// current round, and returns the inconsistencies found.
+///
+/// For each contribution, the audit checks that its files exist, that the contribution files have the size
+/// expected at their round, that the hashes signed in its contribution file signatures match the challenge,
+/// the response and the next challenge, and that the signatures verify for the contributor and the verifier.
+///
+/// The unverified contributions of the archived rounds have been removed, they are not reported as missing.
+///
+/// On failure to read the current round height, this function returns a `CoordinatorError`.
+///
+pub fn audit_storage<S: Storage>(
+    storage: &S,
+    environment: &Environment,
+    signature: &dyn Signature,
+) -> Result<Vec<AuditIssue>, CoordinatorError> {
+    let round_states = RoundStates::read(storage)?;
+    Ok(audit_round_states(storage, environment, signature, round_states))
+}
+
+///
+/// Audits the contribution files referenced by the given round states, reading them from the base directory of
+/// the environment, where both the disk and the sqlite storages keep them. The round states are read beforehand,
+/// so the files can be hashed without holding the lock of the coordinator.
+///
+/// See [audit_storage] for the checks run on each contribution. A file updated by the coordinator during the
+/// audit may be reported, the next audit tells whether the inconsistency is persistent.
+///
+pub fn audit_files(environment: &Environment, signature: &dyn Signature, round_states: RoundStates) -> Vec<AuditIssue> {
+    audit_round_states(&Disk::new(environment), environment, signature, round_states)
+}
+
+/// The round states of a storage, from round 0 up to the current round, to be audited.
+#[derive(Debug, Clone)]
+pub struct RoundStates {
+    current_round_height: u64,
+    rounds: Vec<(u64, Round)>,
+    /// The issues of the round states which can't be read.
+    issues: Vec<AuditIssue>,
+}
+
+impl RoundStates {
+    /// Reads the round states of the given storage, or returns a `CoordinatorError` on failure to read the
+    /// current round height.
+    pub fn read<S: Storage>(storage: &S) -> Result<Self, CoordinatorError> {
+        let current_round_height = match storage.get(&Locator::RoundHeight)? {
+            Object::RoundHeight(round_height) => round_height,
+            _ => return Err(CoordinatorError::StorageFailed),
+        };
+
+        let mut rounds = Vec::new();
+        let mut issues = Vec::new();
+        for round_height in 0..=current_round_height {
+            match storage.get(&Locator::RoundState { round_height }) {
+                Ok(Object::RoundState(round)) => rounds.push((round_height, round)),
+                Ok(_) => issues.push(round_issue(round_height, CoordinatorError::StorageFailed)),
+                Err(e) => issues.push(round_issue(round_height, e)),
+            }
+        }
+
+        Ok(Self {
+            current_round_height,
+            rounds,
+            issues,
+        })
+    }
+}
+
+fn audit_round_states<S: Storage>(
+    storage: &S,
+    environment: &Environment,
+    signature: &dyn Signature,
+    round_states: RoundStates,
+) -> Vec<AuditIssue> {
+    let current_round_height = round_states.current_round_height;
+
+    let mut issues = round_states.issues;
+    for (round_height, round) in round_states.rounds {
+        let archived = environment
+            .retention_rounds()
+            .map_or(false, |rounds| round_height + rounds.max(1) <= current_round_height);
+        let mut auditor = Auditor {
+            storage,
+            environment,
+            signature,
+            round_height,
+            archived,
+            issues: Vec::new(),
+        };
+        auditor.audit_round(&round);
+
+        debug!("Audited round {}, {} issues", round_height, auditor.issues.len());
+        issues.extend(auditor.issues);
+    }
+    issues.sort_by_key(|issue| issue.round_height);
+
+    match issues.is_empty() {
+        true => info!("Audited rounds 0 to {}, no issues", current_round_height),
+        false => warn!("Audited rounds 0 to {}, {} issues", current_round_height, issues.len()),
+    }
+
+    issues
+}
+
+fn round_issue(round_height: u64, error: CoordinatorError) -> AuditIssue {
+    AuditIssue {
+        round_height,
+        chunk_id: None,
+        contribution_id: None,
+        kind: AuditIssueKind::RoundStateUnreadable {
+            reason: error.to_string(),
+        },
+    }
+}
+
+/// A contribution file referenced by a round state, with its hash if it could be read.
+type HashedFile<'a> = (Option<&'a LocatorPath>, Option<Vec<u8>>);
+
+/// The audit of the contributions of a round.
+struct Auditor<'a, S> {
+    storage: &'a S,
+    environment: &'a Environment,
+    signature: &'a dyn Signature,
+    round_height: u64,
+    /// Whether the round is out of the retention window of the environment.
+    archived: bool,
+    issues: Vec<AuditIssue>,
+}
+
+impl<'a, S: Storage> Auditor<'a, S> {
+    fn audit_round(&mut self, round: &Round) {
+        let round_height = self.round_height;
+
+        for chunk in round.chunks() {
+            // The challenge of a contribution is the next challenge of the previous one.
+            let mut challenge: HashedFile = (None, None);
+
+            for (contribution_id, contribution) in chunk.get_contributions() {
+                let mut issues = Vec::new();
+
+                // The response of the contributor, removed from the archived rounds once verified.
+                let response_path = match self.archived && contribution.is_verified() {
+                    true => None,
+                    false => contribution.get_contributed_location().as_ref(),
+                };
+                let response = (
+                    response_path,
+                    response_path.and_then(|path| self.hash_contribution(path, &mut issues)),
+                );
+
+                // The next challenge, the only file of contribution 0.
+                let next_challenge_path = contribution.get_verified_location().as_ref();
+                let next_challenge = (
+                    next_challenge_path,
+                    next_challenge_path.and_then(|path| self.hash_contribution(path, &mut issues)),
+                );
+
+                // The signature of contribution 0 is the one of the final contribution of the previous round.
+                if *contribution_id > 0 {
+                    let contributed_signature = contribution.get_contributed_signature_location().as_ref();
+                    if let (Some(path), Some(contributor)) = (contributed_signature, contribution.get_contributor()) {
+                        if response_path.is_some() {
+                            self.check_signature(path, contributor, &challenge, &response, None, &mut issues);
+                        }
+                    }
+
+                    let verified_signature = contribution.get_verified_signature_location().as_ref();
+                    if let (Some(path), Some(verifier)) = (verified_signature, contribution.get_verifier()) {
+                        self.check_signature(
+                            path,
+                            verifier,
+                            &challenge,
+                            &response,
+                            Some(&next_challenge),
+                            &mut issues,
+                        );
+                    }
+                }
+
+                self.issues.extend(issues.into_iter().map(|kind| AuditIssue {
+                    round_height,
+                    chunk_id: Some(chunk.chunk_id()),
+                    contribution_id: Some(*contribution_id),
+                    kind,
+                }));
+                challenge = next_challenge;
+            }
+        }
+    }
+
+    /// Checks the existence and the size of the given contribution file, and returns its hash.
+    fn hash_contribution(&self, path: &LocatorPath, issues: &mut Vec<AuditIssueKind>) -> Option<Vec<u8>> {
+        let contribution_locator = match self.locator(path, issues) {
+            Some(Locator::ContributionFile(contribution_locator)) => contribution_locator,
+            Some(_) => {
+                issues.push(unreadable(path, CoordinatorError::ContributionLocatorIncorrect));
+                return None;
+            }
+            None => return None,
+        };
+        let locator = Locator::ContributionFile(contribution_locator);
+
+        let expected = Object::anoma_contribution_file_size(
+            self.environment.parameters().circuits(),
+            contribution_locator.round_height(),
+            contribution_locator.contribution_id(),
+        );
+        let size = expected.and_then(|expected| Ok((expected, self.storage.size(&locator)?)));
+        match size {
+            Ok((expected, found)) if expected != found => {
+                issues.push(AuditIssueKind::FileSizeMismatch {
+                    path: path.clone(),
+                    expected,
+                    found,
+                });
+                return None;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                issues.push(unreadable(path, e));
+                return None;
+            }
+        }
+
+        match self.storage.reader(&locator) {
+            Ok(reader) => Some(calculate_hash(reader.as_ref()).to_vec()),
+            Err(e) => {
+                issues.push(unreadable(path, e));
+                None
+            }
+        }
+    }
+
+    ///
+    /// Checks the contribution file signature at the given path against the hashes of the challenge, the
+    /// response and the next challenge for the verifiers, and verifies it for the given participant.
+    /// The hashes of the files which couldn't be read are not checked.
+    ///
+    fn check_signature(
+        &self,
+        path: &LocatorPath,
+        participant: &Participant,
+        challenge: &HashedFile,
+        response: &HashedFile,
+        next_challenge: Option<&HashedFile>,
+        issues: &mut Vec<AuditIssueKind>,
+    ) {
+        let locator = match self.locator(path, issues) {
+            Some(locator) => locator,
+            None => return,
+        };
+        let contribution_file_signature: ContributionFileSignature = match self
+            .storage
+            .reader(&locator)
+            .and_then(|reader| Ok(serde_json::from_slice(reader.as_ref())?))
+        {
+            Ok(contribution_file_signature) => contribution_file_signature,
+            Err(e) => {
+                issues.push(unreadable(path, e));
+                return;
+            }
+        };
+
+        let mut signed_hashes = vec![
+            (Some(contribution_file_signature.get_challenge_hash()), challenge),
+            (Some(contribution_file_signature.get_response_hash()), response),
+        ];
+        if let Some(next_challenge) = next_challenge {
+            let signed_hash = contribution_file_signature.get_next_challenge_hash().as_deref();
+            signed_hashes.push((signed_hash, next_challenge));
+        }
+        for (signed_hash, (file, hash)) in signed_hashes {
+            if let (Some(file), Some(hash)) = (file, hash) {
+                if signed_hash
+                    .and_then(|signed_hash| hex::decode(signed_hash).ok())
+                    .as_ref()
+                    != Some(hash)
+                {
+                    issues.push(AuditIssueKind::HashMismatch {
+                        path: path.clone(),
+                        file: (*file).clone(),
+                    });
+                }
+            }
+        }
+
+        // The participants sign with the key of the address in their identifier.
+        let address = participant.to_string();
+        let address = address
+            .split('.')
+            .next()
+            .expect("splitting a string should yield at least one item");
+        let verified = serde_json::to_string(contribution_file_signature.get_state()).map_or(false, |message| {
+            self.signature
+                .verify(address, &message, contribution_file_signature.get_signature())
+        });
+        if !verified {
+            issues.push(AuditIssueKind::SignatureInvalid {
+                path: path.clone(),
+                participant: participant.clone(),
+            });
+        }
+    }
+
+    /// Returns the locator of the given path, if it exists in storage.
+    fn locator(&self, path: &LocatorPath, issues: &mut Vec<AuditIssueKind>) -> Option<Locator> {
+        match self.storage.to_locator(path) {
+            Ok(locator) if self.storage.exists(&locator) => Some(locator),
+            Ok(_) => {
+                issues.push(AuditIssueKind::FileMissing { path: path.clone() });
+                None
+            }
+            Err(e) => {
+                issues.push(unreadable(path, e));
+                None
+            }
+        }
+    }
+}
+
+fn unreadable(path: &LocatorPath, error: CoordinatorError) -> AuditIssueKind {
+    AuditIssueKind::FileUnreadable {
+        path: path.clone(),
+        reason: error.to_string(),
+    }
+}
@@ -1,33 +1,40 @@
 use crate::{
     environment::Environment,
-    objects::{ContributionFileSignature, ContributionInfo, Round, TrimmedContributionInfo},
     storage::{
-        ContributionLocator, ContributionSignatureLocator, Locator, Object, ObjectReader, ObjectWriter, StorageLocator,
-        StorageObject,
+        ContributionLocator, ContributionSignatureLocator, Locator, Object, ObjectReader, ObjectWriter, Storage,
+        StorageLocator, StorageObject,
     },
     CoordinatorError, CoordinatorState,
 };
 
-use anyhow::Result;
 use fs_err::{self as fs, File, OpenOptions};
 use itertools::Itertools;
 use memmap::MmapOptions;
 
 use std::{
     convert::TryFrom,
-    io::{Read, Write},
+    io::Write,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
     str::FromStr,
 };
-use tracing::{debug, error, trace};
+use time::{Duration, OffsetDateTime};
+use tracing::{debug, error, trace, warn};
 
-use super::{LocatorPath, StorageAction};
+use super::{archive, LocatorPath};
+
+/// The number of coordinator state snapshots kept next to `coordinator.json`.
+const COORDINATOR_STATE_SNAPSHOTS: usize = 16;
+
+/// The minimum interval between two snapshots of the coordinator state of the same round.
+const COORDINATOR_STATE_SNAPSHOT_INTERVAL: Duration = Duration::minutes(1);
 
 #[derive(Debug)]
 pub struct Disk {
     environment: Environment,
     resolver: DiskResolver,
+    /// The time and the round height of the last coordinator state snapshot.
+    last_snapshot: Option<(OffsetDateTime, Option<u64>)>,
 }
 
 impl Disk {
@@ -46,10 +53,12 @@ impl Disk {
         }
 
         // Create a new `Storage` instance, and set the `Environment`.
-        let mut storage = Self {
-            environment: environment.clone(),
-            resolver: DiskResolver::new(environment.local_base_directory()),
-        };
+        let mut storage = Self::new(environment);
+
+        // Check the coordinator state left by a previous run, restoring a snapshot if needed.
+        if storage.exists(&Locator::CoordinatorState) {
+            storage.recover_coordinator_state()?;
+        }
 
         // Create the coordinator state locator if it does not exist yet.
         if !storage.exists(&Locator::CoordinatorState) {
@@ -71,8 +80,276 @@ impl Disk {
         Ok(storage)
     }
 
+    /// Creates an instance of `Disk` on the base directory of the environment, without loading any object.
+    pub(crate) fn new(environment: &Environment) -> Self {
+        Self {
+            environment: environment.clone(),
+            resolver: DiskResolver::new(environment.local_base_directory()),
+            last_snapshot: None,
+        }
+    }
+
+    /// Removes the contribution files of the next round and the ones of the given round, except its initial
+    /// challenges.
+    pub(super) fn clear_round_directories(&mut self, round_height: u64) {
+        // Let's first fully clear any files in the next round - these will be
+        // verifications and represent the initial challenges.
+        let next_round_dir = self.resolver.round_directory(round_height + 1);
+        self.clear_dir_files(next_round_dir.into(), true);
+
+        // Now, let's clear all the contributions made on this round.
+        let round_dir = self.resolver.round_directory(round_height);
+        self.clear_dir_files(round_dir.into(), false);
+    }
+
+    /// Returns the path of the given contribution file or signature relative to the base directory, as kept in
+    /// the archive once its round is out of the retention window. The other objects are never archived.
+    fn archive_relative_path(&self, locator: &Locator) -> Option<PathBuf> {
+        match locator {
+            Locator::ContributionFile(_) | Locator::ContributionFileSignature(_) => {}
+            _ => return None,
+        }
+
+        let path = self.to_path(locator).ok()?;
+        path.as_path()
+            .strip_prefix(self.environment.local_base_directory())
+            .ok()
+            .map(Path::to_path_buf)
+    }
+
+    /// Reads the archived copy of the given locator, if its round has been archived.
+    fn read_archived(&self, locator: &Locator) -> Result<Option<Vec<u8>>, CoordinatorError> {
+        match self.archive_relative_path(locator) {
+            Some(relative) => archive::read_archived(Path::new(self.environment.local_base_directory()), &relative),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks whether the given locator only exists in the archive.
+    fn is_archived(&self, locator: &Locator) -> bool {
+        self.archive_relative_path(locator).map_or(false, |relative| {
+            archive::archived_path(Path::new(self.environment.local_base_directory()), &relative).is_file()
+        })
+    }
+
+    /// Returns the content of the given locator, from the archive if it is not in its round directory anymore.
+    fn read_bytes(&self, locator: &Locator) -> Result<Vec<u8>, CoordinatorError> {
+        let path = self.to_path(locator)?;
+        if path.as_path().is_file() {
+            return Ok(fs::read(path)?);
+        }
+
+        self.read_archived(locator)?.ok_or_else(|| {
+            error!("Locator {} is missing from the round directory and the archive", path);
+            CoordinatorError::StorageLocatorMissing
+        })
+    }
+
+    /// The staging file of a locator lives next to it with the `.part` extension.
+    fn staging_path(&self, locator: &Locator) -> Result<PathBuf, CoordinatorError> {
+        Ok(PathBuf::from(format!("{}.part", self.to_path(locator)?)))
+    }
+
+    ///
+    /// Checks that the coordinator state can be deserialized and matches the round height and round state
+    /// in storage. If it doesn't, the newest snapshot passing the same checks replaces it.
+    ///
+    /// A coordinator state of the previous round, left by a crash while advancing the round, is accepted and the
+    /// round height in storage is rolled back to it, for the coordinator to advance the round again.
+    ///
+    fn recover_coordinator_state(&mut self) -> Result<(), CoordinatorError> {
+        let path: PathBuf = self.to_path(&Locator::CoordinatorState)?.into();
+
+        let mut candidates = vec![path.clone()];
+        candidates.extend(self.coordinator_state_snapshots().into_iter().rev());
+
+        for candidate in candidates {
+            let state = fs::read(&candidate)
+                .map_err(CoordinatorError::from)
+                .and_then(|bytes| self.check_coordinator_state(&bytes));
+
+            match state {
+                Ok(state) => {
+                    if let Some(round_height) = state.current_round_height_opt() {
+                        self.rollback_next_round(round_height)?;
+                    }
+                    if candidate != path {
+                        warn!("Restoring the coordinator state from {}", candidate.display());
+                        self.update(&Locator::CoordinatorState, Object::CoordinatorState(state))?;
+                    }
+                    return Ok(());
+                }
+                Err(e) => warn!("Coordinator state at {} is invalid - {}", candidate.display(), e),
+            }
+        }
+
+        error!("No valid coordinator state or snapshot found in storage");
+        Err(CoordinatorError::CoordinatorStateCorrupted)
+    }
+
+    ///
+    /// Deserializes the coordinator state and checks it against the round height and round state in storage.
+    /// The coordinator state may be one round behind the round height, the next round being written to
+    /// storage before the coordinator state.
+    ///
+    fn check_coordinator_state(&self, bytes: &[u8]) -> Result<CoordinatorState, CoordinatorError> {
+        let state: CoordinatorState = serde_json::from_slice(bytes)?;
+
+        let round_height = match self.exists(&Locator::RoundHeight) {
+            true => match self.get(&Locator::RoundHeight)? {
+                Object::RoundHeight(round_height) => Some(round_height),
+                _ => return Err(CoordinatorError::StorageFailed),
+            },
+            false => None,
+        };
+        let state_round_height = state.current_round_height_opt();
+        if state_round_height != round_height && state_round_height.map(|h| h + 1) != round_height {
+            return Err(CoordinatorError::RoundHeightMismatch);
+        }
+
+        if let Some(round_height) = state_round_height {
+            match self.get(&Locator::RoundState { round_height })? {
+                Object::RoundState(round) if round.round_height() == round_height => {}
+                _ => return Err(CoordinatorError::RoundHeightMismatch),
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Removes the state and the contribution files of the round following the given one, except its initial
+    /// challenges, and rolls the round height back to it, if the coordinator state wasn't saved after advancing the
+    /// round.
+    fn rollback_next_round(&mut self, round_height: u64) -> Result<(), CoordinatorError> {
+        let next_round = Locator::RoundState {
+            round_height: round_height + 1,
+        };
+        if self.exists(&next_round) {
+            warn!(
+                "Removing the state and the contributions of round {} left by an interrupted round advance",
+                round_height + 1
+            );
+            let next_round_dir = self.resolver.round_directory(round_height + 1);
+            self.clear_dir_files(next_round_dir.into(), false);
+            self.remove(&next_round)?;
+        }
+
+        if let Object::RoundHeight(current_round_height) = self.get(&Locator::RoundHeight)? {
+            if current_round_height != round_height {
+                warn!("Rolling the round height back to {}", round_height);
+                self.update(&Locator::RoundHeight, Object::RoundHeight(round_height))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the paths of the coordinator state snapshots, from the oldest to the newest.
+    fn coordinator_state_snapshots(&self) -> Vec<PathBuf> {
+        let entries = match fs::read_dir(self.resolver.snapshot_directory()) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let timestamp = entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix("coordinator_")?
+                    .strip_suffix(".json")?
+                    .parse::<i128>()
+                    .ok()?;
+                Some((timestamp, entry.path()))
+            })
+            .sorted()
+            .map(|(_, path)| path)
+            .collect()
+    }
+
+    /// Writes a timestamped snapshot of the coordinator state and removes the oldest ones.
+    fn snapshot_coordinator_state(&self, bytes: &[u8]) -> Result<(), CoordinatorError> {
+        let directory = self.resolver.snapshot_directory();
+        fs::create_dir_all(&directory)?;
+
+        let timestamp = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        write_atomically(
+            &Path::new(&directory).join(format!("coordinator_{}.json", timestamp)),
+            bytes,
+        )?;
+
+        let snapshots = self.coordinator_state_snapshots();
+        let expired = snapshots.len().saturating_sub(COORDINATOR_STATE_SNAPSHOTS);
+        for snapshot in &snapshots[..expired] {
+            fs::remove_file(snapshot)?;
+        }
+
+        Ok(())
+    }
+
+    fn clear_dir_files(&mut self, path: PathBuf, delete_initial_contribution: bool) {
+        let entries = match fs::read_dir(path.as_path()) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Could not read directory at {:?} - {:?}", path, e);
+                return;
+            }
+        };
+
+        for entry in entries {
+            if let Err(e) = entry {
+                tracing::error!("Found erroneous entry - {:?}", e);
+                continue;
+            }
+
+            let entry = entry.unwrap();
+
+            match entry.path().is_dir() {
+                true => self.clear_dir_files(entry.path(), delete_initial_contribution),
+                false => {
+                    let file_path = match entry.path().to_str() {
+                        Some(file_path) => file_path.to_owned(),
+                        None => {
+                            tracing::error!("Could not turn fs entry into file path");
+                            continue;
+                        }
+                    };
+
+                    if !delete_initial_contribution && file_path.contains("contribution_0")
+                        || file_path.contains("state.json")
+                    {
+                        continue;
+                    }
+
+                    // Staging files of partial uploads and interrupted writes have no locator
+                    if file_path.ends_with(".part") || file_path.ends_with(".tmp") {
+                        if let Err(e) = fs::remove_file(entry.path()) {
+                            tracing::error!("Could not remove staging file - {:?}", e);
+                        }
+                        continue;
+                    }
+
+                    let locator = match self.resolver.to_locator(&LocatorPath::new(file_path)) {
+                        Ok(locator) => locator,
+                        Err(e) => {
+                            tracing::error!("Could not turn file path into locator - {:?}", e);
+                            continue;
+                        }
+                    };
+
+                    if let Err(e) = self.remove(&locator) {
+                        tracing::error!("Could not remove locator - {:?}", e);
+                    }
+                }
+            };
+        }
+    }
+}
+
+impl Storage for Disk {
     /// Initializes the location corresponding to the given locator.
-    pub fn initialize(&mut self, locator: Locator, size: u64) -> Result<(), CoordinatorError> {
+    fn initialize(&mut self, locator: Locator, size: u64) -> Result<(), CoordinatorError> {
         let locator_path = self.to_path(&locator)?;
         trace!("Initializing {:?}", locator_path);
 
@@ -106,7 +383,7 @@ impl Disk {
     }
 
     /// Checks whether the given locator exists in the storage or not.
-    pub fn exists(&self, locator: &Locator) -> bool {
+    fn exists(&self, locator: &Locator) -> bool {
         let path = match self.to_path(locator) {
             Ok(path) => path,
             Err(e) => {
@@ -118,44 +395,12 @@ impl Disk {
         trace!("Ensuring that {} exists in storage", path);
         match fs::metadata(path) {
             Ok(metadata) => metadata.is_file(),
-            Err(_) => false,
+            Err(_) => self.is_archived(locator),
         }
     }
 
-    /// Retrieve the json encoded summary file
-    pub fn get_contributions_summary(&self) -> Result<Vec<u8>, CoordinatorError> {
-        // Check that the given locator exists in storage.
-        if !self.exists(&Locator::ContributionsInfoSummary) {
-            error!(
-                "Locator missing in call to get() in storage - {:?}",
-                Locator::ContributionsInfoSummary
-            );
-            return Err(CoordinatorError::StorageLocatorMissing);
-        }
-
-        let path = self.to_path(&Locator::ContributionsInfoSummary)?;
-
-        Ok(fs::read(path)?)
-    }
-
-    /// Retrieve the json encoded state file
-    pub fn get_coordinator_state(&self) -> Result<Vec<u8>, CoordinatorError> {
-        // Check that the given locator exists in storage.
-        if !self.exists(&Locator::CoordinatorState) {
-            error!(
-                "Locator missing in call to get() in storage - {:?}",
-                Locator::CoordinatorState
-            );
-            return Err(CoordinatorError::StorageLocatorMissing);
-        }
-
-        let path = self.to_path(&Locator::CoordinatorState)?;
-
-        Ok(fs::read(path)?)
-    }
-
     /// Returns a copy of an object at the given locator in storage, if it exists.
-    pub fn get(&self, locator: &Locator) -> Result<Object, CoordinatorError> {
+    fn get(&self, locator: &Locator) -> Result<Object, CoordinatorError> {
         let path = self.to_path(locator)?;
         trace!("Fetching {}", path);
 
@@ -166,116 +411,15 @@ impl Disk {
         }
 
         // read the file to a byte array
-        let file_bytes = fs::read(path)?;
-
-        let object = match locator {
-            Locator::CoordinatorState => {
-                let coordinator_state: CoordinatorState = serde_json::from_slice(&file_bytes)?;
-                Ok(Object::CoordinatorState(coordinator_state))
-            }
-            Locator::RoundHeight => {
-                let round_height: u64 = serde_json::from_slice(&file_bytes)?;
-                Ok(Object::RoundHeight(round_height))
-            }
-            Locator::RoundState { round_height: _ } => {
-                let round: Round = serde_json::from_slice(&file_bytes)?;
-                Ok(Object::RoundState(round))
-            }
-            Locator::RoundFile { round_height } => {
-                // Check that the round size is correct.
-                let expected_size = Object::round_file_size(&self.environment);
-                let found_size = file_bytes.len() as u64;
-                debug!("Round {} filesize is {}", round_height, found_size);
-                if found_size == 0 || expected_size != found_size {
-                    error!("Round file size should be {} but found {}", expected_size, found_size);
-                    return Err(CoordinatorError::RoundFileSizeMismatch.into());
-                }
-
-                Ok(Object::RoundFile(file_bytes))
-            }
-            Locator::ContributionFile(contribution_locator) => {
-                // Check that the contribution size is correct.
-                let expected_size = Object::anoma_contribution_file_size(
-                    contribution_locator.round_height(),
-                    contribution_locator.contribution_id(),
-                );
-                let found_size = file_bytes.len() as u64;
-                debug!(
-                    "Round {} chunk {} filesize is {}",
-                    contribution_locator.round_height(),
-                    contribution_locator.chunk_id(),
-                    found_size
-                );
-                if found_size == 0 || expected_size != found_size {
-                    error!(
-                        "Contribution file size should be {} but found {}",
-                        expected_size, found_size
-                    );
-                    return Err(CoordinatorError::ContributionFileSizeMismatch.into());
-                }
-
-                let mut contribution_file: Vec<u8> = Vec::with_capacity(expected_size as usize);
-                contribution_file.write_all(&file_bytes)?;
-                Ok(Object::ContributionFile(contribution_file))
-            }
-            Locator::ContributionFileSignature(contribution_locator) => {
-                // Check that the contribution file signature size is correct.
-                let expected_size = Object::contribution_file_signature_size(contribution_locator.is_verified());
-                let found_size = file_bytes.len() as u64;
-                debug!(
-                    "Round {} chunk {} contribution {} signature filesize is {}",
-                    contribution_locator.round_height(),
-                    contribution_locator.chunk_id(),
-                    contribution_locator.contribution_id(),
-                    found_size
-                );
-                if found_size == 0 || expected_size != found_size {
-                    error!(
-                        "Contribution signature file size should be {} but found {}",
-                        expected_size, found_size
-                    );
-                    return Err(CoordinatorError::ContributionSignatureFileSizeMismatch.into());
-                }
-
-                let contribution_file_signature: ContributionFileSignature = serde_json::from_slice(&file_bytes)?;
-                Ok(Object::ContributionFileSignature(contribution_file_signature))
-            }
-            Locator::ContributionInfoFile { round_height: _ } => {
-                let contribution_info: ContributionInfo = serde_json::from_slice(&file_bytes)?;
-                Ok(Object::ContributionInfoFile(contribution_info))
-            }
-            Locator::ContributionsInfoSummary => {
-                let summary: Vec<TrimmedContributionInfo> = serde_json::from_slice(&file_bytes)?;
-                Ok(Object::ContributionsInfoSummary(summary))
-            }
-        };
+        let file_bytes = self.read_bytes(locator)?;
+        let object = Object::from_bytes(&self.environment, locator, file_bytes)?;
 
         trace!("Fetched {}", self.to_path(locator)?);
-        object
-    }
-
-    /// Inserts a new object at the given locator into storage, if it does not exist.
-    pub fn insert(&mut self, locator: Locator, object: Object) -> Result<(), CoordinatorError> {
-        trace!("Inserting {}", self.to_path(&locator)?);
-
-        // Check that the given locator does not exist in storage.
-        if self.exists(&locator) {
-            error!("Locator in call to insert() already exists in storage.");
-            return Err(CoordinatorError::StorageLocatorAlreadyExists);
-        }
-
-        // Initialize the new file with the object size.
-        self.initialize(locator.clone(), object.size())?;
-
-        // Insert the object at the given locator.
-        self.update(&locator, object)?;
-
-        trace!("Inserted {}", self.to_path(&locator)?);
-        Ok(())
+        Ok(object)
     }
 
     /// Updates an existing object for the given locator in storage, if it exists.
-    pub fn update(&mut self, locator: &Locator, object: Object) -> Result<(), CoordinatorError> {
+    fn update(&mut self, locator: &Locator, object: Object) -> Result<(), CoordinatorError> {
         let path = self.to_path(locator)?;
         trace!("Updating {}", path);
 
@@ -285,51 +429,41 @@ impl Disk {
             return Err(CoordinatorError::StorageLocatorMissing);
         }
 
-        let mut file = OpenOptions::new().write(true).open(path)?;
-        file.set_len(object.size())?;
-        file.write_all(&object.to_bytes())?;
-        // Sync all in-memory data to disk.
-        file.flush()?;
-
-        trace!("Updated {}", self.to_path(&locator)?);
-        Ok(())
-    }
-
-    /// Copies an object from the given source locator to the given destination locator.
-    pub fn copy(&mut self, source_locator: &Locator, destination_locator: &Locator) -> Result<(), CoordinatorError> {
-        trace!(
-            "Copying from A to B\n\n\tA: {}\n\tB: {}\n",
-            self.to_path(source_locator)?,
-            self.to_path(destination_locator)?
-        );
-
-        // Check that the given source locator exists in storage.
-        if !self.exists(source_locator) {
-            error!("Source locator missing in call to copy() in storage.");
-            return Err(CoordinatorError::StorageLocatorMissing);
+        let bytes = object.to_bytes();
+        match locator {
+            // The states of the ceremony must never be left half written by a crash.
+            Locator::RoundHeight | Locator::RoundState { .. } | Locator::CoordinatorState => {
+                write_atomically(path.as_ref(), &bytes)?
+            }
+            _ => {
+                let mut file = OpenOptions::new().write(true).open(path)?;
+                file.set_len(bytes.len() as u64)?;
+                file.write_all(&bytes)?;
+                // Sync all in-memory data to disk.
+                file.flush()?;
+            }
         }
 
-        // Check that the given destination locator does NOT exist in storage.
-        if self.exists(destination_locator) {
-            error!("Destination locator in call to copy() already exists in storage.");
-            return Err(CoordinatorError::StorageLocatorAlreadyExists);
+        // Keep previous coordinator states around in case the latest one can't be used, one per interval and
+        // at least one per round.
+        if let Object::CoordinatorState(state) = &object {
+            let now = OffsetDateTime::now_utc();
+            let round_height = state.current_round_height_opt();
+            let due = self.last_snapshot.map_or(true, |(time, height)| {
+                height != round_height || now - time >= COORDINATOR_STATE_SNAPSHOT_INTERVAL
+            });
+            if due {
+                self.snapshot_coordinator_state(&bytes)?;
+                self.last_snapshot = Some((now, round_height));
+            }
         }
 
-        // Fetch the source object.
-        let source_object = self.get(source_locator)?;
-
-        // Initialize the destination file with the source object size.initializeinitialize
-        self.initialize(destination_locator.clone(), source_object.size())?;
-
-        // Update the destination locator with the copied source object.
-        self.update(destination_locator, source_object)?;
-
-        trace!("Copied to {}", self.to_path(destination_locator)?);
+        trace!("Updated {}", self.to_path(&locator)?);
         Ok(())
     }
 
     /// Removes the object corresponding to the given locator from storage.
-    pub fn remove(&mut self, locator: &Locator) -> Result<(), CoordinatorError> {
+    fn remove(&mut self, locator: &Locator) -> Result<(), CoordinatorError> {
         let path = self.to_path(locator)?;
         trace!("Removing {}", path);
 
@@ -342,14 +476,19 @@ impl Disk {
         // TODO: if any of the locators are directories, make this
         // detect whether the path is a directory of a file and call
         // the appropriate function.
-        fs::remove_file(path.clone())?;
+        match self.archive_relative_path(locator) {
+            Some(relative) if !path.as_path().is_file() => {
+                archive::remove_archived(Path::new(self.environment.local_base_directory()), &relative)?;
+            }
+            _ => fs::remove_file(path.clone())?,
+        }
 
         trace!("Removed {}", path);
         Ok(())
     }
 
     /// Returns the size of the object stored at the given locator.
-    pub fn size(&self, locator: &Locator) -> Result<u64, CoordinatorError> {
+    fn size(&self, locator: &Locator) -> Result<u64, CoordinatorError> {
         let path = self.to_path(locator)?;
         trace!("Fetching size of {}", path);
 
@@ -359,6 +498,11 @@ impl Disk {
             return Err(CoordinatorError::StorageLocatorMissing);
         }
 
+        // The size of an archived file is the size of its decompressed content.
+        if !path.as_path().is_file() {
+            return Ok(self.read_bytes(locator)?.len() as u64);
+        }
+
         // Open the file.
         let file = OpenOptions::new().read(true).write(true).open(path.clone())?;
 
@@ -366,105 +510,78 @@ impl Disk {
         Ok(file.metadata()?.len())
     }
 
-    /// Process a [StorageAction] which mutates the storage.
-    pub fn process(&mut self, action: StorageAction) -> Result<()> {
-        match action {
-            StorageAction::Remove(remove_action) => {
-                let locator = remove_action.try_into_locator(self)?;
-                Ok(self.remove(&locator)?)
-            }
-            StorageAction::Update(update_action) => Ok(self.update(&update_action.locator, update_action.object)?),
-            StorageAction::ClearRoundFiles(round_height) => Ok(self.clear_round_files(round_height)),
+    /// Returns the number of bytes received so far for an object uploaded in multiple parts at the given locator.
+    fn staged_size(&self, locator: &Locator) -> Result<u64, CoordinatorError> {
+        let path = self.staging_path(locator)?;
+
+        match fs::metadata(path) {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(_) => Ok(0),
         }
     }
 
-    /// Clears all files related to a round - used for round reset purposes.
-    fn clear_round_files(&mut self, round_height: u64) {
-        // Let's first fully clear any files in the next round - these will be
-        // verifications and represent the initial challenges.
-        let next_round_dir = self.resolver.round_directory(round_height + 1);
-        self.clear_dir_files(next_round_dir.into(), true);
-
-        // Now, let's clear all the contributions made on this round.
-        let round_dir = self.resolver.round_directory(round_height);
-        self.clear_dir_files(round_dir.into(), false);
+    /// Appends a part of the object uploaded at the given locator to its staging file. A part at offset zero
+    /// restarts the upload, any other part must start where the previous one ended. Returns the number of
+    /// bytes received so far.
+    fn stage(&mut self, locator: &Locator, offset: u64, bytes: &[u8]) -> Result<u64, CoordinatorError> {
+        let path = self.staging_path(locator)?;
+        trace!("Staging {} bytes at offset {} of {:?}", bytes.len(), offset, path);
 
-        // Delete contribution file and trim contribution summary file if these files exist
-        self.clear_info_files(round_height);
-    }
-
-    /// Delete contribution file and trim contribution summary file if these files exist
-    pub fn clear_info_files(&mut self, round_height: u64) {
-        if let Err(e) = self.remove(&Locator::ContributionInfoFile { round_height }) {
-            tracing::warn!("Could not delete contribution file: {}", e);
+        // Check that the given locator exists in storage.
+        if !self.exists(locator) {
+            error!("Locator missing in call to stage() in storage.");
+            return Err(CoordinatorError::StorageLocatorMissing);
         }
 
-        match self.get(&Locator::ContributionsInfoSummary) {
-            Ok(o) => {
-                if let Object::ContributionsInfoSummary(mut s) = o {
-                    // NOTE: the vec is ordered for ascending round heights
-                    if let Some(contrib) = s.last() {
-                        if contrib.ceremony_round() == round_height {
-                            s.pop();
-                            // Update contribution summary file in storage
-                            self.update(&Locator::ContributionsInfoSummary, Object::ContributionsInfoSummary(s))
-                                .expect("ERROR: failure while updating contribution summary file");
-                        }
-                    }
-                }
-            }
-            Err(e) => tracing::warn!("Could not retrieve contribution summary file: {}", e),
+        let staged_size = if offset == 0 { 0 } else { self.staged_size(locator)? };
+        if offset != staged_size {
+            return Err(CoordinatorError::StorageUploadOffsetMismatch {
+                expected: staged_size,
+                actual: offset,
+            });
         }
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(offset != 0)
+            .truncate(offset == 0)
+            .open(path)?;
+        file.write_all(bytes)?;
+        // Sync all in-memory data to disk.
+        file.flush()?;
+
+        Ok(staged_size + bytes.len() as u64)
     }
 
-    fn clear_dir_files(&mut self, path: PathBuf, delete_initial_contribution: bool) {
-        let entries = match fs::read_dir(path.as_path()) {
-            Ok(entries) => entries,
-            Err(e) => {
-                tracing::warn!("Could not read directory at {:?} - {:?}", path, e);
-                return;
-            }
-        };
+    /// Returns the content of the staging file of the given locator.
+    fn get_staged(&self, locator: &Locator) -> Result<Vec<u8>, CoordinatorError> {
+        Ok(fs::read(self.staging_path(locator)?)?)
+    }
 
-        for entry in entries {
-            if let Err(e) = entry {
-                tracing::error!("Found erroneous entry - {:?}", e);
-                continue;
-            }
+    /// Removes the staging file of the given locator, if any.
+    fn clear_staged(&mut self, locator: &Locator) -> Result<(), CoordinatorError> {
+        let path = self.staging_path(locator)?;
 
-            let entry = entry.unwrap();
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
 
-            match entry.path().is_dir() {
-                true => self.clear_dir_files(entry.path(), delete_initial_contribution),
-                false => {
-                    let file_path = match entry.path().to_str() {
-                        Some(file_path) => file_path.to_owned(),
-                        None => {
-                            tracing::error!("Could not turn fs entry into file path");
-                            continue;
-                        }
-                    };
+        Ok(())
+    }
 
-                    if !delete_initial_contribution && file_path.contains("contribution_0")
-                        || file_path.contains("state.json")
-                    {
-                        continue;
-                    }
+    /// Clears all files related to a round - used for round reset purposes.
+    fn clear_round_files(&mut self, round_height: u64) {
+        self.clear_round_directories(round_height);
 
-                    let locator = match self.resolver.to_locator(&LocatorPath::new(file_path)) {
-                        Ok(locator) => locator,
-                        Err(e) => {
-                            tracing::error!("Could not turn file path into locator - {:?}", e);
-                            continue;
-                        }
-                    };
+        // Delete contribution file and trim contribution summary file if these files exist
+        self.clear_info_files(round_height);
+    }
 
-                    if let Err(e) = self.remove(&locator) {
-                        tracing::error!("Could not remove locator - {:?}", e);
-                    }
-                }
-            };
-        }
+    /// Compresses the verified contributions of the round into the archive directory and removes the others.
+    fn archive_round(&mut self, round_height: u64) -> Result<(), CoordinatorError> {
+        archive::archive_round(Path::new(self.environment.local_base_directory()), round_height)?;
+        Ok(())
     }
 }
 
@@ -546,18 +663,13 @@ impl StorageObject for Disk {
             return Err(CoordinatorError::StorageLocatorMissing);
         }
 
-        let file = OpenOptions::new().read(true).open(path)?;
-
-        // Load the file into memory.
-        let mut data = vec![];
-        file.file()
-            .read_to_end(&mut data)
-            .map_err(|e| CoordinatorError::IOError(e))?;
+        // Load the file into memory, from the archive if its round has been archived.
+        let data = self.read_bytes(locator)?;
 
         match locator {
             Locator::RoundFile { round_height } => {
                 // Check that the round size is correct.
-                let expected_size = Object::round_file_size(&self.environment);
+                let expected_size = Object::round_file_size(&self.environment)?;
                 let found_size = data.len() as u64;
                 debug!("Round {} filesize is {}", round_height, found_size);
                 if found_size != expected_size {
@@ -571,9 +683,10 @@ impl StorageObject for Disk {
             Locator::ContributionFile(contribution_locator) => {
                 // Check that the contribution size is correct.
                 let expected_size = Object::anoma_contribution_file_size(
+                    self.environment.parameters().circuits(),
                     contribution_locator.round_height(),
                     contribution_locator.contribution_id(),
-                );
+                )?;
                 let found_size = data.len() as u64;
                 debug!(
                     "Round {} chunk {} filesize is {}",
@@ -606,6 +719,12 @@ impl StorageObject for Disk {
             return Err(CoordinatorError::StorageLocatorMissing);
         }
 
+        // The archived rounds are read only.
+        if !path.as_path().is_file() {
+            error!("Locator {} is archived in call to writer() in storage.", path);
+            return Err(CoordinatorError::StorageLocatorMissing);
+        }
+
         let file = OpenOptions::new().read(true).write(true).open(path)?;
 
         // Load the file into memory.
@@ -613,7 +732,7 @@ impl StorageObject for Disk {
         match locator {
             Locator::RoundFile { round_height: _ } => {
                 // Check that the round size is correct.
-                let expected_size = Object::round_file_size(&self.environment);
+                let expected_size = Object::round_file_size(&self.environment)?;
                 let found_size = memmap.len() as u64;
                 debug!("File size of {} is {}", self.to_path(locator)?, found_size);
                 if found_size != expected_size {
@@ -627,9 +746,10 @@ impl StorageObject for Disk {
             Locator::ContributionFile(contribution_locator) => {
                 // Check that the contribution size is correct.
                 let expected_size = Object::anoma_contribution_file_size(
+                    self.environment.parameters().circuits(),
                     contribution_locator.round_height(),
                     contribution_locator.contribution_id(),
-                );
+                )?;
                 let found_size = memmap.len() as u64;
                 debug!("File size of {} is {}", self.to_path(locator)?, found_size);
                 if found_size != expected_size {
@@ -647,14 +767,35 @@ impl StorageObject for Disk {
     }
 }
 
+///
+/// Replaces the file at the given path with the given bytes, so that a crash leaves either the previous
+/// or the new content. The bytes are written and synced to a temporary file renamed over the original one.
+///
+pub(super) fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), CoordinatorError> {
+    let tmp_path = PathBuf::from(format!("{}.tmp", path.display()));
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+
+    // Sync the directory for the rename to be durable.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        File::open(parent)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
-struct DiskResolver {
+pub(super) struct DiskResolver {
     base: String,
 }
 
 impl DiskResolver {
     #[inline]
-    fn new(base: &str) -> Self {
+    pub(super) fn new(base: &str) -> Self {
         Self { base: base.to_string() }
     }
 }
@@ -865,6 +1006,12 @@ impl StorageLocator for DiskResolver {
 }
 
 impl DiskResolver {
+    /// Returns the directory of the coordinator state snapshots.
+    #[inline]
+    fn snapshot_directory(&self) -> String {
+        format!("{}/snapshots", self.base)
+    }
+
     /// Returns the round directory for a given round height from the coordinator.
     #[inline]
     fn round_directory(&self, round_height: u64) -> String {
@@ -901,7 +1048,7 @@ impl DiskResolver {
 #[cfg(test)]
 mod tests {
     use super::*;
-    // use crate::testing::prelude::*;
+    use crate::testing::prelude::*;
 
     #[test]
     fn test_to_path_coordinator_state() {
@@ -1355,4 +1502,138 @@ mod tests {
             Locator::ContributionFileSignature(ContributionSignatureLocator::new(1, 1, 1, true))
         );
     }
+
+    #[test]
+    #[serial]
+    fn test_stage() {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+        let mut storage = test_storage(&TEST_ENVIRONMENT_ANOMA);
+
+        let locator = Locator::ContributionFile(ContributionLocator::new(1, 0, 1, false));
+        assert!(storage.stage(&locator, 0, &[1, 2]).is_err());
+        storage.initialize(locator.clone(), 4).unwrap();
+        assert_eq!(storage.staged_size(&locator).unwrap(), 0);
+
+        // Parts must be contiguous
+        assert_eq!(storage.stage(&locator, 0, &[1, 2]).unwrap(), 2);
+        assert!(storage.stage(&locator, 3, &[3]).is_err());
+        assert_eq!(storage.stage(&locator, 2, &[3, 4]).unwrap(), 4);
+        assert_eq!(storage.get_staged(&locator).unwrap(), vec![1, 2, 3, 4]);
+
+        // A part at offset zero restarts the upload
+        assert_eq!(storage.stage(&locator, 0, &[5]).unwrap(), 1);
+        assert_eq!(storage.get_staged(&locator).unwrap(), vec![5]);
+
+        // The staged object doesn't replace the one at the locator
+        assert_eq!(storage.size(&locator).unwrap(), 4);
+
+        storage.clear_staged(&locator).unwrap();
+        assert_eq!(storage.staged_size(&locator).unwrap(), 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_recover_coordinator_state() {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+        let mut storage = test_storage(&TEST_ENVIRONMENT_ANOMA);
+        let path: PathBuf = storage.to_path(&Locator::CoordinatorState).unwrap().into();
+
+        let mut state = CoordinatorState::new(TEST_ENVIRONMENT_ANOMA.clone());
+        state.initialize(0);
+        storage
+            .insert(
+                Locator::RoundState { round_height: 0 },
+                Object::RoundState(test_round_0_json().unwrap()),
+            )
+            .unwrap();
+        storage.insert(Locator::RoundHeight, Object::RoundHeight(0)).unwrap();
+
+        // The coordinator state is snapshotted once per interval, only the latest snapshots are kept
+        for _ in 0..COORDINATOR_STATE_SNAPSHOTS + 1 {
+            for _ in 0..2 {
+                storage
+                    .update(&Locator::CoordinatorState, Object::CoordinatorState(state.clone()))
+                    .unwrap();
+            }
+            storage.last_snapshot = storage
+                .last_snapshot
+                .map(|(time, round_height)| (time - COORDINATOR_STATE_SNAPSHOT_INTERVAL, round_height));
+        }
+        assert_eq!(storage.coordinator_state_snapshots().len(), COORDINATOR_STATE_SNAPSHOTS);
+        assert!(!PathBuf::from(format!("{}.tmp", path.display())).exists());
+
+        // A truncated coordinator state is restored from the newest snapshot
+        fs::write(&path, b"{\"current_round_height\"").unwrap();
+        let storage = test_storage(&TEST_ENVIRONMENT_ANOMA);
+        match storage.get(&Locator::CoordinatorState).unwrap() {
+            Object::CoordinatorState(state) => assert_eq!(state.current_round_height_opt(), Some(0)),
+            _ => panic!("Unexpected object"),
+        }
+
+        // An uninitialized coordinator state with a round height in storage is restored as well
+        let stale_state = CoordinatorState::new(TEST_ENVIRONMENT_ANOMA.clone());
+        fs::write(&path, serde_json::to_vec(&stale_state).unwrap()).unwrap();
+        let mut storage = test_storage(&TEST_ENVIRONMENT_ANOMA);
+        match storage.get(&Locator::CoordinatorState).unwrap() {
+            Object::CoordinatorState(state) => assert_eq!(state.current_round_height_opt(), Some(0)),
+            _ => panic!("Unexpected object"),
+        }
+
+        // A coordinator state saved before advancing the round rolls the round back
+        storage
+            .insert(
+                Locator::RoundState { round_height: 1 },
+                Object::RoundState(test_round_0_json().unwrap()),
+            )
+            .unwrap();
+        storage.update(&Locator::RoundHeight, Object::RoundHeight(1)).unwrap();
+        fs::write(&path, serde_json::to_vec(&state).unwrap()).unwrap();
+        let storage = test_storage(&TEST_ENVIRONMENT_ANOMA);
+        assert!(matches!(
+            storage.get(&Locator::RoundHeight).unwrap(),
+            Object::RoundHeight(0)
+        ));
+        assert!(!storage.exists(&Locator::RoundState { round_height: 1 }));
+
+        // Without a valid snapshot the storage can't be loaded
+        fs::remove_dir_all(storage.resolver.snapshot_directory()).unwrap();
+        fs::write(&path, b"").unwrap();
+        assert!(matches!(
+            Disk::load(&TEST_ENVIRONMENT_ANOMA),
+            Err(CoordinatorError::CoordinatorStateCorrupted)
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test_archive_round() {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+        let mut storage = test_storage(&TEST_ENVIRONMENT_ANOMA);
+
+        let size = Object::anoma_contribution_file_size(TEST_ENVIRONMENT_ANOMA.parameters().circuits(), 1, 1).unwrap();
+        let verified = Locator::ContributionFile(ContributionLocator::new(1, 0, 1, true));
+        let unverified = Locator::ContributionFile(ContributionLocator::new(1, 0, 2, false));
+        storage
+            .insert(verified, Object::ContributionFile(vec![1; size as usize]))
+            .unwrap();
+        storage.initialize(unverified, size).unwrap();
+
+        storage.archive_round(1).unwrap();
+        let path: PathBuf = storage.to_path(&verified).unwrap().into();
+        assert!(!path.exists());
+        assert!(!storage.exists(&unverified));
+
+        // The archived contribution is still readable, but can't be written anymore
+        assert!(storage.exists(&verified));
+        assert_eq!(storage.size(&verified).unwrap(), size);
+        assert_eq!(storage.reader(&verified).unwrap().to_vec(), vec![1; size as usize]);
+        assert!(matches!(
+            storage.get(&verified).unwrap(),
+            Object::ContributionFile(bytes) if bytes == vec![1; size as usize]
+        ));
+        assert!(storage.writer(&verified).is_err());
+
+        storage.remove(&verified).unwrap();
+        assert!(!storage.exists(&verified));
+    }
 }
@@ -0,0 +1,359 @@
+use crate::{
+    environment::Environment,
+    storage::{
+        disk::DiskResolver, Locator, LocatorPath, Object, ObjectReader, ObjectWriter, Storage, StorageLocator,
+        StorageObject,
+    },
+    CoordinatorError, CoordinatorState,
+};
+
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+use tracing::{error, trace};
+
+/// The objects of a [Memory] storage, shared with its writers.
+type Objects = Arc<RwLock<HashMap<Locator, Vec<u8>>>>;
+
+/// A storage keeping the objects of the ceremony in memory, so that tests
+/// don't have to share the transcript directory on disk.
+///
+/// The locator paths are the ones the [Disk](super::Disk) storage would use
+/// for the same environment.
+#[derive(Debug)]
+pub struct Memory {
+    environment: Environment,
+    resolver: DiskResolver,
+    objects: Objects,
+    staged: HashMap<Locator, Vec<u8>>,
+}
+
+impl Memory {
+    /// Loads a new, empty instance of `Memory`.
+    pub fn load(environment: &Environment) -> Result<Self, CoordinatorError> {
+        trace!("Loading memory storage");
+
+        let mut storage = Self {
+            environment: environment.clone(),
+            resolver: DiskResolver::new(environment.local_base_directory()),
+            objects: Default::default(),
+            staged: HashMap::new(),
+        };
+
+        storage.insert(
+            Locator::CoordinatorState,
+            Object::CoordinatorState(CoordinatorState::new(environment.clone())),
+        )?;
+        storage.insert(
+            Locator::ContributionsInfoSummary,
+            Object::ContributionsInfoSummary(vec![]),
+        )?;
+
+        trace!("Loaded memory storage");
+        Ok(storage)
+    }
+
+    /// Returns the locators of all the objects in storage.
+    pub fn locators(&self) -> Vec<Locator> {
+        self.read().keys().cloned().collect()
+    }
+
+    fn read(&self) -> RwLockReadGuard<HashMap<Locator, Vec<u8>>> {
+        self.objects.read().expect("Unable to obtain lock to read the storage")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<HashMap<Locator, Vec<u8>>> {
+        self.objects
+            .write()
+            .expect("Unable to obtain lock to write the storage")
+    }
+}
+
+impl Storage for Memory {
+    fn initialize(&mut self, locator: Locator, size: u64) -> Result<(), CoordinatorError> {
+        trace!("Initializing {:?}", locator);
+
+        // Check that the locator does not already exist in storage.
+        if self.exists(&locator) {
+            error!(
+                "Locator {:?} in call to initialize() already exists in storage.",
+                locator
+            );
+            return Err(CoordinatorError::StorageLocatorAlreadyExists);
+        }
+
+        self.write().insert(locator, vec![0; size as usize]);
+        Ok(())
+    }
+
+    fn exists(&self, locator: &Locator) -> bool {
+        self.read().contains_key(locator)
+    }
+
+    fn get(&self, locator: &Locator) -> Result<Object, CoordinatorError> {
+        trace!("Fetching {:?}", locator);
+
+        let bytes = self.read().get(locator).cloned().ok_or_else(|| {
+            error!("Locator missing in call to get() in storage - {:?}", locator);
+            CoordinatorError::StorageLocatorMissing
+        })?;
+
+        Object::from_bytes(&self.environment, locator, bytes)
+    }
+
+    fn update(&mut self, locator: &Locator, object: Object) -> Result<(), CoordinatorError> {
+        trace!("Updating {:?}", locator);
+
+        match self.write().get_mut(locator) {
+            Some(bytes) => *bytes = object.to_bytes(),
+            None => {
+                error!("Locator missing in call to update() in storage.");
+                return Err(CoordinatorError::StorageLocatorMissing);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove(&mut self, locator: &Locator) -> Result<(), CoordinatorError> {
+        trace!("Removing {:?}", locator);
+
+        match self.write().remove(locator) {
+            Some(_) => Ok(()),
+            None => {
+                error!("Locator in call to remove() doesn't exist in storage.");
+                Err(CoordinatorError::StorageLocatorMissing)
+            }
+        }
+    }
+
+    fn size(&self, locator: &Locator) -> Result<u64, CoordinatorError> {
+        self.read()
+            .get(locator)
+            .map(|bytes| bytes.len() as u64)
+            .ok_or(CoordinatorError::StorageLocatorMissing)
+    }
+
+    fn staged_size(&self, locator: &Locator) -> Result<u64, CoordinatorError> {
+        Ok(self.staged.get(locator).map_or(0, |bytes| bytes.len() as u64))
+    }
+
+    fn stage(&mut self, locator: &Locator, offset: u64, bytes: &[u8]) -> Result<u64, CoordinatorError> {
+        trace!("Staging {} bytes at offset {} of {:?}", bytes.len(), offset, locator);
+
+        // Check that the given locator exists in storage.
+        if !self.exists(locator) {
+            error!("Locator missing in call to stage() in storage.");
+            return Err(CoordinatorError::StorageLocatorMissing);
+        }
+
+        let staged_size = if offset == 0 { 0 } else { self.staged_size(locator)? };
+        if offset != staged_size {
+            return Err(CoordinatorError::StorageUploadOffsetMismatch {
+                expected: staged_size,
+                actual: offset,
+            });
+        }
+
+        let staged = self.staged.entry(*locator).or_default();
+        if offset == 0 {
+            staged.clear();
+        }
+        staged.extend_from_slice(bytes);
+
+        Ok(staged.len() as u64)
+    }
+
+    fn get_staged(&self, locator: &Locator) -> Result<Vec<u8>, CoordinatorError> {
+        self.staged
+            .get(locator)
+            .cloned()
+            .ok_or(CoordinatorError::StorageLocatorMissing)
+    }
+
+    fn clear_staged(&mut self, locator: &Locator) -> Result<(), CoordinatorError> {
+        self.staged.remove(locator);
+        Ok(())
+    }
+
+    fn clear_round_files(&mut self, round_height: u64) {
+        self.write()
+            .retain(|locator, _| !is_cleared_with_round(locator, round_height));
+        self.staged
+            .retain(|locator, _| !is_cleared_with_round(locator, round_height));
+
+        // Delete contribution file and trim contribution summary file if these files exist
+        self.clear_info_files(round_height);
+    }
+}
+
+/// Mirrors the files removed by [Disk](super::Disk) when clearing a round: everything in the
+/// next round and everything but the initial challenges in the given round. The round states are kept.
+fn is_cleared_with_round(locator: &Locator, round_height: u64) -> bool {
+    let (height, contribution_id) = match locator {
+        Locator::RoundFile { round_height } => (*round_height, None),
+        Locator::ContributionFile(locator) => (locator.round_height(), Some(locator.contribution_id())),
+        Locator::ContributionFileSignature(locator) => (locator.round_height(), Some(locator.contribution_id())),
+        _ => return false,
+    };
+
+    height == round_height + 1 || (height == round_height && contribution_id != Some(0))
+}
+
+impl StorageLocator for Memory {
+    #[inline]
+    fn to_path(&self, locator: &Locator) -> Result<LocatorPath, CoordinatorError> {
+        self.resolver.to_path(locator)
+    }
+
+    #[inline]
+    fn to_locator(&self, path: &LocatorPath) -> Result<Locator, CoordinatorError> {
+        self.resolver.to_locator(path)
+    }
+}
+
+pub struct MemoryObjectReader {
+    data: Vec<u8>,
+}
+
+impl Deref for MemoryObjectReader {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &*self.data
+    }
+}
+
+impl AsRef<[u8]> for MemoryObjectReader {
+    fn as_ref(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+}
+
+impl ObjectReader for MemoryObjectReader {}
+
+/// A copy of an object, written back to the storage when flushed or dropped.
+pub struct MemoryObjectWriter {
+    locator: Locator,
+    data: Vec<u8>,
+    objects: Objects,
+}
+
+impl Deref for MemoryObjectWriter {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &*self.data
+    }
+}
+
+impl DerefMut for MemoryObjectWriter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut *self.data
+    }
+}
+
+impl AsMut<[u8]> for MemoryObjectWriter {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.data.as_mut()
+    }
+}
+
+impl ObjectWriter for MemoryObjectWriter {
+    fn flush(&self) -> std::io::Result<()> {
+        self.objects
+            .write()
+            .expect("Unable to obtain lock to write the storage")
+            .insert(self.locator, self.data.clone());
+
+        Ok(())
+    }
+}
+
+impl Drop for MemoryObjectWriter {
+    fn drop(&mut self) {
+        // Writes through the writer are visible without flushing, as with a memory map.
+        let _ = self.flush();
+    }
+}
+
+impl StorageObject for Memory {
+    type Reader = MemoryObjectReader;
+    type Writer = MemoryObjectWriter;
+
+    /// Returns an object reader for the given locator.
+    fn reader(&self, locator: &Locator) -> Result<Self::Reader, CoordinatorError> {
+        let data = self.read().get(locator).cloned().ok_or_else(|| {
+            error!("Locator {:?} missing in call to reader() in storage.", locator);
+            CoordinatorError::StorageLocatorMissing
+        })?;
+
+        Ok(MemoryObjectReader { data })
+    }
+
+    /// Returns an object writer for the given locator.
+    fn writer(&self, locator: &Locator) -> Result<Self::Writer, CoordinatorError> {
+        let data = self.read().get(locator).cloned().ok_or_else(|| {
+            error!("Locator {:?} missing in call to writer() in storage.", locator);
+            CoordinatorError::StorageLocatorMissing
+        })?;
+
+        Ok(MemoryObjectWriter {
+            locator: *locator,
+            data,
+            objects: self.objects.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage::ContributionLocator, testing::prelude::*};
+
+    #[test]
+    fn test_writer() {
+        let mut storage = Memory::load(&TEST_ENVIRONMENT_ANOMA).unwrap();
+
+        let locator = Locator::ContributionFile(ContributionLocator::new(1, 0, 1, false));
+        storage.initialize(locator, 4).unwrap();
+        assert!(storage.initialize(locator, 4).is_err());
+
+        // Writes are visible once the writer is dropped
+        storage
+            .writer(&locator)
+            .unwrap()
+            .as_mut()
+            .copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(storage.reader(&locator).unwrap().to_vec(), vec![1, 2, 3, 4]);
+
+        // The paths are the ones of the disk storage
+        let path = storage.to_path(&locator).unwrap();
+        assert_eq!(storage.to_locator(&path).unwrap(), locator);
+
+        storage.remove(&locator).unwrap();
+        assert!(!storage.exists(&locator));
+    }
+
+    #[test]
+    fn test_clear_round_files() {
+        let mut storage = Memory::load(&TEST_ENVIRONMENT_ANOMA).unwrap();
+
+        let initial_challenge = Locator::ContributionFile(ContributionLocator::new(1, 0, 0, true));
+        let contribution = Locator::ContributionFile(ContributionLocator::new(1, 0, 1, false));
+        let next_challenge = Locator::ContributionFile(ContributionLocator::new(2, 0, 0, true));
+        for locator in &[initial_challenge, contribution, next_challenge] {
+            storage.initialize(*locator, 4).unwrap();
+        }
+        storage.stage(&contribution, 0, &[1, 2]).unwrap();
+
+        storage.clear_round_files(1);
+        assert!(storage.exists(&initial_challenge));
+        assert!(!storage.exists(&contribution));
+        assert!(!storage.exists(&next_challenge));
+        assert_eq!(storage.staged_size(&contribution).unwrap(), 0);
+        assert!(storage.exists(&Locator::CoordinatorState));
+    }
+}
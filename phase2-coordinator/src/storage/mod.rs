@@ -1,5 +1,25 @@
+pub mod archive;
+pub use archive::*;
+
+pub mod audit;
+pub use audit::*;
+
 pub mod disk;
 pub use disk::*;
 
+pub mod memory;
+pub use memory::*;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::*;
+
 pub mod storage;
 pub use storage::*;
+
+/// The storage of the coordinator, [Disk] or [Sqlite] with the `sqlite` feature.
+#[cfg(not(feature = "sqlite"))]
+pub type DefaultStorage = Disk;
+#[cfg(feature = "sqlite")]
+pub type DefaultStorage = Sqlite;
@@ -0,0 +1,629 @@
+//! A storage keeping the records of the ceremony in a SQLite database, enabled with the `sqlite` feature.
+//!
+//! The records (coordinator state, round height, round states and contribution info) are JSON documents split
+//! into entries, one per field and one per element of each map or list field, addressed by their JSON pointer.
+//! Only the entries that changed are written on update, and every change is appended to the `history` table:
+//!
+//! ```sql
+//! SELECT datetime(updated_at, 'unixepoch'), path, value FROM history
+//! WHERE document = 'coordinator_state' AND path LIKE '/queue/%' ORDER BY id;
+//! ```
+//!
+//! The contribution files and their signatures are kept on disk, in the layout of the [Disk] storage.
+
+use crate::{
+    environment::Environment,
+    storage::{
+        disk::{DiskObjectReader, DiskObjectWriter},
+        Disk, Locator, LocatorPath, Object, ObjectReader, Storage, StorageLocator, StorageObject,
+    },
+    CoordinatorError, CoordinatorState,
+};
+
+use fs_err as fs;
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use time::OffsetDateTime;
+use tracing::{error, info, trace};
+
+/// The database file, in the base directory of the environment.
+pub const DATABASE_FILE: &str = "coordinator.db";
+
+const SCHEMA: &str = "
+    PRAGMA journal_mode = WAL;
+    PRAGMA synchronous = FULL;
+
+    CREATE TABLE IF NOT EXISTS entries (
+        document TEXT NOT NULL,
+        path TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (document, path)
+    );
+
+    CREATE TABLE IF NOT EXISTS history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        updated_at INTEGER NOT NULL,
+        document TEXT NOT NULL,
+        path TEXT NOT NULL,
+        value TEXT
+    );
+    CREATE INDEX IF NOT EXISTS history_document ON history (document, path);
+";
+
+/// The entries of a document, by JSON pointer.
+type Entries = BTreeMap<String, String>;
+
+/// A change of an entry of a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// The unix timestamp of the change, in seconds.
+    pub updated_at: i64,
+    /// The JSON pointer of the entry in the record.
+    pub path: String,
+    /// The JSON value of the entry, `None` if it was removed.
+    pub value: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Sqlite {
+    environment: Environment,
+    files: Disk,
+    connection: Mutex<Connection>,
+    documents: HashMap<String, Entries>,
+}
+
+impl Sqlite {
+    /// Loads a new instance of `Sqlite`, creating the database if it does not exist yet.
+    pub fn load(environment: &Environment) -> Result<Self, CoordinatorError> {
+        trace!("Loading sqlite storage");
+
+        fs::create_dir_all(environment.local_base_directory())?;
+
+        let connection = Connection::open(Self::database_path(environment))?;
+        connection.execute_batch(SCHEMA)?;
+
+        // The entries are cached to only write the ones that change.
+        let mut documents: HashMap<String, Entries> = HashMap::new();
+        {
+            let mut statement = connection.prepare("SELECT document, path, value FROM entries")?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                documents
+                    .entry(row.get(0)?)
+                    .or_default()
+                    .insert(row.get(1)?, row.get(2)?);
+            }
+        }
+
+        let mut storage = Self {
+            environment: environment.clone(),
+            files: Disk::new(environment),
+            connection: Mutex::new(connection),
+            documents,
+        };
+
+        // Create the coordinator state record if it does not exist yet.
+        if !storage.exists(&Locator::CoordinatorState) {
+            storage.insert(
+                Locator::CoordinatorState,
+                Object::CoordinatorState(CoordinatorState::new(environment.clone())),
+            )?;
+        }
+
+        // Create the contributions summary record if it does not exist yet.
+        if !storage.exists(&Locator::ContributionsInfoSummary) {
+            storage.insert(
+                Locator::ContributionsInfoSummary,
+                Object::ContributionsInfoSummary(vec![]),
+            )?;
+        }
+
+        trace!("Loaded sqlite storage");
+        Ok(storage)
+    }
+
+    /// Returns the path of the database of the given environment.
+    pub fn database_path(environment: &Environment) -> PathBuf {
+        Path::new(environment.local_base_directory()).join(DATABASE_FILE)
+    }
+
+    ///
+    /// Copies the records of the [Disk] storage of the environment, kept in JSON files, to a new database
+    /// next to them. Returns the number of records migrated.
+    ///
+    /// The contribution files are shared by both storages and are left in place.
+    ///
+    pub fn migrate(environment: &Environment) -> Result<usize, CoordinatorError> {
+        let path = Self::database_path(environment);
+        if path.exists() {
+            error!("The database {} already exists", path.display());
+            return Err(CoordinatorError::StorageLocatorAlreadyExists);
+        }
+
+        let result = Self::copy_records(environment);
+        if result.is_err() {
+            // Don't leave a partial database behind.
+            if let Err(e) = fs::remove_file(&path) {
+                error!("Could not remove the partial database - {}", e);
+            }
+        }
+
+        result
+    }
+
+    fn copy_records(environment: &Environment) -> Result<usize, CoordinatorError> {
+        let disk = Disk::load(environment)?;
+        let mut storage = Self::load(environment)?;
+
+        let mut locators = vec![
+            Locator::CoordinatorState,
+            Locator::ContributionsInfoSummary,
+            Locator::RoundHeight,
+        ];
+        if let Ok(Object::RoundHeight(current_round_height)) = disk.get(&Locator::RoundHeight) {
+            for round_height in 0..=current_round_height {
+                locators.push(Locator::RoundState { round_height });
+                locators.push(Locator::ContributionInfoFile { round_height });
+            }
+        }
+
+        let mut migrated = 0;
+        for locator in locators.iter().filter(|locator| disk.exists(locator)) {
+            let object = disk.get(locator)?;
+            match storage.exists(locator) {
+                true => storage.update(locator, object)?,
+                false => storage.insert(*locator, object)?,
+            }
+            info!("Migrated {}", disk.to_path(locator)?);
+            migrated += 1;
+        }
+
+        Ok(migrated)
+    }
+
+    /// Returns the changes of the record at the given locator, from the oldest to the newest.
+    pub fn history(&self, locator: &Locator) -> Result<Vec<HistoryEntry>, CoordinatorError> {
+        let document = document(locator).ok_or(CoordinatorError::StorageLocatorFormatIncorrect)?;
+
+        let connection = self.connection.lock().expect("Unable to obtain lock on the database");
+        let mut statement =
+            connection.prepare("SELECT updated_at, path, value FROM history WHERE document = ?1 ORDER BY id")?;
+        let history = statement
+            .query_map([document], |row| {
+                Ok(HistoryEntry {
+                    updated_at: row.get(0)?,
+                    path: row.get(1)?,
+                    value: row.get(2)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(history)
+    }
+
+    /// Returns the JSON serialization of the record at the given locator.
+    fn record_bytes(&self, locator: &Locator, document: &str) -> Result<Vec<u8>, CoordinatorError> {
+        let entries = self.documents.get(document).ok_or_else(|| {
+            error!("Locator missing in call to get() in storage - {:?}", locator);
+            CoordinatorError::StorageLocatorMissing
+        })?;
+
+        Ok(serde_json::to_vec(&from_entries(entries)?)?)
+    }
+
+    /// Replaces the entries of the given document, writing only the ones that changed.
+    fn write_entries(&mut self, document: &str, entries: Entries) -> Result<(), CoordinatorError> {
+        let empty = Entries::new();
+        let previous = self.documents.get(document).unwrap_or(&empty);
+        let updated_at = OffsetDateTime::now_utc().unix_timestamp();
+
+        let mut connection = self.connection.lock().expect("Unable to obtain lock on the database");
+        let transaction = connection.transaction()?;
+        {
+            let mut upsert = transaction.prepare_cached(
+                "INSERT INTO entries (document, path, value) VALUES (?1, ?2, ?3)
+                ON CONFLICT (document, path) DO UPDATE SET value = excluded.value",
+            )?;
+            let mut delete = transaction.prepare_cached("DELETE FROM entries WHERE document = ?1 AND path = ?2")?;
+            let mut record = transaction
+                .prepare_cached("INSERT INTO history (updated_at, document, path, value) VALUES (?1, ?2, ?3, ?4)")?;
+
+            for (path, value) in &entries {
+                if previous.get(path) != Some(value) {
+                    upsert.execute(params![document, path, value])?;
+                    record.execute(params![updated_at, document, path, Some(value)])?;
+                }
+            }
+            for path in previous.keys().filter(|path| !entries.contains_key(*path)) {
+                delete.execute(params![document, path])?;
+                record.execute(params![updated_at, document, path, None::<String>])?;
+            }
+        }
+        transaction.commit()?;
+
+        self.documents.insert(document.to_string(), entries);
+        Ok(())
+    }
+}
+
+impl Storage for Sqlite {
+    /// Initializes the location corresponding to the given locator.
+    fn initialize(&mut self, locator: Locator, size: u64) -> Result<(), CoordinatorError> {
+        let document = match document(&locator) {
+            Some(document) => document,
+            None => return self.files.initialize(locator, size),
+        };
+        trace!("Initializing {}", document);
+
+        // Check that the locator does not already exist in storage.
+        if self.exists(&locator) {
+            error!(
+                "Locator {:?} in call to initialize() already exists in storage.",
+                locator
+            );
+            return Err(CoordinatorError::StorageLocatorAlreadyExists);
+        }
+
+        // The record is empty until its first update.
+        self.write_entries(&document, Entries::from([(String::new(), String::new())]))
+    }
+
+    /// Checks whether the given locator exists in the storage or not.
+    fn exists(&self, locator: &Locator) -> bool {
+        match document(locator) {
+            Some(document) => self.documents.contains_key(&document),
+            None => self.files.exists(locator),
+        }
+    }
+
+    /// Returns a copy of an object at the given locator in storage, if it exists.
+    fn get(&self, locator: &Locator) -> Result<Object, CoordinatorError> {
+        match document(locator) {
+            Some(document) => {
+                trace!("Fetching {}", document);
+                let bytes = self.record_bytes(locator, &document)?;
+                Object::from_bytes(&self.environment, locator, bytes)
+            }
+            None => self.files.get(locator),
+        }
+    }
+
+    /// Updates an existing object for the given locator in storage, if it exists.
+    fn update(&mut self, locator: &Locator, object: Object) -> Result<(), CoordinatorError> {
+        let document = match document(locator) {
+            Some(document) => document,
+            None => return self.files.update(locator, object),
+        };
+        trace!("Updating {}", document);
+
+        // Check that the given locator exists in storage.
+        if !self.exists(locator) {
+            error!("Locator missing in call to update() in storage.");
+            return Err(CoordinatorError::StorageLocatorMissing);
+        }
+
+        let value: Value = serde_json::from_slice(&object.to_bytes())?;
+        self.write_entries(&document, to_entries(&value))
+    }
+
+    /// Removes the object corresponding to the given locator from storage.
+    fn remove(&mut self, locator: &Locator) -> Result<(), CoordinatorError> {
+        let document = match document(locator) {
+            Some(document) => document,
+            None => return self.files.remove(locator),
+        };
+        trace!("Removing {}", document);
+
+        // Check that the locator exists in storage.
+        if !self.exists(locator) {
+            error!("Locator in call to remove() doesn't exist in storage.");
+            return Err(CoordinatorError::StorageLocatorMissing);
+        }
+
+        self.write_entries(&document, Entries::new())?;
+        self.documents.remove(&document);
+
+        Ok(())
+    }
+
+    /// Returns the size of the object stored at the given locator.
+    fn size(&self, locator: &Locator) -> Result<u64, CoordinatorError> {
+        match document(locator) {
+            Some(document) => Ok(self.record_bytes(locator, &document)?.len() as u64),
+            None => self.files.size(locator),
+        }
+    }
+
+    /// Returns the number of bytes received so far for an object uploaded in multiple parts at the given locator.
+    fn staged_size(&self, locator: &Locator) -> Result<u64, CoordinatorError> {
+        self.files.staged_size(locator)
+    }
+
+    /// Appends a part of the object uploaded at the given locator to its staging file.
+    fn stage(&mut self, locator: &Locator, offset: u64, bytes: &[u8]) -> Result<u64, CoordinatorError> {
+        self.files.stage(locator, offset, bytes)
+    }
+
+    /// Returns the content of the staging file of the given locator.
+    fn get_staged(&self, locator: &Locator) -> Result<Vec<u8>, CoordinatorError> {
+        self.files.get_staged(locator)
+    }
+
+    /// Removes the staging file of the given locator, if any.
+    fn clear_staged(&mut self, locator: &Locator) -> Result<(), CoordinatorError> {
+        self.files.clear_staged(locator)
+    }
+
+    /// Clears all files related to a round - used for round reset purposes.
+    fn clear_round_files(&mut self, round_height: u64) {
+        self.files.clear_round_directories(round_height);
+
+        // Delete contribution record and trim contribution summary record if these records exist
+        self.clear_info_files(round_height);
+    }
+
+    fn archive_round(&mut self, round_height: u64) -> Result<(), CoordinatorError> {
+        self.files.archive_round(round_height)
+    }
+}
+
+/// Returns the name of the document of the record at the given locator, `None` for the locators kept on disk.
+fn document(locator: &Locator) -> Option<String> {
+    match locator {
+        Locator::CoordinatorState => Some(String::from("coordinator_state")),
+        Locator::RoundHeight => Some(String::from("round_height")),
+        Locator::RoundState { round_height } => Some(format!("round_{}", round_height)),
+        Locator::ContributionInfoFile { round_height } => Some(format!("contribution_info_{}", round_height)),
+        Locator::ContributionsInfoSummary => Some(String::from("contributions_summary")),
+        Locator::RoundFile { .. } | Locator::ContributionFile(_) | Locator::ContributionFileSignature(_) => None,
+    }
+}
+
+///
+/// Splits a JSON document in entries: the document itself, its fields, and the fields of its fields. Maps and
+/// lists are stored empty, their fields being separate entries. The fields of lists are keyed by their zero
+/// padded index, so that the entries are sorted like the list.
+///
+fn to_entries(document: &Value) -> Entries {
+    let mut entries = Entries::new();
+    entries.insert(String::new(), shallow(document));
+
+    for (field, value) in fields(document) {
+        let path = format!("/{}", field);
+        for (key, value) in fields(value) {
+            entries.insert(format!("{}/{}", path, key), value.to_string());
+        }
+        entries.insert(path, shallow(value));
+    }
+
+    entries
+}
+
+/// Rebuilds a JSON document from its entries.
+fn from_entries(entries: &Entries) -> Result<Value, serde_json::Error> {
+    let mut document: Value = serde_json::from_str(entries.get("").map(String::as_str).unwrap_or_default())?;
+
+    // The fields are inserted before their own fields, each in the order of their entries.
+    for depth in 1..=2 {
+        for (path, value) in entries {
+            let segments: Vec<String> = path.split('/').skip(1).map(unescape).collect();
+            if segments.len() != depth {
+                continue;
+            }
+
+            let parent = match segments.split_last() {
+                Some((_, [])) => Some(&mut document),
+                Some((_, [field])) => field_mut(&mut document, field),
+                _ => None,
+            };
+            if let Some(parent) = parent {
+                insert_field(parent, &segments[depth - 1], serde_json::from_str(value)?);
+            }
+        }
+    }
+
+    Ok(document)
+}
+
+/// Returns the value with its maps and lists emptied.
+fn shallow(value: &Value) -> String {
+    match value {
+        Value::Object(_) => String::from("{}"),
+        Value::Array(_) => String::from("[]"),
+        value => value.to_string(),
+    }
+}
+
+/// Returns the fields of a map or list, with their escaped keys.
+fn fields(value: &Value) -> Vec<(String, &Value)> {
+    match value {
+        Value::Object(map) => map.iter().map(|(key, value)| (escape(key), value)).collect(),
+        Value::Array(list) => list
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (format!("{:010}", index), value))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn field_mut<'a>(value: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Object(map) => map.get_mut(key),
+        Value::Array(list) => key.parse::<usize>().ok().and_then(move |index| list.get_mut(index)),
+        _ => None,
+    }
+}
+
+fn insert_field(value: &mut Value, key: &str, field: Value) {
+    match value {
+        Value::Object(map) => {
+            map.insert(key.to_string(), field);
+        }
+        Value::Array(list) => list.push(field),
+        _ => {}
+    }
+}
+
+/// Escapes a key as a segment of a JSON pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+impl StorageLocator for Sqlite {
+    #[inline]
+    fn to_path(&self, locator: &Locator) -> Result<LocatorPath, CoordinatorError> {
+        self.files.to_path(locator)
+    }
+
+    #[inline]
+    fn to_locator(&self, path: &LocatorPath) -> Result<Locator, CoordinatorError> {
+        self.files.to_locator(path)
+    }
+}
+
+pub enum SqliteObjectReader {
+    File(DiskObjectReader),
+    Record(Vec<u8>),
+}
+
+impl Deref for SqliteObjectReader {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            SqliteObjectReader::File(reader) => reader,
+            SqliteObjectReader::Record(data) => data,
+        }
+    }
+}
+
+impl AsRef<[u8]> for SqliteObjectReader {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl ObjectReader for SqliteObjectReader {}
+
+impl StorageObject for Sqlite {
+    type Reader = SqliteObjectReader;
+    type Writer = DiskObjectWriter;
+
+    /// Returns an object reader for the given locator.
+    fn reader(&self, locator: &Locator) -> Result<Self::Reader, CoordinatorError> {
+        match document(locator) {
+            Some(document) => Ok(SqliteObjectReader::Record(self.record_bytes(locator, &document)?)),
+            None => Ok(SqliteObjectReader::File(self.files.reader(locator)?)),
+        }
+    }
+
+    /// Returns an object writer for the given locator. The records can only be written with [Storage::update].
+    fn writer(&self, locator: &Locator) -> Result<Self::Writer, CoordinatorError> {
+        if document(locator).is_some() {
+            error!("Locator {:?} in call to writer() is a database record.", locator);
+            return Err(CoordinatorError::StorageFailed);
+        }
+
+        self.files.writer(locator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{objects::Round, storage::ContributionLocator, testing::prelude::*};
+
+    #[test]
+    fn test_entries() {
+        let document = serde_json::json!({
+            "height": 3,
+            "queue": {"a/b": [1, 2], "c~": null},
+            "dropped": ["x", "y", "z", "w", "v", "u", "t", "s", "r", "q", "p"],
+            "empty": {},
+        });
+
+        let entries = to_entries(&document);
+        assert_eq!(entries[""], "{}");
+        assert_eq!(entries["/queue"], "{}");
+        assert_eq!(entries["/queue/a~1b"], "[1,2]");
+        assert_eq!(entries["/dropped/0000000010"], "\"p\"");
+        assert_eq!(from_entries(&entries).unwrap(), document);
+
+        assert_eq!(
+            from_entries(&to_entries(&serde_json::json!(7))).unwrap(),
+            serde_json::json!(7)
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_incremental_update() {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+        let mut storage = Sqlite::load(&TEST_ENVIRONMENT_ANOMA).unwrap();
+
+        let round: Round = test_round_0_json().unwrap();
+        let locator = Locator::RoundState { round_height: 0 };
+        storage.insert(locator, Object::RoundState(round.clone())).unwrap();
+        let inserted = storage.history(&locator).unwrap().len();
+
+        // Updating a record with the same object doesn't write anything
+        storage.update(&locator, Object::RoundState(round.clone())).unwrap();
+        assert_eq!(storage.history(&locator).unwrap().len(), inserted);
+
+        // The records are loaded back from the database
+        let storage = Sqlite::load(&TEST_ENVIRONMENT_ANOMA).unwrap();
+        match storage.get(&locator).unwrap() {
+            Object::RoundState(stored) => assert_eq!(stored, round),
+            _ => panic!("Unexpected object"),
+        }
+
+        // The contribution files are kept on disk
+        let mut storage = storage;
+        let contribution = Locator::ContributionFile(ContributionLocator::new(1, 0, 1, false));
+        storage.initialize(contribution, 4).unwrap();
+        assert!(Path::new(&storage.to_path(&contribution).unwrap().to_string()).exists());
+
+        storage.remove(&locator).unwrap();
+        assert!(!storage.exists(&locator));
+        assert!(storage.history(&locator).unwrap().last().unwrap().value.is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_migrate() {
+        initialize_test_environment(&TEST_ENVIRONMENT_ANOMA);
+        let mut disk = test_storage(&TEST_ENVIRONMENT_ANOMA);
+
+        let round: Round = test_round_0_json().unwrap();
+        disk.insert(Locator::RoundHeight, Object::RoundHeight(0)).unwrap();
+        disk.insert(
+            Locator::RoundState { round_height: 0 },
+            Object::RoundState(round.clone()),
+        )
+        .unwrap();
+
+        assert_eq!(Sqlite::migrate(&TEST_ENVIRONMENT_ANOMA).unwrap(), 4);
+        assert!(Sqlite::migrate(&TEST_ENVIRONMENT_ANOMA).is_err());
+
+        let storage = Sqlite::load(&TEST_ENVIRONMENT_ANOMA).unwrap();
+        match storage.get(&Locator::RoundHeight).unwrap() {
+            Object::RoundHeight(round_height) => assert_eq!(round_height, 0),
+            _ => panic!("Unexpected object"),
+        }
+        match storage.get(&Locator::RoundState { round_height: 0 }).unwrap() {
+            Object::RoundState(stored) => assert_eq!(stored, round),
+            _ => panic!("Unexpected object"),
+        }
+    }
+}
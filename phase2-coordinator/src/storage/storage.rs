@@ -1,5 +1,5 @@
 use crate::{
-    environment::Environment,
+    environment::{CircuitSet, Environment},
     objects::{ContributionFileSignature, ContributionInfo, Round, TrimmedContributionInfo},
     CoordinatorError, CoordinatorState,
 };
@@ -12,8 +12,7 @@ use std::{
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
 };
-
-use super::Disk;
+use tracing::{debug, error, trace};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ContributionLocator {
@@ -23,17 +22,6 @@ pub struct ContributionLocator {
     is_verified: bool,
 }
 
-// Parameters generated from `masp-mpc` crate have size 84_720_180, to this add the needed 64 bytes for the hash of the contribution that is placed at the head of the contribution file.
-#[cfg(not(debug_assertions))]
-pub const ANOMA_BASE_FILE_SIZE: u64 = 84_720_244; // 145_449_460 prod: 84_720_244, testing: 2_332
-#[cfg(debug_assertions)]
-pub const ANOMA_BASE_FILE_SIZE: u64 = 2_332; // prod: 84_720_244, testing: 2_332
-                                             // With `masp-mpc` the contribution file grows by 1632 bytes on each new contribution
-#[cfg(not(debug_assertions))]
-pub const ANOMA_PER_ROUND_FILE_SIZE_INCREASE: u64 = 1_632; // prod: 1_632, testing: 544
-#[cfg(debug_assertions)]
-pub const ANOMA_PER_ROUND_FILE_SIZE_INCREASE: u64 = 544; // prod: 1_632, testing: 544
-
 impl ContributionLocator {
     pub fn new(round_height: u64, chunk_id: u64, contribution_id: u64, is_verified: bool) -> Self {
         Self {
@@ -156,6 +144,91 @@ impl Object {
         }
     }
 
+    /// Parses the bytes stored at the given locator into an object, checking
+    /// the size of the ceremony files against the given environment.
+    pub fn from_bytes(environment: &Environment, locator: &Locator, bytes: Vec<u8>) -> Result<Self, CoordinatorError> {
+        match locator {
+            Locator::CoordinatorState => {
+                let coordinator_state: CoordinatorState = serde_json::from_slice(&bytes)?;
+                Ok(Object::CoordinatorState(coordinator_state))
+            }
+            Locator::RoundHeight => {
+                let round_height: u64 = serde_json::from_slice(&bytes)?;
+                Ok(Object::RoundHeight(round_height))
+            }
+            Locator::RoundState { round_height: _ } => {
+                let round: Round = serde_json::from_slice(&bytes)?;
+                Ok(Object::RoundState(round))
+            }
+            Locator::RoundFile { round_height } => {
+                // Check that the round size is correct.
+                let expected_size = Object::round_file_size(environment)?;
+                let found_size = bytes.len() as u64;
+                debug!("Round {} filesize is {}", round_height, found_size);
+                if found_size == 0 || expected_size != found_size {
+                    error!("Round file size should be {} but found {}", expected_size, found_size);
+                    return Err(CoordinatorError::RoundFileSizeMismatch);
+                }
+
+                Ok(Object::RoundFile(bytes))
+            }
+            Locator::ContributionFile(contribution_locator) => {
+                // Check that the contribution size is correct.
+                let expected_size = Object::anoma_contribution_file_size(
+                    environment.parameters().circuits(),
+                    contribution_locator.round_height(),
+                    contribution_locator.contribution_id(),
+                )?;
+                let found_size = bytes.len() as u64;
+                debug!(
+                    "Round {} chunk {} filesize is {}",
+                    contribution_locator.round_height(),
+                    contribution_locator.chunk_id(),
+                    found_size
+                );
+                if found_size == 0 || expected_size != found_size {
+                    error!(
+                        "Contribution file size should be {} but found {}",
+                        expected_size, found_size
+                    );
+                    return Err(CoordinatorError::ContributionFileSizeMismatch);
+                }
+
+                Ok(Object::ContributionFile(bytes))
+            }
+            Locator::ContributionFileSignature(contribution_locator) => {
+                // Check that the contribution file signature size is correct.
+                let expected_size = Object::contribution_file_signature_size(contribution_locator.is_verified());
+                let found_size = bytes.len() as u64;
+                debug!(
+                    "Round {} chunk {} contribution {} signature filesize is {}",
+                    contribution_locator.round_height(),
+                    contribution_locator.chunk_id(),
+                    contribution_locator.contribution_id(),
+                    found_size
+                );
+                if found_size == 0 || expected_size != found_size {
+                    error!(
+                        "Contribution signature file size should be {} but found {}",
+                        expected_size, found_size
+                    );
+                    return Err(CoordinatorError::ContributionSignatureFileSizeMismatch);
+                }
+
+                let contribution_file_signature: ContributionFileSignature = serde_json::from_slice(&bytes)?;
+                Ok(Object::ContributionFileSignature(contribution_file_signature))
+            }
+            Locator::ContributionInfoFile { round_height: _ } => {
+                let contribution_info: ContributionInfo = serde_json::from_slice(&bytes)?;
+                Ok(Object::ContributionInfoFile(contribution_info))
+            }
+            Locator::ContributionsInfoSummary => {
+                let summary: Vec<TrimmedContributionInfo> = serde_json::from_slice(&bytes)?;
+                Ok(Object::ContributionsInfoSummary(summary))
+            }
+        }
+    }
+
     /// Returns the size in bytes of the object.
     pub fn size(&self) -> u64 {
         match self {
@@ -171,20 +244,24 @@ impl Object {
     }
 
     /// Returns the expected file size of an aggregated round.
-    pub fn round_file_size(environment: &Environment) -> u64 {
+    pub fn round_file_size(environment: &Environment) -> Result<u64, CoordinatorError> {
         let compressed = environment.compressed_inputs();
         let settings = environment.parameters();
 
-        match settings.curve() {
+        Ok(match settings.curve() {
             // TODO: change round_filesize
-            CurveKind::Bls12_381 => ANOMA_BASE_FILE_SIZE,
+            CurveKind::Bls12_381 => settings.circuits().base_file_size()?,
             CurveKind::Bls12_377 => round_filesize!(Bls12_377, settings, compressed),
             CurveKind::BW6 => round_filesize!(BW6_761, settings, compressed),
-        }
+        })
     }
 
     /// Returns the expected file size of a chunked contribution.
-    pub fn contribution_file_size(environment: &Environment, chunk_id: u64, verified: bool) -> u64 {
+    pub fn contribution_file_size(
+        environment: &Environment,
+        chunk_id: u64,
+        verified: bool,
+    ) -> Result<u64, CoordinatorError> {
         let settings = environment.parameters();
         let curve = settings.curve();
 
@@ -195,23 +272,30 @@ impl Object {
             false => environment.compressed_outputs(),
         };
 
-        match (curve, verified) {
+        Ok(match (curve, verified) {
             // TODO: add correct verified_contribution_size
-            (CurveKind::Bls12_381, true) => ANOMA_BASE_FILE_SIZE,
-            (CurveKind::Bls12_381, false) => ANOMA_BASE_FILE_SIZE,
+            (CurveKind::Bls12_381, true) => settings.circuits().base_file_size()?,
+            (CurveKind::Bls12_381, false) => settings.circuits().base_file_size()?,
             (CurveKind::Bls12_377, true) => verified_contribution_size!(Bls12_377, settings, chunk_id, compressed),
             (CurveKind::Bls12_377, false) => unverified_contribution_size!(Bls12_377, settings, chunk_id, compressed),
             (CurveKind::BW6, true) => verified_contribution_size!(BW6_761, settings, chunk_id, compressed),
             (CurveKind::BW6, false) => unverified_contribution_size!(BW6_761, settings, chunk_id, compressed),
-        }
+        })
     }
 
     /// Returns dynamically the expected file size of a contribution file.
-    pub fn anoma_contribution_file_size(round_height: u64, contribution_id: u64) -> u64 {
-        match round_height {
-            0 => ANOMA_BASE_FILE_SIZE,
-            _ => ANOMA_BASE_FILE_SIZE + (ANOMA_PER_ROUND_FILE_SIZE_INCREASE * (round_height + contribution_id - 1)),
-        }
+    pub fn anoma_contribution_file_size(
+        circuits: &CircuitSet,
+        round_height: u64,
+        contribution_id: u64,
+    ) -> Result<u64, CoordinatorError> {
+        Ok(match round_height {
+            0 => circuits.base_file_size()?,
+            _ => {
+                circuits.base_file_size()?
+                    + (circuits.per_round_file_size_increase() * (round_height + contribution_id - 1))
+            }
+        })
     }
 
     /// Returns the expected file size of a contribution signature.
@@ -301,14 +385,14 @@ pub enum LocatorOrPath {
 }
 
 impl LocatorOrPath {
-    pub fn try_into_locator(self, storage: &Disk) -> Result<Locator, CoordinatorError> {
+    pub fn try_into_locator(self, storage: &(impl StorageLocator + ?Sized)) -> Result<Locator, CoordinatorError> {
         match self {
             LocatorOrPath::Path(path) => storage.to_locator(&path),
             LocatorOrPath::Locator(locator) => Ok(locator),
         }
     }
 
-    pub fn try_into_path(self, storage: &Disk) -> Result<LocatorPath, CoordinatorError> {
+    pub fn try_into_path(self, storage: &(impl StorageLocator + ?Sized)) -> Result<LocatorPath, CoordinatorError> {
         match self {
             LocatorOrPath::Path(path) => Ok(path),
             LocatorOrPath::Locator(locator) => storage.to_path(&locator),
@@ -350,11 +434,11 @@ impl RemoveAction {
 
     /// Obtain the location of the item to be removed from [Storage]
     /// as a [Locator].
-    pub fn try_into_locator(self, storage: &Disk) -> Result<Locator, CoordinatorError> {
+    pub fn try_into_locator(self, storage: &(impl StorageLocator + ?Sized)) -> Result<Locator, CoordinatorError> {
         self.locator_or_path.try_into_locator(storage)
     }
 
-    pub fn try_into_path(self, storage: &Disk) -> Result<LocatorPath, CoordinatorError> {
+    pub fn try_into_path(self, storage: &(impl StorageLocator + ?Sized)) -> Result<LocatorPath, CoordinatorError> {
         self.locator_or_path.try_into_path(storage)
     }
 }
@@ -392,3 +476,153 @@ pub trait StorageObject {
     /// Returns an object writer for the given locator.
     fn writer(&self, locator: &Locator) -> Result<Self::Writer, CoordinatorError>;
 }
+
+/// The storage of the ceremony objects of a [Coordinator](crate::Coordinator).
+///
+/// [Disk](super::Disk) keeps the objects in files under the base directory of the environment,
+/// `Sqlite` keeps the records in a database next to the contribution files, with the `sqlite` feature,
+/// [Memory](super::Memory) keeps them in memory, for testing.
+pub trait Storage: StorageLocator + StorageObject + Send + Sync {
+    /// Initializes the location corresponding to the given locator.
+    fn initialize(&mut self, locator: Locator, size: u64) -> Result<(), CoordinatorError>;
+
+    /// Checks whether the given locator exists in the storage or not.
+    fn exists(&self, locator: &Locator) -> bool;
+
+    /// Returns a copy of an object at the given locator in storage, if it exists.
+    fn get(&self, locator: &Locator) -> Result<Object, CoordinatorError>;
+
+    /// Inserts a new object at the given locator into storage, if it does not exist.
+    fn insert(&mut self, locator: Locator, object: Object) -> Result<(), CoordinatorError> {
+        trace!("Inserting {}", self.to_path(&locator)?);
+
+        // Check that the given locator does not exist in storage.
+        if self.exists(&locator) {
+            error!("Locator in call to insert() already exists in storage.");
+            return Err(CoordinatorError::StorageLocatorAlreadyExists);
+        }
+
+        // Initialize the new object with its size.
+        self.initialize(locator.clone(), object.size())?;
+
+        // Insert the object at the given locator.
+        self.update(&locator, object)?;
+
+        trace!("Inserted {}", self.to_path(&locator)?);
+        Ok(())
+    }
+
+    /// Updates an existing object for the given locator in storage, if it exists.
+    fn update(&mut self, locator: &Locator, object: Object) -> Result<(), CoordinatorError>;
+
+    /// Copies an object from the given source locator to the given destination locator.
+    fn copy(&mut self, source_locator: &Locator, destination_locator: &Locator) -> Result<(), CoordinatorError> {
+        trace!(
+            "Copying from A to B\n\n\tA: {}\n\tB: {}\n",
+            self.to_path(source_locator)?,
+            self.to_path(destination_locator)?
+        );
+
+        // Check that the given source locator exists in storage.
+        if !self.exists(source_locator) {
+            error!("Source locator missing in call to copy() in storage.");
+            return Err(CoordinatorError::StorageLocatorMissing);
+        }
+
+        // Check that the given destination locator does NOT exist in storage.
+        if self.exists(destination_locator) {
+            error!("Destination locator in call to copy() already exists in storage.");
+            return Err(CoordinatorError::StorageLocatorAlreadyExists);
+        }
+
+        // Fetch the source object.
+        let source_object = self.get(source_locator)?;
+
+        // Initialize the destination with the source object size.
+        self.initialize(destination_locator.clone(), source_object.size())?;
+
+        // Update the destination locator with the copied source object.
+        self.update(destination_locator, source_object)?;
+
+        trace!("Copied to {}", self.to_path(destination_locator)?);
+        Ok(())
+    }
+
+    /// Removes the object corresponding to the given locator from storage.
+    fn remove(&mut self, locator: &Locator) -> Result<(), CoordinatorError>;
+
+    /// Returns the size of the object stored at the given locator.
+    fn size(&self, locator: &Locator) -> Result<u64, CoordinatorError>;
+
+    /// Returns the number of bytes received so far for an object uploaded in multiple parts at the given locator.
+    fn staged_size(&self, locator: &Locator) -> Result<u64, CoordinatorError>;
+
+    /// Appends a part of the object uploaded at the given locator to its staging area. A part at offset zero
+    /// restarts the upload, any other part must start where the previous one ended. Returns the number of
+    /// bytes received so far.
+    fn stage(&mut self, locator: &Locator, offset: u64, bytes: &[u8]) -> Result<u64, CoordinatorError>;
+
+    /// Returns the content of the staging area of the given locator.
+    fn get_staged(&self, locator: &Locator) -> Result<Vec<u8>, CoordinatorError>;
+
+    /// Removes the staging area of the given locator, if any.
+    fn clear_staged(&mut self, locator: &Locator) -> Result<(), CoordinatorError>;
+
+    /// Retrieve the json encoded summary file
+    fn get_contributions_summary(&self) -> Result<Vec<u8>, CoordinatorError> {
+        Ok(self.get(&Locator::ContributionsInfoSummary)?.to_bytes())
+    }
+
+    /// Retrieve the json encoded state file
+    fn get_coordinator_state(&self) -> Result<Vec<u8>, CoordinatorError> {
+        Ok(self.get(&Locator::CoordinatorState)?.to_bytes())
+    }
+
+    /// Process a [StorageAction] which mutates the storage.
+    fn process(&mut self, action: StorageAction) -> anyhow::Result<()> {
+        match action {
+            StorageAction::Remove(remove_action) => {
+                let locator = remove_action.try_into_locator(&*self)?;
+                Ok(self.remove(&locator)?)
+            }
+            StorageAction::Update(update_action) => Ok(self.update(&update_action.locator, update_action.object)?),
+            StorageAction::ClearRoundFiles(round_height) => Ok(self.clear_round_files(round_height)),
+        }
+    }
+
+    /// Clears all files related to a round - used for round reset purposes.
+    ///
+    /// Removes the contributions of the next round and the contributions of the given round,
+    /// except its initial challenges, then calls [Storage::clear_info_files()].
+    fn clear_round_files(&mut self, round_height: u64);
+
+    /// Delete contribution file and trim contribution summary file if these files exist
+    fn clear_info_files(&mut self, round_height: u64) {
+        if let Err(e) = self.remove(&Locator::ContributionInfoFile { round_height }) {
+            tracing::warn!("Could not delete contribution file: {}", e);
+        }
+
+        match self.get(&Locator::ContributionsInfoSummary) {
+            Ok(o) => {
+                if let Object::ContributionsInfoSummary(mut s) = o {
+                    // NOTE: the vec is ordered for ascending round heights
+                    if let Some(contrib) = s.last() {
+                        if contrib.ceremony_round() == round_height {
+                            s.pop();
+                            // Update contribution summary file in storage
+                            self.update(&Locator::ContributionsInfoSummary, Object::ContributionsInfoSummary(s))
+                                .expect("ERROR: failure while updating contribution summary file");
+                        }
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Could not retrieve contribution summary file: {}", e),
+        }
+    }
+
+    /// Archives the contribution files of a round out of the retention window of the coordinator. They can
+    /// still be read from storage afterwards. Does nothing for the storages keeping every round in place.
+    fn archive_round(&mut self, _round_height: u64) -> Result<(), CoordinatorError> {
+        Ok(())
+    }
+}
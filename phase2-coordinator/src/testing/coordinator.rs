@@ -2,8 +2,8 @@ use crate::{
     authentication::Dummy,
     environment::{Environment, Parameters, Testing},
     objects::{Participant, Round},
-    storage::Disk,
-    Coordinator, CoordinatorError,
+    storage::{Disk, Memory},
+    Coordinator, CoordinatorError, TimeSource,
 };
 
 use once_cell::sync::Lazy;
@@ -74,6 +74,23 @@ pub fn test_coordinator(environment: &Environment) -> anyhow::Result<Coordinator
     Ok(coordinator)
 }
 
+/// Instantiates a coordinator keeping its storage in memory, so that the tests
+/// using it don't share the transcript directory and can run in parallel.
+pub fn test_memory_coordinator(
+    environment: &Environment,
+    time: Arc<dyn TimeSource>,
+) -> anyhow::Result<Coordinator<Memory>> {
+    test_logger();
+
+    let storage = Memory::load(environment)?;
+    Ok(Coordinator::new_with_storage(
+        environment.clone(),
+        Arc::new(Dummy),
+        storage,
+        time,
+    )?)
+}
+
 pub fn test_coordinator_contributor(environment: &Environment) -> anyhow::Result<Participant> {
     Ok(environment
         .coordinator_contributors()
@@ -121,7 +138,7 @@ pub fn clear_test_storage(environment: &Environment) {
 
 /// Initializes a test storage object.
 pub fn test_storage(environment: &Environment) -> Disk {
-    environment.storage().unwrap()
+    Disk::load(environment).unwrap()
 }
 
 /// Loads the reference JSON object with a serialized round for testing purposes only.
@@ -1,16 +1,14 @@
 use crate::{
-    authentication::Dummy,
     commands::{Seed, SigningKey, SEED_LENGTH},
-    environment::{Environment, Parameters, Settings, Testing},
+    environment::{CircuitSet, Environment, Parameters, Settings, Testing},
     objects::Task,
-    storage::{Disk, StorageLocator},
+    storage::{Locator, Memory, Storage, StorageLocator},
     testing::prelude::*,
-    Coordinator, CoordinatorError, MockTimeSource, Participant, Round,
+    CoordinatorError, MockTimeSource, Participant, Round, SystemTimeSource,
 };
 use phase2::{helpers::CurveKind, ContributionMode, ProvingSystem};
 use time::OffsetDateTime;
 
-use fs_err as fs;
 use rand::RngCore;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use std::{
@@ -20,6 +18,9 @@ use std::{
     sync::Arc,
 };
 
+/// The coordinator of these tests keeps its storage in memory, so that they can run in parallel.
+type Coordinator = crate::Coordinator<Memory>;
+
 fn create_contributor(id: &str) -> (Participant, SigningKey, Seed) {
     let contributor = Participant::Contributor(format!("test-contributor-{}", id));
     let contributor_signing_key: SigningKey = "secret_key".to_string();
@@ -87,19 +88,22 @@ fn create_verifier_test_details(id: &str) -> VerifierTestDetails {
 }
 
 fn execute_round(proving_system: ProvingSystem, curve: CurveKind) -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        proving_system,
-        curve,
-        7,  /* power */
-        32, /* batch_size */
-        32, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            proving_system,
+            curve,
+            7,  /* power */
+            32, /* batch_size */
+            32, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -205,22 +209,24 @@ fn fetch_task_for_verifier(coordinator: &Coordinator) -> Option<Task> {
 }
 
 #[test]
-#[serial]
 /// Drops a contributor who does not affect other contributors or verifiers.
 fn coordinator_drop_contributor_basic() {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy)).unwrap();
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new())).unwrap();
 
     // Initialize the ceremony to round 0.
     coordinator.initialize().unwrap();
@@ -321,22 +327,24 @@ fn coordinator_drop_contributor_basic() {
 }
 
 #[test]
-#[serial]
 /// Drops a contributor in between two contributors.
 fn coordinator_drop_contributor_in_between_two_contributors() -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment.clone(), Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -447,22 +455,24 @@ fn coordinator_drop_contributor_in_between_two_contributors() -> anyhow::Result<
 }
 
 #[test]
-#[serial]
 /// Drops a contributor with other contributors in pending tasks.
 fn coordinator_drop_contributor_with_contributors_in_pending_tasks() -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment.clone(), Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -606,22 +616,24 @@ fn coordinator_drop_contributor_with_contributors_in_pending_tasks() -> anyhow::
 }
 
 #[test]
-#[serial]
 /// Drops a contributor with locked chunks and other contributors in pending tasks.
 fn coordinator_drop_contributor_locked_chunks() -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment.clone(), Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -771,22 +783,24 @@ fn coordinator_drop_contributor_locked_chunks() -> anyhow::Result<()> {
 }
 
 #[test]
-#[serial]
 /// Drops a contributor and removes all contributions from the contributor.
 fn coordinator_drop_contributor_removes_contributions() -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -888,22 +902,24 @@ fn coordinator_drop_contributor_removes_contributions() -> anyhow::Result<()> {
 }
 
 #[test]
-#[serial]
 /// Drops a contributor and clears locks for contributors/verifiers working on disposed tasks.
 fn coordinator_drop_contributor_clear_locks() -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment.clone(), Arc::new(Dummy)).unwrap();
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new())).unwrap();
 
     // Initialize the ceremony to round 0.
     coordinator.initialize().unwrap();
@@ -1096,7 +1112,6 @@ fn coordinator_drop_contributor_clear_locks() -> anyhow::Result<()> {
 
 /// Drops a contributor and removes all subsequent contributions.
 #[test]
-#[serial]
 fn coordinator_drop_contributor_removes_subsequent_contributions() -> anyhow::Result<()> {
     let parameters = Parameters::Custom(Settings {
         contribution_mode: ContributionMode::Chunked,
@@ -1105,14 +1120,15 @@ fn coordinator_drop_contributor_removes_subsequent_contributions() -> anyhow::Re
         power: 1,
         batch_size: 2,
         chunk_size: 2,
+        circuits: CircuitSet::MaspTest,
     });
     let (replacement_contributor, ..) = create_contributor("replacement-1");
     let testing = Testing::from(parameters).coordinator_contributors(&[replacement_contributor.clone()]);
-    let environment = initialize_test_environment(&testing.into());
+    let environment: Environment = testing.into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -1180,7 +1196,6 @@ fn coordinator_drop_contributor_removes_subsequent_contributions() -> anyhow::Re
 /// by a contributor and then immediately drop the contributor
 /// without contributing
 #[test]
-#[serial]
 fn coordinator_drop_contributor_and_release_locks() {
     // Unwraps are used to find out the exact line which produces the error
     // When the test returns Result with an Err, the line is unknown
@@ -1192,14 +1207,15 @@ fn coordinator_drop_contributor_and_release_locks() {
         power: 1,
         batch_size: 2,
         chunk_size: 2,
+        circuits: CircuitSet::MaspTest,
     });
     let replacement_contributor = create_contributor_test_details("replacement-1");
     let testing = Testing::from(parameters).coordinator_contributors(&[replacement_contributor.participant.clone()]);
-    let environment = initialize_test_environment(&testing.into());
+    let environment: Environment = testing.into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy)).unwrap();
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new())).unwrap();
 
     // Initialize the ceremony to round 0.
     coordinator.initialize().unwrap();
@@ -1272,7 +1288,6 @@ fn coordinator_drop_contributor_and_release_locks() {
 /// The goal of this test is to reproduce a specific error
 /// which happens in the integration tests at the moment
 #[test]
-#[serial]
 #[ignore]
 fn coordinator_drop_several_contributors() {
     let parameters = Parameters::Custom(Settings {
@@ -1282,6 +1297,7 @@ fn coordinator_drop_several_contributors() {
         power: 2,
         batch_size: 2,
         chunk_size: 2,
+        circuits: CircuitSet::MaspTest,
     });
     let replacement_contributor_1 = create_contributor_test_details("replacement-1");
     let replacement_contributor_2 = create_contributor_test_details("replacement-2");
@@ -1289,10 +1305,10 @@ fn coordinator_drop_several_contributors() {
         replacement_contributor_1.participant.clone(),
         replacement_contributor_2.participant.clone(),
     ]);
-    let environment = initialize_test_environment(&testing.into());
+    let environment: Environment = testing.into();
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy)).unwrap();
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new())).unwrap();
 
     // Initialize the ceremony to round 0.
     coordinator.initialize().unwrap();
@@ -1417,7 +1433,7 @@ fn coordinator_drop_several_contributors() {
     assert_eq!(0, coordinator.number_of_queue_contributors());
 }
 
-fn check_round_matches_storage_files(storage: &Disk, round: &Round) {
+fn check_round_matches_storage_files(storage: &Memory, round: &Round) {
     debug!("Checking round {}", round.round_height());
     for chunk in round.chunks() {
         debug!("Checking chunk {}", chunk.chunk_id());
@@ -1436,7 +1452,19 @@ fn check_round_matches_storage_files(storage: &Disk, round: &Round) {
         let path = initial_challenge_location.as_path();
         let chunk_dir = path.parent().unwrap();
 
-        let n_files = fs::read_dir(&chunk_dir).unwrap().count();
+        let n_files = storage
+            .locators()
+            .iter()
+            .filter(|locator| match locator {
+                Locator::ContributionFile(locator) => {
+                    (locator.round_height(), locator.chunk_id()) == (round.round_height(), chunk.chunk_id())
+                }
+                Locator::ContributionFileSignature(locator) => {
+                    (locator.round_height(), locator.chunk_id()) == (round.round_height(), chunk.chunk_id())
+                }
+                _ => false,
+            })
+            .count();
 
         let contributions_complete = chunk.only_contributions_complete(round.expected_number_of_contributions());
 
@@ -1504,7 +1532,6 @@ fn check_round_matches_storage_files(storage: &Disk, round: &Round) {
 /// contributor. The tasks of a verifier should be updated
 /// properly
 #[test]
-#[serial]
 fn coordinator_drop_contributor_and_update_verifier_tasks() {
     // Unwraps are used to find out the exact line which produces the error
     // When the test returns Result with an Err, the line is unknown
@@ -1516,14 +1543,15 @@ fn coordinator_drop_contributor_and_update_verifier_tasks() {
         power: 1,
         batch_size: 2,
         chunk_size: 2,
+        circuits: CircuitSet::MaspTest,
     });
     let replacement_contributor = create_contributor_test_details("replacement-1");
     let testing = Testing::from(parameters).coordinator_contributors(&[replacement_contributor.participant.clone()]);
-    let environment = initialize_test_environment(&testing.into());
+    let environment: Environment = testing.into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy)).unwrap();
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new())).unwrap();
 
     // Initialize the ceremony to round 0.
     coordinator.initialize().unwrap();
@@ -1592,28 +1620,30 @@ fn coordinator_drop_contributor_and_update_verifier_tasks() {
 }
 
 #[test]
-#[serial]
 /// Drops a multiple contributors an replaces with the coordinator contributor.
 fn coordinator_drop_multiple_contributors() -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
     let testing = Testing::from(parameters).coordinator_contributors(&[
         Participant::new_contributor("testing-coordinator-contributor-1"),
         Participant::new_contributor("testing-coordinator-contributor-2"),
         Participant::new_contributor("testing-coordinator-contributor-3"),
     ]);
-    let environment = initialize_test_environment(&testing.into());
+    let environment: Environment = testing.into();
 
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment.clone(), Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -1752,21 +1782,23 @@ fn coordinator_drop_multiple_contributors() -> anyhow::Result<()> {
 }
 
 #[test]
-#[serial]
 fn try_lock_blocked() -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        7,  /* power */
-        32, /* batch_size */
-        32, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            7,  /* power */
+            32, /* batch_size */
+            32, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -1870,7 +1902,6 @@ fn try_lock_blocked() -> anyhow::Result<()> {
 }
 
 #[test]
-#[serial]
 fn drop_all_contributors_and_complete_round() -> anyhow::Result<()> {
     let parameters = Parameters::Custom(Settings {
         contribution_mode: ContributionMode::Chunked,
@@ -1879,6 +1910,7 @@ fn drop_all_contributors_and_complete_round() -> anyhow::Result<()> {
         power: 6,
         batch_size: 16,
         chunk_size: 16,
+        circuits: CircuitSet::MaspTest,
     });
 
     // Create replacement contributors
@@ -1889,12 +1921,12 @@ fn drop_all_contributors_and_complete_round() -> anyhow::Result<()> {
         replacement_contributor_1.participant.clone(),
         replacement_contributor_2.participant.clone(),
     ]);
-    let environment = initialize_test_environment(&testing.into());
+    let environment: Environment = testing.into();
 
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment.clone(), Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -1984,21 +2016,23 @@ fn drop_all_contributors_and_complete_round() -> anyhow::Result<()> {
 }
 
 #[test]
-#[serial]
 fn drop_contributor_and_reassign_tasks() -> anyhow::Result<()> {
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
-    let environment = initialize_test_environment(&Testing::from(parameters).into());
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
+    let environment: Environment = Testing::from(parameters).into();
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new(environment, Arc::new(Dummy))?;
+    let mut coordinator = test_memory_coordinator(&environment, Arc::new(SystemTimeSource::new()))?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -2062,27 +2096,29 @@ fn drop_contributor_and_reassign_tasks() -> anyhow::Result<()> {
 /// Test that participants who have not been seen for longer than the
 /// [Environment::contributor_timeout_in_minutes] will be dropped.
 #[test]
-#[serial]
 fn contributor_timeout_drop_test() -> anyhow::Result<()> {
     let time = Arc::new(MockTimeSource::new(OffsetDateTime::now_utc()));
 
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
 
     let testing_deployment: Testing = Testing::from(parameters)
         .contributor_seen_timeout(time::Duration::minutes(5))
         .participant_lock_timeout(time::Duration::minutes(10));
 
-    let environment = initialize_test_environment(&Environment::from(testing_deployment));
+    let environment = Environment::from(testing_deployment);
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new_with_time(environment, Arc::new(Dummy), time.clone())?;
+    let mut coordinator = test_memory_coordinator(&environment, time.clone())?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -2124,27 +2160,29 @@ fn contributor_timeout_drop_test() -> anyhow::Result<()> {
 /// Test that participant who is waiting for a verifier to verify
 /// chunks that it depends on is not dropped from the round.
 #[test]
-#[serial]
 fn contributor_wait_verifier_test() -> anyhow::Result<()> {
     let time = Arc::new(MockTimeSource::new(OffsetDateTime::now_utc()));
 
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
     let testing_deployment: Testing = Testing::from(parameters)
         .contributor_seen_timeout(time::Duration::minutes(5))
         .participant_lock_timeout(time::Duration::minutes(8));
 
-    let environment = initialize_test_environment(&Environment::from(testing_deployment));
+    let environment = Environment::from(testing_deployment);
     let number_of_chunks = environment.number_of_chunks() as usize;
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new_with_time(environment, Arc::new(Dummy), time.clone())?;
+    let mut coordinator = test_memory_coordinator(&environment, time.clone())?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -2201,27 +2239,29 @@ fn contributor_wait_verifier_test() -> anyhow::Result<()> {
 /// than [Environment::participant_lock_timeout] is dropped from the
 /// round by the coordinator.
 #[test]
-#[serial]
 fn participant_lock_timeout_drop_test() -> anyhow::Result<()> {
     let time = Arc::new(MockTimeSource::new(OffsetDateTime::now_utc()));
 
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
 
     let testing_deployment: Testing = Testing::from(parameters)
         .contributor_seen_timeout(time::Duration::minutes(20))
         .participant_lock_timeout(time::Duration::minutes(10));
 
-    let environment = initialize_test_environment(&Environment::from(testing_deployment));
+    let environment = Environment::from(testing_deployment);
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new_with_time(environment, Arc::new(Dummy), time.clone())?;
+    let mut coordinator = test_memory_coordinator(&environment, time.clone())?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -2268,27 +2308,29 @@ fn participant_lock_timeout_drop_test() -> anyhow::Result<()> {
 /// than [Environment::queue_seen_timeout] is dropped from the
 /// queue by the coordinator.
 #[test]
-#[serial]
 fn queue_seen_timeout_drop_test() -> anyhow::Result<()> {
     let time = Arc::new(MockTimeSource::new(OffsetDateTime::now_utc()));
 
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
 
     let testing_deployment: Testing = Testing::from(parameters)
         .contributor_seen_timeout(time::Duration::days(20))
         .participant_lock_timeout(time::Duration::days(20));
 
-    let environment = initialize_test_environment(&Environment::from(testing_deployment));
+    let environment = Environment::from(testing_deployment);
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new_with_time(environment, Arc::new(Dummy), time.clone())?;
+    let mut coordinator = test_memory_coordinator(&environment, time.clone())?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -2336,27 +2378,29 @@ fn queue_seen_timeout_drop_test() -> anyhow::Result<()> {
 
 /// Test that a participant can remain in the queue by sending heartbeats.
 #[test]
-#[serial]
 fn queue_seen_timeout_heartbeat_test() -> anyhow::Result<()> {
     let time = Arc::new(MockTimeSource::new(OffsetDateTime::now_utc()));
 
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
 
     let testing_deployment: Testing = Testing::from(parameters)
         .contributor_seen_timeout(time::Duration::days(20))
         .participant_lock_timeout(time::Duration::days(20));
 
-    let environment = initialize_test_environment(&Environment::from(testing_deployment));
+    let environment = Environment::from(testing_deployment);
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new_with_time(environment, Arc::new(Dummy), time.clone())?;
+    let mut coordinator = test_memory_coordinator(&environment, time.clone())?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -2410,27 +2454,29 @@ fn queue_seen_timeout_heartbeat_test() -> anyhow::Result<()> {
 /// than [Environment::participant_lock_timeout] is dropped from the
 /// round by the coordinator.
 #[test]
-#[serial]
 fn rollback_locked_chunk() -> anyhow::Result<()> {
     let time = Arc::new(MockTimeSource::new(OffsetDateTime::now_utc()));
 
-    let parameters = Parameters::Custom(Settings::new(
-        ContributionMode::Chunked,
-        ProvingSystem::Groth16,
-        CurveKind::Bls12_377,
-        6,  /* power */
-        16, /* batch_size */
-        16, /* chunk_size */
-    ));
+    let parameters = Parameters::Custom(
+        Settings::new(
+            ContributionMode::Chunked,
+            ProvingSystem::Groth16,
+            CurveKind::Bls12_377,
+            6,  /* power */
+            16, /* batch_size */
+            16, /* chunk_size */
+        )
+        .with_circuits(CircuitSet::MaspTest),
+    );
 
     let testing_deployment: Testing = Testing::from(parameters)
         .contributor_seen_timeout(time::Duration::minutes(20))
         .participant_lock_timeout(time::Duration::minutes(10));
 
-    let environment = initialize_test_environment(&Environment::from(testing_deployment));
+    let environment = Environment::from(testing_deployment);
 
     // Instantiate a coordinator.
-    let mut coordinator = Coordinator::new_with_time(environment, Arc::new(Dummy), time.clone())?;
+    let mut coordinator = test_memory_coordinator(&environment, time.clone())?;
 
     // Initialize the ceremony to round 0.
     coordinator.initialize()?;
@@ -2471,19 +2517,16 @@ fn rollback_locked_chunk() -> anyhow::Result<()> {
 }
 
 #[test]
-#[serial]
 fn round_on_groth16_bls12_377() {
     execute_round(ProvingSystem::Groth16, CurveKind::Bls12_377).unwrap();
 }
 
 #[test]
-#[serial]
 fn round_on_groth16_bw6_761() {
     execute_round(ProvingSystem::Groth16, CurveKind::BW6).unwrap();
 }
 
 #[test]
-#[serial]
 fn round_on_marlin_bls12_377() {
     execute_round(ProvingSystem::Marlin, CurveKind::Bls12_377).unwrap();
 }
@@ -0,0 +1,266 @@
+//! The contribution tokens of the participants.
+//!
+//! A token is the base58 encoding of the JSON of its [TokenClaims]: the cohort it is valid for, with the time window
+//! of the cohort, and a random identifier. The tokens of the cohort files are only valid if listed there, while the
+//! [SignedToken]s carry the signature of the issuer of the ceremony and are checked on their own, so that the tokens
+//! of a new cohort can be issued without uploading them to the coordinator.
+//!
+//! The cohort files are JSON lists of tokens named after the number of their cohort, see [cohort_file_name].
+
+use crate::authentication::{KeyPair, Production, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TokenError {
+    #[error("The token is not base58 encoded: {0}")]
+    Encoding(#[from] bs58::decode::Error),
+    #[error("The token is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Error while signing the token: {0}")]
+    Signing(String),
+    #[error("{0} is not a cohort file")]
+    InvalidFileName(String),
+    #[error("{0} is not a list of tokens: {1}")]
+    InvalidCohortFile(String, serde_json::Error),
+    #[error("The file of cohort {0} is missing")]
+    MissingCohort(usize),
+}
+
+type Result<T> = std::result::Result<T, TokenError>;
+
+/// The cohort a token is valid for, and its identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    /// The unix timestamp of the start of the cohort.
+    pub from: u64,
+    /// The unix timestamp of the end of the cohort, excluded.
+    pub to: u64,
+    /// The number of the cohort, starting from 1.
+    pub index: u64,
+    /// A random hex encoded identifier, unique to the token.
+    pub id: String,
+}
+
+impl TokenClaims {
+    /// Returns true if the given unix timestamp falls in the window of the cohort.
+    pub fn is_in_window(&self, timestamp: u64) -> bool {
+        self.from <= timestamp && timestamp < self.to
+    }
+}
+
+/// The issuer of the signed tokens of a ceremony.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenIssuer {
+    /// The hex encoded public key the tokens are signed with.
+    pub pubkey: String,
+    /// The number of cohorts the issuer signs tokens for. The ceremony lasts until the last one of them, or of the
+    /// cohort files.
+    pub cohorts: usize,
+}
+
+/// A token, signed by the issuer if it is not listed in the cohort files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedToken {
+    #[serde(flatten)]
+    pub claims: TokenClaims,
+    /// The signature of the JSON of the claims by the issuer, missing in the tokens of the cohort files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl SignedToken {
+    ///
+    /// Signs the given claims with the key of the issuer.
+    ///
+    /// On failure to sign the claims, this function returns a `TokenError`.
+    ///
+    pub fn sign(claims: TokenClaims, issuer: &KeyPair) -> Result<Self> {
+        let message = serde_json::to_string(&claims)?;
+        let signature = Production
+            .sign(issuer.sigkey(), &message)
+            .map_err(|e| TokenError::Signing(e.to_string()))?;
+
+        Ok(Self {
+            claims,
+            signature: Some(signature),
+        })
+    }
+
+    /// Decodes a token, signed or not.
+    pub fn decode(token: &str) -> Result<Self> {
+        let bytes = bs58::decode(token).into_vec()?;
+
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    ///
+    /// Returns the token to hand out to the participant. The unsigned tokens keep the JSON layout of the ones of
+    /// `generate_tokens.py`, matched by [TOKEN_REGEX](crate::rest_utils::TOKEN_REGEX).
+    ///
+    pub fn encode(&self) -> String {
+        let json = match self.signature {
+            Some(_) => serde_json::to_string(self).expect("Serialization of a token should not fail"),
+            None => format!(
+                "{{\"from\": {}, \"to\": {}, \"index\": {}, \"id\": {}}}",
+                self.claims.from,
+                self.claims.to,
+                self.claims.index,
+                serde_json::to_string(&self.claims.id).expect("Serialization of a string should not fail")
+            ),
+        };
+
+        bs58::encode(json).into_string()
+    }
+
+    /// Returns true if the token is signed by the given issuer.
+    pub fn verify(&self, issuer_pubkey: &str) -> bool {
+        match (&self.signature, serde_json::to_string(&self.claims)) {
+            (Some(signature), Ok(message)) => Production.verify(issuer_pubkey, &message, signature),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the name of the file of the tokens of the given cohort, starting from 1.
+pub fn cohort_file_name(prefix: &str, cohort: usize) -> String {
+    format!("{}_{}.json", prefix, cohort)
+}
+
+/// Returns the cohort of the given file name, if it is the one of a cohort file.
+fn cohort_of_file_name(prefix: &str, file_name: &str) -> Option<usize> {
+    file_name
+        .strip_prefix(prefix)?
+        .strip_prefix('_')?
+        .strip_suffix(".json")?
+        .parse()
+        .ok()
+        .filter(|cohort| *cohort > 0)
+}
+
+///
+/// Parses the cohort files of the given prefix, indexed by file name, into the tokens of each cohort. The files
+/// must cover every cohort from the first one.
+///
+/// On a file which is not a cohort file or not a list of tokens, or on a missing cohort, this function returns a
+/// `TokenError`.
+///
+pub fn parse_cohort_files(prefix: &str, files: &HashMap<String, Vec<u8>>) -> Result<Vec<Vec<String>>> {
+    let mut cohorts = BTreeMap::new();
+    for (file_name, bytes) in files {
+        let cohort =
+            cohort_of_file_name(prefix, file_name).ok_or_else(|| TokenError::InvalidFileName(file_name.clone()))?;
+        let tokens: Vec<String> =
+            serde_json::from_slice(bytes).map_err(|e| TokenError::InvalidCohortFile(file_name.clone(), e))?;
+        cohorts.insert(cohort, tokens);
+    }
+
+    // The cohorts are numbered from 1, without gaps
+    cohorts
+        .into_iter()
+        .enumerate()
+        .map(|(i, (cohort, tokens))| match cohort == i + 1 {
+            true => Ok(tokens),
+            false => Err(TokenError::MissingCohort(i + 1)),
+        })
+        .collect()
+}
+
+/// Returns the tokens listed more than once in the given cohorts, with the cohorts listing them.
+pub fn find_duplicates(cohorts: &[Vec<String>]) -> BTreeMap<&str, Vec<usize>> {
+    let mut occurrences: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, tokens) in cohorts.iter().enumerate() {
+        for token in tokens {
+            occurrences.entry(token.as_str()).or_default().push(i + 1);
+        }
+    }
+    occurrences.retain(|_, cohorts| cohorts.len() > 1);
+
+    occurrences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_token() {
+        let issuer = KeyPair::new();
+        let claims = TokenClaims {
+            from: 1660000000,
+            to: 1660001200,
+            index: 1,
+            id: "0123456789abcdef01234567".to_string(),
+        };
+
+        let token = SignedToken::sign(claims.clone(), &issuer).unwrap();
+        let decoded = SignedToken::decode(&token.encode()).unwrap();
+        assert_eq!(decoded, token);
+        assert!(decoded.verify(issuer.pubkey()));
+        assert!(!decoded.verify(KeyPair::new().pubkey()));
+        assert!(decoded.claims.is_in_window(1660000000));
+        assert!(!decoded.claims.is_in_window(1660001200));
+
+        // The claims can't be changed
+        let mut tampered = decoded;
+        tampered.claims.index = 2;
+        assert!(!tampered.verify(issuer.pubkey()));
+
+        // The tokens of the cohort files decode as unsigned ones
+        let unsigned = SignedToken::decode(
+            "9nFeNpukSn1eVwNc2vkfP7rdLh2njm5ewmCGxSLTW3GYmKP51fKjbRUvHDmntjEaQiq7iFux9tumgWEWVHwHQCs31oitpqBpMWpMydo1DnuFyLpsD6C",
+        )
+        .unwrap();
+        assert_eq!(unsigned.signature, None);
+        assert!(!unsigned.verify(issuer.pubkey()));
+        assert_eq!(
+            unsigned.encode(),
+            "9nFeNpukSn1eVwNc2vkfP7rdLh2njm5ewmCGxSLTW3GYmKP51fKjbRUvHDmntjEaQiq7iFux9tumgWEWVHwHQCs31oitpqBpMWpMydo1DnuFyLpsD6C"
+        );
+        assert!(SignedToken::decode("0OIl").is_err());
+    }
+
+    #[test]
+    fn test_parse_cohort_files() {
+        let mut files: HashMap<String, Vec<u8>> = HashMap::new();
+        files.insert(cohort_file_name("namada_tokens_cohort", 2), b"[\"c\", \"a\"]".to_vec());
+        files.insert(cohort_file_name("namada_tokens_cohort", 1), b"[\"a\", \"b\"]".to_vec());
+
+        let cohorts = parse_cohort_files("namada_tokens_cohort", &files).unwrap();
+        assert_eq!(cohorts, vec![vec!["a", "b"], vec!["c", "a"]]);
+        assert_eq!(
+            find_duplicates(&cohorts).into_iter().collect::<Vec<_>>(),
+            vec![("a", vec![1, 2])]
+        );
+
+        // A gap in the cohorts
+        files.insert(cohort_file_name("namada_tokens_cohort", 4), b"[]".to_vec());
+        assert!(matches!(
+            parse_cohort_files("namada_tokens_cohort", &files),
+            Err(TokenError::MissingCohort(3))
+        ));
+
+        // Files which are not cohort files
+        for file_name in [
+            "namada_tokens_cohort_0.json",
+            "namada_tokens_cohort_x.json",
+            "tokens_1.json",
+            "README",
+        ] {
+            let mut files = HashMap::new();
+            files.insert(file_name.to_string(), b"[]".to_vec());
+            assert!(matches!(
+                parse_cohort_files("namada_tokens_cohort", &files),
+                Err(TokenError::InvalidFileName(_))
+            ));
+        }
+
+        let mut files = HashMap::new();
+        files.insert(cohort_file_name("namada_tokens_cohort", 1), b"{}".to_vec());
+        assert!(matches!(
+            parse_cohort_files("namada_tokens_cohort", &files),
+            Err(TokenError::InvalidCohortFile(..))
+        ));
+    }
+}
@@ -13,17 +13,23 @@ use std::{
 use blake2::Digest;
 use phase2_coordinator::{
     authentication::{KeyPair, Production, Signature},
-    commands::{Computation, RandomSource},
+    commands::{Beacon, Computation, RandomSource},
     coordinator_state::CoordinatorState,
-    environment::Testing,
+    environment::{CircuitSet, Testing},
+    object_store::SharedObjectStore,
     objects::{ContributionInfo, LockedLocators, TrimmedContributionInfo},
+    operators::{Operators, SharedOperators},
     rest,
     rest_utils::{
-        self, ContributorStatus, PostChunkRequest, ACCESS_SECRET_HEADER, BODY_DIGEST_HEADER, CONTENT_LENGTH_HEADER,
-        PUBKEY_HEADER, SIGNATURE_HEADER, TOKENS_ZIP_FILE,
+        self, ContributionStatus, ContributorStatus, PostChunkRequest, RequestContent, SignatureHeaders,
+        ACCESS_SECRET_HEADER, BODY_DIGEST_HEADER, CONTENT_LENGTH_HEADER, NONCE_HEADER, PUBKEY_HEADER, SIGNATURE_HEADER,
+        TIMESTAMP_HEADER, TOKENS_ZIP_FILE,
     },
+    s3::S3Ctx,
+    secret::{AccessSecret, FileSink, SharedAccessSecret},
     storage::{ContributionLocator, ContributionSignatureLocator, Object},
     testing::coordinator,
+    tokens::{SignedToken, TokenClaims, TokenIssuer},
     ContributionFileSignature, ContributionState, Coordinator, Participant,
 };
 use reqwest::header::{HeaderValue, CONTENT_TYPE};
@@ -40,6 +46,11 @@ use sha2::Sha256;
 use zip::write::FileOptions;
 
 const ROUND_HEIGHT: u64 = 1;
+const ACCESS_SECRET: &str = "test-access_token";
+/// The duration of each cohort, in seconds.
+const COHORT_TIME: u64 = 15;
+/// The status file returned by the healthcheck.
+const HEALTH_PATH: &str = "./test_health.json";
 
 struct TestParticipant {
     _inner: Participant,
@@ -55,15 +66,24 @@ struct TestCtx {
     coordinator: TestParticipant,
     // Keep TempDir in scope for some tests
     _tokens_tmp_dir: tempfile::TempDir,
+    auth_tmp_dir: tempfile::TempDir,
 }
 
 /// Build the rocket server for testing with the proper configuration.
 fn build_context() -> TestCtx {
+    build_context_with(Testing::default())
+}
+
+/// Build the rocket server for testing with the given deployment.
+fn build_context_with(deployment: Testing) -> TestCtx {
+    // The configuration is read from the env once for all the tests
     std::env::set_var("TOKEN_BLACKLIST", "true");
     std::env::set_var("NAMADA_MPC_IP_BAN", "true");
+    std::env::set_var("NAMADA_COHORT_TIME", COHORT_TIME.to_string());
+    std::env::set_var("HEALTH_PATH", HEALTH_PATH);
 
     // Reset storage to prevent state conflicts between tests and initialize test environment
-    let environment = coordinator::initialize_test_environment(&Testing::default().into());
+    let environment = coordinator::initialize_test_environment(&deployment.into());
 
     // Create token file
     // Need a fixed-name temp dir because of the lazy_static variables based on env
@@ -124,6 +144,15 @@ fn build_context() -> TestCtx {
     let (_, locked_locators) = coordinator.try_lock(&contributor1).unwrap();
 
     let coordinator: Arc<RwLock<Coordinator>> = Arc::new(RwLock::new(coordinator));
+    let object_store: SharedObjectStore = Arc::new(S3Ctx::new().unwrap());
+
+    // The rotated secrets and the operator keys are in a separate directory, the tokens one only contains the cohorts
+    let auth_tmp_dir = tempfile::tempdir().unwrap();
+    let access_secret: SharedAccessSecret = Arc::new(AccessSecret::new(
+        ACCESS_SECRET.to_string(),
+        Box::new(FileSink::new(auth_tmp_dir.path().join("access_secret"))),
+    ));
+    let operators: SharedOperators = Arc::new(Operators::load(auth_tmp_dir.path().join("operators.toml")).unwrap());
 
     let rocket = rocket::build()
         .mount(
@@ -132,22 +161,33 @@ fn build_context() -> TestCtx {
                 rest::join_queue,
                 rest::lock_chunk,
                 rest::contribute_chunk,
+                rest::get_challenge,
+                rest::get_upload_offset,
+                rest::post_contribution_part,
+                rest::post_contribution_signature,
                 rest::update_coordinator,
                 rest::heartbeat,
                 rest::stop_coordinator,
                 rest::verify_chunks,
                 rest::get_contributor_queue_status,
+                rest::get_contribution_status,
                 rest::post_contribution_info,
                 rest::get_contributions_info,
                 rest::get_healthcheck,
+                rest::get_circuits,
                 rest::get_contribution_url,
                 rest::get_challenge_url,
                 rest::get_coordinator_state,
+                rest::rotate_secret,
                 rest::update_cohorts,
+                rest::finalize_ceremony,
                 rest::post_attestation
             ],
         )
         .manage(coordinator)
+        .manage(object_store)
+        .manage(access_secret)
+        .manage(operators)
         .register(
             "/",
             catchers![
@@ -187,28 +227,59 @@ fn build_context() -> TestCtx {
         unknown_participant,
         coordinator: coord_verifier,
         _tokens_tmp_dir: tmp_dir,
+        auth_tmp_dir,
     }
 }
 
+/// Returns the signature headers of a new request, signed now with a random nonce
+fn signature_headers<'a>(
+    req: &LocalRequest,
+    keypair: &'a KeyPair,
+    content: Option<RequestContent<'a>>,
+) -> SignatureHeaders<'a> {
+    SignatureHeaders::new(
+        keypair.pubkey(),
+        req.inner().method().as_str(),
+        req.inner().uri().to_string().into(),
+        content,
+        None,
+    )
+}
+
+/// Sign the request with the given signature headers
+fn add_signature(req: &mut LocalRequest, keypair: &KeyPair, headers: &SignatureHeaders) {
+    let signature = Production.sign(keypair.sigkey(), &headers.to_string()).unwrap();
+
+    req.add_header(Header::new(PUBKEY_HEADER, headers.pubkey.to_owned()));
+    req.add_header(Header::new(TIMESTAMP_HEADER, headers.timestamp.to_string()));
+    req.add_header(Header::new(NONCE_HEADER, headers.nonce.to_string()));
+    req.add_header(Header::new(SIGNATURE_HEADER, signature));
+}
+
+/// Add the digest and the length of the body to the request
+fn add_content(req: &mut LocalRequest, body: &[u8]) -> RequestContent<'static> {
+    let mut hasher = Sha256::new();
+    hasher.update(body);
+    let content = RequestContent::new(body.len(), hasher.finalize());
+
+    let (len, digest) = content.to_header();
+    req.add_header(Header::new(BODY_DIGEST_HEADER, digest));
+    req.add_header(Header::new(CONTENT_LENGTH_HEADER, len.to_string()));
+
+    content
+}
+
 /// Add headers and optional body to the request
 fn set_request<'a, T>(mut req: LocalRequest<'a>, keypair: &'a KeyPair, body: Option<&T>) -> LocalRequest<'a>
 where
     T: Serialize,
 {
-    let mut msg = keypair.pubkey().to_owned();
-    req.add_header(Header::new(PUBKEY_HEADER, keypair.pubkey().to_owned()));
+    let mut content = None;
 
     if let Some(body) = body {
-        // Body digest
+        // Body digest and length
         let json_body = serde_json::to_string(body).unwrap();
-        let mut hasher = Sha256::new();
-        hasher.update(&json_body);
-        let digest = base64::encode(hasher.finalize());
-        msg = format!("{}{}{}", msg, json_body.len(), &digest);
-        req.add_header(Header::new(BODY_DIGEST_HEADER, format!("sha-256={}", digest)));
-
-        // Body length
-        req.add_header(Header::new(CONTENT_LENGTH_HEADER, json_body.len().to_string()));
+        content = Some(add_content(&mut req, json_body.as_bytes()));
 
         // Attach json serialized body
         req.add_header(ContentType::JSON);
@@ -216,22 +287,32 @@ where
     }
 
     // Sign request
-    let signature = Production.sign(keypair.sigkey(), &msg).unwrap();
-    req.add_header(Header::new(SIGNATURE_HEADER, signature));
+    let headers = signature_headers(&req, keypair, content);
+    add_signature(&mut req, keypair, &headers);
 
     req
 }
 
+/// Add headers and raw body to the request
+fn set_raw_request<'a>(mut req: LocalRequest<'a>, keypair: &'a KeyPair, body: &[u8]) -> LocalRequest<'a> {
+    // Body digest and length
+    let content = add_content(&mut req, body);
+
+    // Sign request
+    let headers = signature_headers(&req, keypair, Some(content));
+    add_signature(&mut req, keypair, &headers);
+
+    req.header(ContentType::Binary).body(body)
+}
+
 #[test]
 fn get_status() {
-    let access_token = "test-access_token";
-    std::env::set_var("ACCESS_SECRET", access_token);
     let ctx = build_context();
     let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
 
     // Retrieve coordinator.json file with valid token
     let mut req = client.get("/coordinator_status");
-    req.add_header(Header::new(ACCESS_SECRET_HEADER, access_token));
+    req.add_header(Header::new(ACCESS_SECRET_HEADER, ACCESS_SECRET));
     let response = req.dispatch();
     assert_eq!(response.status(), Status::Ok);
     assert!(response.body().is_some());
@@ -308,6 +389,70 @@ fn update_cohorts() {
     assert!(std::fs::metadata(TOKENS_ZIP_FILE).is_ok());
 }
 
+#[test]
+fn finalize_ceremony() {
+    let ctx = build_context();
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    let beacon = Beacon {
+        value: hex::encode([1u8; 32]),
+        iterations_exp: 2,
+    };
+
+    // Wrong, request from non-coordinator participant
+    let mut req = client.post("/finalize");
+    req = set_request::<Beacon>(req, &ctx.contributors[0].keypair, Some(&beacon));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(response.body().is_some());
+
+    // Wrong, a contribution is still in progress
+    req = client.post("/finalize");
+    req = set_request::<Beacon>(req, &ctx.coordinator.keypair, Some(&beacon));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::InternalServerError);
+    assert!(response.body().is_some());
+}
+
+#[test]
+fn rotate_secret() {
+    let ctx = build_context();
+    let secret_path = ctx.auth_tmp_dir.path().join("access_secret");
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    // Wrong, invalid secret
+    let mut req = client.post("/rotate_secret");
+    req.add_header(Header::new(ACCESS_SECRET_HEADER, "wrong token"));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(!secret_path.exists());
+
+    // Rotate the secret, which is published to the sink
+    req = client.post("/rotate_secret");
+    req.add_header(Header::new(ACCESS_SECRET_HEADER, ACCESS_SECRET));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let new_secret = std::fs::read_to_string(&secret_path).unwrap();
+    assert_ne!(new_secret, ACCESS_SECRET);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&secret_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // Only the new secret grants access
+    req = client.get("/coordinator_status");
+    req.add_header(Header::new(ACCESS_SECRET_HEADER, ACCESS_SECRET));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    req = client.get("/coordinator_status");
+    req.add_header(Header::new(ACCESS_SECRET_HEADER, new_secret.as_str()));
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+}
+
 #[test]
 fn stop_coordinator() {
     let ctx = build_context();
@@ -331,11 +476,9 @@ fn stop_coordinator() {
 #[test]
 fn get_healthcheck() {
     // Create status file
-    let mut status_file = tempfile::NamedTempFile::new_in(".").unwrap();
     let file_content =
         "{\"hash\":\"2e7f10b5a96f9f1e8c959acbce08483ccd9508e1\",\"timestamp\":\"Tue Jun 21 10:28:35 CEST 2022\"}";
-    status_file.write_all(file_content.as_bytes()).unwrap();
-    std::env::set_var("HEALTH_PATH", status_file.path());
+    std::fs::write(HEALTH_PATH, file_content).unwrap();
 
     let ctx = build_context();
     let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
@@ -348,11 +491,23 @@ fn get_healthcheck() {
     // It's impossible to extract the String out of the Body struct of the response, need to pass through serde
     let response_body: serde_json::Value = response.into_json().unwrap();
     let response_str = serde_json::to_string(&response_body).unwrap();
+    std::fs::remove_file(HEALTH_PATH).unwrap();
     if response_str != file_content {
         panic!("JSON status content doesn't match the expected one")
     }
 }
 
+#[test]
+fn get_circuits() {
+    let ctx = build_context();
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    let req = client.get("/circuits");
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<CircuitSet>().unwrap(), CircuitSet::MaspTest);
+}
+
 #[test]
 fn get_contributor_queue_status() {
     let ctx = build_context();
@@ -399,6 +554,53 @@ fn heartbeat() {
     assert!(response.body().is_none());
 }
 
+#[test]
+fn replayed_request() {
+    let ctx = build_context();
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    // Ok
+    let req = set_request::<()>(
+        client.post("/contributor/heartbeat"),
+        &ctx.contributors[0].keypair,
+        None,
+    );
+    let response = req.clone().dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    // Wrong, same nonce
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(response.into_string().unwrap().contains("nonce"));
+
+    // Wrong, stale timestamp
+    let mut req = client.post("/contributor/heartbeat");
+    let mut headers = signature_headers(&req, &ctx.contributors[0].keypair, None);
+    headers.timestamp -= 3600;
+    add_signature(&mut req, &ctx.contributors[0].keypair, &headers);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(response.into_string().unwrap().contains("timestamp"));
+
+    // Wrong, signed for another path
+    let mut req = client.post("/contributor/heartbeat");
+    let mut headers = signature_headers(&req, &ctx.contributors[0].keypair, None);
+    headers.path = "/contributor/join_queue".into();
+    add_signature(&mut req, &ctx.contributors[0].keypair, &headers);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert!(response.into_string().unwrap().contains("signature is invalid"));
+
+    // Wrong, timestamp too far in the future
+    let mut req = client.post("/contributor/heartbeat");
+    let mut headers = signature_headers(&req, &ctx.contributors[0].keypair, None);
+    headers.timestamp += 60;
+    add_signature(&mut req, &ctx.contributors[0].keypair, &headers);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(response.into_string().unwrap().contains("ahead"));
+}
+
 #[test]
 fn update_coordinator() {
     let ctx = build_context();
@@ -419,6 +621,66 @@ fn update_coordinator() {
     assert!(response.body().is_none());
 }
 
+#[test]
+fn operator_roles() {
+    let ctx = build_context();
+    let operators_path = ctx.auth_tmp_dir.path().join("operators.toml");
+    let operator = &ctx.contributors[1].keypair;
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    // Wrong, not an operator yet
+    let mut req = client.get("/update");
+    req = set_request::<()>(req, operator, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // The operator keys are reloaded without restarting the coordinator, as by its periodic refresh
+    let operators = client.rocket().state::<SharedOperators>().unwrap();
+    let write_operators = |content: String| {
+        std::fs::write(&operators_path, content).unwrap();
+        operators.refresh().unwrap();
+    };
+    let write_operator = |role: &str| {
+        write_operators(format!(
+            "[[operators]]\nname = \"alice\"\npubkey = \"{}\"\nrole = \"{}\"\n",
+            operator.pubkey(),
+            role
+        ))
+    };
+    write_operator("operator");
+    req = client.get("/update");
+    req = set_request::<()>(req, operator, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    // Wrong, the admin role is required
+    req = client.get("/stop");
+    req = set_request::<()>(req, operator, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // Wrong, the observer role is not enough
+    write_operator("observer");
+    req = client.get("/update");
+    req = set_request::<()>(req, operator, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // Revoke the key
+    write_operators(String::new());
+    req = client.get("/update");
+    req = set_request::<()>(req, operator, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // Shut the server down as an admin
+    write_operator("admin");
+    req = client.get("/stop");
+    req = set_request::<()>(req, operator, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+}
+
 #[test]
 fn wrong_post_attestation() {
     let ctx = build_context();
@@ -510,6 +772,67 @@ fn join_queue() {
     assert!(response.body().is_some());
 }
 
+#[test]
+fn join_queue_signed_token() {
+    let issuer = KeyPair::new();
+    let ctx = build_context_with(Testing::default().token_issuer(Some(TokenIssuer {
+        pubkey: issuer.pubkey().to_owned(),
+        cohorts: 1,
+    })));
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp() as u64;
+    let claims = TokenClaims {
+        from: now - 60,
+        to: now + 600,
+        index: 1,
+        id: "0123456789abcdef01234567".to_string(),
+    };
+    let socket_address = SocketAddr::new(ctx.unknown_participant.address, 8080);
+    let keypair = &ctx.unknown_participant.keypair;
+    let join_queue = |token: &SignedToken| {
+        let req = client.post("/contributor/join_queue").remote(socket_address);
+        set_request::<String>(req, keypair, Some(&token.encode())).dispatch()
+    };
+
+    // Wrong request, token of another issuer
+    let token = SignedToken::sign(claims.clone(), &KeyPair::new()).unwrap();
+    assert_eq!(join_queue(&token).status(), Status::Unauthorized);
+
+    // Wrong request, token of another cohort
+    let mut wrong_claims = claims.clone();
+    wrong_claims.index = 2;
+    let token = SignedToken::sign(wrong_claims, &issuer).unwrap();
+    assert_eq!(join_queue(&token).status(), Status::Unauthorized);
+
+    // Wrong request, expired token
+    let mut wrong_claims = claims.clone();
+    wrong_claims.to = now - 1;
+    let token = SignedToken::sign(wrong_claims, &issuer).unwrap();
+    assert_eq!(join_queue(&token).status(), Status::Unauthorized);
+
+    // Wrong request, tampered token
+    let mut token = SignedToken::sign(claims.clone(), &issuer).unwrap();
+    token.claims.id = "76543210fedcba9876543210".to_string();
+    assert_eq!(join_queue(&token).status(), Status::Unauthorized);
+
+    // Ok request, the token is not in the cohort file
+    let token = SignedToken::sign(claims, &issuer).unwrap();
+    let response = join_queue(&token);
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<u64>(), Some(1));
+
+    // Wrong request, the same token encoded differently is already in use
+    let reencoded = bs58::encode(serde_json::to_vec_pretty(&token).unwrap()).into_string();
+    assert_ne!(reencoded, token.encode());
+    let req = client
+        .post("/contributor/join_queue")
+        .remote(SocketAddr::new(ctx.contributors[1].address, 8080));
+    let response = set_request::<String>(req, &ctx.contributors[1].keypair, Some(&reencoded)).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(response.into_string().unwrap().contains("currently being used"));
+}
+
 /// Test wrong usage of lock_chunk.
 #[test]
 fn wrong_lock_chunk() {
@@ -612,6 +935,72 @@ fn wrong_contribute_chunk() {
     assert!(response.body().is_some());
 }
 
+/// Test the direct upload of a contribution to the coordinator.
+#[test]
+fn contribution_upload() {
+    let ctx = build_context();
+    let client = Client::tracked(ctx.rocket).expect("Invalid rocket instance");
+
+    // Stream the challenge
+    let mut req = client.get(format!("/contributor/challenge/{}", ROUND_HEIGHT));
+    req = set_request::<()>(req, &ctx.contributors[0].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_bytes().unwrap().len() > 0);
+
+    // Nothing uploaded yet
+    req = client.get(format!("/contributor/upload/{}", ROUND_HEIGHT));
+    req = set_request::<()>(req, &ctx.contributors[0].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<u64>().unwrap(), 0);
+
+    // Non-current-contributor
+    req = client.post(format!("/contributor/upload/{}/0", ROUND_HEIGHT));
+    req = set_raw_request(req, &ctx.contributors[1].keypair, &[1, 2, 3, 4]);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // Upload the first part
+    req = client.post(format!("/contributor/upload/{}/0", ROUND_HEIGHT));
+    req = set_raw_request(req, &ctx.contributors[0].keypair, &[1, 2, 3, 4]);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<u64>().unwrap(), 4);
+
+    // Wrong body digest
+    req = client.post(format!("/contributor/upload/{}/4", ROUND_HEIGHT));
+    req = set_raw_request(req, &ctx.contributors[0].keypair, &[5, 6, 7, 8]).body(&[0u8; 4]);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    // Non-contiguous part
+    req = client.post(format!("/contributor/upload/{}/2", ROUND_HEIGHT));
+    req = set_raw_request(req, &ctx.contributors[0].keypair, &[5, 6, 7, 8]);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+
+    // Part past the expected contribution size
+    let contrib_size = Object::anoma_contribution_file_size(&CircuitSet::MaspTest, ROUND_HEIGHT, 1).unwrap();
+    req = client.post(format!("/contributor/upload/{}/{}", ROUND_HEIGHT, contrib_size));
+    req = set_raw_request(req, &ctx.contributors[0].keypair, &[5, 6, 7, 8]);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+
+    // Wrong round height
+    req = client.post(format!("/contributor/upload/{}/4", ROUND_HEIGHT + 1));
+    req = set_raw_request(req, &ctx.contributors[0].keypair, &[5, 6, 7, 8]);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+
+    // Rejected parts are not staged
+    req = client.get(format!("/contributor/upload/{}", ROUND_HEIGHT));
+    req = set_request::<()>(req, &ctx.contributors[0].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<u64>().unwrap(), 4);
+}
+
 #[test]
 fn wrong_verify() {
     let ctx = build_context();
@@ -688,8 +1077,6 @@ fn wrong_post_contribution_info() {
 ///
 #[test]
 fn contribution() {
-    const COHORT_TIME: u64 = 15;
-    std::env::set_var("NAMADA_COHORT_TIME", COHORT_TIME.to_string()); // 15 seconds for each cohort
     use setup_utils::calculate_hash;
 
     let ctx = build_context();
@@ -733,10 +1120,10 @@ fn contribution() {
     let mut contribution: Vec<u8> = Vec::new();
     contribution.write_all(challenge_hash.as_slice()).unwrap();
     let entropy = RandomSource::Entropy(String::from("entropy"));
-    Computation::contribute_test_masp(&challenge, &mut contribution, &entropy);
+    Computation::contribute_circuits(&CircuitSet::MaspTest, &challenge, &mut contribution, &entropy);
 
-    // Initial contribution size is 2332 but the Coordinator expect ANOMA_BASE_FILE_SIZE. Extend to this size with trailing 0s
-    let contrib_size = Object::anoma_contribution_file_size(ROUND_HEIGHT, 1);
+    // Initial contribution size is 2332 but the Coordinator expect the base file size of the circuits. Extend to this size with trailing 0s
+    let contrib_size = Object::anoma_contribution_file_size(&CircuitSet::MaspTest, ROUND_HEIGHT, 1).unwrap();
     contribution.resize(contrib_size as usize, 0);
 
     let contribution_file_signature_locator = ContributionSignatureLocator::new(ROUND_HEIGHT, 0, 1, false);
@@ -782,6 +1169,16 @@ fn contribution() {
     assert_eq!(response.status(), Status::Ok);
     assert!(response.body().is_none());
 
+    // No contribution submitted yet
+    req = client.get("/contributor/contribution_status");
+    req = set_request::<()>(req, &ctx.contributors[0].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_json::<ContributionStatus>().unwrap(),
+        ContributionStatus::Other
+    );
+
     // Contribute
     let post_chunk = PostChunkRequest::new(ROUND_HEIGHT, contribution_locator, contribution_file_signature_locator);
 
@@ -791,6 +1188,16 @@ fn contribution() {
     assert_eq!(response.status(), Status::Ok);
     assert!(response.body().is_none());
 
+    // The contribution has been verified right away
+    req = client.get("/contributor/contribution_status");
+    req = set_request::<()>(req, &ctx.contributors[0].keypair, None);
+    let response = req.dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_json::<ContributionStatus>().unwrap(),
+        ContributionStatus::Valid
+    );
+
     // Verify chunk
     req = client.get("/verify");
     req = set_request::<()>(req, &ctx.coordinator.keypair, None);
@@ -155,6 +155,19 @@ pub fn beacon_randomness(mut beacon_hash: [u8; 32]) -> [u8; 32] {
     beacon_hash
 }
 
+/// Performs 2^exponent SHA256 iterations over the beacon hash, without printing the interstitial states
+#[cfg(not(feature = "wasm"))]
+pub fn iterate_beacon_hash(mut beacon_hash: [u8; 32], exponent: u32) -> [u8; 32] {
+    for _ in 0..(1u64 << exponent) {
+        let mut h = Sha256::new();
+        h.update(&beacon_hash);
+        let result = h.finalize();
+        beacon_hash.copy_from_slice(&result);
+    }
+
+    beacon_hash
+}
+
 /// Interpret the first 32 bytes of the digest as 8 32-bit words
 pub fn get_rng(digest: &[u8]) -> impl Rng + CryptoRng {
     let seed = from_slice(digest);
@@ -324,6 +337,18 @@ mod tests {
             &(G2Affine::prime_subgroup_generator(), gx)
         ));
     }
+
+    #[test]
+    #[cfg(not(feature = "wasm"))]
+    fn test_iterate_beacon_hash() {
+        let beacon_hash = [42; 32];
+
+        assert_eq!(iterate_beacon_hash(beacon_hash, 10), beacon_randomness(beacon_hash));
+        assert_eq!(
+            iterate_beacon_hash(iterate_beacon_hash(beacon_hash, 3), 3),
+            iterate_beacon_hash(beacon_hash, 4)
+        );
+    }
 }
 
 pub fn merge_pairs<G: AffineCurve>(v1: &[G], v2: &[G]) -> (G, G) {
@@ -1,8 +1,32 @@
 #!/bin/bash -e
 
 # Run the coordinator and e2e tests against a remote S3 bucket. Clean the bucket before each test.
+# Pass --minio to run them against a local MinIO container instead (requires docker).
 
-# Check credentials
+if [ "$1" == "--minio" ]; then
+    export AWS_ACCESS_KEY_ID=${AWS_ACCESS_KEY_ID:-minioadmin}
+    export AWS_SECRET_ACCESS_KEY=${AWS_SECRET_ACCESS_KEY:-minioadmin}
+    export AWS_S3_BUCKET=${AWS_S3_BUCKET:-namada-trusted-setup}
+    export AWS_REGION=${AWS_REGION:-us-east-1}
+    export AWS_S3_ENDPOINT=${AWS_S3_ENDPOINT:-http://localhost:9000}
+    export AWS_S3_PATH_STYLE=true
+    export AWS_CREDENTIALS_SOURCE=environment
+
+    echo "Starting MinIO..."
+    docker rm -f namada-ts-minio > /dev/null 2>&1 || true
+    docker run -d --name namada-ts-minio -p 9000:9000 \
+        -e MINIO_ROOT_USER=$AWS_ACCESS_KEY_ID \
+        -e MINIO_ROOT_PASSWORD=$AWS_SECRET_ACCESS_KEY \
+        minio/minio server /data > /dev/null
+    trap "docker rm -f namada-ts-minio > /dev/null" EXIT
+
+    until curl -s $AWS_S3_ENDPOINT/minio/health/live > /dev/null; do
+        sleep 1
+    done
+    aws --endpoint-url $AWS_S3_ENDPOINT s3 mb s3://$AWS_S3_BUCKET
+fi
+
+# Check credentials
 for cred in "$AWS_ACCESS_KEY_ID" "$AWS_SECRET_ACCESS_KEY" "$AWS_S3_BUCKET"; do
     if [ -z $cred ]; then
         echo "Credentials env variables must be set!"
@@ -10,12 +34,17 @@ for cred in "$AWS_ACCESS_KEY_ID" "$AWS_SECRET_ACCESS_KEY" "$AWS_S3_BUCKET"; do
     fi
 done
 
-# Coordinator test
+ENDPOINT_FLAG=""
+if [ -n "$AWS_S3_ENDPOINT" ]; then
+    ENDPOINT_FLAG="--endpoint-url $AWS_S3_ENDPOINT"
+fi
+
+# Coordinator test
 echo "Cleaning S3 bucket for coordinator test..."
-aws s3 rm s3://$AWS_S3_BUCKET --recursive
+aws $ENDPOINT_FLAG s3 rm s3://$AWS_S3_BUCKET --recursive
 cargo test --test test_coordinator -- --test-threads=1
 
 # e2e test
 echo "Cleaning S3 bucket for e2e test..."
-aws s3 rm s3://$AWS_S3_BUCKET --recursive
+aws $ENDPOINT_FLAG s3 rm s3://$AWS_S3_BUCKET --recursive
 cargo test --test e2e -- --test-threads=1